@@ -0,0 +1,247 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+//! A running sum of range-checked commitments.
+
+use group::Curve;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::commitments::Commitment;
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::{padded_params, RangeProof};
+use crate::util;
+
+/// A commitment to a running total, which is only updated by deltas
+/// carrying a valid range proof.
+///
+/// Since every delta is an `n`-bit value, the total commits to the sum
+/// of the accepted deltas, e.g. the balance of an account, and no delta
+/// can wrap it around the scalar field.  The holder of the blinding
+/// factors can open the total with the sum of the deltas' blindings.
+///
+/// # Encoding
+///
+/// The state is encoded as the compressed total commitment, followed by
+/// the number of accepted deltas as a little-endian `u64` and the
+/// bitsize as a byte, in 57 bytes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Accumulator {
+    total: Commitment,
+    updates: u64,
+    n: usize,
+}
+
+impl Accumulator {
+    /// Creates an empty accumulator for deltas of bitsize `n`, whose
+    /// total is the commitment to zero with zero blinding.
+    ///
+    /// Returns [`ProofError::InvalidBitsize`] if `n` is not between 1
+    /// and 64.  Deltas of a bitsize other than 8, 16, 32 or 64 are
+    /// proven with padding, as by [`RangeProof::prove_single_with_rng`].
+    pub fn new(n: usize) -> Result<Self, ProofError> {
+        Accumulator::from_commitment(Commitment::identity(), n)
+    }
+
+    /// Creates an accumulator for deltas of bitsize `n`, starting from
+    /// the commitment `initial`.
+    pub fn from_commitment(initial: Commitment, n: usize) -> Result<Self, ProofError> {
+        let (padded_n, _) = padded_params(n, 1);
+        if !(padded_n == 8 || padded_n == 16 || padded_n == 32 || padded_n == 64) {
+            return Err(ProofError::InvalidBitsize);
+        }
+        Ok(Accumulator {
+            total: initial,
+            updates: 0,
+            n,
+        })
+    }
+
+    /// Verifies the range proof of `delta` and adds it to the total.
+    ///
+    /// The proof must be an `n`-bit proof created with a transcript in
+    /// the same state as `transcript`.  The total is left unchanged if
+    /// the proof does not verify.
+    pub fn add_with_rng<T: RngCore + CryptoRng>(
+        &mut self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        delta: &Commitment,
+        proof: &RangeProof,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let updates = self.updates.checked_add(1).ok_or(ProofError::FormatError)?;
+        proof.verify_single_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &delta.0.to_affine(),
+            self.n,
+            rng,
+        )?;
+
+        self.total += *delta;
+        self.updates = updates;
+        Ok(())
+    }
+
+    /// Verifies the range proof of `delta` and adds it to the total.
+    /// This is a convenience wrapper around [`Accumulator::add_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn add(
+        &mut self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        delta: &Commitment,
+        proof: &RangeProof,
+    ) -> Result<(), ProofError> {
+        self.add_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            delta,
+            proof,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Returns the commitment to the total.
+    pub fn total(&self) -> &Commitment {
+        &self.total
+    }
+
+    /// Returns the number of deltas added to the total.
+    pub fn updates(&self) -> u64 {
+        self.updates
+    }
+
+    /// Returns the bitsize of the deltas.
+    pub fn bitsize(&self) -> usize {
+        self.n
+    }
+
+    /// Serializes the accumulator state into a 57-byte array.
+    pub fn to_bytes(&self) -> [u8; 57] {
+        let mut buf = [0u8; 57];
+        buf[..48].copy_from_slice(&self.total.to_bytes());
+        buf[48..56].copy_from_slice(&self.updates.to_le_bytes());
+        buf[56] = self.n as u8;
+        buf
+    }
+
+    /// Deserializes the accumulator state from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into an `Accumulator`.
+    pub fn from_bytes(slice: &[u8]) -> Result<Accumulator, ProofError> {
+        if slice.len() != 57 {
+            return Err(ProofError::FormatError);
+        }
+        let total = Commitment::from_bytes(&slice[..48])?;
+        let mut updates = [0u8; 8];
+        updates.copy_from_slice(&slice[48..56]);
+
+        let mut accumulator = Accumulator::from_commitment(total, slice[56] as usize)?;
+        accumulator.updates = u64::from_le_bytes(updates);
+        Ok(accumulator)
+    }
+}
+
+impl Serialize for Accumulator {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for Accumulator {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        util::deserialize_bytes(deserializer, "Accumulator", Accumulator::from_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::Scalar;
+    use group::ff::Field;
+
+    #[test]
+    fn accumulates_range_checked_deltas() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut rng = rand::thread_rng();
+
+        let mut accumulator = Accumulator::new(32).unwrap();
+        let mut blinding = Scalar::zero();
+        for value in [5u64, 1 << 20, 7] {
+            let delta_blinding = Scalar::random(&mut rng);
+            let mut transcript = Transcript::new(b"AccumulatorTest");
+            let (proof, delta) = RangeProof::prove_single(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                value,
+                &delta_blinding,
+                32,
+            )
+            .unwrap();
+
+            let mut transcript = Transcript::new(b"AccumulatorTest");
+            accumulator
+                .add(&bp_gens, &pc_gens, &mut transcript, &delta.into(), &proof)
+                .unwrap();
+            blinding += delta_blinding;
+
+            // A proof does not verify for another delta
+            let mut transcript = Transcript::new(b"AccumulatorTest");
+            let other = Commitment::new(
+                &pc_gens,
+                Scalar::from(value),
+                delta_blinding + Scalar::one(),
+            );
+            assert!(accumulator
+                .add(&bp_gens, &pc_gens, &mut transcript, &other, &proof)
+                .is_err());
+        }
+
+        assert_eq!(accumulator.updates(), 3);
+        assert_eq!(
+            *accumulator.total(),
+            Commitment::new(&pc_gens, Scalar::from(12 + (1u64 << 20)), blinding)
+        );
+
+        let decoded = Accumulator::from_bytes(&accumulator.to_bytes()).unwrap();
+        assert_eq!(decoded, accumulator);
+        let decoded: Accumulator =
+            bincode::deserialize(&bincode::serialize(&accumulator).unwrap()).unwrap();
+        assert_eq!(decoded, accumulator);
+
+        let mut bytes = accumulator.to_bytes();
+        bytes[56] = 65;
+        assert_eq!(
+            Accumulator::from_bytes(&bytes),
+            Err(ProofError::InvalidBitsize)
+        );
+        assert_eq!(
+            Accumulator::from_bytes(&bytes[1..]),
+            Err(ProofError::FormatError)
+        );
+    }
+}
@@ -0,0 +1,486 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+//! Fixed-point decimal amounts.
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::str::FromStr;
+
+use blstrs::Scalar;
+
+use crate::errors::AmountError;
+
+/// The largest supported scale, since \\(10^{20}\\) does not fit in 64 bits.
+pub const MAX_SCALE: u8 = 19;
+
+/// A fixed-point decimal amount, stored as an integer number of base
+/// units of \\(10^{-s}\\) for a scale \\(s\\).
+///
+/// Range proofs are over integers, so an amount has to be converted to
+/// base units at an agreed scale before proving, and both prover and
+/// verifier have to use the same scale.  `Amount` keeps the scale next
+/// to the value, and [`RangeProof::prove_amounts`](crate::RangeProof::prove_amounts)
+/// converts it to the proof's scale, failing instead of silently
+/// proving a mis-scaled value.
+///
+/// Amounts parse from and format as decimal strings, e.g. `"1.5"` at
+/// scale 9 is \\(1500000000\\) base units.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "AmountRepr")]
+pub struct Amount {
+    units: u64,
+    scale: u8,
+}
+
+/// The serialized fields of an [`Amount`], which are checked with
+/// [`Amount::from_units`] when deserializing.
+#[derive(Deserialize)]
+struct AmountRepr {
+    units: u64,
+    scale: u8,
+}
+
+impl TryFrom<AmountRepr> for Amount {
+    // Serde reports the error with Display, which our error type only
+    // implements when it implements std::error::Error.
+    #[cfg(feature = "std")]
+    type Error = AmountError;
+    #[cfg(not(feature = "std"))]
+    type Error = &'static str;
+
+    fn try_from(repr: AmountRepr) -> Result<Amount, Self::Error> {
+        let amount = Amount::from_units(repr.units, repr.scale);
+        #[cfg(feature = "std")]
+        return amount;
+        // In no-std contexts, drop the error message.
+        #[cfg(not(feature = "std"))]
+        return amount.map_err(|_| "amount scale exceeds MAX_SCALE");
+    }
+}
+
+impl Amount {
+    /// Creates an amount of `units` base units of \\(10^{-\mathrm{scale}}\\).
+    ///
+    /// Returns [`AmountError::TooPrecise`] if `scale` exceeds [`MAX_SCALE`].
+    pub fn from_units(units: u64, scale: u8) -> Result<Amount, AmountError> {
+        if scale > MAX_SCALE {
+            return Err(AmountError::TooPrecise { scale });
+        }
+        Ok(Amount { units, scale })
+    }
+
+    /// Parses a decimal string such as `"12"` or `"12.345"` at the given `scale`.
+    ///
+    /// Returns [`AmountError::TooPrecise`] if the string has more than
+    /// `scale` fractional digits, and [`AmountError::Overflow`] if the
+    /// amount does not fit in 64 bits of base units.
+    pub fn parse(s: &str, scale: u8) -> Result<Amount, AmountError> {
+        if scale > MAX_SCALE {
+            return Err(AmountError::TooPrecise { scale });
+        }
+
+        let (whole, frac) = match s.find('.') {
+            Some(i) => (&s[..i], &s[i + 1..]),
+            None => (s, ""),
+        };
+        let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if whole.is_empty() || !is_digits(whole) || !is_digits(frac) {
+            return Err(AmountError::ParseError);
+        }
+        if s.len() > whole.len() && frac.is_empty() {
+            return Err(AmountError::ParseError);
+        }
+
+        let frac = frac.trim_end_matches('0');
+        if frac.len() > scale as usize {
+            return Err(AmountError::TooPrecise { scale });
+        }
+
+        let overflow = AmountError::Overflow { scale };
+        let mut units: u64 = 0;
+        for b in whole.bytes().chain(frac.bytes()) {
+            units = units
+                .checked_mul(10)
+                .and_then(|u| u.checked_add(u64::from(b - b'0')))
+                .ok_or_else(|| overflow.clone())?;
+        }
+        units = units
+            .checked_mul(pow10(scale - frac.len() as u8))
+            .ok_or(overflow)?;
+
+        Ok(Amount { units, scale })
+    }
+
+    /// Returns the amount in base units.
+    pub fn units(&self) -> u64 {
+        self.units
+    }
+
+    /// Returns the number of fractional digits of the base unit.
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    /// Converts the amount to the given `scale`.
+    ///
+    /// Returns [`AmountError::TooPrecise`] if the conversion would lose
+    /// precision, and [`AmountError::Overflow`] if the amount does not
+    /// fit in 64 bits of base units at the new scale.
+    pub fn rescale(&self, scale: u8) -> Result<Amount, AmountError> {
+        if scale > MAX_SCALE {
+            return Err(AmountError::TooPrecise { scale });
+        }
+
+        let units = if scale >= self.scale {
+            self.units
+                .checked_mul(pow10(scale - self.scale))
+                .ok_or(AmountError::Overflow { scale })?
+        } else {
+            let divisor = pow10(self.scale - scale);
+            let (units, remainder) = (self.units / divisor, self.units % divisor);
+            if remainder != 0 {
+                return Err(AmountError::TooPrecise { scale });
+            }
+            units
+        };
+
+        Ok(Amount { units, scale })
+    }
+
+    /// Returns the amount in base units at the given `scale`, checking
+    /// that it fits in a range proof of bitsize `n`.
+    pub fn to_units(&self, scale: u8, n: usize) -> Result<u64, AmountError> {
+        let units = self.rescale(scale)?.units;
+        if n < 64 && units >> n != 0 {
+            return Err(AmountError::ExceedsBitsize { units, bitsize: n });
+        }
+        Ok(units)
+    }
+
+    /// Adds `other` to the amount, at the larger of the two scales.
+    ///
+    /// Returns [`AmountError::Overflow`] if the sum does not fit in 64
+    /// bits of base units.
+    pub fn checked_add(&self, other: &Amount) -> Result<Amount, AmountError> {
+        let scale = self.scale.max(other.scale);
+        let units = self
+            .rescale(scale)?
+            .units
+            .checked_add(other.rescale(scale)?.units)
+            .ok_or(AmountError::Overflow { scale })?;
+        Ok(Amount { units, scale })
+    }
+
+    /// Subtracts `other` from the amount, at the larger of the two scales.
+    ///
+    /// Returns [`AmountError::Overflow`] if `other` is larger than the
+    /// amount.
+    pub fn checked_sub(&self, other: &Amount) -> Result<Amount, AmountError> {
+        let scale = self.scale.max(other.scale);
+        let units = self
+            .rescale(scale)?
+            .units
+            .checked_sub(other.rescale(scale)?.units)
+            .ok_or(AmountError::Overflow { scale })?;
+        Ok(Amount { units, scale })
+    }
+
+    /// Returns the amount in base units at the given `scale` as a
+    /// scalar, checking that it fits in a range proof of bitsize `n`.
+    ///
+    /// This is the value committed to by a range proof of the amount.
+    pub fn to_scalar(&self, scale: u8, n: usize) -> Result<Scalar, AmountError> {
+        self.to_units(scale, n).map(Scalar::from)
+    }
+
+    /// Converts a scalar holding a number of base units of the given
+    /// `scale`, e.g. an opened commitment, back into an amount.
+    ///
+    /// Returns [`AmountError::Overflow`] if the scalar does not fit in
+    /// 64 bits, and [`AmountError::ExceedsBitsize`] if it does not fit
+    /// in a range proof of bitsize `n`.
+    pub fn from_scalar(value: &Scalar, scale: u8, n: usize) -> Result<Amount, AmountError> {
+        let bytes = value.to_bytes_le();
+        if bytes[8..].iter().any(|b| *b != 0) {
+            return Err(AmountError::Overflow { scale });
+        }
+        let mut units = [0u8; 8];
+        units.copy_from_slice(&bytes[..8]);
+        let units = u64::from_le_bytes(units);
+        if n < 64 && units >> n != 0 {
+            return Err(AmountError::ExceedsBitsize { units, bitsize: n });
+        }
+        Amount::from_units(units, scale)
+    }
+}
+
+/// An amount together with the blinding factor of its commitment,
+/// which tracks whether the commitment has a range proof.
+///
+/// Any change of the amount or the blinding factor changes the
+/// commitment, which then needs a new range proof.  The checked
+/// arithmetic of `TrackedAmount` records this, and
+/// [`TrackedAmount::pending_proof`] returns the value and blinding
+/// factor to pass to [`RangeProof::prove_single`](crate::RangeProof::prove_single)
+/// until the new proof is recorded with [`TrackedAmount::mark_proven`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TrackedAmount {
+    amount: Amount,
+    blinding: Scalar,
+    needs_proof: bool,
+}
+
+impl TrackedAmount {
+    /// Creates a tracked `amount` committed with `blinding`, which
+    /// does not have a range proof yet.
+    pub fn new(amount: Amount, blinding: Scalar) -> Self {
+        TrackedAmount {
+            amount,
+            blinding,
+            needs_proof: true,
+        }
+    }
+
+    /// Creates a tracked `amount` committed with `blinding`, whose
+    /// commitment already has a range proof.
+    pub fn proven(amount: Amount, blinding: Scalar) -> Self {
+        TrackedAmount {
+            amount,
+            blinding,
+            needs_proof: false,
+        }
+    }
+
+    /// Returns the amount.
+    pub fn amount(&self) -> &Amount {
+        &self.amount
+    }
+
+    /// Returns the blinding factor of the commitment.
+    pub fn blinding(&self) -> &Scalar {
+        &self.blinding
+    }
+
+    /// Returns `true` if the commitment changed since its last range proof.
+    pub fn needs_proof(&self) -> bool {
+        self.needs_proof
+    }
+
+    /// Adds `amount`, committed with `blinding`, to the tracked amount,
+    /// so that the commitment of the result is the sum of both
+    /// commitments.
+    ///
+    /// The result needs a new range proof unless both `amount` and
+    /// `blinding` are zero.
+    pub fn checked_add(&self, amount: &Amount, blinding: &Scalar) -> Result<Self, AmountError> {
+        Ok(self.update(self.amount.checked_add(amount)?, self.blinding + blinding))
+    }
+
+    /// Subtracts `amount`, committed with `blinding`, from the tracked
+    /// amount, so that the commitment of the result is the difference
+    /// of both commitments.
+    ///
+    /// The result needs a new range proof unless both `amount` and
+    /// `blinding` are zero.
+    pub fn checked_sub(&self, amount: &Amount, blinding: &Scalar) -> Result<Self, AmountError> {
+        Ok(self.update(self.amount.checked_sub(amount)?, self.blinding - blinding))
+    }
+
+    /// Returns the value in base units at the given `scale` and the
+    /// blinding factor to prove the commitment with, if it needs a new
+    /// range proof of bitsize `n`.
+    pub fn pending_proof(&self, scale: u8, n: usize) -> Result<Option<(u64, Scalar)>, AmountError> {
+        if !self.needs_proof {
+            return Ok(None);
+        }
+        Ok(Some((self.amount.to_units(scale, n)?, self.blinding)))
+    }
+
+    /// Records that the commitment has a range proof.
+    pub fn mark_proven(&mut self) {
+        self.needs_proof = false;
+    }
+
+    fn update(&self, amount: Amount, blinding: Scalar) -> Self {
+        let changed =
+            amount.rescale(self.amount.scale) != Ok(self.amount) || blinding != self.blinding;
+        TrackedAmount {
+            amount,
+            blinding,
+            needs_proof: self.needs_proof || changed,
+        }
+    }
+}
+
+fn pow10(exp: u8) -> u64 {
+    10u64.pow(u32::from(exp))
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.scale == 0 {
+            return write!(f, "{}", self.units);
+        }
+        let divisor = pow10(self.scale);
+        write!(
+            f,
+            "{}.{:0width$}",
+            self.units / divisor,
+            self.units % divisor,
+            width = self.scale as usize
+        )
+    }
+}
+
+impl FromStr for Amount {
+    type Err = AmountError;
+
+    /// Parses a decimal string at the smallest scale that represents it exactly.
+    fn from_str(s: &str) -> Result<Amount, AmountError> {
+        let frac_digits = match s.find('.') {
+            Some(i) => s[i + 1..].trim_end_matches('0').len(),
+            None => 0,
+        };
+        if frac_digits > MAX_SCALE as usize {
+            return Err(AmountError::TooPrecise { scale: MAX_SCALE });
+        }
+        Amount::parse(s, frac_digits as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use group::ff::Field;
+
+    #[test]
+    fn parse_and_display() {
+        let a = Amount::parse("1.5", 9).unwrap();
+        assert_eq!(a.units(), 1_500_000_000);
+        assert_eq!(a.to_string(), "1.500000000");
+        assert_eq!(
+            "1.50".parse::<Amount>().unwrap(),
+            Amount::parse("1.5", 1).unwrap()
+        );
+        assert_eq!(Amount::parse("42", 0).unwrap().to_string(), "42");
+
+        assert_eq!(Amount::parse("1.", 9), Err(AmountError::ParseError));
+        assert_eq!(Amount::parse("-1", 9), Err(AmountError::ParseError));
+        assert_eq!(Amount::parse("1.2.3", 9), Err(AmountError::ParseError));
+        assert_eq!(
+            Amount::parse("0.0001", 3),
+            Err(AmountError::TooPrecise { scale: 3 })
+        );
+        assert_eq!(
+            Amount::parse("18446744073.709551616", 9),
+            Err(AmountError::Overflow { scale: 9 })
+        );
+    }
+
+    #[test]
+    fn deserialize_checks_scale() {
+        let a = Amount::parse("1.5", 9).unwrap();
+        let decoded: Amount = bincode::deserialize(&bincode::serialize(&a).unwrap()).unwrap();
+        assert_eq!(decoded, a);
+
+        let bytes = bincode::serialize(&(15u64, 20u8)).unwrap();
+        assert!(bincode::deserialize::<Amount>(&bytes).is_err());
+        assert!(serde_json::from_str::<Amount>(r#"{"units":15,"scale":20}"#).is_err());
+    }
+
+    #[test]
+    fn rescale_and_bitsize() {
+        let a = Amount::parse("2.5", 1).unwrap();
+        assert_eq!(a.rescale(3).unwrap().units(), 2500);
+        assert_eq!(a.rescale(0), Err(AmountError::TooPrecise { scale: 0 }));
+        assert_eq!(a.to_units(9, 32).unwrap(), 2_500_000_000);
+        assert_eq!(
+            a.to_units(9, 16),
+            Err(AmountError::ExceedsBitsize {
+                units: 2_500_000_000,
+                bitsize: 16
+            })
+        );
+        assert_eq!(
+            Amount::from_units(u64::MAX, 0).unwrap().rescale(1),
+            Err(AmountError::Overflow { scale: 1 })
+        );
+    }
+
+    #[test]
+    fn scalar_round_trip() {
+        let a = Amount::parse("3.25", 2).unwrap();
+        let value = a.to_scalar(2, 16).unwrap();
+        assert_eq!(value, Scalar::from(325u64));
+        assert_eq!(Amount::from_scalar(&value, 2, 16), Ok(a));
+        assert_eq!(a.to_scalar(4, 64).unwrap(), Scalar::from(32500u64));
+
+        assert_eq!(
+            Amount::from_scalar(&value, 2, 8),
+            Err(AmountError::ExceedsBitsize {
+                units: 325,
+                bitsize: 8
+            })
+        );
+        let too_large = Scalar::from(u64::MAX) + Scalar::from(1u64);
+        assert_eq!(
+            Amount::from_scalar(&too_large, 2, 64),
+            Err(AmountError::Overflow { scale: 2 })
+        );
+        assert_eq!(
+            Amount::from_scalar(&-Scalar::from(1u64), 2, 64),
+            Err(AmountError::Overflow { scale: 2 })
+        );
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        let a = Amount::parse("1.5", 1).unwrap();
+        let b = Amount::parse("0.25", 2).unwrap();
+        assert_eq!(a.checked_add(&b), Amount::parse("1.75", 2));
+        assert_eq!(a.checked_sub(&b), Amount::parse("1.25", 2));
+        assert_eq!(b.checked_sub(&a), Err(AmountError::Overflow { scale: 2 }));
+        assert_eq!(
+            Amount::from_units(u64::MAX, 0)
+                .unwrap()
+                .checked_add(&Amount::from_units(1, 0).unwrap()),
+            Err(AmountError::Overflow { scale: 0 })
+        );
+    }
+
+    #[test]
+    fn tracks_pending_proofs() {
+        let zero = Amount::from_units(0, 2).unwrap();
+        let mut balance = TrackedAmount::new(Amount::parse("10", 2).unwrap(), Scalar::from(5u64));
+        assert_eq!(
+            balance.pending_proof(2, 32),
+            Ok(Some((1000, Scalar::from(5u64))))
+        );
+        balance.mark_proven();
+        assert_eq!(balance.pending_proof(2, 32), Ok(None));
+
+        // A zero delta with a zero blinding leaves the commitment unchanged
+        let same = balance.checked_add(&zero, &Scalar::zero()).unwrap();
+        assert!(!same.needs_proof());
+        // Reblinding alone changes the commitment
+        let reblinded = balance.checked_add(&zero, &Scalar::one()).unwrap();
+        assert!(reblinded.needs_proof());
+
+        let spent = balance
+            .checked_sub(&Amount::parse("2.5", 1).unwrap(), &Scalar::from(2u64))
+            .unwrap();
+        assert_eq!(
+            spent.pending_proof(2, 32),
+            Ok(Some((750, Scalar::from(3u64))))
+        );
+        assert_eq!(
+            balance.checked_sub(&Amount::parse("11", 0).unwrap(), &Scalar::zero()),
+            Err(AmountError::Overflow { scale: 2 })
+        );
+    }
+}
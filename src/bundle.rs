@@ -0,0 +1,639 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Range proofs bundled with the commitments they were created for.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use blstrs::{G1Affine, G2Affine, G2Projective, Scalar};
+use byteorder::{ByteOrder, LittleEndian};
+use group::Curve;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::merkle;
+use crate::range_proof::{padded_params, RangeProof};
+use crate::util::{self, read48, read96};
+
+/// Domain separation tag for hashing a bundle digest to \\(\mathbb G\_2\\).
+const BUNDLE_DST: &[u8] = b"BLS_BULLETPROOFS_PROOF_BUNDLE_BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+/// Flag set in the bitsize byte of the encoding of a signed bundle.
+const SIGNED_FLAG: u8 = 0x80;
+
+/// Domain tag of the Merkle tree of bundle digests.
+const MERKLE_TAG: &[u8] = b"ProofBundle";
+
+/// A range proof together with the value commitments and the bitsize
+/// it was created for.
+///
+/// Keeping the three in one value means a proof cannot be verified
+/// against the wrong set of commitments by mistake.  A bundle can only
+/// be constructed with a proof whose header agrees with the bitsize
+/// and the number of commitments.
+///
+/// A bundle may carry a BLS signature by its issuer, e.g. a mint, over
+/// its [digest](ProofBundle::digest), which [`ProofBundle::verify_signed`]
+/// checks along with the proof.
+///
+/// # Encoding
+///
+/// The bundle is encoded as one byte for the bitsize \\(n\\), whose top
+/// bit is set if the bundle is signed, a 4-byte little-endian number of
+/// commitments \\(m\\), the \\(m\\) compressed commitments, the
+/// compressed signature if any, and the proof as encoded by
+/// [`RangeProof::to_bytes`].
+/// The encoding is canonical: [`ProofBundle::from_bytes`] only accepts
+/// the bytes [`ProofBundle::to_bytes`] produces.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProofBundle {
+    commitments: Vec<G1Affine>,
+    proof: RangeProof,
+    n: usize,
+    signature: Option<G2Affine>,
+}
+
+/// A proof that a bundle is included under the Merkle root of a set of
+/// bundles, as returned by [`ProofBundle::inclusion_proof`].
+///
+/// The leaves of the tree are the [digests](ProofBundle::digest) of the
+/// bundles, so a light client holding the root, e.g. from a block
+/// header, can check that one bundle was committed to without
+/// downloading the others.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BundleInclusionProof {
+    /// The index of the bundle's leaf
+    pub index: usize,
+    /// The number of leaves in the tree
+    pub num_leaves: usize,
+    /// The sibling hashes on the path from the leaf to the root
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl ProofBundle {
+    /// Bundles an `n`-bit `proof` with the `commitments` it was created for.
+    ///
+    /// Returns [`ProofError::InvalidBitsize`] if `n` is not 8, 16, 32
+    /// or 64, or for a single commitment not between 1 and 64,
+    /// [`ProofError::InvalidAggregation`] if the number of commitments
+    /// is not a power of 2, and [`ProofError::ParameterMismatch`] if
+    /// the proof header records other parameters.
+    pub fn new(
+        proof: RangeProof,
+        commitments: Vec<G1Affine>,
+        n: usize,
+    ) -> Result<ProofBundle, ProofError> {
+        let (padded_n, m) = padded_params(n, commitments.len());
+        if !(padded_n == 8 || padded_n == 16 || padded_n == 32 || padded_n == 64) {
+            return Err(ProofError::InvalidBitsize);
+        }
+        if !m.is_power_of_two() {
+            return Err(ProofError::InvalidAggregation);
+        }
+        if proof.bitsize().is_some() && proof.bitsize() != Some(padded_n) {
+            return Err(ProofError::ParameterMismatch);
+        }
+        if proof.aggregation_size().is_some() && proof.aggregation_size() != Some(m) {
+            return Err(ProofError::ParameterMismatch);
+        }
+        Ok(ProofBundle {
+            commitments,
+            proof,
+            n,
+            signature: None,
+        })
+    }
+
+    /// Creates an aggregated `n`-bit range proof for `values` and
+    /// bundles it with the commitments to the values.
+    ///
+    /// See [`RangeProof::prove_multiple_with_rng`] for the requirements
+    /// on the arguments.
+    #[cfg(feature = "prover")]
+    pub fn prove_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<ProofBundle, ProofError> {
+        let (proof, commitments) = RangeProof::prove_multiple_with_rng(
+            bp_gens, pc_gens, transcript, values, blindings, n, rng,
+        )?;
+        ProofBundle::new(proof, commitments, n)
+    }
+
+    /// Creates an aggregated `n`-bit range proof for `values` and
+    /// bundles it with the commitments to the values.
+    /// This is a convenience wrapper around [`ProofBundle::prove_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(all(feature = "thread_rng", feature = "prover"))]
+    pub fn prove(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+    ) -> Result<ProofBundle, ProofError> {
+        ProofBundle::prove_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies the proof against the bundled commitments and bitsize.
+    pub fn verify_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.proof.verify_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &self.commitments,
+            self.n,
+            rng,
+        )
+    }
+
+    /// Verifies the proof against the bundled commitments and bitsize.
+    /// This is a convenience wrapper around [`ProofBundle::verify_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_with_rng(bp_gens, pc_gens, transcript, &mut thread_rng())
+    }
+
+    /// Checks the issuer's signature with `issuer_public_key`, and
+    /// verifies the proof against the bundled commitments and bitsize.
+    ///
+    /// The signature is checked with a pairing before the proof is
+    /// verified, and both use the commitments decoded with the bundle.
+    /// Returns [`ProofError::VerificationError`] if the bundle is not
+    /// signed or the signature is invalid.
+    pub fn verify_signed_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        issuer_public_key: &G1Affine,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let signature = self.signature.ok_or(ProofError::VerificationError)?;
+        let message = self.message_point().to_affine();
+        if !util::verify_bls_signature(issuer_public_key, &message, &signature) {
+            return Err(ProofError::VerificationError);
+        }
+        self.verify_with_rng(bp_gens, pc_gens, transcript, rng)
+    }
+
+    /// Checks the issuer's signature with `issuer_public_key`, and
+    /// verifies the proof against the bundled commitments and bitsize.
+    /// This is a convenience wrapper around [`ProofBundle::verify_signed_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify_signed(
+        &self,
+        issuer_public_key: &G1Affine,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_signed_with_rng(
+            issuer_public_key,
+            bp_gens,
+            pc_gens,
+            transcript,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Signs the bundle with the issuer's `secret_key`, replacing any
+    /// previous signature.
+    pub fn sign(&mut self, secret_key: &Scalar) {
+        self.signature = Some((self.message_point() * secret_key).to_affine());
+    }
+
+    /// Returns the issuer's signature, if the bundle is signed.
+    pub fn signature(&self) -> Option<&G2Affine> {
+        self.signature.as_ref()
+    }
+
+    /// Returns the digest of the bitsize, commitments and proof, which
+    /// is the message signed by the issuer.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut transcript = Transcript::new(b"ProofBundle");
+        transcript.append_message(b"dom-sep", b"proof-bundle v1");
+        transcript.append_u64(b"n", self.n as u64);
+        transcript.append_u64(b"m", self.commitments.len() as u64);
+        for V in self.commitments.iter() {
+            transcript.append_message(b"V", &V.to_compressed());
+        }
+        transcript.append_message(b"proof", &self.proof.to_bytes());
+
+        let mut digest = [0u8; 32];
+        transcript.challenge_bytes(b"bundle-digest", &mut digest);
+        digest
+    }
+
+    /// Returns the root of the Merkle tree whose leaves are the digests
+    /// of `bundles`, in order.
+    ///
+    /// The last node of a level with an odd number of nodes is carried
+    /// up to the next level unchanged.
+    pub fn merkle_root(bundles: &[ProofBundle]) -> [u8; 32] {
+        merkle::root(MERKLE_TAG, bundles.iter().map(leaf_hash).collect())
+    }
+
+    /// Returns the proof that the bundle at `index` is included under
+    /// the [`ProofBundle::merkle_root`] of `bundles`, or `None` if there
+    /// is no such bundle.
+    pub fn inclusion_proof(bundles: &[ProofBundle], index: usize) -> Option<BundleInclusionProof> {
+        if index >= bundles.len() {
+            return None;
+        }
+        let leaves = bundles.iter().map(leaf_hash).collect();
+        Some(BundleInclusionProof {
+            index,
+            num_leaves: bundles.len(),
+            siblings: merkle::path(MERKLE_TAG, leaves, index),
+        })
+    }
+
+    /// Checks that the bundle is included under `root` with `inclusion`,
+    /// and verifies the proof against the bundled commitments and
+    /// bitsize.
+    ///
+    /// The inclusion is checked first, as it only takes hashing.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_included_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        root: &[u8; 32],
+        inclusion: &BundleInclusionProof,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        inclusion.verify(root, self)?;
+        self.verify_with_rng(bp_gens, pc_gens, transcript, rng)
+    }
+
+    /// Checks that the bundle is included under `root` with `inclusion`,
+    /// and verifies the proof against the bundled commitments and
+    /// bitsize.
+    /// This is a convenience wrapper around [`ProofBundle::verify_included_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify_included(
+        &self,
+        root: &[u8; 32],
+        inclusion: &BundleInclusionProof,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_included_with_rng(
+            root,
+            inclusion,
+            bp_gens,
+            pc_gens,
+            transcript,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Hashes the digest of the bundle to \\(\mathbb G\_2\\).
+    fn message_point(&self) -> G2Projective {
+        G2Projective::hash_to_curve(&self.digest(), BUNDLE_DST, &[])
+    }
+
+    /// Returns the value commitments the proof was created for.
+    pub fn commitments(&self) -> &[G1Affine] {
+        &self.commitments
+    }
+
+    /// Returns the range proof.
+    pub fn proof(&self) -> &RangeProof {
+        &self.proof
+    }
+
+    /// Returns the bitsize \\(n\\) of the proof.
+    pub fn bitsize(&self) -> usize {
+        self.n
+    }
+
+    /// Serializes the bundle into a byte vector.
+    ///
+    /// See the type-level documentation for the layout.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let proof = self.proof.to_bytes();
+        let mut buf = Vec::with_capacity(101 + 48 * self.commitments.len() + proof.len());
+        let mut len = [0u8; 4];
+
+        let flags = if self.signature.is_some() {
+            SIGNED_FLAG
+        } else {
+            0
+        };
+        buf.push(self.n as u8 | flags);
+        LittleEndian::write_u32(&mut len, self.commitments.len() as u32);
+        buf.extend_from_slice(&len);
+        for V in self.commitments.iter() {
+            buf.extend_from_slice(&V.to_compressed());
+        }
+        if let Some(signature) = self.signature {
+            buf.extend_from_slice(&signature.to_compressed());
+        }
+        buf.extend_from_slice(&proof);
+        buf
+    }
+
+    /// Deserializes the bundle from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a
+    /// `ProofBundle`, or if the parameters are invalid as for
+    /// [`ProofBundle::new`].
+    pub fn from_bytes(slice: &[u8]) -> Result<ProofBundle, ProofError> {
+        if slice.len() < 5 {
+            return Err(ProofError::FormatError);
+        }
+        let n = (slice[0] & !SIGNED_FLAG) as usize;
+        let signed = slice[0] & SIGNED_FLAG != 0;
+        let m = LittleEndian::read_u32(&slice[1..5]) as usize;
+        let slice = &slice[5..];
+        if m > slice.len() / 48 {
+            return Err(ProofError::FormatError);
+        }
+
+        let commitments = slice[..48 * m]
+            .chunks(48)
+            .map(|bytes| {
+                Option::from(G1Affine::from_compressed(&read48(bytes)))
+                    .ok_or(ProofError::FormatError)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut slice = &slice[48 * m..];
+        let signature = if signed {
+            if slice.len() < 96 {
+                return Err(ProofError::FormatError);
+            }
+            let signature = Option::from(G2Affine::from_compressed(&read96(slice)))
+                .ok_or(ProofError::FormatError)?;
+            slice = &slice[96..];
+            Some(signature)
+        } else {
+            None
+        };
+
+        let proof = RangeProof::from_bytes(slice)?;
+        let mut bundle = ProofBundle::new(proof, commitments, n)?;
+        bundle.signature = signature;
+        Ok(bundle)
+    }
+}
+
+impl BundleInclusionProof {
+    /// Checks that `bundle` is included under `root`.
+    ///
+    /// Only the digest of the bundle is checked; its range proof must
+    /// still be verified, e.g. with [`ProofBundle::verify_included`].
+    pub fn verify(&self, root: &[u8; 32], bundle: &ProofBundle) -> Result<(), ProofError> {
+        merkle::verify_path(
+            MERKLE_TAG,
+            leaf_hash(bundle),
+            self.index,
+            self.num_leaves,
+            &self.siblings,
+            root,
+        )
+    }
+}
+
+fn leaf_hash(bundle: &ProofBundle) -> [u8; 32] {
+    merkle::leaf_hash(MERKLE_TAG, &[&bundle.digest()])
+}
+
+impl Serialize for ProofBundle {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for ProofBundle {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        util::deserialize_bytes(deserializer, "ProofBundle", ProofBundle::from_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use group::ff::Field;
+    use group::prime::PrimeCurveAffine;
+
+    #[test]
+    fn bundle_verifies_against_its_commitments() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
+        let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+
+        let mut transcript = Transcript::new(b"BundleTest");
+        let bundle =
+            ProofBundle::prove(&bp_gens, &pc_gens, &mut transcript, &[7, 9], &blindings, 32)
+                .unwrap();
+        assert_eq!(bundle.commitments().len(), 2);
+        assert_eq!(bundle.bitsize(), 32);
+        assert!(bundle
+            .verify(&bp_gens, &pc_gens, &mut Transcript::new(b"BundleTest"))
+            .is_ok());
+
+        let bytes = bundle.to_bytes();
+        assert_eq!(ProofBundle::from_bytes(&bytes), Ok(bundle.clone()));
+        let decoded: ProofBundle =
+            bincode::deserialize(&bincode::serialize(&bundle).unwrap()).unwrap();
+        assert_eq!(decoded, bundle);
+
+        // Trailing bytes, or a bitsize other than the proof's
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert!(ProofBundle::from_bytes(&longer).is_err());
+        let mut other_bitsize = bytes.clone();
+        other_bitsize[0] = 64;
+        assert_eq!(
+            ProofBundle::from_bytes(&other_bitsize),
+            Err(ProofError::ParameterMismatch)
+        );
+
+        // Swapping the commitments breaks the proof
+        let mut swapped = bundle.commitments().to_vec();
+        swapped.swap(0, 1);
+        let swapped = ProofBundle::new(bundle.proof().clone(), swapped, 32).unwrap();
+        assert_eq!(
+            swapped.verify(&bp_gens, &pc_gens, &mut Transcript::new(b"BundleTest")),
+            Err(ProofError::VerificationError)
+        );
+        assert_eq!(
+            ProofBundle::new(
+                bundle.proof().clone(),
+                bundle.commitments()[..1].to_vec(),
+                32
+            ),
+            Err(ProofError::ParameterMismatch)
+        );
+    }
+
+    #[test]
+    fn signed_bundle_verifies_with_issuer_key() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let mut rng = rand::thread_rng();
+
+        let mut transcript = Transcript::new(b"SignedBundleTest");
+        let mut bundle = ProofBundle::prove(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[1 << 40],
+            &[Scalar::random(&mut rng)],
+            64,
+        )
+        .unwrap();
+        let secret_key = Scalar::random(&mut rng);
+        let public_key = (G1Affine::generator() * secret_key).to_affine();
+
+        let verify = |bundle: &ProofBundle, public_key: &G1Affine| {
+            let mut transcript = Transcript::new(b"SignedBundleTest");
+            bundle.verify_signed(public_key, &bp_gens, &pc_gens, &mut transcript)
+        };
+        assert_eq!(
+            verify(&bundle, &public_key),
+            Err(ProofError::VerificationError)
+        );
+
+        bundle.sign(&secret_key);
+        assert!(bundle.signature().is_some());
+        assert!(verify(&bundle, &public_key).is_ok());
+        let decoded = ProofBundle::from_bytes(&bundle.to_bytes()).unwrap();
+        assert_eq!(decoded, bundle);
+        assert!(verify(&decoded, &public_key).is_ok());
+
+        let other_key = (G1Affine::generator() * Scalar::random(&mut rng)).to_affine();
+        assert_eq!(
+            verify(&bundle, &other_key),
+            Err(ProofError::VerificationError)
+        );
+
+        // The signature does not carry over to other commitments
+        let other = ProofBundle::prove(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"SignedBundleTest"),
+            &[1 << 40],
+            &[Scalar::random(&mut rng)],
+            64,
+        )
+        .unwrap();
+        let mut forged = other.clone();
+        forged.signature = bundle.signature;
+        assert!(other
+            .verify(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"SignedBundleTest")
+            )
+            .is_ok());
+        assert_eq!(
+            verify(&forged, &public_key),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn merkle_inclusion() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let bundles: Vec<ProofBundle> = (0..5u64)
+            .map(|v| {
+                ProofBundle::prove(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"MerkleTest"),
+                    &[v],
+                    &[Scalar::from(v + 1)],
+                    8,
+                )
+                .unwrap()
+            })
+            .collect();
+        let root = ProofBundle::merkle_root(&bundles);
+
+        for (index, bundle) in bundles.iter().enumerate() {
+            let inclusion = ProofBundle::inclusion_proof(&bundles, index).unwrap();
+            assert!(bundle
+                .verify_included(
+                    &root,
+                    &inclusion,
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"MerkleTest")
+                )
+                .is_ok());
+        }
+        assert!(ProofBundle::inclusion_proof(&bundles, 5).is_none());
+
+        // A path for another bundle, or another root, does not verify
+        let inclusion = ProofBundle::inclusion_proof(&bundles, 1).unwrap();
+        assert_eq!(
+            inclusion.verify(&root, &bundles[2]),
+            Err(ProofError::VerificationError)
+        );
+        assert_eq!(
+            inclusion.verify(&ProofBundle::merkle_root(&bundles[..4]), &bundles[1]),
+            Err(ProofError::VerificationError)
+        );
+        let mut truncated = inclusion.clone();
+        truncated.siblings.pop();
+        assert_eq!(
+            truncated.verify(&root, &bundles[1]),
+            Err(ProofError::VerificationError)
+        );
+    }
+}
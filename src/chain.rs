@@ -0,0 +1,161 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+//! All-or-nothing binding of a sequence of independent proofs.
+
+use merlin::Transcript;
+use subtle::ConstantTimeEq;
+
+use crate::errors::ProofError;
+
+/// Binds a sequence of proofs of any type, e.g. the range proofs and
+/// opening proofs of the outputs of a transaction, so that they only
+/// verify as a complete set, in the order they were created.
+///
+/// Each proof is created or verified with a transcript passed by
+/// [`ProofChain::bind`], which is bound to the position of the proof
+/// and to a digest of the transcripts of all previous proofs.  After a
+/// proof, a digest of its transcript is chained into the next one, so
+/// a proof taken out of its set, or moved to another position, does
+/// not verify.
+///
+/// Dropping proofs from the end of the set does not affect the ones
+/// before, so the prover also publishes the [`ChainTag`] returned by
+/// [`ProofChain::finish`], which commits to the number of proofs and
+/// to the digest of the last one.  The verifier accepts the set only
+/// if all proofs verify and [`ProofChain::verify`] accepts the tag.
+pub struct ProofChain {
+    transcript: Transcript,
+    links: u64,
+}
+
+/// The tag committing to a complete [`ProofChain`].
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ChainTag([u8; 32]);
+
+impl ProofChain {
+    /// Starts a chain with the given transcript `label`.
+    pub fn new(label: &'static [u8]) -> ProofChain {
+        let mut transcript = Transcript::new(label);
+        transcript.append_message(b"dom-sep", b"proof-chain v1");
+        ProofChain {
+            transcript,
+            links: 0,
+        }
+    }
+
+    /// Returns the number of proofs bound so far.
+    pub fn len(&self) -> usize {
+        self.links as usize
+    }
+
+    /// Returns true if no proof was bound yet.
+    pub fn is_empty(&self) -> bool {
+        self.links == 0
+    }
+
+    /// Creates or verifies the next proof of the chain with `f`, which
+    /// is passed the transcript of the proof, and returns its result.
+    ///
+    /// The digest of the transcript is chained regardless of the
+    /// result, so the chain can only be completed for a set of proofs
+    /// whose verifications all succeed.
+    pub fn bind<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut Transcript) -> R,
+    {
+        let mut transcript = self.transcript.clone();
+        transcript.append_u64(b"chain-link", self.links);
+        let result = f(&mut transcript);
+
+        let mut digest = [0u8; 32];
+        transcript.challenge_bytes(b"chain-digest", &mut digest);
+        self.transcript.append_message(b"chain-digest", &digest);
+        self.links += 1;
+        result
+    }
+
+    /// Ends the chain, and returns the tag committing to it.
+    pub fn finish(mut self) -> ChainTag {
+        self.transcript.append_u64(b"chain-links", self.links);
+        let mut tag = [0u8; 32];
+        self.transcript.challenge_bytes(b"chain-tag", &mut tag);
+        ChainTag(tag)
+    }
+
+    /// Ends the chain, and checks it against the `tag` of the prover.
+    ///
+    /// Returns [`ProofError::ChainMismatch`] if the chain is missing
+    /// proofs, or has other proofs than the prover's.
+    pub fn verify(self, tag: &ChainTag) -> Result<(), ProofError> {
+        if bool::from(self.finish().0.ct_eq(&tag.0)) {
+            Ok(())
+        } else {
+            Err(ProofError::ChainMismatch)
+        }
+    }
+}
+
+impl ChainTag {
+    /// Returns the tag as a 32-byte array.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    /// Reads a tag from a 32-byte array.
+    pub fn from_bytes(bytes: [u8; 32]) -> ChainTag {
+        ChainTag(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::Scalar;
+
+    use crate::generators::{BulletproofGens, PedersenGens};
+    use crate::opening::OpeningProof;
+    use crate::range_proof::RangeProof;
+
+    #[test]
+    fn chain_binds_complete_set() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::from(5u64);
+
+        let mut chain = ProofChain::new(b"ChainTest");
+        let (range, commitments) = chain
+            .bind(|t| RangeProof::prove_multiple(&bp_gens, &pc_gens, t, &[9], &[blinding], 32))
+            .unwrap();
+        let (opening, commitment) = chain
+            .bind(|t| OpeningProof::create(&pc_gens, t, b"memo", blinding))
+            .unwrap();
+        let tag = chain.finish();
+        assert_eq!(ChainTag::from_bytes(tag.to_bytes()), tag);
+
+        let verify_range =
+            |t: &mut Transcript| range.verify_multiple(&bp_gens, &pc_gens, t, &commitments, 32);
+        let verify_opening = |t: &mut Transcript| opening.verify(&pc_gens, t, &commitment, b"memo");
+
+        let mut chain = ProofChain::new(b"ChainTest");
+        assert_eq!(chain.bind(verify_range), Ok(()));
+        assert_eq!(chain.bind(verify_opening), Ok(()));
+        assert_eq!(chain.verify(&tag), Ok(()));
+
+        // The first proof verifies, but the set is incomplete
+        let mut chain = ProofChain::new(b"ChainTest");
+        assert_eq!(chain.bind(verify_range), Ok(()));
+        assert_eq!(chain.verify(&tag), Err(ProofError::ChainMismatch));
+
+        // The proofs do not verify out of order
+        let mut chain = ProofChain::new(b"ChainTest");
+        assert_eq!(
+            chain.bind(verify_opening),
+            Err(ProofError::VerificationError)
+        );
+    }
+}
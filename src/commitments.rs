@@ -0,0 +1,578 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Pedersen commitments to values, and their blinding factors.
+
+use core::borrow::Borrow;
+use core::fmt;
+use core::iter::Sum;
+use core::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use clear_on_drop::clear::Clear;
+use digest::Digest;
+use group::ff::Field;
+use group::{Curve, Group};
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha3::Sha3_256;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use crate::errors::ProofError;
+use crate::generators::PedersenGens;
+use crate::util::{self, read32, read48};
+
+/// Domain separation tag for deriving blinding factors.  It names the
+/// proof receipts, which derived their blinding factors first.
+const BLINDING_DST: &[u8] = b"BLS_BULLETPROOFS_RECEIPT_BLINDING_SHA3-256_CHACHA20_";
+
+/// A Pedersen commitment \\(V = v \cdot B + \tilde{v} \cdot \tilde{B}\\)
+/// to a value \\(v\\) with blinding factor \\(\tilde{v}\\).
+///
+/// Commitments are additively homomorphic: the sum of commitments is a
+/// commitment to the sum of the values, with the sum of the blinding
+/// factors.
+///
+/// A `Commitment` can be passed anywhere a value commitment is expected,
+/// e.g. to [`RangeProof::verify_multiple`](crate::RangeProof::verify_multiple).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Commitment(pub G1Projective);
+
+impl Commitment {
+    /// Commits to `value` with the given `blinding` factor.
+    pub fn new(pc_gens: &PedersenGens, value: Scalar, blinding: Scalar) -> Self {
+        Commitment(pc_gens.commit(value, blinding))
+    }
+
+    /// Returns the commitment to zero with zero blinding, i.e. the identity.
+    pub fn identity() -> Self {
+        Commitment(G1Projective::identity())
+    }
+
+    /// Returns the underlying point.
+    pub fn as_point(&self) -> &G1Projective {
+        &self.0
+    }
+
+    /// Serializes the commitment into its 48-byte compressed encoding.
+    pub fn to_bytes(&self) -> [u8; 48] {
+        self.0.to_compressed()
+    }
+
+    /// Deserializes the commitment from a 48-byte compressed encoding.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a `Commitment`.
+    pub fn from_bytes(slice: &[u8]) -> Result<Commitment, ProofError> {
+        if slice.len() != 48 {
+            return Err(ProofError::FormatError);
+        }
+        let point: G1Affine = Option::from(G1Affine::from_compressed(&read48(slice)))
+            .ok_or(ProofError::FormatError)?;
+        Ok(Commitment(point.into()))
+    }
+}
+
+impl Default for Commitment {
+    fn default() -> Self {
+        Commitment::identity()
+    }
+}
+
+impl From<G1Projective> for Commitment {
+    fn from(point: G1Projective) -> Self {
+        Commitment(point)
+    }
+}
+
+impl From<G1Affine> for Commitment {
+    fn from(point: G1Affine) -> Self {
+        Commitment(point.into())
+    }
+}
+
+impl From<Commitment> for G1Projective {
+    fn from(commitment: Commitment) -> Self {
+        commitment.0
+    }
+}
+
+impl From<Commitment> for G1Affine {
+    fn from(commitment: Commitment) -> Self {
+        commitment.0.to_affine()
+    }
+}
+
+impl<'a> Add<&'a Commitment> for &Commitment {
+    type Output = Commitment;
+
+    fn add(self, other: &'a Commitment) -> Commitment {
+        Commitment(self.0 + other.0)
+    }
+}
+
+impl Add for Commitment {
+    type Output = Commitment;
+
+    fn add(self, other: Commitment) -> Commitment {
+        Commitment(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Commitment {
+    fn add_assign(&mut self, other: Commitment) {
+        self.0 += other.0;
+    }
+}
+
+impl<'a> Sub<&'a Commitment> for &Commitment {
+    type Output = Commitment;
+
+    fn sub(self, other: &'a Commitment) -> Commitment {
+        Commitment(self.0 - other.0)
+    }
+}
+
+impl Sub for Commitment {
+    type Output = Commitment;
+
+    fn sub(self, other: Commitment) -> Commitment {
+        Commitment(self.0 - other.0)
+    }
+}
+
+impl SubAssign for Commitment {
+    fn sub_assign(&mut self, other: Commitment) {
+        self.0 -= other.0;
+    }
+}
+
+impl Neg for Commitment {
+    type Output = Commitment;
+
+    fn neg(self) -> Commitment {
+        Commitment(-self.0)
+    }
+}
+
+impl Neg for &Commitment {
+    type Output = Commitment;
+
+    fn neg(self) -> Commitment {
+        Commitment(-self.0)
+    }
+}
+
+impl Sum for Commitment {
+    fn sum<I: Iterator<Item = Commitment>>(iter: I) -> Self {
+        iter.fold(Commitment::identity(), |acc, c| acc + c)
+    }
+}
+
+impl<'a> Sum<&'a Commitment> for Commitment {
+    fn sum<I: Iterator<Item = &'a Commitment>>(iter: I) -> Self {
+        iter.fold(Commitment::identity(), |acc, c| &acc + c)
+    }
+}
+
+impl Serialize for Commitment {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for Commitment {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        util::deserialize_bytes(deserializer, "Commitment", Commitment::from_bytes)
+    }
+}
+
+/// The blinding factor \\(\tilde{v}\\) of a [`Commitment`].
+///
+/// Blinding factors add and subtract like the commitments they blind,
+/// so the blinding factor of a sum of commitments is the sum of their
+/// blinding factors.  The underlying scalar is cleared when the
+/// `BlindingFactor` is dropped, and is not shown by its `Debug` impl,
+/// so that it does not end up in logs.
+///
+/// [`PedersenGens::commit`] and the `prove_single` and `prove_multiple`
+/// functions of [`RangeProof`](crate::RangeProof) take blinding factors
+/// as well as bare scalars.
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct BlindingFactor(Scalar);
+
+impl BlindingFactor {
+    /// Creates a uniformly random blinding factor.
+    pub fn random<T: RngCore + CryptoRng>(rng: &mut T) -> Self {
+        BlindingFactor(Scalar::random(rng))
+    }
+
+    /// Derives the blinding factor of the `j`-th value under `path`
+    /// from the secret `blinding_key`.
+    ///
+    /// The derivation is deterministic, so a wallet can recompute the
+    /// blinding factors of its commitments from its key, e.g. to
+    /// re-create a proof, instead of storing them.
+    pub fn derive(blinding_key: &[u8; 32], path: &[u8], j: usize) -> Self {
+        let mut sha3 = Sha3_256::new();
+        sha3.update((BLINDING_DST.len() as u64).to_le_bytes());
+        sha3.update(BLINDING_DST);
+        sha3.update(blinding_key);
+        sha3.update((path.len() as u64).to_le_bytes());
+        sha3.update(path);
+        sha3.update((j as u64).to_le_bytes());
+
+        let mut rng = ChaCha20Rng::from_seed(sha3.finalize().into());
+        BlindingFactor(Scalar::random(&mut rng))
+    }
+
+    /// Returns the underlying scalar.
+    pub fn as_scalar(&self) -> &Scalar {
+        &self.0
+    }
+
+    /// Returns `true` if the blinding factor is zero, in which case a
+    /// commitment using it does not hide its value.
+    pub fn is_zero(&self) -> bool {
+        bool::from(self.0.is_zero())
+    }
+
+    /// Commits to `value` with this blinding factor.
+    pub fn commit(&self, pc_gens: &PedersenGens, value: Scalar) -> Commitment {
+        Commitment::new(pc_gens, value, self.0)
+    }
+
+    /// Returns the sum of `blindings`, or `None` if the sum is zero.
+    pub fn checked_sum<'a, I>(blindings: I) -> Option<BlindingFactor>
+    where
+        I: IntoIterator<Item = &'a BlindingFactor>,
+    {
+        let sum: BlindingFactor = blindings.into_iter().sum();
+        if sum.is_zero() {
+            None
+        } else {
+            Some(sum)
+        }
+    }
+
+    /// Returns the negation of this blinding factor, or `None` if it is zero.
+    pub fn checked_neg(&self) -> Option<BlindingFactor> {
+        if self.is_zero() {
+            None
+        } else {
+            Some(-self)
+        }
+    }
+
+    /// Returns the blinding factor for a final output that balances
+    /// the `inputs` against the other `outputs`, i.e. the sum of the
+    /// input blinding factors minus the sum of the output blinding
+    /// factors.
+    ///
+    /// With that blinding factor, the output commitments sum to the
+    /// input commitments whenever the values balance.  Returns `None`
+    /// if the result is zero, since it would not hide the final output.
+    pub fn balancing(
+        inputs: &[BlindingFactor],
+        outputs: &[BlindingFactor],
+    ) -> Option<BlindingFactor> {
+        let balance =
+            inputs.iter().sum::<BlindingFactor>() - outputs.iter().sum::<BlindingFactor>();
+        if balance.is_zero() {
+            None
+        } else {
+            Some(balance)
+        }
+    }
+
+    /// Serializes the blinding factor into its 32-byte encoding.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes_le()
+    }
+
+    /// Deserializes the blinding factor from a 32-byte encoding.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a `BlindingFactor`.
+    pub fn from_bytes(slice: &[u8]) -> Result<BlindingFactor, ProofError> {
+        if slice.len() != 32 {
+            return Err(ProofError::FormatError);
+        }
+        let scalar =
+            Option::from(Scalar::from_bytes_le(&read32(slice))).ok_or(ProofError::FormatError)?;
+        Ok(BlindingFactor(scalar))
+    }
+}
+
+impl From<Scalar> for BlindingFactor {
+    fn from(scalar: Scalar) -> Self {
+        BlindingFactor(scalar)
+    }
+}
+
+impl From<&BlindingFactor> for Scalar {
+    fn from(blinding: &BlindingFactor) -> Self {
+        blinding.0
+    }
+}
+
+impl Borrow<Scalar> for BlindingFactor {
+    fn borrow(&self) -> &Scalar {
+        &self.0
+    }
+}
+
+impl fmt::Debug for BlindingFactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BlindingFactor(..)")
+    }
+}
+
+impl Drop for BlindingFactor {
+    fn drop(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Overwrites the blinding factor with zero, as dropping it does.
+#[cfg(feature = "zeroize")]
+impl Zeroize for BlindingFactor {
+    fn zeroize(&mut self) {
+        self.0.clear();
+    }
+}
+
+impl<'a> Add<&'a BlindingFactor> for &BlindingFactor {
+    type Output = BlindingFactor;
+
+    fn add(self, other: &'a BlindingFactor) -> BlindingFactor {
+        BlindingFactor(self.0 + other.0)
+    }
+}
+
+impl Add for BlindingFactor {
+    type Output = BlindingFactor;
+
+    fn add(self, other: BlindingFactor) -> BlindingFactor {
+        BlindingFactor(self.0 + other.0)
+    }
+}
+
+impl AddAssign<&BlindingFactor> for BlindingFactor {
+    fn add_assign(&mut self, other: &BlindingFactor) {
+        self.0 += other.0;
+    }
+}
+
+impl<'a> Sub<&'a BlindingFactor> for &BlindingFactor {
+    type Output = BlindingFactor;
+
+    fn sub(self, other: &'a BlindingFactor) -> BlindingFactor {
+        BlindingFactor(self.0 - other.0)
+    }
+}
+
+impl Sub for BlindingFactor {
+    type Output = BlindingFactor;
+
+    fn sub(self, other: BlindingFactor) -> BlindingFactor {
+        BlindingFactor(self.0 - other.0)
+    }
+}
+
+impl SubAssign<&BlindingFactor> for BlindingFactor {
+    fn sub_assign(&mut self, other: &BlindingFactor) {
+        self.0 -= other.0;
+    }
+}
+
+impl Neg for &BlindingFactor {
+    type Output = BlindingFactor;
+
+    fn neg(self) -> BlindingFactor {
+        BlindingFactor(-self.0)
+    }
+}
+
+impl Neg for BlindingFactor {
+    type Output = BlindingFactor;
+
+    fn neg(self) -> BlindingFactor {
+        BlindingFactor(-self.0)
+    }
+}
+
+impl Sum for BlindingFactor {
+    fn sum<I: Iterator<Item = BlindingFactor>>(iter: I) -> Self {
+        iter.fold(BlindingFactor::default(), |acc, b| acc + b)
+    }
+}
+
+impl<'a> Sum<&'a BlindingFactor> for BlindingFactor {
+    fn sum<I: Iterator<Item = &'a BlindingFactor>>(iter: I) -> Self {
+        iter.fold(BlindingFactor::default(), |mut acc, b| {
+            acc += b;
+            acc
+        })
+    }
+}
+
+impl Serialize for BlindingFactor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for BlindingFactor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        util::deserialize_bytes(deserializer, "BlindingFactor", BlindingFactor::from_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commitments_are_homomorphic() {
+        let pc_gens = PedersenGens::default();
+        let a = Commitment::new(&pc_gens, Scalar::from(3u64), Scalar::from(10u64));
+        let b = Commitment::new(&pc_gens, Scalar::from(4u64), Scalar::from(20u64));
+
+        assert_eq!(
+            a + b,
+            Commitment::new(&pc_gens, Scalar::from(7u64), Scalar::from(30u64))
+        );
+        assert_eq!((a + b) - b, a);
+        assert_eq!(a + -a, Commitment::identity());
+        assert_eq!([a, b].iter().sum::<Commitment>(), a + b);
+
+        let decoded = Commitment::from_bytes(&a.to_bytes()).unwrap();
+        assert_eq!(decoded, a);
+        let decoded: Commitment = bincode::deserialize(&bincode::serialize(&b).unwrap()).unwrap();
+        assert_eq!(decoded, b);
+        assert_eq!(
+            Commitment::from_bytes(&a.to_bytes()[1..]),
+            Err(ProofError::FormatError)
+        );
+    }
+
+    #[test]
+    fn range_proof_verifies_against_commitments() {
+        use crate::generators::BulletproofGens;
+        use crate::range_proof::RangeProof;
+        use merlin::Transcript;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 2);
+        let blindings = [Scalar::from(1u64), Scalar::from(2u64)];
+
+        let mut transcript = Transcript::new(b"CommitmentTest");
+        let (proof, _) =
+            RangeProof::prove_multiple(&bp_gens, &pc_gens, &mut transcript, &[5, 6], &blindings, 8)
+                .unwrap();
+
+        let commitments = [
+            Commitment::new(&pc_gens, Scalar::from(5u64), blindings[0]),
+            Commitment::new(&pc_gens, Scalar::from(6u64), blindings[1]),
+        ];
+        let mut transcript = Transcript::new(b"CommitmentTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 8)
+            .is_ok());
+    }
+
+    #[test]
+    fn blinding_factors_balance() {
+        let pc_gens = PedersenGens::default();
+        let mut rng = rand::thread_rng();
+
+        let inputs = [
+            BlindingFactor::random(&mut rng),
+            BlindingFactor::random(&mut rng),
+        ];
+        let first = BlindingFactor::random(&mut rng);
+        let last = BlindingFactor::balancing(&inputs, core::slice::from_ref(&first)).unwrap();
+
+        let input_commitments: Commitment = [
+            inputs[0].commit(&pc_gens, Scalar::from(10u64)),
+            inputs[1].commit(&pc_gens, Scalar::from(5u64)),
+        ]
+        .iter()
+        .sum();
+        let output_commitments =
+            first.commit(&pc_gens, Scalar::from(12u64)) + last.commit(&pc_gens, Scalar::from(3u64));
+        assert_eq!(input_commitments, output_commitments);
+
+        assert!(BlindingFactor::balancing(&inputs, &inputs).is_none());
+        assert!(BlindingFactor::checked_sum(&[first.clone(), -&first]).is_none());
+        assert!(BlindingFactor::default().checked_neg().is_none());
+
+        let decoded: BlindingFactor =
+            bincode::deserialize(&bincode::serialize(&first).unwrap()).unwrap();
+        assert_eq!(decoded, first);
+    }
+
+    #[test]
+    fn blinding_factors_are_accepted_by_the_prover() {
+        use crate::generators::BulletproofGens;
+        use crate::range_proof::RangeProof;
+        use merlin::Transcript;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 2);
+        let key = [7u8; 32];
+        let blinding = BlindingFactor::derive(&key, b"m/0", 0);
+        assert_eq!(blinding, BlindingFactor::derive(&key, b"m/0", 0));
+        assert_ne!(blinding, BlindingFactor::derive(&key, b"m/0", 1));
+        assert_eq!(format!("{:?}", blinding), "BlindingFactor(..)");
+
+        let (proof, V) = RangeProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"BlindingTest"),
+            5,
+            &blinding,
+            8,
+        )
+        .unwrap();
+        assert_eq!(V, pc_gens.commit(Scalar::from(5u64), &blinding).to_affine());
+        assert!(proof
+            .verify_single(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"BlindingTest"),
+                &V,
+                8
+            )
+            .is_ok());
+
+        let blindings = [blinding.clone(), BlindingFactor::derive(&key, b"m/0", 1)];
+        assert!(RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"BlindingTest"),
+            &[5, 6],
+            &blindings,
+            8,
+        )
+        .is_ok());
+    }
+}
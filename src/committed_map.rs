@@ -0,0 +1,349 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! A map of keys to range-proofed commitments, e.g. the balance sheet
+//! of a confidential ledger.
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+
+use blstrs::G1Affine;
+#[cfg(feature = "prover")]
+use blstrs::Scalar;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::commitments::Commitment;
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::merkle;
+use crate::range_proof::RangeProof;
+
+/// Domain tag of the Merkle tree of the entries.
+const MERKLE_TAG: &[u8] = b"CommittedMap";
+
+/// A map of keys to Pedersen commitments, each with a range proof that
+/// its value is an `n`-bit value.
+///
+/// The owner of the values inserts or updates an entry with
+/// [`CommittedMap::insert`], which returns a [`MapUpdate`] with the new
+/// commitment, its range proof, and the delta to the previous
+/// commitment.  Replicas of the map, e.g. those of auditors or
+/// validators, apply the update with [`CommittedMap::apply`], which
+/// verifies it.  The delta lets observers who only track the total of
+/// the map follow the update without looking up the entry.
+///
+/// Each range proof is created on a copy of the transcript of the map,
+/// bound to its key, so it cannot be moved to another key.
+/// [`CommittedMap::root`] returns the root of a Merkle tree over the
+/// entries in the order of their keys, for anchoring the map externally.
+#[derive(Clone, Debug)]
+pub struct CommittedMap<K> {
+    entries: BTreeMap<K, MapEntry>,
+    n: usize,
+}
+
+/// An entry of a [`CommittedMap`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MapEntry {
+    /// The commitment to the value
+    pub commitment: G1Affine,
+    /// The range proof on the value
+    pub proof: RangeProof,
+}
+
+/// An insert or update of an entry of a [`CommittedMap`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MapUpdate<K> {
+    /// The key of the entry
+    pub key: K,
+    /// The new entry
+    pub entry: MapEntry,
+    /// The new commitment minus the previous one, or the new commitment
+    /// if the key was not in the map
+    pub delta: Commitment,
+}
+
+impl<K: Ord + Clone + AsRef<[u8]>> CommittedMap<K> {
+    /// Creates an empty map of `n`-bit values.
+    pub fn new(n: usize) -> Self {
+        CommittedMap {
+            entries: BTreeMap::new(),
+            n,
+        }
+    }
+
+    /// Returns the bitsize of the values.
+    pub fn bitsize(&self) -> usize {
+        self.n
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entry of `key`, if any.
+    pub fn get(&self, key: &K) -> Option<&MapEntry> {
+        self.entries.get(key)
+    }
+
+    /// Returns an iterator over the entries, in the order of their keys.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &MapEntry)> {
+        self.entries.iter()
+    }
+
+    /// Returns the sum of all commitments, i.e. a commitment to the
+    /// total of the values.
+    pub fn total(&self) -> Commitment {
+        self.entries
+            .values()
+            .map(|entry| Commitment::from(entry.commitment))
+            .sum()
+    }
+
+    /// Commits to `value` with `blinding` under `key`, replacing any
+    /// previous entry, and proves that it is an `n`-bit value.
+    ///
+    /// The range proof is created on a copy of `transcript`.  Returns
+    /// the update for the replicas of the map.
+    #[cfg(feature = "prover")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn insert_with_rng<T: RngCore + CryptoRng>(
+        &mut self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        key: K,
+        value: u64,
+        blinding: &Scalar,
+        rng: &mut T,
+    ) -> Result<MapUpdate<K>, ProofError> {
+        let mut transcript = entry_transcript(transcript, key.as_ref());
+        let (proof, commitment) = RangeProof::prove_single_with_rng(
+            bp_gens,
+            pc_gens,
+            &mut transcript,
+            value,
+            blinding,
+            self.n,
+            rng,
+        )?;
+
+        let entry = MapEntry { commitment, proof };
+        let delta = self.delta(&key, &entry);
+        self.entries.insert(key.clone(), entry.clone());
+        Ok(MapUpdate { key, entry, delta })
+    }
+
+    /// Commits to `value` with `blinding` under `key`, replacing any
+    /// previous entry, and proves that it is an `n`-bit value.
+    /// This is a convenience wrapper around [`CommittedMap::insert_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(all(feature = "thread_rng", feature = "prover"))]
+    pub fn insert(
+        &mut self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        key: K,
+        value: u64,
+        blinding: &Scalar,
+    ) -> Result<MapUpdate<K>, ProofError> {
+        self.insert_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            key,
+            value,
+            blinding,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies an `update` created by [`CommittedMap::insert`] on a map
+    /// with the same entries, and applies it.
+    ///
+    /// Returns an error, leaving the map unchanged, if the range proof
+    /// does not verify against a copy of `transcript`, or if the delta
+    /// does not match the previous entry.
+    pub fn apply_with_rng<T: RngCore + CryptoRng>(
+        &mut self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        update: MapUpdate<K>,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let MapUpdate { key, entry, delta } = update;
+        if self.delta(&key, &entry) != delta {
+            return Err(ProofError::VerificationError);
+        }
+
+        let mut transcript = entry_transcript(transcript, key.as_ref());
+        entry.proof.verify_single_with_rng(
+            bp_gens,
+            pc_gens,
+            &mut transcript,
+            &entry.commitment,
+            self.n,
+            rng,
+        )?;
+
+        self.entries.insert(key, entry);
+        Ok(())
+    }
+
+    /// Verifies an `update` created by [`CommittedMap::insert`] on a map
+    /// with the same entries, and applies it.
+    /// This is a convenience wrapper around [`CommittedMap::apply_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn apply(
+        &mut self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        update: MapUpdate<K>,
+    ) -> Result<(), ProofError> {
+        self.apply_with_rng(bp_gens, pc_gens, transcript, update, &mut thread_rng())
+    }
+
+    /// Returns the root of the Merkle tree over the keys and commitments
+    /// of the entries, in the order of their keys.
+    ///
+    /// The last node of a level with an odd number of nodes is carried
+    /// to the next level unchanged.
+    pub fn root(&self) -> [u8; 32] {
+        let leaves = self
+            .entries
+            .iter()
+            .map(|(key, entry)| leaf_hash(key.as_ref(), &entry.commitment))
+            .collect();
+        merkle::root(MERKLE_TAG, leaves)
+    }
+
+    /// Returns the commitment of `entry` minus the commitment of the
+    /// current entry of `key`.
+    fn delta(&self, key: &K, entry: &MapEntry) -> Commitment {
+        let previous = self
+            .entries
+            .get(key)
+            .map(|previous| Commitment::from(previous.commitment))
+            .unwrap_or_default();
+        Commitment::from(entry.commitment) - previous
+    }
+}
+
+/// Returns the transcript for the range proof of the entry of `key`.
+fn entry_transcript(transcript: &Transcript, key: &[u8]) -> Transcript {
+    let mut transcript = transcript.clone();
+    transcript.append_message(b"dom-sep", b"committed-map v1");
+    transcript.append_message(b"key", key);
+    transcript
+}
+
+fn leaf_hash(key: &[u8], commitment: &G1Affine) -> [u8; 32] {
+    merkle::leaf_hash(
+        MERKLE_TAG,
+        &[
+            &(key.len() as u64).to_le_bytes(),
+            key,
+            &commitment.to_compressed(),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_updates_replicate() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let transcript = Transcript::new(b"CommittedMapTest");
+
+        let mut owner = CommittedMap::new(32);
+        let mut replica = CommittedMap::new(32);
+        let empty_root = owner.root();
+
+        let alice = owner
+            .insert(
+                &bp_gens,
+                &pc_gens,
+                &transcript,
+                b"alice".to_vec(),
+                100,
+                &Scalar::from(1u64),
+            )
+            .unwrap();
+        let bob = owner
+            .insert(
+                &bp_gens,
+                &pc_gens,
+                &transcript,
+                b"bob".to_vec(),
+                50,
+                &Scalar::from(2u64),
+            )
+            .unwrap();
+        let alice_update = owner
+            .insert(
+                &bp_gens,
+                &pc_gens,
+                &transcript,
+                b"alice".to_vec(),
+                70,
+                &Scalar::from(3u64),
+            )
+            .unwrap();
+        assert_eq!(owner.len(), 2);
+        assert_eq!(
+            alice_update.delta,
+            Commitment::new(&pc_gens, -Scalar::from(30u64), Scalar::from(2u64))
+        );
+        assert_eq!(
+            owner.total(),
+            Commitment::new(&pc_gens, Scalar::from(120u64), Scalar::from(5u64))
+        );
+
+        // An update out of order does not match the previous entry
+        assert_eq!(
+            replica.apply(&bp_gens, &pc_gens, &transcript, alice_update.clone()),
+            Err(ProofError::VerificationError)
+        );
+
+        // A proof moved to another key does not verify
+        let mut moved = bob.clone();
+        moved.key = b"carol".to_vec();
+        assert_eq!(
+            replica.apply(&bp_gens, &pc_gens, &transcript, moved),
+            Err(ProofError::VerificationError)
+        );
+        assert!(replica.is_empty());
+
+        for update in [alice, bob, alice_update] {
+            replica
+                .apply(&bp_gens, &pc_gens, &transcript, update)
+                .unwrap();
+        }
+        assert_eq!(replica.root(), owner.root());
+        assert_ne!(owner.root(), empty_root);
+        assert_eq!(replica.total(), owner.total());
+    }
+}
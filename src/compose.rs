@@ -0,0 +1,323 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Traits for composing proofs of different types over a transcript
+//! owned by the caller, e.g. inside the caller's own sigma protocol.
+//!
+//! A [`TranscriptProver`] bundles a statement with its witness, and a
+//! [`TranscriptVerifier`] bundles a statement with its proof, so that
+//! proofs of different types can be handled as trait objects, e.g. a
+//! `Vec<Box<dyn TranscriptProver>>` run in order over one transcript.
+//! [`ProofSession::prove`](crate::ProofSession::prove) and
+//! [`ProofSession::verify`](crate::ProofSession::verify) frame each
+//! proof with its index and [`label`](TranscriptProver::label), so the
+//! prover and verifier use the transcript consistently.
+//!
+//! Constraint system proofs are not covered: their statements are
+//! built by gadgets on the prover's constraint system, rather than
+//! given up front.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use blstrs::{G1Affine, Scalar};
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+
+use crate::commitments::Commitment;
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::one_of_many::OneOfManyProof;
+use crate::opening::OpeningProof;
+use crate::range_proof::RangeProof;
+
+/// An RNG suitable for cryptographic use, as a trait object.
+pub trait CryptoRngCore: RngCore + CryptoRng {}
+
+impl<T: RngCore + CryptoRng> CryptoRngCore for T {}
+
+/// A statement with its witness, which can be proven against an
+/// externally owned transcript.
+pub trait TranscriptProver {
+    /// Returns the label of the proof type.
+    fn label(&self) -> &'static [u8];
+
+    /// Proves the statement, appending to `transcript`, and returns the
+    /// serialized proof.
+    fn prove(
+        &self,
+        transcript: &mut Transcript,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Result<Vec<u8>, ProofError>;
+}
+
+/// A statement with its proof, which can be verified against an
+/// externally owned transcript.
+pub trait TranscriptVerifier {
+    /// Returns the label of the proof type.
+    fn label(&self) -> &'static [u8];
+
+    /// Verifies the proof, appending to `transcript` as the prover did.
+    fn verify(
+        &self,
+        transcript: &mut Transcript,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Result<(), ProofError>;
+}
+
+/// Proves that `values` are `n`-bit values, as in
+/// [`RangeProof::prove_multiple`].
+#[cfg(feature = "prover")]
+pub struct RangeProver<'a> {
+    /// The generators of the proof
+    pub bp_gens: &'a BulletproofGens,
+    /// The generators of the commitments
+    pub pc_gens: &'a PedersenGens,
+    /// The values
+    pub values: &'a [u64],
+    /// The blinding factors of the commitments to the values
+    pub blindings: &'a [Scalar],
+    /// The bitsize of the range
+    pub n: usize,
+}
+
+/// Verifies a [`RangeProof`] on `commitments`, as in
+/// [`RangeProof::verify_multiple`].
+pub struct RangeVerifier<'a> {
+    /// The generators of the proof
+    pub bp_gens: &'a BulletproofGens,
+    /// The generators of the commitments
+    pub pc_gens: &'a PedersenGens,
+    /// The proof
+    pub proof: &'a RangeProof,
+    /// The commitments to the values
+    pub commitments: &'a [G1Affine],
+    /// The bitsize of the range
+    pub n: usize,
+}
+
+#[cfg(feature = "prover")]
+impl<'a> TranscriptProver for RangeProver<'a> {
+    fn label(&self) -> &'static [u8] {
+        b"range"
+    }
+
+    fn prove(
+        &self,
+        transcript: &mut Transcript,
+        mut rng: &mut dyn CryptoRngCore,
+    ) -> Result<Vec<u8>, ProofError> {
+        let (proof, _) = RangeProof::prove_multiple_with_rng(
+            self.bp_gens,
+            self.pc_gens,
+            transcript,
+            self.values,
+            self.blindings,
+            self.n,
+            &mut rng,
+        )?;
+        Ok(proof.to_bytes())
+    }
+}
+
+impl<'a> TranscriptVerifier for RangeVerifier<'a> {
+    fn label(&self) -> &'static [u8] {
+        b"range"
+    }
+
+    fn verify(
+        &self,
+        transcript: &mut Transcript,
+        mut rng: &mut dyn CryptoRngCore,
+    ) -> Result<(), ProofError> {
+        self.proof.verify_multiple_with_rng(
+            self.bp_gens,
+            self.pc_gens,
+            transcript,
+            self.commitments,
+            self.n,
+            &mut rng,
+        )
+    }
+}
+
+/// Proves that the commitment to `msg` with `blinding` opens to `msg`,
+/// as in [`OpeningProof::create`].
+pub struct OpeningProver<'a> {
+    /// The generators of the commitment
+    pub pc_gens: &'a PedersenGens,
+    /// The committed message
+    pub msg: &'a [u8],
+    /// The blinding factor of the commitment
+    pub blinding: Scalar,
+}
+
+/// Verifies an [`OpeningProof`] that `commitment` commits to `msg`, as
+/// in [`OpeningProof::verify`].
+pub struct OpeningVerifier<'a> {
+    /// The generators of the commitment
+    pub pc_gens: &'a PedersenGens,
+    /// The proof
+    pub proof: &'a OpeningProof,
+    /// The commitment
+    pub commitment: &'a Commitment,
+    /// The committed message
+    pub msg: &'a [u8],
+}
+
+impl<'a> TranscriptProver for OpeningProver<'a> {
+    fn label(&self) -> &'static [u8] {
+        b"opening"
+    }
+
+    fn prove(
+        &self,
+        transcript: &mut Transcript,
+        mut rng: &mut dyn CryptoRngCore,
+    ) -> Result<Vec<u8>, ProofError> {
+        let (proof, _) = OpeningProof::create_with_rng(
+            self.pc_gens,
+            transcript,
+            self.msg,
+            self.blinding,
+            &mut rng,
+        )?;
+        Ok(proof.to_bytes().to_vec())
+    }
+}
+
+impl<'a> TranscriptVerifier for OpeningVerifier<'a> {
+    fn label(&self) -> &'static [u8] {
+        b"opening"
+    }
+
+    fn verify(
+        &self,
+        transcript: &mut Transcript,
+        _rng: &mut dyn CryptoRngCore,
+    ) -> Result<(), ProofError> {
+        self.proof
+            .verify(self.pc_gens, transcript, self.commitment, self.msg)
+    }
+}
+
+/// Proves that `commitments[index]` is a commitment to zero with
+/// `blinding`, as in [`OneOfManyProof::create`].
+pub struct OneOfManyProver<'a> {
+    /// The generators of the commitments
+    pub pc_gens: &'a PedersenGens,
+    /// The commitments
+    pub commitments: &'a [G1Affine],
+    /// The index of the commitment to zero
+    pub index: usize,
+    /// The blinding factor of the commitment to zero
+    pub blinding: Scalar,
+}
+
+/// Verifies a [`OneOfManyProof`] that one of `commitments` is a
+/// commitment to zero, as in [`OneOfManyProof::verify`].
+pub struct OneOfManyVerifier<'a> {
+    /// The generators of the commitments
+    pub pc_gens: &'a PedersenGens,
+    /// The proof
+    pub proof: &'a OneOfManyProof,
+    /// The commitments
+    pub commitments: &'a [G1Affine],
+}
+
+impl<'a> TranscriptProver for OneOfManyProver<'a> {
+    fn label(&self) -> &'static [u8] {
+        b"one-of-many"
+    }
+
+    fn prove(
+        &self,
+        transcript: &mut Transcript,
+        mut rng: &mut dyn CryptoRngCore,
+    ) -> Result<Vec<u8>, ProofError> {
+        let proof = OneOfManyProof::create_with_rng(
+            self.pc_gens,
+            transcript,
+            self.commitments,
+            self.index,
+            &self.blinding,
+            &mut rng,
+        )?;
+        Ok(proof.to_bytes())
+    }
+}
+
+impl<'a> TranscriptVerifier for OneOfManyVerifier<'a> {
+    fn label(&self) -> &'static [u8] {
+        b"one-of-many"
+    }
+
+    fn verify(
+        &self,
+        transcript: &mut Transcript,
+        mut rng: &mut dyn CryptoRngCore,
+    ) -> Result<(), ProofError> {
+        self.proof
+            .verify_with_rng(self.pc_gens, transcript, self.commitments, &mut rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use group::Curve;
+
+    use crate::session::ProofSession;
+
+    #[test]
+    fn one_of_many_round_trips_through_session() {
+        let pc_gens = PedersenGens::default();
+        let blinding = Scalar::from(5u64);
+        let commitments: Vec<G1Affine> = (1..=3u64)
+            .map(|v| {
+                pc_gens
+                    .commit(Scalar::from(v), Scalar::from(v + 10))
+                    .to_affine()
+            })
+            .chain(Some((pc_gens.B_blinding * blinding).to_affine()))
+            .collect();
+
+        let mut prover = ProofSession::new(b"ComposeTest");
+        let bytes = prover
+            .prove(&OneOfManyProver {
+                pc_gens: &pc_gens,
+                commitments: &commitments,
+                index: 3,
+                blinding,
+            })
+            .unwrap();
+        let digest = prover.finish();
+
+        let proof = OneOfManyProof::from_bytes(&bytes).unwrap();
+        let verifier = |commitments| OneOfManyVerifier {
+            pc_gens: &pc_gens,
+            proof: &proof,
+            commitments,
+        };
+        let mut session = ProofSession::new(b"ComposeTest");
+        session.verify(&verifier(&commitments)).unwrap();
+        assert_eq!(session.finish(), digest);
+
+        // The proof is bound to the session and to the commitments
+        assert!(ProofSession::new(b"OtherTest")
+            .verify(&verifier(&commitments))
+            .is_err());
+        let mut other = commitments.clone();
+        other[3] = commitments[0];
+        assert!(ProofSession::new(b"ComposeTest")
+            .verify(&verifier(&other))
+            .is_err());
+    }
+}
@@ -45,6 +45,65 @@ pub enum ProofError {
         error("Invalid generators size, too few generators for proof")
     )]
     InvalidGeneratorsLength,
+    /// This error occurs when externally specified Pedersen bases are
+    /// not distinct, non-identity points of the prime-order subgroup.
+    #[cfg_attr(feature = "std", error("Invalid generators supplied."))]
+    InvalidGenerators,
+    /// This error occurs when the inner-product recursion cutoff is
+    /// not a power of two no larger than the length of the vectors.
+    #[cfg_attr(
+        feature = "std",
+        error("Invalid inner-product cutoff, must be a power of 2 no larger than the vectors.")
+    )]
+    InvalidCutoff,
+    /// This error occurs when a proof is verified with a bitsize or
+    /// number of commitments other than those recorded in its header.
+    #[cfg_attr(feature = "std", error("Proof parameters do not match its header."))]
+    ParameterMismatch,
+    /// This error occurs during batch verification if the number of
+    /// supplied weights does not match the number of proofs, or if a
+    /// weight is zero.
+    #[cfg_attr(feature = "std", error("Invalid batch weights supplied."))]
+    InvalidBatchWeights,
+    /// This error occurs when the input values of a transaction do not
+    /// sum to its output values.
+    #[cfg_attr(feature = "std", error("Input and output values do not balance."))]
+    UnbalancedValues,
+    /// This error occurs when a proof is bound to a nonce other than
+    /// the one expected by the verifier.
+    #[cfg_attr(feature = "std", error("Proof is bound to another nonce."))]
+    NonceMismatch,
+    /// This error occurs when a chain of proofs does not match the tag
+    /// of its prover, because proofs are missing or were substituted.
+    #[cfg_attr(feature = "std", error("Proof chain does not match its tag."))]
+    ChainMismatch,
+    /// This error occurs when attempting to prove that a value is at
+    /// least a public offset which exceeds it.
+    #[cfg_attr(feature = "std", error("Value is below the proof offset."))]
+    ValueBelowOffset,
+    /// This error occurs when attempting to prove that a value is in
+    /// a public interval which does not contain it.
+    #[cfg_attr(feature = "std", error("Value is outside the proof interval."))]
+    ValueOutsideInterval,
+    /// This error occurs when a proof re-created from a receipt does
+    /// not match the digest recorded in the receipt.
+    #[cfg_attr(feature = "std", error("Re-created proof does not match its receipt."))]
+    ReceiptMismatch,
+    /// This error occurs when a verification record does not match the
+    /// proofs it is checked against.
+    #[cfg_attr(
+        feature = "std",
+        error("Verification record does not match the proofs.")
+    )]
+    RecordMismatch,
+    /// This error occurs when proving is aborted through its
+    /// cancellation flag.
+    #[cfg_attr(feature = "std", error("Proof creation was cancelled."))]
+    Cancelled,
+    /// This error occurs when an [`Amount`](crate::Amount) cannot be
+    /// represented at the requested scale and bitsize.
+    #[cfg_attr(feature = "std", error("Invalid amount: {0}"))]
+    InvalidAmount(AmountError),
     /// This error results from an internal error during proving.
     ///
     /// The single-party prover is implemented by performing
@@ -61,6 +120,7 @@ impl From<MPCError> for ProofError {
             MPCError::InvalidBitsize => ProofError::InvalidBitsize,
             MPCError::InvalidAggregation => ProofError::InvalidAggregation,
             MPCError::InvalidGeneratorsLength => ProofError::InvalidGeneratorsLength,
+            MPCError::InvalidCutoff => ProofError::InvalidCutoff,
             _ => ProofError::ProvingError(e),
         }
     }
@@ -97,6 +157,10 @@ pub enum MPCError {
         error("Invalid generators size, too few generators for proof")
     )]
     InvalidGeneratorsLength,
+    /// This error occurs when the inner-product recursion cutoff is
+    /// not a power of two no larger than the length of the vectors.
+    #[cfg_attr(feature = "std", error("Invalid inner-product cutoff"))]
+    InvalidCutoff,
     /// This error occurs when the dealer is given the wrong number of
     /// value commitments.
     #[cfg_attr(feature = "std", error("Wrong number of value commitments"))]
@@ -119,6 +183,62 @@ pub enum MPCError {
         /// A vector with the indexes of the parties whose shares were malformed.
         bad_shares: Vec<usize>,
     },
+    /// This error occurs when a sealed audit record cannot be opened,
+    /// because the audit key is wrong or the record was altered.
+    #[cfg_attr(feature = "std", error("Invalid audit record"))]
+    InvalidAuditRecord,
+    /// This error occurs when the encoding of a message is malformed.
+    #[cfg_attr(feature = "std", error("Message data could not be parsed."))]
+    FormatError,
+}
+
+impl From<AmountError> for ProofError {
+    fn from(e: AmountError) -> ProofError {
+        ProofError::InvalidAmount(e)
+    }
+}
+
+/// Represents an error in parsing or scaling an [`Amount`](crate::Amount).
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "std", derive(Error))]
+pub enum AmountError {
+    /// This error occurs when a decimal string is not of the form
+    /// `123` or `123.456`.
+    #[cfg_attr(feature = "std", error("Amount could not be parsed."))]
+    ParseError,
+    /// This error occurs when an amount has more fractional digits
+    /// than its scale allows, so it would have to be rounded.
+    #[cfg_attr(
+        feature = "std",
+        error("Amount has more than {scale} fractional digits.")
+    )]
+    TooPrecise {
+        /// The number of fractional digits of the requested scale.
+        scale: u8,
+    },
+    /// This error occurs when an amount in base units does not fit in
+    /// 64 bits at the requested scale.
+    #[cfg_attr(feature = "std", error("Amount overflows 64 bits at scale {scale}."))]
+    Overflow {
+        /// The number of fractional digits of the requested scale.
+        scale: u8,
+    },
+    /// This error occurs when an amount in base units does not fit in
+    /// the bitsize of the range proof.
+    #[cfg_attr(
+        feature = "std",
+        error("Amount of {units} base units does not fit in {bitsize} bits.")
+    )]
+    ExceedsBitsize {
+        /// The amount in base units.
+        units: u64,
+        /// The bitsize of the range proof.
+        bitsize: usize,
+    },
+    /// This error occurs when a sum of values in base units does not
+    /// fit in 64 bits.
+    #[cfg_attr(feature = "std", error("Sum of values overflows 64 bits."))]
+    SumOverflow,
 }
 
 /// Represents an error during the proving or verifying of a constraint system.
@@ -12,16 +12,23 @@
 
 extern crate alloc;
 
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use blstrs::{G1Projective, Scalar};
+use core::mem::size_of;
 use digest::Digest;
-use group::Group;
+use group::{Curve, Group};
 use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
 use sha3::Sha3_256;
 
+use crate::errors::ProofError;
+
 const PED_GEN_DOMAIN: &[u8; 20] = b"bulletproofs-ped-gen";
 
+/// Domain separation tag for hashing a ceremony seed to the Pedersen base.
+const CEREMONY_DST: &[u8] = b"BLS_BULLETPROOFS_GENERATORS_CEREMONY_BLS12381G1_XMD:SHA-256_SSWU_RO_";
+
 /// Represents a pair of base points for Pedersen commitments.
 ///
 /// The Bulletproofs implementation and API is designed to support
@@ -42,10 +49,33 @@ pub struct PedersenGens {
 }
 
 impl PedersenGens {
+    /// Creates Pedersen generators from externally specified bases.
+    ///
+    /// Returns [`ProofError::InvalidGenerators`] unless both points are
+    /// in the prime-order subgroup, neither is the identity, and they
+    /// are distinct.  This does not check that the discrete logarithm
+    /// relation between the bases is unknown, which depends on how
+    /// they were derived.
+    pub fn from_points_checked(
+        B: G1Projective,
+        B_blinding: G1Projective,
+    ) -> Result<Self, ProofError> {
+        let valid = |P: &G1Projective| {
+            !bool::from(P.is_identity()) && bool::from(P.to_affine().is_torsion_free())
+        };
+        if !valid(&B) || !valid(&B_blinding) || B == B_blinding {
+            return Err(ProofError::InvalidGenerators);
+        }
+        Ok(PedersenGens { B, B_blinding })
+    }
+
     /// Creates a Pedersen commitment using the value scalar and a blinding factor.
-    pub fn commit(&self, value: Scalar, blinding: Scalar) -> G1Projective {
+    ///
+    /// The blinding factor can be a bare `Scalar` or a reference to a
+    /// [`BlindingFactor`](crate::BlindingFactor).
+    pub fn commit(&self, value: Scalar, blinding: impl Into<Scalar>) -> G1Projective {
         // TODO: replace this dot product with blst_p1s_mult_pippenger once it's supported in blstrs
-        self.B * value + self.B_blinding * blinding
+        self.B * value + self.B_blinding * blinding.into()
     }
 }
 
@@ -134,6 +164,12 @@ impl Iterator for GeneratorsChain {
 /// chain, and even forward-compatible to multiparty aggregation of
 /// constraint system proofs, since the generators are namespaced by
 /// their party index.
+///
+/// The precomputed generators are reference-counted, so cloning a
+/// `BulletproofGens` is cheap and clones share one generator table,
+/// e.g. across the request handlers of a server.  Increasing the
+/// capacity of a clone copies the table first, leaving the other
+/// clones unchanged.
 #[derive(Clone)]
 pub struct BulletproofGens {
     /// The maximum number of usable generators for each party.
@@ -141,9 +177,12 @@ pub struct BulletproofGens {
     /// Number of values or parties
     pub party_capacity: usize,
     /// Precomputed \\(\mathbf G\\) generators for each party.
-    G_vec: Vec<Vec<G1Projective>>,
+    G_vec: Arc<Vec<Vec<G1Projective>>>,
     /// Precomputed \\(\mathbf H\\) generators for each party.
-    H_vec: Vec<Vec<G1Projective>>,
+    H_vec: Arc<Vec<Vec<G1Projective>>>,
+    /// Prefix of the labels of the generator chains, empty for the
+    /// default generators.
+    seed: Vec<u8>,
 }
 
 impl BulletproofGens {
@@ -160,11 +199,18 @@ impl BulletproofGens {
     /// * `party_capacity` is the maximum number of parties that can
     ///    produce an aggregated proof.
     pub fn new(gens_capacity: usize, party_capacity: usize) -> Self {
+        BulletproofGens::with_seed(Vec::new(), gens_capacity, party_capacity)
+    }
+
+    /// Create a new `BulletproofGens` object whose generator chains are
+    /// namespaced by `seed`.
+    fn with_seed(seed: Vec<u8>, gens_capacity: usize, party_capacity: usize) -> Self {
         let mut gens = BulletproofGens {
             gens_capacity: 0,
             party_capacity,
-            G_vec: (0..party_capacity).map(|_| Vec::new()).collect(),
-            H_vec: (0..party_capacity).map(|_| Vec::new()).collect(),
+            G_vec: Arc::new((0..party_capacity).map(|_| Vec::new()).collect()),
+            H_vec: Arc::new((0..party_capacity).map(|_| Vec::new()).collect()),
+            seed,
         };
         gens.increase_capacity(gens_capacity);
         gens
@@ -188,18 +234,24 @@ impl BulletproofGens {
             return;
         }
 
+        let mut label = self.seed.clone();
+        label.extend_from_slice(&[b'G', 0, 0, 0, 0]);
+        let tag = label.len() - 5;
+
+        let G_vec = Arc::make_mut(&mut self.G_vec);
+        let H_vec = Arc::make_mut(&mut self.H_vec);
         for i in 0..self.party_capacity {
             let party_index = i as u32;
-            let mut label = [b'G', 0, 0, 0, 0];
-            LittleEndian::write_u32(&mut label[1..5], party_index);
-            self.G_vec[i].extend(
+            label[tag] = b'G';
+            LittleEndian::write_u32(&mut label[tag + 1..], party_index);
+            G_vec[i].extend(
                 &mut GeneratorsChain::new(&label)
                     .skip(self.gens_capacity)
                     .take(new_capacity - self.gens_capacity),
             );
 
-            label[0] = b'H';
-            self.H_vec[i].extend(
+            label[tag] = b'H';
+            H_vec[i].extend(
                 &mut GeneratorsChain::new(&label)
                     .skip(self.gens_capacity)
                     .take(new_capacity - self.gens_capacity),
@@ -208,7 +260,21 @@ impl BulletproofGens {
         self.gens_capacity = new_capacity;
     }
 
+    /// Returns the number of bytes of memory held by the generators,
+    /// including the precomputed tables.
+    ///
+    /// Since clones share their tables, the tables are counted in full
+    /// for every clone.
+    pub fn memory_footprint(&self) -> usize {
+        let table = |vec: &Vec<Vec<G1Projective>>| {
+            vec.capacity() * size_of::<Vec<G1Projective>>()
+                + vec.iter().map(|v| v.capacity()).sum::<usize>() * size_of::<G1Projective>()
+        };
+        size_of::<Self>() + self.seed.capacity() + table(&self.G_vec) + table(&self.H_vec)
+    }
+
     /// Return an iterator over the aggregation of the parties' G generators with given size `n`.
+    #[cfg(feature = "prover")]
     pub(crate) fn G(&self, n: usize, m: usize) -> impl Iterator<Item = &G1Projective> {
         AggregatedGensIter {
             n,
@@ -220,6 +286,7 @@ impl BulletproofGens {
     }
 
     /// Return an iterator over the aggregation of the parties' H generators with given size `n`.
+    #[cfg(feature = "prover")]
     pub(crate) fn H(&self, n: usize, m: usize) -> impl Iterator<Item = &G1Projective> {
         AggregatedGensIter {
             n,
@@ -231,6 +298,7 @@ impl BulletproofGens {
     }
 }
 
+#[cfg(feature = "prover")]
 struct AggregatedGensIter<'a> {
     array: &'a Vec<Vec<G1Projective>>,
     n: usize,
@@ -239,6 +307,7 @@ struct AggregatedGensIter<'a> {
     gen_idx: usize,
 }
 
+#[cfg(feature = "prover")]
 impl<'a> Iterator for AggregatedGensIter<'a> {
     type Item = &'a G1Projective;
 
@@ -263,6 +332,108 @@ impl<'a> Iterator for AggregatedGensIter<'a> {
     }
 }
 
+/// A record of the derivation of generators by [`derive_generators`].
+///
+/// The record contains the public inputs of the derivation and a digest
+/// of the derived generators, so that anyone can re-run the derivation
+/// with [`GeneratorsRecord::verify`] and confirm that the generators
+/// were derived from the published beacon value.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GeneratorsRecord {
+    /// The protocol label
+    pub label: Vec<u8>,
+    /// The public beacon value, e.g. a block hash
+    pub beacon: Vec<u8>,
+    /// The number of generators derived for each party
+    pub gens_capacity: usize,
+    /// The number of parties generators were derived for
+    pub party_capacity: usize,
+    /// SHA3-256 digest of the compressed derived generators
+    pub digest: [u8; 32],
+}
+
+/// Derives generators from a public `beacon` value and a protocol
+/// `label`, e.g. a block hash produced after the label was fixed.
+///
+/// The label and beacon are hashed to a seed.  The Pedersen base
+/// \\(B\\) is the hash of the seed to the curve, and the
+/// [`BulletproofGens`] chains are namespaced by the seed.  As for
+/// [`PedersenGens::default`], \\(\tilde B\\) is the standard
+/// generator, so that no discrete logarithm relation between any of
+/// the derived generators is known to anyone.
+///
+/// Returns the generators with a [`GeneratorsRecord`] that others can
+/// use to check the derivation.
+pub fn derive_generators(
+    label: &[u8],
+    beacon: &[u8],
+    gens_capacity: usize,
+    party_capacity: usize,
+) -> (PedersenGens, BulletproofGens, GeneratorsRecord) {
+    let mut sha3 = Sha3_256::new();
+    sha3.update(b"GeneratorsCeremony");
+    sha3.update((label.len() as u64).to_le_bytes());
+    sha3.update(label);
+    sha3.update((beacon.len() as u64).to_le_bytes());
+    sha3.update(beacon);
+    let seed = sha3.finalize();
+
+    let pc_gens = PedersenGens {
+        B: G1Projective::hash_to_curve(&seed, CEREMONY_DST, &[]),
+        B_blinding: G1Projective::generator(),
+    };
+    let bp_gens = BulletproofGens::with_seed(seed.to_vec(), gens_capacity, party_capacity);
+
+    let record = GeneratorsRecord {
+        label: label.to_vec(),
+        beacon: beacon.to_vec(),
+        gens_capacity,
+        party_capacity,
+        digest: GeneratorsRecord::digest(&pc_gens, &bp_gens),
+    };
+
+    (pc_gens, bp_gens, record)
+}
+
+impl GeneratorsRecord {
+    /// Re-runs the derivation and checks that it produces generators
+    /// with the recorded digest, returning them if it does.
+    pub fn verify(&self) -> Result<(PedersenGens, BulletproofGens), ProofError> {
+        let (pc_gens, bp_gens, record) = derive_generators(
+            &self.label,
+            &self.beacon,
+            self.gens_capacity,
+            self.party_capacity,
+        );
+        if record == *self {
+            Ok((pc_gens, bp_gens))
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Checks that the given generators are those of this record.
+    pub fn check(&self, pc_gens: &PedersenGens, bp_gens: &BulletproofGens) -> bool {
+        bp_gens.gens_capacity == self.gens_capacity
+            && bp_gens.party_capacity == self.party_capacity
+            && GeneratorsRecord::digest(pc_gens, bp_gens) == self.digest
+    }
+
+    /// Hashes the compressed generators.
+    fn digest(pc_gens: &PedersenGens, bp_gens: &BulletproofGens) -> [u8; 32] {
+        let mut sha3 = Sha3_256::new();
+        sha3.update(b"GeneratorsRecord");
+        sha3.update(pc_gens.B.to_compressed());
+        sha3.update(pc_gens.B_blinding.to_compressed());
+        for (G_j, H_j) in bp_gens.G_vec.iter().zip(bp_gens.H_vec.iter()) {
+            for P in G_j.iter().chain(H_j.iter()) {
+                sha3.update(P.to_compressed());
+            }
+        }
+        sha3.finalize().into()
+    }
+}
+
 /// Represents a view of the generators used by a specific party in an
 /// aggregated proof.
 ///
@@ -295,6 +466,71 @@ impl<'a> BulletproofGensShare<'a> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn pedersen_gens_from_checked_points() {
+        use blstrs::G1Affine;
+
+        let gens = PedersenGens::default();
+        let checked = PedersenGens::from_points_checked(gens.B, gens.B_blinding).unwrap();
+        assert_eq!((checked.B, checked.B_blinding), (gens.B, gens.B_blinding));
+
+        let invalid = |B, B_blinding| {
+            assert_eq!(
+                PedersenGens::from_points_checked(B, B_blinding).err(),
+                Some(ProofError::InvalidGenerators)
+            );
+        };
+        invalid(gens.B, gens.B);
+        invalid(G1Projective::identity(), gens.B_blinding);
+        invalid(gens.B, G1Projective::identity());
+
+        // A point on the curve, but outside the prime-order subgroup
+        let outside = (0u8..=255)
+            .find_map(|x| {
+                let mut bytes = [0u8; 48];
+                bytes[0] = 0x80;
+                bytes[47] = x;
+                let point: Option<G1Affine> = G1Affine::from_compressed_unchecked(&bytes).into();
+                point.filter(|P| !bool::from(P.is_torsion_free()))
+            })
+            .unwrap();
+        invalid(outside.into(), gens.B_blinding);
+        invalid(gens.B, outside.into());
+    }
+
+    #[test]
+    fn generators_ceremony() {
+        let (pc_gens, bp_gens, record) = derive_generators(b"TestProtocol", b"beacon", 16, 2);
+        assert_eq!(pc_gens.B_blinding, G1Projective::generator());
+        assert!(record.check(&pc_gens, &bp_gens));
+
+        let (pc_rerun, bp_rerun) = record.verify().unwrap();
+        assert_eq!(pc_rerun.B, pc_gens.B);
+        assert_eq!(bp_rerun.G_vec, bp_gens.G_vec);
+        assert_eq!(bp_rerun.H_vec, bp_gens.H_vec);
+
+        // Growing the derived generators extends the same chains.
+        let (_, mut bp_small, _) = derive_generators(b"TestProtocol", b"beacon", 8, 2);
+        bp_small.increase_capacity(16);
+        assert!(record.check(&pc_gens, &bp_small));
+
+        // Default generators, or another beacon or label, do not match.
+        let default_gens = BulletproofGens::new(16, 2);
+        assert!(!record.check(&pc_gens, &default_gens));
+        assert!(!record.check(&PedersenGens::default(), &bp_gens));
+        let (_, bp_other, other) = derive_generators(b"TestProtocol", b"beacon2", 16, 2);
+        assert!(!record.check(&pc_gens, &bp_other));
+        assert_ne!(other.digest, record.digest);
+
+        let mut forged = record.clone();
+        forged.beacon = b"beacon2".to_vec();
+        assert_eq!(forged.verify().err(), Some(ProofError::VerificationError));
+
+        let decoded: GeneratorsRecord =
+            bincode::deserialize(&bincode::serialize(&record).unwrap()).unwrap();
+        assert_eq!(decoded, record);
+    }
+
     #[test]
     fn aggregated_gens_iter_matches_flat_map() {
         let gens = BulletproofGens::new(64, 8);
@@ -358,4 +594,32 @@ mod tests {
         helper(32, 8);
         helper(16, 8);
     }
+
+    #[test]
+    fn clones_share_generators() {
+        let gens = BulletproofGens::new(32, 2);
+        let shared = gens.clone();
+        assert!(Arc::ptr_eq(&gens.G_vec, &shared.G_vec));
+        assert!(Arc::ptr_eq(&gens.H_vec, &shared.H_vec));
+
+        let handle = std::thread::spawn(move || shared.G(32, 2).count());
+        assert_eq!(handle.join().unwrap(), 64);
+
+        // Growing a clone leaves the shared table untouched
+        let mut grown = gens.clone();
+        grown.increase_capacity(64);
+        assert!(!Arc::ptr_eq(&gens.G_vec, &grown.G_vec));
+        assert_eq!(gens.G_vec[0].len(), 32);
+        assert_eq!(grown.G_vec[0].len(), 64);
+    }
+
+    #[test]
+    fn memory_footprint_counts_tables() {
+        let mut gens = BulletproofGens::new(32, 4);
+        let footprint = gens.memory_footprint();
+        assert!(footprint >= 2 * 32 * 4 * size_of::<G1Projective>());
+
+        gens.increase_capacity(64);
+        assert!(gens.memory_footprint() >= footprint + 2 * 32 * 4 * size_of::<G1Projective>());
+    }
 }
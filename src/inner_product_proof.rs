@@ -15,17 +15,36 @@ use alloc::vec::Vec;
 use blstrs::{G1Projective, Scalar};
 use core::iter;
 use group::ff::Field;
+use group::Group;
 use merlin::Transcript;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::errors::ProofError;
 use crate::transcript::TranscriptProtocol;
-
+use crate::util;
+
+/// An inner-product argument: a proof of knowledge of vectors
+/// \\(\mathbf{a}, \mathbf{b}\\) such that
+/// \\(P = \langle \mathbf{a}, \mathbf{G} \rangle + \langle \mathbf{b}, \mathbf{H}' \rangle + \langle \mathbf{a}, \mathbf{b} \rangle Q\\).
+///
+/// The argument is used by [`RangeProof`](crate::RangeProof), and can
+/// also be used on its own in other protocols with
+/// [`InnerProductProof::create`] and [`InnerProductProof::verify`].
+///
+/// # Encoding
+///
+/// The byte layout of [`InnerProductProof::to_bytes`] is stable: it
+/// only changes with a major version of the crate, and proofs encoded
+/// by earlier versions keep decoding.  The `serde` implementations
+/// encode the proof as these bytes.
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub struct InnerProductProof {
     pub(crate) L_vec: Vec<G1Projective>,
     pub(crate) R_vec: Vec<G1Projective>,
-    pub(crate) a: Scalar,
-    pub(crate) b: Scalar,
+    /// The final \\(k\\)-element vectors \\(\mathbf{a}, \mathbf{b}\\),
+    /// where \\(k\\) is the recursion cutoff.
+    pub(crate) a_vec: Vec<Scalar>,
+    pub(crate) b_vec: Vec<Scalar>,
 }
 
 impl InnerProductProof {
@@ -41,15 +60,68 @@ impl InnerProductProof {
     /// The lengths of the vectors must all be the same, and must all be
     /// either 0 or a power of 2.
     pub fn create(
+        transcript: &mut Transcript,
+        Q: &G1Projective,
+        G_factors: &[Scalar],
+        H_factors: &[Scalar],
+        G_vec: Vec<G1Projective>,
+        H_vec: Vec<G1Projective>,
+        a_vec: Vec<Scalar>,
+        b_vec: Vec<Scalar>,
+    ) -> Result<InnerProductProof, ProofError> {
+        InnerProductProof::create_with_cutoff(
+            transcript, Q, G_factors, H_factors, G_vec, H_vec, a_vec, b_vec, 1,
+        )
+    }
+
+    /// Create an inner-product proof that stops recursing once the
+    /// vectors have length `k`, and sends the final \\(k\\)-element
+    /// vectors in the clear.
+    ///
+    /// Compared to [`InnerProductProof::create`], the proof has
+    /// \\(2 \lg k\\) fewer points and \\(2(k - 1)\\) more scalars,
+    /// and the verifier derives \\(\lg k\\) fewer challenges.
+    ///
+    /// The cutoff `k` must be a power of 2 no larger than the length of
+    /// the vectors, otherwise [`ProofError::InvalidCutoff`] is returned.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_with_cutoff(
+        transcript: &mut Transcript,
+        Q: &G1Projective,
+        G_factors: &[Scalar],
+        H_factors: &[Scalar],
+        G_vec: Vec<G1Projective>,
+        H_vec: Vec<G1Projective>,
+        a_vec: Vec<Scalar>,
+        b_vec: Vec<Scalar>,
+        k: usize,
+    ) -> Result<InnerProductProof, ProofError> {
+        InnerProductProof::create_with_progress(
+            transcript, Q, G_factors, H_factors, G_vec, H_vec, a_vec, b_vec, k, None,
+        )
+    }
+
+    /// Create an inner-product proof with cutoff `k`, as by
+    /// [`InnerProductProof::create_with_cutoff`], reporting the fraction
+    /// of the work done to `progress` after every round.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn create_with_progress(
         transcript: &mut Transcript,
         Q: &G1Projective,
         G_factors: &[Scalar],
         H_factors: &[Scalar],
         mut G_vec: Vec<G1Projective>,
         mut H_vec: Vec<G1Projective>,
-        mut a_vec: Vec<Scalar>,
-        mut b_vec: Vec<Scalar>,
+        a_vec: Vec<Scalar>,
+        b_vec: Vec<Scalar>,
+        k: usize,
+        progress: Option<&dyn Fn(f32)>,
     ) -> Result<InnerProductProof, ProofError> {
+        // The folded vectors hold the secret witness, e.g. the l(x)
+        // and r(x) of a range proof, so they are wiped on return.
+        let mut a_vec = util::SecretScalars(a_vec);
+        let mut b_vec = util::SecretScalars(b_vec);
+
         // Create slices G, H, a, b backed by their respective
         // vectors.  This lets us reslice as we compress the lengths
         // of the vectors in the main loop below.
@@ -59,6 +131,15 @@ impl InnerProductProof {
         let mut b = &mut b_vec[..];
 
         let mut n = G.len();
+        let initial_n = n;
+
+        // The work of a round is proportional to the length of the
+        // vectors, so after a round, the work left is about the new length.
+        let report = |n: usize| {
+            if let Some(progress) = progress {
+                progress(1.0 - n as f32 / initial_n as f32);
+            }
+        };
 
         // All of the input vectors must have the same length.
         assert_eq!(G.len(), n);
@@ -71,15 +152,19 @@ impl InnerProductProof {
         // All of the input vectors must have a length that is a power of two.
         assert!(n.is_power_of_two());
 
+        if !k.is_power_of_two() || k > n {
+            return Err(ProofError::InvalidCutoff);
+        }
+
         transcript.innerproduct_domain_sep(n as u64);
 
-        let lg_n = n.next_power_of_two().trailing_zeros() as usize;
+        let lg_n = (n / k).trailing_zeros() as usize;
         let mut L_vec = Vec::with_capacity(lg_n);
         let mut R_vec = Vec::with_capacity(lg_n);
 
         // If it's the first iteration, unroll the Hprime = H*y_inv scalar mults
         // into multiscalar muls, for performance.
-        if n != 1 {
+        if n != k {
             n = n / 2;
             let (a_L, a_R) = a.split_at_mut(n);
             let (b_L, b_R) = b.split_at_mut(n);
@@ -89,33 +174,31 @@ impl InnerProductProof {
             let c_L = inner_product(&a_L, &b_R);
             let c_R = inner_product(&a_R, &b_L);
 
-            let L = a_L
-                .iter()
-                .zip(G_factors[n..2 * n].into_iter())
-                .map(|(a_L_i, g)| a_L_i * g)
-                .chain(
-                    b_R.iter()
-                        .zip(H_factors[0..n].into_iter())
-                        .map(|(b_R_i, h)| b_R_i * h),
-                )
-                .chain(iter::once(c_L))
-                .zip(G_R.iter().chain(H_L.iter()).chain(iter::once(Q)))
-                .map(|(s, P)| P * s)
-                .sum();
-
-            let R = a_R
-                .iter()
-                .zip(G_factors[0..n].into_iter())
-                .map(|(a_R_i, g)| a_R_i * g)
-                .chain(
-                    b_L.iter()
-                        .zip(H_factors[n..2 * n].into_iter())
-                        .map(|(b_L_i, h)| b_L_i * h),
-                )
-                .chain(iter::once(c_R))
-                .zip(G_L.iter().chain(H_R.iter()).chain(iter::once(Q)))
-                .map(|(s, P)| P * s)
-                .sum();
+            let L = multiscalar_mul(
+                a_L.iter()
+                    .zip(G_factors[n..2 * n].into_iter())
+                    .map(|(a_L_i, g)| a_L_i * g)
+                    .chain(
+                        b_R.iter()
+                            .zip(H_factors[0..n].into_iter())
+                            .map(|(b_R_i, h)| b_R_i * h),
+                    )
+                    .chain(iter::once(c_L)),
+                G_R.iter().chain(H_L.iter()).chain(iter::once(Q)),
+            );
+
+            let R = multiscalar_mul(
+                a_R.iter()
+                    .zip(G_factors[0..n].into_iter())
+                    .map(|(a_R_i, g)| a_R_i * g)
+                    .chain(
+                        b_L.iter()
+                            .zip(H_factors[n..2 * n].into_iter())
+                            .map(|(b_L_i, h)| b_L_i * h),
+                    )
+                    .chain(iter::once(c_R)),
+                G_L.iter().chain(H_R.iter()).chain(iter::once(Q)),
+            );
 
             L_vec.push(L);
             R_vec.push(R);
@@ -129,17 +212,18 @@ impl InnerProductProof {
             for i in 0..n {
                 a_L[i] = a_L[i] * u + a_R[i] * u_inv;
                 b_L[i] = b_L[i] * u_inv + b_R[i] * u;
-                G_L[i] = G_L[i] * (u_inv * G_factors[i]) + G_R[i] * (u * G_factors[n + i]);
-                H_L[i] = H_L[i] * (u * H_factors[i]) + H_R[i] * (u_inv * H_factors[n + i]);
             }
+            fold_points(G_L, G_R, |i| (u_inv * G_factors[i], u * G_factors[n + i]));
+            fold_points(H_L, H_R, |i| (u * H_factors[i], u_inv * H_factors[n + i]));
 
             a = a_L;
             b = b_L;
             G = G_L;
             H = H_L;
+            report(n);
         }
 
-        while n != 1 {
+        while n != k {
             n = n / 2;
             let (a_L, a_R) = a.split_at_mut(n);
             let (b_L, b_R) = b.split_at_mut(n);
@@ -149,21 +233,21 @@ impl InnerProductProof {
             let c_L = inner_product(&a_L, &b_R);
             let c_R = inner_product(&a_R, &b_L);
 
-            let L = a_L
-                .iter()
-                .chain(b_R.iter())
-                .chain(iter::once(&c_L))
-                .zip(G_R.iter().chain(H_L.iter()).chain(iter::once(Q)))
-                .map(|(s, P)| P * s)
-                .sum();
-
-            let R = a_R
-                .iter()
-                .chain(b_L.iter())
-                .chain(iter::once(&c_R))
-                .zip(G_L.iter().chain(H_R.iter()).chain(iter::once(Q)))
-                .map(|(s, P)| P * s)
-                .sum();
+            let L = multiscalar_mul(
+                a_L.iter()
+                    .chain(b_R.iter())
+                    .chain(iter::once(&c_L))
+                    .copied(),
+                G_R.iter().chain(H_L.iter()).chain(iter::once(Q)),
+            );
+
+            let R = multiscalar_mul(
+                a_R.iter()
+                    .chain(b_L.iter())
+                    .chain(iter::once(&c_R))
+                    .copied(),
+                G_L.iter().chain(H_R.iter()).chain(iter::once(Q)),
+            );
 
             L_vec.push(L);
             R_vec.push(R);
@@ -177,31 +261,80 @@ impl InnerProductProof {
             for i in 0..n {
                 a_L[i] = a_L[i] * u + a_R[i] * u_inv;
                 b_L[i] = b_L[i] * u_inv + b_R[i] * u;
-                G_L[i] = G_L[i] * u_inv + G_R[i] * u;
-                H_L[i] = H_L[i] * u + H_R[i] * u_inv;
             }
+            fold_points(G_L, G_R, |_| (u_inv, u));
+            fold_points(H_L, H_R, |_| (u, u_inv));
 
             a = a_L;
             b = b_L;
             G = G_L;
             H = H_L;
+            report(n);
         }
 
         Ok(InnerProductProof {
             L_vec: L_vec,
             R_vec: R_vec,
-            a: a[0],
-            b: b[0],
+            a_vec: a.to_vec(),
+            b_vec: b.to_vec(),
         })
     }
 
+    /// Returns the points \\(L\_j\\) of the rounds of the argument, in
+    /// the order in which they were created.
+    pub fn L_vec(&self) -> &[G1Projective] {
+        &self.L_vec
+    }
+
+    /// Returns the points \\(R\_j\\) of the rounds of the argument, in
+    /// the order in which they were created.
+    pub fn R_vec(&self) -> &[G1Projective] {
+        &self.R_vec
+    }
+
+    /// Returns the final vector \\(\mathbf{a}\\) of length \\(k\\).
+    pub fn a(&self) -> &[Scalar] {
+        &self.a_vec
+    }
+
+    /// Returns the final vector \\(\mathbf{b}\\) of length \\(k\\).
+    pub fn b(&self) -> &[Scalar] {
+        &self.b_vec
+    }
+
+    /// Returns the recursion cutoff \\(k\\), the length of the final
+    /// vectors sent in the clear.
+    pub fn cutoff(&self) -> usize {
+        self.a_vec.len()
+    }
+
     /// Computes three vectors of verification scalars \\([u\_{i}^{2}]\\), \\([u\_{i}^{-2}]\\) and \\([s\_{i}]\\) for combined multiscalar multiplication
     /// in a parent protocol. See [inner product protocol notes](index.html#verification-equation) for details.
     /// The verifier must provide the input length \\(n\\) explicitly to avoid unbounded allocation within the inner product proof.
+    ///
+    /// With a recursion cutoff \\(k\\), there are \\(n / k\\) values
+    /// \\(s\_t\\), and the \\(i\\)-th generator is scaled by
+    /// \\(s\_{\lfloor i / k \rfloor}\\) and the \\((i \bmod k)\\)-th final scalar.
     pub(crate) fn verification_scalars(
         &self,
         n: usize,
         transcript: &mut Transcript,
+    ) -> Result<(Vec<Scalar>, Vec<Scalar>, Vec<Scalar>), ProofError> {
+        self.verification_scalars_inverting(n, transcript, &mut [])
+    }
+
+    /// Computes the verification scalars as [`Self::verification_scalars`]
+    /// does, and replaces every scalar of `extra` by its inverse, with
+    /// the same batch inversion as the challenges.
+    ///
+    /// Returns [`ProofError::FormatError`] if any challenge or any
+    /// scalar of `extra` is zero.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn verification_scalars_inverting(
+        &self,
+        n: usize,
+        transcript: &mut Transcript,
+        extra: &mut [Scalar],
     ) -> Result<(Vec<Scalar>, Vec<Scalar>, Vec<Scalar>), ProofError> {
         let lg_n = self.L_vec.len();
         if lg_n >= 32 {
@@ -209,7 +342,8 @@ impl InnerProductProof {
             // and this check prevents overflow in 1<<lg_n below.
             return Err(ProofError::VerificationError);
         }
-        if n != (1 << lg_n) {
+        let k = self.cutoff();
+        if !k.is_power_of_two() || n != (k << lg_n) || self.b_vec.len() != k {
             return Err(ProofError::VerificationError);
         }
 
@@ -224,15 +358,14 @@ impl InnerProductProof {
             challenges.push(transcript.challenge_scalar(b"u"));
         }
 
-        // 2. Compute 1/(u_k...u_1) and 1/u_k, ..., 1/u_1
+        // 2. Compute 1/(u_k...u_1) and 1/u_k, ..., 1/u_1, inverting
+        // `extra` in the same batch
 
-        // TODO: very non-optimal code, check if blst has the equivalent Scalar::batch_invert function
-        // https://docs.rs/curve25519-dalek-ng/4.1.1/curve25519_dalek_ng/scalar/struct.Scalar.html#method.batch_invert
-        let mut challenges_inv = challenges
-            .clone()
-            .into_iter()
-            .map(|u| Option::from(u.invert()).ok_or(ProofError::FormatError))
-            .collect::<Result<Vec<_>, _>>()?;
+        let mut inverses: Vec<Scalar> = challenges.iter().chain(extra.iter()).copied().collect();
+        util::batch_invert(&mut inverses)?;
+        extra.copy_from_slice(&inverses[lg_n..]);
+        inverses.truncate(lg_n);
+        let mut challenges_inv = inverses;
         // todo: replace fold() with product() when supported in blstrs
         let allinv = challenges_inv
             .iter()
@@ -250,9 +383,9 @@ impl InnerProductProof {
 
         // 4. Compute s values inductively.
 
-        let mut s = Vec::with_capacity(n);
+        let mut s = Vec::with_capacity(n / k);
         s.push(allinv);
-        for i in 1..n {
+        for i in 1..(n / k) {
             let lg_i = (32 - 1 - (i as u32).leading_zeros()) as usize;
             let k = 1 << lg_i;
             // The challenges are stored in "creation order" as [u_k,...,u_1],
@@ -280,6 +413,42 @@ impl InnerProductProof {
         G: &[G1Projective],
         H: &[G1Projective],
     ) -> Result<(), ProofError>
+    where
+        IG: IntoIterator,
+        IG::Item: Borrow<Scalar>,
+        IH: IntoIterator,
+        IH::Item: Borrow<Scalar>,
+    {
+        let (scalars, points) =
+            self.verification_terms(n, transcript, G_factors, H_factors, P, Q, G, H)?;
+        let check = util::multiscalar_mul(&scalars, &points);
+
+        if bool::from(check.is_identity()) {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Returns the terms of the verification equation as a pair of
+    /// equally long scalar and point vectors.
+    ///
+    /// The proof is valid iff \\(\sum\_i s\_i \cdot P\_i\\) over the returned
+    /// terms is the identity, so the terms can be merged into a larger
+    /// multiscalar multiplication together with the caller's own terms.
+    /// Multiply all returned scalars by a random weight before merging.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verification_terms<IG, IH>(
+        &self,
+        n: usize,
+        transcript: &mut Transcript,
+        G_factors: IG,
+        H_factors: IH,
+        P: &G1Projective,
+        Q: &G1Projective,
+        G: &[G1Projective],
+        H: &[G1Projective],
+    ) -> Result<(Vec<Scalar>, Vec<G1Projective>), ProofError>
     where
         IG: IntoIterator,
         IG::Item: Borrow<Scalar>,
@@ -288,109 +457,130 @@ impl InnerProductProof {
     {
         let (u_sq, u_inv_sq, s) = self.verification_scalars(n, transcript)?;
 
+        let k = self.cutoff();
+
         let g_times_a_times_s = G_factors
             .into_iter()
-            .zip(s.iter())
-            .map(|(g_i, s_i)| (self.a * s_i) * g_i.borrow())
+            .zip(
+                s.iter()
+                    .flat_map(|s_t| self.a_vec.iter().map(move |a_j| a_j * s_t)),
+            )
+            .map(|(g_i, a_s_i)| a_s_i * g_i.borrow())
             .take(G.len());
 
-        // 1/s[i] is s[!i], and !i runs from n-1 to 0 as i runs from 0 to n-1
+        // 1/s[i] is s[!i], and !i runs from n/k-1 to 0 as i runs from 0 to n/k-1
         let inv_s = s.iter().rev();
 
         let h_times_b_div_s = H_factors
             .into_iter()
-            .zip(inv_s)
-            .map(|(h_i, s_i_inv)| (self.b * s_i_inv) * h_i.borrow());
+            .zip(inv_s.flat_map(|s_t_inv| self.b_vec.iter().map(move |b_j| b_j * s_t_inv)))
+            .map(|(h_i, b_s_i_inv)| b_s_i_inv * h_i.borrow())
+            .take(s.len() * k);
 
         let neg_u_sq = u_sq.iter().map(|ui| -ui);
         let neg_u_inv_sq = u_inv_sq.iter().map(|ui| -ui);
 
-        let scalars = iter::once(self.a * self.b)
+        let scalars = iter::once(inner_product(&self.a_vec, &self.b_vec))
             .chain(g_times_a_times_s)
             .chain(h_times_b_div_s)
             .chain(neg_u_sq)
-            .chain(neg_u_inv_sq);
+            .chain(neg_u_inv_sq)
+            .chain(iter::once(-Scalar::one()))
+            .collect();
         let points = iter::once(Q)
             .chain(G.iter())
             .chain(H.iter())
             .chain(self.L_vec.iter())
-            .chain(self.R_vec.iter());
-        let expect_P: G1Projective = scalars.zip(points).map(|(s, P)| P * s).sum();
+            .chain(self.R_vec.iter())
+            .chain(iter::once(P))
+            .copied()
+            .collect();
 
-        if expect_P == *P {
-            Ok(())
-        } else {
-            Err(ProofError::VerificationError)
-        }
+        Ok((scalars, points))
     }
 
     /// Returns the size in bytes required to serialize the inner
     /// product proof.
     ///
-    /// For vectors of length `n` the proof size is
-    /// \\(32 \cdot (2\lg n+2)\\) bytes.
+    /// For vectors of length `n` and a cutoff \\(k = 1\\) the proof size is
+    /// \\(48 \cdot 2\lg n + 32 \cdot 2\\) bytes.  With a cutoff
+    /// \\(k > 1\\) it is \\(1 + 48 \cdot 2\lg(n/k) + 32 \cdot 2k\\) bytes.
     pub fn serialized_size(&self) -> usize {
-        (self.L_vec.len() * 2) * 48 + 2 * 32
+        let header = if self.cutoff() > 1 { 1 } else { 0 };
+        header + (self.L_vec.len() * 2) * 48 + 2 * self.cutoff() * 32
     }
 
-    /// Serializes the proof into a byte array of \\(2n+2\\) 32-byte elements.
+    /// Serializes the proof into a byte array.
     /// The layout of the inner product proof is:
-    /// * \\(n\\) pairs of compressed Ristretto points \\(L_0, R_0 \dots, L_{n-1}, R_{n-1}\\),
-    /// * two scalars \\(a, b\\).
+    /// * if the cutoff \\(k\\) is larger than 1, one byte \\(\lg k\\),
+    /// * \\(n\\) pairs of compressed G1 points \\(L_0, R_0 \dots, L_{n-1}, R_{n-1}\\),
+    /// * \\(k\\) scalars \\(\mathbf{a}\\), then \\(k\\) scalars \\(\mathbf{b}\\).
+    ///
+    /// Proofs without a cutoff have an even length, and proofs with one
+    /// an odd length, so proofs without a cutoff keep the original layout.
     #[allow(dead_code)]
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(self.serialized_size());
-        for (l, r) in self.L_vec.iter().zip(self.R_vec.iter()) {
-            buf.extend_from_slice(&l.to_compressed());
-            buf.extend_from_slice(&r.to_compressed());
-        }
-        buf.extend_from_slice(&self.a.to_bytes_le());
-        buf.extend_from_slice(&self.b.to_bytes_le());
-        buf
+        self.to_bytes_iter().collect()
     }
 
-    /// Converts the proof into a byte iterator over serialized view of the proof.
-    /// The layout of the inner product proof is:
-    /// * \\(n\\) pairs of compressed Ristretto points \\(L_0, R_0 \dots, L_{n-1}, R_{n-1}\\),
-    /// * two scalars \\(a, b\\).
+    /// Converts the proof into a byte iterator over serialized view of the proof,
+    /// with the layout described in [`InnerProductProof::to_bytes`].
     #[inline]
     pub(crate) fn to_bytes_iter(&self) -> impl Iterator<Item = u8> + '_ {
-        self.L_vec
-            .iter()
-            .zip(self.R_vec.iter())
-            .flat_map(|(l, r)| {
+        let header = if self.cutoff() > 1 {
+            Some(self.cutoff().trailing_zeros() as u8)
+        } else {
+            None
+        };
+        header
+            .into_iter()
+            .chain(self.L_vec.iter().zip(self.R_vec.iter()).flat_map(|(l, r)| {
                 l.to_compressed()
                     .iter()
                     .copied()
                     .chain(r.to_compressed())
                     .collect::<Vec<_>>()
-            })
-            .chain(self.a.to_bytes_le())
-            .chain(self.b.to_bytes_le())
+            }))
+            .chain(self.a_vec.iter().flat_map(|a| a.to_bytes_le()))
+            .chain(self.b_vec.iter().flat_map(|b| b.to_bytes_le()))
     }
 
     /// Deserializes the proof from a byte slice.
     /// Returns an error in the following cases:
-    /// * the slice does not have \\(2n\\) 48-byte elements + 2 32-byte elements,
+    /// * the slice has an odd length, and its first byte \\(\lg k\\) is
+    ///   zero or \\(\lg k + n\\) is at least 32,
+    /// * the rest of the slice does not have \\(2n\\) 48-byte elements + \\(2k\\) 32-byte elements,
     /// * \\(n\\) is larger or equal to 32 (proof is too big),
     /// * any of \\(2n\\) points are not valid compressed bls12-381 G1 points,
-    /// * any of 2 scalars are not canonical scalars modulo bls12-381 G1 group order.
+    /// * any of \\(2k\\) scalars are not canonical scalars modulo bls12-381 G1 group order.
     pub fn from_bytes(slice: &[u8]) -> Result<InnerProductProof, ProofError> {
+        // Only proofs with a cutoff have an odd length.
+        let (lg_k, slice) = match slice.split_first() {
+            Some((&lg_k, rest)) if slice.len() % 2 == 1 => {
+                if lg_k == 0 || lg_k >= 32 {
+                    return Err(ProofError::FormatError);
+                }
+                (lg_k as usize, rest)
+            }
+            _ => (0, slice),
+        };
+        let k = 1usize << lg_k;
+
         let b = slice.len();
-        if b < 2 * 32 {
+        if b < 2 * k * 32 {
             return Err(ProofError::FormatError);
         }
-        if (b - 32 * 2) % 48 != 0 {
-            // last two elements are scalars,
+        if (b - 2 * k * 32) % 48 != 0 {
+            // last 2k elements are scalars,
             return Err(ProofError::FormatError);
         }
-        let num_points = (b - 32 * 2) / 48;
+        let num_points = (b - 2 * k * 32) / 48;
         if num_points % 2 != 0 {
             return Err(ProofError::FormatError);
         }
 
         let lg_n = num_points / 2;
-        if lg_n >= 32 {
+        if lg_n + lg_k >= 32 {
             return Err(ProofError::FormatError);
         }
 
@@ -411,12 +601,42 @@ impl InnerProductProof {
         }
 
         let pos = 2 * lg_n * 48;
-        let a = Option::from(Scalar::from_bytes_le(&read32(&slice[pos..])))
-            .ok_or(ProofError::FormatError)?;
-        let b = Option::from(Scalar::from_bytes_le(&read32(&slice[pos + 32..])))
-            .ok_or(ProofError::FormatError)?;
+        let scalars = slice[pos..]
+            .chunks(32)
+            .map(|chunk| {
+                Option::from(Scalar::from_bytes_le(&read32(chunk))).ok_or(ProofError::FormatError)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let (a_vec, b_vec) = scalars.split_at(k);
+
+        Ok(InnerProductProof {
+            L_vec,
+            R_vec,
+            a_vec: a_vec.to_vec(),
+            b_vec: b_vec.to_vec(),
+        })
+    }
+}
+
+impl Serialize for InnerProductProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
 
-        Ok(InnerProductProof { L_vec, R_vec, a, b })
+impl<'de> Deserialize<'de> for InnerProductProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        util::deserialize_bytes(
+            deserializer,
+            "InnerProductProof",
+            InnerProductProof::from_bytes,
+        )
     }
 }
 
@@ -436,6 +656,73 @@ pub fn inner_product(a: &[Scalar], b: &[Scalar]) -> Scalar {
     out
 }
 
+/// Computes \\(\sum\_{i} s\_i \cdot P\_i\\) for `scalars` and `points`,
+/// by [`util::multiscalar_mul`] when the `fast-msm` feature is enabled,
+/// and otherwise split across threads when the `rayon` feature is.
+fn multiscalar_mul<'a, S, P>(scalars: S, points: P) -> G1Projective
+where
+    S: IntoIterator<Item = Scalar>,
+    P: IntoIterator<Item = &'a G1Projective>,
+{
+    #[cfg(feature = "fast-msm")]
+    {
+        let scalars: Vec<Scalar> = scalars.into_iter().collect();
+        let points: Vec<G1Projective> = points.into_iter().copied().collect();
+        util::multiscalar_mul(&scalars, &points)
+    }
+
+    #[cfg(all(feature = "rayon", not(feature = "fast-msm")))]
+    {
+        use rayon::prelude::*;
+
+        let scalars: Vec<Scalar> = scalars.into_iter().collect();
+        let points: Vec<&G1Projective> = points.into_iter().collect();
+        scalars
+            .par_iter()
+            .zip(points.par_iter())
+            .with_min_len(MIN_CHUNK_SIZE)
+            .map(|(s, P)| *P * s)
+            .sum()
+    }
+
+    #[cfg(not(any(feature = "rayon", feature = "fast-msm")))]
+    scalars.into_iter().zip(points).map(|(s, P)| P * s).sum()
+}
+
+/// Folds the generators `hi` into `lo`, as
+/// \\(lo\_i \leftarrow lo\_i \cdot x\_i + hi\_i \cdot y\_i\\) for
+/// \\((x\_i, y\_i)\\) given by `factors`, split across threads when the
+/// `rayon` feature is enabled.
+fn fold_points<F>(lo: &mut [G1Projective], hi: &[G1Projective], factors: F)
+where
+    F: Fn(usize) -> (Scalar, Scalar) + Sync,
+{
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+
+        lo.par_iter_mut()
+            .zip(hi.par_iter())
+            .enumerate()
+            .with_min_len(MIN_CHUNK_SIZE)
+            .for_each(|(i, (lo, hi))| {
+                let (x, y) = factors(i);
+                *lo = *lo * x + hi * y;
+            });
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    for (i, (lo, hi)) in lo.iter_mut().zip(hi.iter()).enumerate() {
+        let (x, y) = factors(i);
+        *lo = *lo * x + hi * y;
+    }
+}
+
+/// The smallest number of group operations handed to a thread, below
+/// which the overhead of splitting outweighs the gain.
+#[cfg(feature = "rayon")]
+const MIN_CHUNK_SIZE: usize = 16;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -443,6 +730,10 @@ mod tests {
     use crate::util;
 
     fn test_helper_create(n: usize) {
+        test_helper_create_with_cutoff(n, 1);
+    }
+
+    fn test_helper_create_with_cutoff(n: usize, k: usize) {
         let mut rng = rand::thread_rng();
 
         use crate::generators::BulletproofGens;
@@ -481,7 +772,7 @@ mod tests {
             .sum();
 
         let mut verifier = Transcript::new(b"innerproducttest");
-        let proof = InnerProductProof::create(
+        let proof = InnerProductProof::create_with_cutoff(
             &mut verifier,
             &Q,
             &G_factors,
@@ -490,8 +781,11 @@ mod tests {
             H.clone(),
             a.clone(),
             b.clone(),
+            k,
         )
         .unwrap();
+        assert_eq!(proof.cutoff(), k);
+        assert_eq!(proof.to_bytes().len(), proof.serialized_size());
 
         let mut verifier = Transcript::new(b"innerproducttest");
         assert!(proof
@@ -508,6 +802,12 @@ mod tests {
             .is_ok());
 
         let proof = InnerProductProof::from_bytes(proof.to_bytes().as_slice()).unwrap();
+        assert_eq!(proof.L_vec().len(), proof.R_vec().len());
+        assert_eq!(proof.a().len(), k);
+        assert_eq!(proof.b().len(), k);
+        let decoded: InnerProductProof =
+            bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+        assert_eq!(decoded, proof);
         let mut verifier = Transcript::new(b"innerproducttest");
         assert!(proof
             .verify(
@@ -548,6 +848,13 @@ mod tests {
         test_helper_create(64);
     }
 
+    #[test]
+    fn make_ipp_with_cutoff() {
+        test_helper_create_with_cutoff(64, 4);
+        test_helper_create_with_cutoff(32, 32);
+        test_helper_create_with_cutoff(2, 2);
+    }
+
     #[test]
     fn test_inner_product() {
         let a = vec![
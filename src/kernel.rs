@@ -0,0 +1,118 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Mimblewimble-style transaction kernel helpers.
+//!
+//! In a balanced transaction the output commitments minus the input
+//! commitments leave no value component, only the *excess*
+//! \\(x \cdot \tilde{B}\\), where \\(x\\) is the sum of the output
+//! blinding factors minus the sum of the input blinding factors.  The
+//! transactor proves the balance by signing the kernel with \\(x\\) as
+//! the secret key, and verifiers check that the commitments sum to the
+//! excess.
+
+use blstrs::Scalar;
+use group::ff::Field;
+
+use crate::commitments::{BlindingFactor, Commitment};
+use crate::errors::ProofError;
+use crate::generators::PedersenGens;
+
+/// The excess of a balanced transaction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Excess {
+    /// The excess commitment \\(x \cdot \tilde{B}\\), the public key of the kernel.
+    pub commitment: Commitment,
+    /// The aggregate blinding factor \\(x\\), the secret key of the kernel.
+    pub blinding: BlindingFactor,
+}
+
+/// Computes the excess of a transaction with the given input and output
+/// `(value, blinding)` pairs.
+///
+/// Returns [`ProofError::UnbalancedValues`] if the input values do not
+/// sum to the output values, since the excess would then commit to
+/// the difference.
+pub fn excess(
+    pc_gens: &PedersenGens,
+    inputs: &[(u64, BlindingFactor)],
+    outputs: &[(u64, BlindingFactor)],
+) -> Result<Excess, ProofError> {
+    let input_value: u128 = inputs.iter().map(|(v, _)| u128::from(*v)).sum();
+    let output_value: u128 = outputs.iter().map(|(v, _)| u128::from(*v)).sum();
+    if input_value != output_value {
+        return Err(ProofError::UnbalancedValues);
+    }
+
+    let blinding = outputs.iter().map(|(_, b)| b).sum::<BlindingFactor>()
+        - inputs.iter().map(|(_, b)| b).sum::<BlindingFactor>();
+    let commitment = blinding.commit(pc_gens, Scalar::zero());
+
+    Ok(Excess {
+        commitment,
+        blinding,
+    })
+}
+
+/// Checks that the `outputs` minus the `inputs` sum to the `excess`
+/// commitment, i.e. that the committed values balance given a valid
+/// kernel signature by `excess`.
+pub fn verify_excess(
+    inputs: &[Commitment],
+    outputs: &[Commitment],
+    excess: &Commitment,
+) -> Result<(), ProofError> {
+    let sum = outputs.iter().sum::<Commitment>() - inputs.iter().sum::<Commitment>();
+    if sum == *excess {
+        Ok(())
+    } else {
+        Err(ProofError::VerificationError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn excess_balances_commitments() {
+        let pc_gens = PedersenGens::default();
+        let mut rng = rand::thread_rng();
+
+        let inputs = [
+            (10u64, BlindingFactor::random(&mut rng)),
+            (5u64, BlindingFactor::random(&mut rng)),
+        ];
+        let outputs = [
+            (12u64, BlindingFactor::random(&mut rng)),
+            (3u64, BlindingFactor::random(&mut rng)),
+        ];
+        let commit = |pairs: &[(u64, BlindingFactor)]| -> Vec<Commitment> {
+            pairs
+                .iter()
+                .map(|(v, b)| b.commit(&pc_gens, Scalar::from(*v)))
+                .collect()
+        };
+
+        let excess = excess(&pc_gens, &inputs, &outputs).unwrap();
+        assert_eq!(
+            excess.commitment,
+            Commitment(pc_gens.B_blinding * excess.blinding.as_scalar())
+        );
+        assert!(verify_excess(&commit(&inputs), &commit(&outputs), &excess.commitment).is_ok());
+        assert_eq!(
+            verify_excess(&commit(&inputs), &commit(&outputs[..1]), &excess.commitment),
+            Err(ProofError::VerificationError)
+        );
+
+        assert_eq!(
+            super::excess(&pc_gens, &inputs, &outputs[..1]),
+            Err(ProofError::UnbalancedValues)
+        );
+    }
+}
@@ -5,6 +5,9 @@
 // Please see the LICENSE file for more details.
 
 #![cfg_attr(not(feature = "std"), no_std)]
+// Without the prover or the verifier, only the proof types and their
+// encodings are left, and the helpers shared by the two are unused.
+#![cfg_attr(not(any(feature = "prover", feature = "verifier")), allow(dead_code))]
 #![cfg_attr(feature = "docs", feature(external_doc))]
 #![cfg_attr(feature = "docs", deny(missing_docs))]
 #![cfg_attr(feature = "docs", doc(include = "../README.md"))]
@@ -30,30 +33,110 @@ mod notes {
     mod r1cs_proof {}
 }
 
+#[cfg(feature = "verifier")]
+mod accumulator;
+mod amount;
+#[cfg(feature = "verifier")]
+mod bundle;
+#[cfg(feature = "verifier")]
+mod chain;
+mod commitments;
+#[cfg(feature = "verifier")]
+mod committed_map;
 mod errors;
 mod generators;
 mod inner_product_proof;
+#[cfg(feature = "verifier")]
+mod merkle;
+#[cfg(feature = "verifier")]
+mod one_of_many;
+#[cfg(feature = "verifier")]
+mod opening;
 mod range_proof;
+#[cfg(feature = "rand_core_09")]
+mod rng;
+#[cfg(feature = "verifier")]
+mod session;
+#[cfg(feature = "verifier")]
+mod telemetry;
 mod transcript;
 
 // re-export crates that are used in our public API.
 pub use blstrs;
 pub use group;
 pub use merlin;
+#[cfg(feature = "thread_rng")]
 pub use rand;
 
-pub use crate::errors::ProofError;
-pub use crate::generators::{BulletproofGens, BulletproofGensShare, PedersenGens};
-pub use crate::range_proof::RangeProof;
+#[cfg(feature = "verifier")]
+pub use crate::accumulator::Accumulator;
+pub use crate::amount::{Amount, TrackedAmount, MAX_SCALE};
+#[cfg(feature = "verifier")]
+pub use crate::bundle::{BundleInclusionProof, ProofBundle};
+#[cfg(feature = "verifier")]
+pub use crate::chain::{ChainTag, ProofChain};
+pub use crate::commitments::{BlindingFactor, Commitment};
+#[cfg(feature = "verifier")]
+pub use crate::committed_map::{CommittedMap, MapEntry, MapUpdate};
+pub use crate::errors::{AmountError, ProofError};
+pub use crate::generators::{
+    derive_generators, BulletproofGens, BulletproofGensShare, GeneratorsRecord, PedersenGens,
+};
+pub use crate::inner_product_proof::InnerProductProof;
+#[cfg(feature = "verifier")]
+pub use crate::one_of_many::OneOfManyProof;
+#[cfg(feature = "verifier")]
+pub use crate::opening::{hash_to_scalar, OpeningProof};
+pub use crate::range_proof::{
+    ArchiveDecoder, ArchiveEncoder, DecodeState, ProofArchive, ProofDecoder, ProofShape,
+    RangeProof, VerificationCost,
+};
+#[cfg(feature = "prover")]
+pub use crate::range_proof::{
+    DelegatedInnerProduct, DelegatingProver, DelegationRequest, IppStep, PendingRangeProof,
+    ProvingPad, ProvingPhase,
+};
+#[cfg(feature = "verifier")]
+pub use crate::range_proof::{
+    FailedCheck, PartialSum, ProofBatch, VerificationRecord, VerificationScalars, VerificationShare,
+};
+#[cfg(all(feature = "prover", feature = "verifier"))]
+pub use crate::range_proof::{NoncedProof, ProofReceipt, ProverBinding};
+#[cfg(feature = "rand_core_09")]
+pub use crate::rng::RngBridge;
+#[cfg(feature = "verifier")]
+pub use crate::session::ProofSession;
 
+#[cfg(feature = "prover")]
 #[cfg_attr(feature = "docs", doc(include = "../docs/aggregation-api.md"))]
 pub mod range_proof_mpc {
     pub use crate::errors::MPCError;
+    pub use crate::range_proof::audit;
     pub use crate::range_proof::dealer;
     pub use crate::range_proof::messages;
     pub use crate::range_proof::party;
 }
 
+#[cfg(feature = "verifier")]
+pub mod compose;
+pub mod hash;
+#[cfg(feature = "verifier")]
+pub mod kernel;
+#[cfg(feature = "verifier")]
+pub mod mint;
+#[cfg(feature = "prover")]
+pub mod poly;
+#[cfg(feature = "verifier")]
+pub mod solvency;
+#[cfg(feature = "verifier")]
+pub mod transfer;
+
+#[cfg(feature = "onchain")]
+pub mod onchain;
+
+#[cfg(feature = "timing-tests")]
+pub mod timing;
+
 #[cfg(feature = "yoloproofs")]
-#[cfg(feature = "std")]
+#[cfg(feature = "thread_rng")]
 pub mod r1cs;
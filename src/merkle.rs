@@ -0,0 +1,156 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+//! Binary Merkle trees over SHA3-256.
+//!
+//! Every tree hashes its leaves, inner nodes and empty root with its own
+//! domain tag, e.g. `b"Solvency"` hashes leaves as
+//! `SHA3-256("SolvencyLeaf" || ...)` and nodes as
+//! `SHA3-256("SolvencyNode" || left || right)`, so that trees of
+//! different kinds cannot be confused.  The last node of a level with
+//! an odd number of nodes is carried up to the next level unchanged.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use digest::Digest;
+use sha3::Sha3_256;
+
+use crate::errors::ProofError;
+
+/// Hashes the concatenation of `parts` as a leaf of a tree tagged `tag`.
+pub(crate) fn leaf_hash(tag: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    let mut sha3 = Sha3_256::new();
+    sha3.update(tag);
+    sha3.update(b"Leaf");
+    for part in parts.iter() {
+        sha3.update(part);
+    }
+    sha3.finalize().into()
+}
+
+/// Hashes two children to their parent in a tree tagged `tag`.
+fn node_hash(tag: &[u8], left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut sha3 = Sha3_256::new();
+    sha3.update(tag);
+    sha3.update(b"Node");
+    sha3.update(left);
+    sha3.update(right);
+    sha3.finalize().into()
+}
+
+/// Hashes the nodes of a level of the tree in pairs.
+fn next_level(tag: &[u8], level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(tag, left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Returns the root of the tree tagged `tag` over the hashed `leaves`.
+pub(crate) fn root(tag: &[u8], mut leaves: Vec<[u8; 32]>) -> [u8; 32] {
+    if leaves.is_empty() {
+        let mut sha3 = Sha3_256::new();
+        sha3.update(tag);
+        sha3.update(b"Empty");
+        return sha3.finalize().into();
+    }
+    while leaves.len() > 1 {
+        leaves = next_level(tag, &leaves);
+    }
+    leaves[0]
+}
+
+/// Returns the sibling hashes on the path from the leaf at `index` to
+/// the root of the tree tagged `tag` over the hashed `leaves`.
+pub(crate) fn path(tag: &[u8], mut leaves: Vec<[u8; 32]>, index: usize) -> Vec<[u8; 32]> {
+    let mut siblings = Vec::new();
+    let mut i = index;
+    while leaves.len() > 1 {
+        if let Some(sibling) = leaves.get(i ^ 1) {
+            siblings.push(*sibling);
+        }
+        leaves = next_level(tag, &leaves);
+        i /= 2;
+    }
+    siblings
+}
+
+/// Verifies that `leaf` is the leaf at `index` of a tree tagged `tag`
+/// with `num_leaves` leaves and the given `root`, with the sibling
+/// hashes returned by [`path`].
+pub(crate) fn verify_path(
+    tag: &[u8],
+    leaf: [u8; 32],
+    index: usize,
+    num_leaves: usize,
+    siblings: &[[u8; 32]],
+    root: &[u8; 32],
+) -> Result<(), ProofError> {
+    if index >= num_leaves {
+        return Err(ProofError::VerificationError);
+    }
+
+    let mut hash = leaf;
+    let mut siblings = siblings.iter();
+    let (mut i, mut len) = (index, num_leaves);
+    while len > 1 {
+        // The last node of an odd level has no sibling
+        if i ^ 1 < len {
+            let sibling = siblings.next().ok_or(ProofError::VerificationError)?;
+            hash = if i % 2 == 0 {
+                node_hash(tag, &hash, sibling)
+            } else {
+                node_hash(tag, sibling, &hash)
+            };
+        }
+        i /= 2;
+        len -= len / 2;
+    }
+
+    if siblings.next().is_none() && hash == *root {
+        Ok(())
+    } else {
+        Err(ProofError::VerificationError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_verify_under_the_root() {
+        let tag = b"MerkleTest";
+        for num_leaves in 1..10u8 {
+            let leaves: Vec<[u8; 32]> = (0..num_leaves).map(|i| leaf_hash(tag, &[&[i]])).collect();
+            let root = root(tag, leaves.clone());
+
+            for (index, leaf) in leaves.iter().enumerate() {
+                let siblings = path(tag, leaves.clone(), index);
+                let num_leaves = num_leaves as usize;
+                assert_eq!(
+                    verify_path(tag, *leaf, index, num_leaves, &siblings, &root),
+                    Ok(())
+                );
+                assert!(verify_path(tag, *leaf, num_leaves, num_leaves, &siblings, &root).is_err());
+                if num_leaves > 1 {
+                    assert!(
+                        verify_path(b"Other", *leaf, index, num_leaves, &siblings, &root).is_err()
+                    );
+                    let other = leaves[(index + 1) % num_leaves];
+                    assert!(verify_path(tag, other, index, num_leaves, &siblings, &root).is_err());
+                    assert!(verify_path(tag, *leaf, index, num_leaves, &[], &root).is_err());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Verification of mint-signed outputs.
+//!
+//! A mint signs the set of output commitments of a transaction with
+//! its BLS key, and every output carries a range proof.  The functions
+//! in this module check both the mint's signature and all of the range
+//! proofs, operating on a single decoded [`ProofBatch`] so that the
+//! commitments are only decompressed once.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use blstrs::{G1Affine, G2Affine, G2Projective, Scalar};
+use group::Curve;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::ProofBatch;
+use crate::util;
+
+/// Domain separation tag for hashing a commitment set to \\(\mathbb G\_2\\).
+const COMMITMENTS_DST: &[u8] = b"BLS_BULLETPROOFS_MINT_COMMITMENTS_BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+/// Returns the message a mint signs for the output commitments in
+/// `batch`: the compressed commitments of every proof, in batch order.
+pub fn commitments_message(batch: &ProofBatch) -> Vec<u8> {
+    batch
+        .iter()
+        .flat_map(|(_, commitments, _)| commitments.iter())
+        .flat_map(|V| V.to_compressed())
+        .collect()
+}
+
+/// Hashes the commitment set of `batch` to \\(\mathbb G\_2\\).
+fn commitments_point(batch: &ProofBatch) -> G2Affine {
+    G2Projective::hash_to_curve(&commitments_message(batch), COMMITMENTS_DST, &[]).to_affine()
+}
+
+/// Signs the output commitments in `batch` with the mint's `secret_key`.
+pub fn sign_commitments(secret_key: &Scalar, batch: &ProofBatch) -> G2Affine {
+    (commitments_point(batch) * secret_key).to_affine()
+}
+
+/// Verifies the mint's `signature` over the output commitments in
+/// `batch`, and all range proofs in `batch`.
+///
+/// The signature is checked against `mint_public_key` with a pairing
+/// check before the range proofs are batch verified as with
+/// [`ProofBatch::verify_with_rng`].
+pub fn verify_outputs_with_rng<T: RngCore + CryptoRng>(
+    mint_public_key: &G1Affine,
+    signature: &G2Affine,
+    batch: &ProofBatch,
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    transcript: &Transcript,
+    rng: &mut T,
+) -> Result<(), ProofError> {
+    if !util::verify_bls_signature(mint_public_key, &commitments_point(batch), signature) {
+        return Err(ProofError::VerificationError);
+    }
+    batch.verify_with_rng(bp_gens, pc_gens, transcript, rng)
+}
+
+/// Verifies the mint's `signature` over the output commitments in
+/// `batch`, and all range proofs in `batch`.
+/// This is a convenience wrapper around [`verify_outputs_with_rng`],
+/// passing in a threadsafe RNG.
+#[cfg(feature = "thread_rng")]
+pub fn verify_outputs(
+    mint_public_key: &G1Affine,
+    signature: &G2Affine,
+    batch: &ProofBatch,
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    transcript: &Transcript,
+) -> Result<(), ProofError> {
+    verify_outputs_with_rng(
+        mint_public_key,
+        signature,
+        batch,
+        bp_gens,
+        pc_gens,
+        transcript,
+        &mut thread_rng(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use group::ff::Field;
+    use group::prime::PrimeCurveAffine;
+
+    use crate::range_proof::RangeProof;
+
+    #[test]
+    fn verifies_signature_and_proofs() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let mut rng = rand::thread_rng();
+
+        let mut batch = ProofBatch::new();
+        for value in [3u64, 1 << 40] {
+            let mut transcript = Transcript::new(b"MintTest");
+            let (proof, commitment) = RangeProof::prove_single(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                value,
+                &Scalar::random(&mut rng),
+                64,
+            )
+            .unwrap();
+            batch.push(proof, vec![commitment], 64).unwrap();
+        }
+
+        let secret_key = Scalar::random(&mut rng);
+        let public_key = (G1Affine::generator() * secret_key).to_affine();
+        let signature = sign_commitments(&secret_key, &batch);
+        let transcript = Transcript::new(b"MintTest");
+
+        assert!(verify_outputs(
+            &public_key,
+            &signature,
+            &batch,
+            &bp_gens,
+            &pc_gens,
+            &transcript
+        )
+        .is_ok());
+
+        let other_key = (G1Affine::generator() * Scalar::random(&mut rng)).to_affine();
+        assert_eq!(
+            verify_outputs(
+                &other_key,
+                &signature,
+                &batch,
+                &bp_gens,
+                &pc_gens,
+                &transcript
+            ),
+            Err(ProofError::VerificationError)
+        );
+    }
+}
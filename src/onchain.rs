@@ -0,0 +1,157 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! A verification-only facade for on-chain runtimes, such as Substrate
+//! pallets and ink! contracts.
+//!
+//! The facade works without the `std` feature, and does not need an
+//! RNG: the random challenge combining the checks of the verification
+//! equation is derived from the transcript after the proof has been
+//! replayed, so verification is a deterministic function of its
+//! inputs.  Proofs and commitments are taken in their serialized
+//! form, as they arrive in extrinsics.
+//!
+//! The cost of verification is dominated by a single multiscalar
+//! multiplication, whose size only depends on the bitsize and the
+//! number of values, and is returned by [`verification_terms`] before
+//! any proof data is decoded.  Runtimes can benchmark the cost of one
+//! term and charge weights proportionally.
+//!
+//! The facade is only available with the `onchain` feature.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use blstrs::{G1Affine, Scalar};
+use group::ff::Field;
+use group::Group;
+
+pub use crate::errors::ProofError;
+pub use crate::generators::{BulletproofGens, PedersenGens};
+pub use merlin::Transcript;
+
+use crate::range_proof::{pad_commitments, padded_params, RangeProof, VerificationAccumulator};
+use crate::transcript::TranscriptProtocol;
+use crate::util::read48;
+
+/// Returns the number of terms of the multiscalar multiplication that
+/// verifies a proof of `m` values in the range \\([0, 2^n)\\).
+///
+/// This is an upper bound, which is attained by proofs created with
+/// the default inner-product cutoff.  Returns `None` if `n` is not a
+/// valid bitsize or `m` is not a power of two.
+pub fn verification_terms(n: usize, m: usize) -> Option<usize> {
+    RangeProof::verification_cost(n, m)
+        .ok()
+        .map(|cost| cost.msm_terms)
+}
+
+/// Verifies a serialized range proof for the given compressed value
+/// commitments.
+///
+/// The proof must be in the versioned encoding, whose header is
+/// checked against `n` and the number of commitments before the rest
+/// of the proof is decoded.  As for [`RangeProof::verify_multiple`],
+/// `transcript` must have the same initial state as the prover's.
+pub fn verify_range_proof(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    transcript: &mut Transcript,
+    proof: &[u8],
+    value_commitments: &[[u8; 48]],
+    n: usize,
+) -> Result<(), ProofError> {
+    let shape = RangeProof::decode_header(proof)?;
+    if (shape.n, shape.m) != padded_params(n, value_commitments.len()) {
+        return Err(ProofError::ParameterMismatch);
+    }
+    let proof = RangeProof::from_bytes(proof)?;
+    let value_commitments = value_commitments
+        .iter()
+        .map(|V| Option::from(G1Affine::from_compressed(&read48(V))).ok_or(ProofError::FormatError))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Derive the combining challenge from a replay of the whole proof,
+    // so that it is bound to all proof data.
+    let mut replay = transcript.clone();
+    let (padded_commitments, padded_n) =
+        pad_commitments(pc_gens, &mut replay, &value_commitments, n);
+    proof.verification_scalars(&mut replay, &padded_commitments, padded_n)?;
+    let c = replay.challenge_scalar(b"c");
+
+    let mut acc = VerificationAccumulator::new(bp_gens, pc_gens);
+    proof.accumulate_verification(
+        &mut acc,
+        Scalar::one(),
+        c,
+        transcript,
+        &value_commitments,
+        n,
+    )?;
+
+    if bool::from(acc.evaluate().is_identity()) {
+        Ok(())
+    } else {
+        Err(ProofError::VerificationError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use group::Curve;
+
+    #[test]
+    fn verifies_serialized_proofs() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let (proof, commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"OnchainTest"),
+            &[5, 9],
+            &[Scalar::from(1u64), Scalar::from(2u64)],
+            32,
+        )
+        .unwrap();
+        let proof = proof.to_bytes();
+        let commitments: Vec<[u8; 48]> = commitments.iter().map(|V| V.to_compressed()).collect();
+
+        let verify = |commitments: &[[u8; 48]], n| {
+            let mut transcript = Transcript::new(b"OnchainTest");
+            verify_range_proof(&bp_gens, &pc_gens, &mut transcript, &proof, commitments, n)
+        };
+        assert_eq!(verify(&commitments, 32), Ok(()));
+        assert_eq!(
+            verify(&[commitments[1], commitments[0]], 32),
+            Err(ProofError::VerificationError)
+        );
+        assert_eq!(
+            verify(&commitments[..1], 32),
+            Err(ProofError::ParameterMismatch)
+        );
+        assert_eq!(verify(&commitments, 64), Err(ProofError::ParameterMismatch));
+
+        let (scalars, _) = RangeProof::from_bytes(&proof)
+            .unwrap()
+            .verification_terms(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"OnchainTest"),
+                &[pc_gens
+                    .commit(Scalar::from(5u64), Scalar::from(1u64))
+                    .to_affine(); 2],
+                32,
+            )
+            .unwrap();
+        assert_eq!(verification_terms(32, 2), Some(scalars.len()));
+        assert_eq!(verification_terms(12, 2), None);
+    }
+}
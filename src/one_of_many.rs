@@ -0,0 +1,537 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Groth–Kohlweiss one-of-many proofs.
+//!
+//! A [`OneOfManyProof`] shows that one commitment in a public list
+//! \\(C\_0, \ldots, C\_{N-1}\\) is a commitment to zero, i.e. that the
+//! prover knows an index \\(\ell\\) and a blinding factor \\(r\\) with
+//! \\(C\_\ell = r \cdot \tilde{B}\\), without revealing \\(\ell\\).  This
+//! is ring membership: to prove that a commitment \\(C\\) is one of a
+//! set of commitments \\(C'\_i\\) to the same value, prove that one of
+//! \\(C'\_i - C\\) commits to zero.
+//!
+//! The proof follows [Groth and Kohlweiss](https://eprint.iacr.org/2014/764)
+//! and has size logarithmic in \\(N\\): the prover commits to the bits
+//! \\(\ell\_j\\) of the index, and shows that the polynomial
+//! \\(\sum\_i p\_i(x) C\_i\\), where \\(p\_i(x)\\) has leading coefficient
+//! one if \\(i = \ell\\) and zero otherwise, has no \\(B\\) component in
+//! its leading coefficient.  Lists whose length is not a power of two
+//! are padded by repeating the last commitment.
+
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use group::ff::Field;
+use group::{Curve, Group};
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::PedersenGens;
+use crate::transcript::TranscriptProtocol;
+use crate::util::{self, read32, read48};
+
+/// A proof that one commitment in a list is a commitment to zero.
+///
+/// # Encoding
+///
+/// For a list padded to \\(2^m\\) commitments, the proof is encoded as
+/// the compressed points \\(\mathbf{c}\_l\\), \\(\mathbf{c}\_a\\),
+/// \\(\mathbf{c}\_b\\) and \\(\mathbf{c}\_d\\), followed by the scalars
+/// \\(\mathbf{f}\\), \\(\mathbf{z}\_a\\), \\(\mathbf{z}\_b\\) and
+/// \\(z\_d\\), in \\(288m + 32\\) bytes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OneOfManyProof {
+    /// Commitments to the bits of the index
+    c_l: Vec<G1Affine>,
+    /// Commitments to the masks of the bits
+    c_a: Vec<G1Affine>,
+    /// Commitments to the products of the bits and their masks
+    c_b: Vec<G1Affine>,
+    /// Commitments to the low coefficients of the polynomial
+    c_d: Vec<G1Affine>,
+    /// The masked bits
+    f: Vec<Scalar>,
+    /// The blinding factors opening the masked bits
+    z_a: Vec<Scalar>,
+    /// The blinding factors showing that the bits are bits
+    z_b: Vec<Scalar>,
+    /// The blinding factor of the polynomial evaluation
+    z_d: Scalar,
+}
+
+impl OneOfManyProof {
+    /// Creates a proof that `commitments[index]` is the commitment to
+    /// zero with the given `blinding` factor.
+    ///
+    /// Returns [`ProofError::FormatError`] if the list is empty or the
+    /// index is out of range, and [`ProofError::VerificationError`] if
+    /// the commitment at `index` is not `blinding * B_blinding`.
+    pub fn create_with_rng<T: RngCore + CryptoRng>(
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        commitments: &[G1Affine],
+        index: usize,
+        blinding: &Scalar,
+        rng: &mut T,
+    ) -> Result<OneOfManyProof, ProofError> {
+        if index >= commitments.len() {
+            return Err(ProofError::FormatError);
+        }
+        if (pc_gens.B_blinding * blinding).to_affine() != commitments[index] {
+            return Err(ProofError::VerificationError);
+        }
+
+        let m = bit_length(commitments.len());
+        let mut random = || -> Vec<Scalar> { (0..m).map(|_| Scalar::random(&mut *rng)).collect() };
+        let (r, a, s, t, rho) = (random(), random(), random(), random(), random());
+        let l: Vec<Scalar> = (0..m)
+            .map(|j| Scalar::from(((index >> j) & 1) as u64))
+            .collect();
+
+        let commit = |v: &Scalar, r: &Scalar| pc_gens.commit(*v, *r).to_affine();
+        let c_l: Vec<G1Affine> = (0..m).map(|j| commit(&l[j], &r[j])).collect();
+        let c_a: Vec<G1Affine> = (0..m).map(|j| commit(&a[j], &s[j])).collect();
+        let c_b: Vec<G1Affine> = (0..m).map(|j| commit(&(l[j] * a[j]), &t[j])).collect();
+
+        // The coefficients of p_i(x) = prod_j f_{j,i_j}(x), where
+        // f_{j,1}(x) = l_j x + a_j and f_{j,0}(x) = (1 - l_j) x - a_j.
+        let mut coefficients = vec![Scalar::zero(); m];
+        let mut c_d: Vec<G1Projective> = (0..m).map(|k| pc_gens.B_blinding * rho[k]).collect();
+        for i in 0..(1 << m) {
+            let mut p = vec![Scalar::one()];
+            for j in 0..m {
+                let (c0, c1) = if (i >> j) & 1 == 1 {
+                    (a[j], l[j])
+                } else {
+                    (-a[j], Scalar::one() - l[j])
+                };
+                let mut next = vec![Scalar::zero(); p.len() + 1];
+                for (k, p_k) in p.iter().enumerate() {
+                    next[k] += c0 * p_k;
+                    next[k + 1] += c1 * p_k;
+                }
+                p = next;
+            }
+            coefficients.copy_from_slice(&p[..m]);
+
+            let C = G1Projective::from(commitments[i.min(commitments.len() - 1)]);
+            for (c_d_k, p_k) in c_d.iter_mut().zip(coefficients.iter()) {
+                *c_d_k += C * p_k;
+            }
+        }
+        let c_d: Vec<G1Affine> = c_d.iter().map(|P| P.to_affine()).collect();
+
+        let x = OneOfManyProof::challenge(transcript, commitments, &c_l, &c_a, &c_b, &c_d);
+
+        let f: Vec<Scalar> = (0..m).map(|j| l[j] * x + a[j]).collect();
+        let z_a = (0..m).map(|j| r[j] * x + s[j]).collect();
+        let z_b = (0..m).map(|j| r[j] * (x - f[j]) + t[j]).collect();
+        let mut x_k = Scalar::one();
+        let mut z_d = Scalar::zero();
+        for rho_k in rho.iter() {
+            z_d -= rho_k * x_k;
+            x_k *= x;
+        }
+        z_d += blinding * x_k;
+
+        Ok(OneOfManyProof {
+            c_l,
+            c_a,
+            c_b,
+            c_d,
+            f,
+            z_a,
+            z_b,
+            z_d,
+        })
+    }
+
+    /// Creates a proof that `commitments[index]` is the commitment to zero
+    /// with the given `blinding` factor.
+    /// This is a convenience wrapper around [`OneOfManyProof::create_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn create(
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        commitments: &[G1Affine],
+        index: usize,
+        blinding: &Scalar,
+    ) -> Result<OneOfManyProof, ProofError> {
+        OneOfManyProof::create_with_rng(
+            pc_gens,
+            transcript,
+            commitments,
+            index,
+            blinding,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies that one of `commitments` is a commitment to zero.
+    ///
+    /// All verification equations are combined with random weights from
+    /// `rng` and checked with one multiscalar multiplication.
+    pub fn verify_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        commitments: &[G1Affine],
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let mut terms = Terms::default();
+        self.verification_terms(transcript, commitments, Scalar::one(), rng, &mut terms)?;
+        terms.check(pc_gens)
+    }
+
+    /// Verifies that one of `commitments` is a commitment to zero.
+    /// This is a convenience wrapper around [`OneOfManyProof::verify_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify(
+        &self,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        commitments: &[G1Affine],
+    ) -> Result<(), ProofError> {
+        self.verify_with_rng(pc_gens, transcript, commitments, &mut thread_rng())
+    }
+
+    /// Verifies a batch of proofs, each against its list of commitments,
+    /// with one multiscalar multiplication.
+    ///
+    /// As with [`ProofBatch`](crate::ProofBatch), every proof is verified
+    /// against its own copy of `transcript`.
+    pub fn verify_batch_with_rng<T: RngCore + CryptoRng>(
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        proofs: &[(&OneOfManyProof, &[G1Affine])],
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let mut terms = Terms::default();
+        for (proof, commitments) in proofs.iter() {
+            let weight = Scalar::random(&mut *rng);
+            proof.verification_terms(
+                &mut transcript.clone(),
+                commitments,
+                weight,
+                rng,
+                &mut terms,
+            )?;
+        }
+        terms.check(pc_gens)
+    }
+
+    /// Verifies a batch of proofs, each against its list of commitments.
+    /// This is a convenience wrapper around [`OneOfManyProof::verify_batch_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify_batch(
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        proofs: &[(&OneOfManyProof, &[G1Affine])],
+    ) -> Result<(), ProofError> {
+        OneOfManyProof::verify_batch_with_rng(pc_gens, transcript, proofs, &mut thread_rng())
+    }
+
+    /// Adds the verification equations of the proof, scaled by
+    /// `weight` and combined with random weights from `rng`, to `terms`.
+    fn verification_terms<T: RngCore + CryptoRng>(
+        &self,
+        transcript: &mut Transcript,
+        commitments: &[G1Affine],
+        weight: Scalar,
+        rng: &mut T,
+        terms: &mut Terms,
+    ) -> Result<(), ProofError> {
+        let m = self.c_l.len();
+        if commitments.is_empty() || m != bit_length(commitments.len()) {
+            return Err(ProofError::VerificationError);
+        }
+
+        let x = OneOfManyProof::challenge(
+            transcript,
+            commitments,
+            &self.c_l,
+            &self.c_a,
+            &self.c_b,
+            &self.c_d,
+        );
+
+        for j in 0..m {
+            // x c_l + c_a == f B + z_a B_blinding
+            let w = weight * Scalar::random(&mut *rng);
+            terms.push(w * x, self.c_l[j]);
+            terms.push(w, self.c_a[j]);
+            terms.B -= w * self.f[j];
+            terms.B_blinding -= w * self.z_a[j];
+
+            // (x - f) c_l + c_b == z_b B_blinding
+            let w = weight * Scalar::random(&mut *rng);
+            terms.push(w * (x - self.f[j]), self.c_l[j]);
+            terms.push(w, self.c_b[j]);
+            terms.B_blinding -= w * self.z_b[j];
+        }
+
+        // sum_i p_i(x) C_i - sum_k x^k c_d_k == z_d B_blinding
+        let w = weight * Scalar::random(&mut *rng);
+        let mut p_sum = Scalar::zero();
+        for i in 0..(1 << m) {
+            let p_i = (0..m).fold(Scalar::one(), |acc, j| {
+                if (i >> j) & 1 == 1 {
+                    acc * self.f[j]
+                } else {
+                    acc * (x - self.f[j])
+                }
+            });
+            // The padding repeats the last commitment
+            if i < commitments.len() - 1 {
+                terms.push(w * p_i, commitments[i]);
+            } else {
+                p_sum += p_i;
+            }
+        }
+        terms.push(w * p_sum, commitments[commitments.len() - 1]);
+        let mut x_k = Scalar::one();
+        for c_d_k in self.c_d.iter() {
+            terms.push(-(w * x_k), *c_d_k);
+            x_k *= x;
+        }
+        terms.B_blinding -= w * self.z_d;
+
+        Ok(())
+    }
+
+    /// Appends the statement and the proof commitments to `transcript`,
+    /// and returns the challenge.
+    fn challenge(
+        transcript: &mut Transcript,
+        commitments: &[G1Affine],
+        c_l: &[G1Affine],
+        c_a: &[G1Affine],
+        c_b: &[G1Affine],
+        c_d: &[G1Affine],
+    ) -> Scalar {
+        transcript.append_message(b"dom-sep", b"one-of-many v1");
+        transcript.append_u64(b"N", commitments.len() as u64);
+        for C in commitments.iter() {
+            transcript.append_point(b"C", &C.into());
+        }
+        for (label, points) in [(b"c_l", c_l), (b"c_a", c_a), (b"c_b", c_b), (b"c_d", c_d)] {
+            for P in points.iter() {
+                transcript.append_point(label, &P.into());
+            }
+        }
+        transcript.challenge_scalar(b"x")
+    }
+
+    /// Serializes the proof into a byte vector.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let m = self.c_l.len();
+        let mut buf = Vec::with_capacity(288 * m + 32);
+        for P in self
+            .c_l
+            .iter()
+            .chain(self.c_a.iter())
+            .chain(self.c_b.iter())
+            .chain(self.c_d.iter())
+        {
+            buf.extend_from_slice(&P.to_compressed());
+        }
+        for s in self
+            .f
+            .iter()
+            .chain(self.z_a.iter())
+            .chain(self.z_b.iter())
+            .chain(core::iter::once(&self.z_d))
+        {
+            buf.extend_from_slice(&s.to_bytes_le());
+        }
+        buf
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a `OneOfManyProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<OneOfManyProof, ProofError> {
+        let m = slice.len().saturating_sub(32) / 288;
+        if m == 0 || m >= 32 || slice.len() != 288 * m + 32 {
+            return Err(ProofError::FormatError);
+        }
+
+        let (points, scalars) = slice.split_at(4 * m * 48);
+        let points = points
+            .chunks(48)
+            .map(|chunk| {
+                Option::from(G1Affine::from_compressed(&read48(chunk)))
+                    .ok_or(ProofError::FormatError)
+            })
+            .collect::<Result<Vec<G1Affine>, ProofError>>()?;
+        let scalars = scalars
+            .chunks(32)
+            .map(|chunk| {
+                Option::from(Scalar::from_bytes_le(&read32(chunk))).ok_or(ProofError::FormatError)
+            })
+            .collect::<Result<Vec<Scalar>, ProofError>>()?;
+
+        Ok(OneOfManyProof {
+            c_l: points[..m].to_vec(),
+            c_a: points[m..2 * m].to_vec(),
+            c_b: points[2 * m..3 * m].to_vec(),
+            c_d: points[3 * m..].to_vec(),
+            f: scalars[..m].to_vec(),
+            z_a: scalars[m..2 * m].to_vec(),
+            z_b: scalars[2 * m..3 * m].to_vec(),
+            z_d: scalars[3 * m],
+        })
+    }
+}
+
+/// The terms of one or more combined verification equations.
+#[derive(Default)]
+struct Terms {
+    /// Scalar for the Pedersen value base \\(B\\)
+    B: Scalar,
+    /// Scalar for the Pedersen blinding base \\(\tilde{B}\\)
+    B_blinding: Scalar,
+    /// Scalars for the proof-specific points
+    scalars: Vec<Scalar>,
+    /// Proof-specific points: proof commitments and the listed commitments
+    points: Vec<G1Projective>,
+}
+
+impl Terms {
+    fn push(&mut self, scalar: Scalar, point: G1Affine) {
+        self.scalars.push(scalar);
+        self.points.push(point.into());
+    }
+
+    /// Checks that the sum of all terms is the identity.
+    fn check(self, pc_gens: &PedersenGens) -> Result<(), ProofError> {
+        let sum: G1Projective = util::multiscalar_mul(&self.scalars, &self.points)
+            + pc_gens.B * self.B
+            + pc_gens.B_blinding * self.B_blinding;
+
+        if bool::from(sum.is_identity()) {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+}
+
+/// Returns the number of bits of the indices of a list of `len`
+/// commitments, which is at least one.
+fn bit_length(len: usize) -> usize {
+    (usize::BITS - (len.max(2) - 1).leading_zeros()) as usize
+}
+
+impl Serialize for OneOfManyProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for OneOfManyProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        util::deserialize_bytes(deserializer, "OneOfManyProof", OneOfManyProof::from_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_of_many_membership() {
+        let pc_gens = PedersenGens::default();
+        let mut rng = rand::thread_rng();
+
+        assert_eq!(bit_length(1), 1);
+        assert_eq!(bit_length(2), 1);
+        assert_eq!(bit_length(5), 3);
+        assert_eq!(bit_length(8), 3);
+
+        // A list of five commitments, padded to eight
+        let blinding = Scalar::random(&mut rng);
+        let mut commitments: Vec<G1Affine> = (1..=5u64)
+            .map(|v| {
+                pc_gens
+                    .commit(Scalar::from(v), Scalar::random(&mut rng))
+                    .to_affine()
+            })
+            .collect();
+
+        let mut proofs = Vec::new();
+        let mut lists = Vec::new();
+        for index in [0, 2, 4] {
+            let mut list = commitments.clone();
+            list[index] = (pc_gens.B_blinding * blinding).to_affine();
+            let mut transcript = Transcript::new(b"OneOfManyTest");
+            let proof =
+                OneOfManyProof::create(&pc_gens, &mut transcript, &list, index, &blinding).unwrap();
+            assert_eq!(proof.to_bytes().len(), 288 * 3 + 32);
+
+            let mut transcript = Transcript::new(b"OneOfManyTest");
+            assert!(proof.verify(&pc_gens, &mut transcript, &list).is_ok());
+            proofs.push(proof);
+            lists.push(list);
+        }
+
+        let batch: Vec<(&OneOfManyProof, &[G1Affine])> = proofs
+            .iter()
+            .zip(lists.iter())
+            .map(|(proof, list)| (proof, &list[..]))
+            .collect();
+        let transcript = Transcript::new(b"OneOfManyTest");
+        assert!(OneOfManyProof::verify_batch(&pc_gens, &transcript, &batch).is_ok());
+
+        let decoded = OneOfManyProof::from_bytes(&proofs[0].to_bytes()).unwrap();
+        assert_eq!(decoded, proofs[0]);
+        let decoded: OneOfManyProof =
+            bincode::deserialize(&bincode::serialize(&proofs[0]).unwrap()).unwrap();
+        assert_eq!(decoded, proofs[0]);
+
+        // The proof does not verify for a list without a commitment to zero
+        let mut transcript = Transcript::new(b"OneOfManyTest");
+        assert_eq!(
+            proofs[0].verify(&pc_gens, &mut transcript, &commitments),
+            Err(ProofError::VerificationError)
+        );
+        let mut swapped = lists.clone();
+        swapped.swap(0, 1);
+        let batch: Vec<(&OneOfManyProof, &[G1Affine])> = proofs
+            .iter()
+            .zip(swapped.iter())
+            .map(|(proof, list)| (proof, &list[..]))
+            .collect();
+        assert!(OneOfManyProof::verify_batch(&pc_gens, &transcript, &batch).is_err());
+
+        let mut transcript = Transcript::new(b"OneOfManyTest");
+        commitments[1] = pc_gens.commit(Scalar::one(), blinding).to_affine();
+        assert_eq!(
+            OneOfManyProof::create(&pc_gens, &mut transcript, &commitments, 1, &blinding),
+            Err(ProofError::VerificationError)
+        );
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Commitments to byte strings, and proofs of their opening.
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use group::ff::Field;
+use group::Curve;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::commitments::Commitment;
+use crate::errors::ProofError;
+use crate::generators::PedersenGens;
+use crate::hash::hash_to_scalar_with_dst;
+use crate::transcript::TranscriptProtocol;
+use crate::util::{self, read32, read48};
+
+/// Domain separation tag for hashing messages to scalars.
+const MESSAGE_DST: &[u8] = b"BLS_BULLETPROOFS_MESSAGE_TO_SCALAR_SHA3-256_CHACHA20_";
+
+/// Hashes `msg` to a scalar, which is the value committed to by
+/// [`Commitment::commit_to_bytes`].
+///
+/// This is [`hash_to_scalar_with_dst`] under a fixed domain
+/// separation tag.
+pub fn hash_to_scalar(msg: &[u8]) -> Scalar {
+    hash_to_scalar_with_dst(MESSAGE_DST, msg)
+}
+
+impl Commitment {
+    /// Commits to the byte string `msg`, hashed to a scalar with
+    /// [`hash_to_scalar`], with the given `blinding` factor.
+    ///
+    /// The commitment can be opened by revealing `msg` together with an
+    /// [`OpeningProof`], without revealing the blinding factor.
+    pub fn commit_to_bytes(pc_gens: &PedersenGens, msg: &[u8], blinding: Scalar) -> Self {
+        Commitment::new(pc_gens, hash_to_scalar(msg), blinding)
+    }
+}
+
+/// A proof that a [`Commitment`] commits to a given byte string.
+///
+/// Given the commitment \\(C\\) and the message \\(m\\), the proof is a
+/// Schnorr proof of knowledge of the blinding factor \\(\tilde{v}\\)
+/// with \\(C - H(m) \cdot B = \tilde{v} \cdot \tilde{B}\\), so it does
+/// not reveal the blinding factor.
+///
+/// # Encoding
+///
+/// The proof is encoded as the compressed nonce commitment \\(R\\),
+/// followed by the response scalar \\(s\\), in 80 bytes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct OpeningProof {
+    R: G1Affine,
+    s: Scalar,
+}
+
+impl OpeningProof {
+    /// Creates a proof that the commitment to `msg` with `blinding`
+    /// (as returned by [`Commitment::commit_to_bytes`]) opens to `msg`.
+    ///
+    /// Returns the proof together with the commitment.
+    pub fn create_with_rng<T: RngCore + CryptoRng>(
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        msg: &[u8],
+        blinding: Scalar,
+        rng: &mut T,
+    ) -> Result<(OpeningProof, Commitment), ProofError> {
+        let commitment = Commitment::commit_to_bytes(pc_gens, msg, blinding);
+
+        let k = Scalar::random(rng);
+        let R = pc_gens.B_blinding * k;
+        let c = OpeningProof::challenge(transcript, &commitment, msg, &R)?;
+        let s = k + c * blinding;
+
+        Ok((
+            OpeningProof {
+                R: R.to_affine(),
+                s,
+            },
+            commitment,
+        ))
+    }
+
+    /// Creates a proof that the commitment to `msg` with `blinding` opens to `msg`.
+    /// This is a convenience wrapper around [`OpeningProof::create_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn create(
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        msg: &[u8],
+        blinding: Scalar,
+    ) -> Result<(OpeningProof, Commitment), ProofError> {
+        OpeningProof::create_with_rng(pc_gens, transcript, msg, blinding, &mut thread_rng())
+    }
+
+    /// Verifies that `commitment` commits to `msg`.
+    pub fn verify(
+        &self,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        commitment: &Commitment,
+        msg: &[u8],
+    ) -> Result<(), ProofError> {
+        let R = G1Projective::from(self.R);
+        let c = OpeningProof::challenge(transcript, commitment, msg, &R)?;
+
+        // s * B_blinding == R + c * (C - H(m) * B)
+        let excess = commitment.0 - pc_gens.B * hash_to_scalar(msg);
+        if pc_gens.B_blinding * self.s == R + excess * c {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Appends the statement and the nonce commitment to `transcript`,
+    /// and returns the challenge.
+    fn challenge(
+        transcript: &mut Transcript,
+        commitment: &Commitment,
+        msg: &[u8],
+        R: &G1Projective,
+    ) -> Result<Scalar, ProofError> {
+        transcript.append_message(b"dom-sep", b"opening v1");
+        transcript.append_point(b"C", commitment.as_point());
+        transcript.append_message(b"msg", msg);
+        transcript.validate_and_append_point(b"R", R)?;
+        Ok(transcript.challenge_scalar(b"c"))
+    }
+
+    /// Serializes the proof into an 80-byte array.
+    pub fn to_bytes(&self) -> [u8; 80] {
+        let mut buf = [0u8; 80];
+        buf[..48].copy_from_slice(&self.R.to_compressed());
+        buf[48..].copy_from_slice(&self.s.to_bytes_le());
+        buf
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into an `OpeningProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<OpeningProof, ProofError> {
+        if slice.len() != 80 {
+            return Err(ProofError::FormatError);
+        }
+        let R = Option::from(G1Affine::from_compressed(&read48(slice)))
+            .ok_or(ProofError::FormatError)?;
+        let s = Option::from(Scalar::from_bytes_le(&read32(&slice[48..])))
+            .ok_or(ProofError::FormatError)?;
+        Ok(OpeningProof { R, s })
+    }
+}
+
+impl Serialize for OpeningProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for OpeningProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        util::deserialize_bytes(deserializer, "OpeningProof", OpeningProof::from_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opening_proof_binds_message() {
+        let pc_gens = PedersenGens::default();
+        let blinding = Scalar::random(&mut rand::thread_rng());
+        let msg = b"metadata blob";
+
+        assert_eq!(hash_to_scalar(msg), hash_to_scalar(msg));
+        assert_ne!(hash_to_scalar(msg), hash_to_scalar(b"metadata blob "));
+
+        let mut transcript = Transcript::new(b"OpeningTest");
+        let (proof, commitment) =
+            OpeningProof::create(&pc_gens, &mut transcript, msg, blinding).unwrap();
+        assert_eq!(
+            commitment,
+            Commitment::commit_to_bytes(&pc_gens, msg, blinding)
+        );
+
+        let verify = |proof: &OpeningProof, commitment: &Commitment, msg: &[u8]| {
+            let mut transcript = Transcript::new(b"OpeningTest");
+            proof.verify(&pc_gens, &mut transcript, commitment, msg)
+        };
+        assert!(verify(&proof, &commitment, msg).is_ok());
+
+        let decoded = OpeningProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(verify(&decoded, &commitment, msg).is_ok());
+        let decoded: OpeningProof =
+            bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+        assert_eq!(decoded, proof);
+
+        assert_eq!(
+            verify(&proof, &commitment, b"other blob"),
+            Err(ProofError::VerificationError)
+        );
+        let other = Commitment::commit_to_bytes(&pc_gens, msg, blinding + Scalar::one());
+        assert_eq!(
+            verify(&proof, &other, msg),
+            Err(ProofError::VerificationError)
+        );
+        assert_eq!(
+            OpeningProof::from_bytes(&proof.to_bytes()[1..]),
+            Err(ProofError::FormatError)
+        );
+    }
+}
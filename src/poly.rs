@@ -0,0 +1,260 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![deny(missing_docs)]
+#![allow(non_snake_case)]
+
+//! Vector and scalar polynomials, as used by the range proof and the
+//! constraint system proof.
+//!
+//! The provers of both protocols commit to vector polynomials
+//! \\(\mathbf{l}(x)\\) and \\(\mathbf{r}(x)\\), and to the
+//! coefficients of their inner product
+//! \\(t(x) = \langle \mathbf{l}(x), \mathbf{r}(x) \rangle\\), before
+//! evaluating them at the challenge \\(x\\).  These types are exported
+//! for protocols built from the same steps.
+//!
+//! All types overwrite their coefficients when dropped.
+
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use blstrs::{G1Projective, Scalar};
+use clear_on_drop::clear::Clear;
+use group::ff::Field;
+
+use crate::generators::PedersenGens;
+pub use crate::inner_product_proof::inner_product;
+
+/// Represents a degree-1 vector polynomial \\(\mathbf{a} + \mathbf{b} \cdot x\\).
+pub struct VecPoly1(pub Vec<Scalar>, pub Vec<Scalar>);
+
+/// Represents a degree-3 vector polynomial
+/// \\(\mathbf{a} + \mathbf{b} \cdot x + \mathbf{c} \cdot x^2 + \mathbf{d} \cdot x^3 \\).
+pub struct VecPoly3(
+    pub Vec<Scalar>,
+    pub Vec<Scalar>,
+    pub Vec<Scalar>,
+    pub Vec<Scalar>,
+);
+
+/// Represents a degree-2 scalar polynomial \\(a + b \cdot x + c \cdot x^2\\)
+pub struct Poly2(pub Scalar, pub Scalar, pub Scalar);
+
+/// Represents a degree-6 scalar polynomial, without the zeroth degree
+/// \\(a \cdot x + b \cdot x^2 + c \cdot x^3 + d \cdot x^4 + e \cdot x^5 + f \cdot x^6\\)
+pub struct Poly6 {
+    /// The coefficient of \\(x\\).
+    pub t1: Scalar,
+    /// The coefficient of \\(x^2\\).
+    pub t2: Scalar,
+    /// The coefficient of \\(x^3\\).
+    pub t3: Scalar,
+    /// The coefficient of \\(x^4\\).
+    pub t4: Scalar,
+    /// The coefficient of \\(x^5\\).
+    pub t5: Scalar,
+    /// The coefficient of \\(x^6\\).
+    pub t6: Scalar,
+}
+
+/// Adds two vectors of scalars elementwise.
+///
+/// Panics if the lengths of \\(\mathbf{a}\\) and \\(\mathbf{b}\\) are not equal.
+pub fn add_vec(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
+    assert_eq!(a.len(), b.len());
+    a.iter().zip(b.iter()).map(|(a_i, b_i)| a_i + b_i).collect()
+}
+
+impl VecPoly1 {
+    /// Returns the zero polynomial of vectors of length `n`.
+    pub fn zero(n: usize) -> Self {
+        VecPoly1(vec![Scalar::zero(); n], vec![Scalar::zero(); n])
+    }
+
+    /// Computes the inner product \\(\langle \mathbf{l}(x), \mathbf{r}(x) \rangle\\)
+    /// of `self` and `rhs` as a scalar polynomial.
+    pub fn inner_product(&self, rhs: &VecPoly1) -> Poly2 {
+        // Uses Karatsuba's method
+        let l = self;
+        let r = rhs;
+
+        let t0 = inner_product(&l.0, &r.0);
+        let t2 = inner_product(&l.1, &r.1);
+
+        let l0_plus_l1 = add_vec(&l.0, &l.1);
+        let r0_plus_r1 = add_vec(&r.0, &r.1);
+
+        let t1 = inner_product(&l0_plus_l1, &r0_plus_r1) - t0 - t2;
+
+        Poly2(t0, t1, t2)
+    }
+
+    /// Evaluates the polynomial at `x`.
+    pub fn eval(&self, x: Scalar) -> Vec<Scalar> {
+        self.0
+            .iter()
+            .zip(self.1.iter())
+            .map(|(a_i, b_i)| a_i + b_i * x)
+            .collect()
+    }
+}
+
+impl VecPoly3 {
+    /// Returns the zero polynomial of vectors of length `n`.
+    pub fn zero(n: usize) -> Self {
+        VecPoly3(
+            vec![Scalar::zero(); n],
+            vec![Scalar::zero(); n],
+            vec![Scalar::zero(); n],
+            vec![Scalar::zero(); n],
+        )
+    }
+
+    /// Compute an inner product of `lhs`, `rhs` which have the property that:
+    /// - `lhs.0` is zero;
+    /// - `rhs.2` is zero.
+    ///
+    /// This is the case in the constraint system proof.
+    pub fn special_inner_product(lhs: &Self, rhs: &Self) -> Poly6 {
+        // TODO: make checks that l_poly.0 and r_poly.2 are zero.
+
+        let t1 = inner_product(&lhs.1, &rhs.0);
+        let t2 = inner_product(&lhs.1, &rhs.1) + inner_product(&lhs.2, &rhs.0);
+        let t3 = inner_product(&lhs.2, &rhs.1) + inner_product(&lhs.3, &rhs.0);
+        let t4 = inner_product(&lhs.1, &rhs.3) + inner_product(&lhs.3, &rhs.1);
+        let t5 = inner_product(&lhs.2, &rhs.3);
+        let t6 = inner_product(&lhs.3, &rhs.3);
+
+        Poly6 {
+            t1,
+            t2,
+            t3,
+            t4,
+            t5,
+            t6,
+        }
+    }
+
+    /// Evaluates the polynomial at `x`.
+    pub fn eval(&self, x: Scalar) -> Vec<Scalar> {
+        (0..self.0.len())
+            .map(|i| self.0[i] + x * (self.1[i] + x * (self.2[i] + x * self.3[i])))
+            .collect()
+    }
+}
+
+impl Poly2 {
+    /// Evaluates the polynomial at `x`.
+    pub fn eval(&self, x: Scalar) -> Scalar {
+        self.0 + x * (self.1 + x * self.2)
+    }
+
+    /// Commits to the coefficients of \\(x\\) and \\(x^2\\) with the
+    /// given blinding factors, returning \\(T\_1\\) and \\(T\_2\\).
+    ///
+    /// The constant coefficient is not committed to: in the range
+    /// proof, the verifier derives its commitment from the value
+    /// commitments and the challenges.
+    pub fn commit(
+        &self,
+        pc_gens: &PedersenGens,
+        t_1_blinding: Scalar,
+        t_2_blinding: Scalar,
+    ) -> (G1Projective, G1Projective) {
+        (
+            pc_gens.commit(self.1, t_1_blinding),
+            pc_gens.commit(self.2, t_2_blinding),
+        )
+    }
+}
+
+impl Poly6 {
+    /// Evaluates the polynomial at `x`.
+    pub fn eval(&self, x: Scalar) -> Scalar {
+        x * (self.t1 + x * (self.t2 + x * (self.t3 + x * (self.t4 + x * (self.t5 + x * self.t6)))))
+    }
+}
+
+impl Drop for VecPoly1 {
+    fn drop(&mut self) {
+        for e in self.0.iter_mut() {
+            e.clear();
+        }
+        for e in self.1.iter_mut() {
+            e.clear();
+        }
+    }
+}
+
+impl Drop for Poly2 {
+    fn drop(&mut self) {
+        self.0.clear();
+        self.1.clear();
+        self.2.clear();
+    }
+}
+
+impl Drop for VecPoly3 {
+    fn drop(&mut self) {
+        for e in self.0.iter_mut() {
+            e.clear();
+        }
+        for e in self.1.iter_mut() {
+            e.clear();
+        }
+        for e in self.2.iter_mut() {
+            e.clear();
+        }
+        for e in self.3.iter_mut() {
+            e.clear();
+        }
+    }
+}
+
+impl Drop for Poly6 {
+    fn drop(&mut self) {
+        self.t1.clear();
+        self.t2.clear();
+        self.t3.clear();
+        self.t4.clear();
+        self.t5.clear();
+        self.t6.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inner_product_commutes_with_eval() {
+        let scalars = |v: &[u64]| v.iter().map(|&x| Scalar::from(x)).collect::<Vec<_>>();
+        let l = VecPoly1(scalars(&[1, 2, 3]), scalars(&[4, 5, 6]));
+        let r = VecPoly1(scalars(&[7, 8, 9]), scalars(&[10, 11, 12]));
+        let t = l.inner_product(&r);
+
+        let x = Scalar::from(13u64);
+        assert_eq!(t.eval(x), inner_product(&l.eval(x), &r.eval(x)));
+
+        let l3 = VecPoly3(
+            scalars(&[0, 0]),
+            scalars(&[1, 2]),
+            scalars(&[3, 4]),
+            scalars(&[5, 6]),
+        );
+        let r3 = VecPoly3(
+            scalars(&[7, 8]),
+            scalars(&[9, 1]),
+            scalars(&[0, 0]),
+            scalars(&[2, 3]),
+        );
+        let t3 = VecPoly3::special_inner_product(&l3, &r3);
+        assert_eq!(t3.eval(x), inner_product(&l3.eval(x), &r3.eval(x)));
+    }
+}
@@ -13,7 +13,9 @@ mod constraint_system;
 mod linear_combination;
 mod metrics;
 mod proof;
+#[cfg(feature = "prover")]
 mod prover;
+#[cfg(feature = "verifier")]
 mod verifier;
 
 pub use self::constraint_system::{
@@ -22,7 +24,9 @@ pub use self::constraint_system::{
 pub use self::linear_combination::{LinearCombination, Variable};
 pub use self::metrics::Metrics;
 pub use self::proof::R1CSProof;
+#[cfg(feature = "prover")]
 pub use self::prover::Prover;
+#[cfg(feature = "verifier")]
 pub use self::verifier::Verifier;
 
 pub use crate::errors::R1CSError;
@@ -22,6 +22,7 @@ use super::{
 use crate::errors::R1CSError;
 use crate::generators::{BulletproofGens, PedersenGens};
 use crate::inner_product_proof::InnerProductProof;
+use crate::poly;
 use crate::r1cs::Metrics;
 use crate::transcript::TranscriptProtocol;
 
@@ -583,8 +584,8 @@ impl<'g, T: BorrowMut<Transcript>> Prover<'g, T> {
 
         let (wL, wR, wO, wV) = self.flattened_constraints(&z);
 
-        let mut l_poly = util::VecPoly3::zero(n);
-        let mut r_poly = util::VecPoly3::zero(n);
+        let mut l_poly = poly::VecPoly3::zero(n);
+        let mut r_poly = poly::VecPoly3::zero(n);
 
         let mut exp_y = Scalar::one(); // y^n starting at n=0
         let y_inv = y.invert();
@@ -613,7 +614,7 @@ impl<'g, T: BorrowMut<Transcript>> Prover<'g, T> {
             exp_y = exp_y * y; // y^i -> y^(i+1)
         }
 
-        let t_poly = util::VecPoly3::special_inner_product(&l_poly, &r_poly);
+        let t_poly = poly::VecPoly3::special_inner_product(&l_poly, &r_poly);
 
         let t_1_blinding = Scalar::random(&mut rng);
         let t_3_blinding = Scalar::random(&mut rng);
@@ -645,7 +646,7 @@ impl<'g, T: BorrowMut<Transcript>> Prover<'g, T> {
             .map(|(c, v_blinding)| c * v_blinding)
             .sum();
 
-        let t_blinding_poly = util::Poly6 {
+        let t_blinding_poly = poly::Poly6 {
             t1: t_1_blinding,
             t2: t_2_blinding,
             t3: t_3_blinding,
@@ -0,0 +1,468 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+//! A versioned container for many range proofs, with an index of their
+//! shapes and a checksum.
+//!
+//! # Encoding
+//!
+//! An archive of \\(c\\) proofs is encoded as
+//!
+//! * the magic bytes `BPAR`, the archive version, and \\(c\\) as a
+//!   4-byte little-endian number;
+//! * the \\(c\\) proofs, as encoded by [`RangeProof::to_bytes`];
+//! * the shape index: for each proof, its length in bytes as a 4-byte
+//!   little-endian number, its bitsize \\(n\\) and \\(\lg m\\);
+//! * the SHA3-256 checksum of all of the preceding bytes.
+//!
+//! Each proof records its own shape in its header, so the proofs can
+//! be written and read one at a time, by [`ArchiveEncoder`] and
+//! [`ArchiveDecoder`], and the index at the end lets
+//! [`ProofArchive::from_bytes`] locate every proof without parsing the
+//! ones before it.  The proofs must be in the versioned encoding, since
+//! legacy encodings do not record their shape.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use byteorder::{ByteOrder, LittleEndian};
+use digest::Digest;
+use sha3::Sha3_256;
+
+use crate::errors::ProofError;
+use crate::range_proof::{ProofShape, RangeProof, SHAPE_PREFIX_LEN};
+
+/// The magic bytes which start an archive.
+const MAGIC: &[u8; 4] = b"BPAR";
+
+/// The length of the magic bytes, the version and the proof count.
+const ARCHIVE_HEADER_LEN: usize = 4 + 1 + 4;
+
+/// The length of an entry of the shape index.
+const INDEX_ENTRY_LEN: usize = 4 + 1 + 1;
+
+/// The length of the checksum.
+const CHECKSUM_LEN: usize = 32;
+
+/// A checked archive of range proofs, whose proofs can be read in any
+/// order.
+///
+/// Opening an archive with [`ProofArchive::from_bytes`] checks its
+/// checksum and that its index agrees with the headers of its proofs,
+/// but does not decode any points or scalars: each proof is decoded
+/// when it is read with [`ProofArchive::proof`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProofArchive {
+    bytes: Vec<u8>,
+    offsets: Vec<usize>,
+    shapes: Vec<ProofShape>,
+}
+
+impl ProofArchive {
+    /// The version of the archive encoding.
+    pub const VERSION: u8 = 1;
+
+    /// Creates an archive of `proofs`.
+    ///
+    /// Returns [`ProofError::FormatError`] if a proof has no header,
+    /// e.g. because it was decoded from the legacy encoding.
+    pub fn new(proofs: &[RangeProof]) -> Result<ProofArchive, ProofError> {
+        let mut encoder = ArchiveEncoder::new(proofs.len());
+        let mut bytes = Vec::new();
+        for proof in proofs {
+            bytes.extend_from_slice(&encoder.push(proof)?);
+        }
+        bytes.extend_from_slice(&encoder.finish()?);
+        ProofArchive::from_bytes(&bytes)
+    }
+
+    /// Opens an encoded archive.
+    ///
+    /// Returns [`ProofError::FormatError`] if the archive is of an
+    /// unknown version, is truncated, does not match its checksum, or
+    /// has an index which disagrees with the headers of its proofs.
+    pub fn from_bytes(slice: &[u8]) -> Result<ProofArchive, ProofError> {
+        let count = read_archive_header(slice)?;
+        let index_len = count
+            .checked_mul(INDEX_ENTRY_LEN)
+            .ok_or(ProofError::FormatError)?;
+        let trailer_len = index_len + CHECKSUM_LEN;
+        if slice.len() < ARCHIVE_HEADER_LEN + trailer_len {
+            return Err(ProofError::FormatError);
+        }
+
+        let (body, checksum) = slice.split_at(slice.len() - CHECKSUM_LEN);
+        if Sha3_256::digest(body)[..] != *checksum {
+            return Err(ProofError::FormatError);
+        }
+
+        let index = &body[body.len() - index_len..];
+        let proofs_end = body.len() - index_len;
+        let mut offsets = Vec::with_capacity(count);
+        let mut shapes = Vec::with_capacity(count);
+        let mut offset = ARCHIVE_HEADER_LEN;
+        for entry in index.chunks(INDEX_ENTRY_LEN) {
+            let byte_len = LittleEndian::read_u32(&entry[..4]) as usize;
+            let end = offset
+                .checked_add(byte_len)
+                .filter(|&end| end <= proofs_end)
+                .ok_or(ProofError::FormatError)?;
+            let shape = RangeProof::decode_header(&slice[offset..end])?;
+            if shape.byte_len != byte_len || !index_entry_matches(entry, &shape) {
+                return Err(ProofError::FormatError);
+            }
+            offsets.push(offset);
+            shapes.push(shape);
+            offset = end;
+        }
+        if offset != proofs_end {
+            return Err(ProofError::FormatError);
+        }
+
+        Ok(ProofArchive {
+            bytes: slice.to_vec(),
+            offsets,
+            shapes,
+        })
+    }
+
+    /// Returns the encoded archive.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the number of proofs in the archive.
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    /// Returns true if the archive holds no proofs.
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    /// Returns the shapes of the proofs, in order.
+    pub fn shapes(&self) -> &[ProofShape] {
+        &self.shapes
+    }
+
+    /// Returns the encoding of the proof at `index`, if any.
+    pub fn proof_bytes(&self, index: usize) -> Option<&[u8]> {
+        let offset = *self.offsets.get(index)?;
+        Some(&self.bytes[offset..offset + self.shapes[index].byte_len])
+    }
+
+    /// Decodes the proof at `index`.
+    ///
+    /// Returns [`ProofError::ParameterMismatch`] if there is no proof
+    /// at `index`, and [`ProofError::FormatError`] if the proof cannot
+    /// be decoded.
+    pub fn proof(&self, index: usize) -> Result<RangeProof, ProofError> {
+        let bytes = self
+            .proof_bytes(index)
+            .ok_or(ProofError::ParameterMismatch)?;
+        RangeProof::from_bytes(bytes)
+    }
+}
+
+/// Encodes an archive of a known number of proofs one proof at a time,
+/// e.g. to write a large archive to the network without buffering it.
+///
+/// The bytes returned by [`ArchiveEncoder::push`] for each proof and
+/// then by [`ArchiveEncoder::finish`] form the archive when
+/// concatenated.
+#[derive(Clone, Debug)]
+pub struct ArchiveEncoder {
+    count: usize,
+    index: Vec<u8>,
+    sha3: Sha3_256,
+}
+
+impl ArchiveEncoder {
+    /// Starts an archive of `count` proofs.
+    ///
+    /// Panics if `count` does not fit in 32 bits.
+    pub fn new(count: usize) -> ArchiveEncoder {
+        assert!(count <= u32::MAX as usize, "too many proofs");
+        ArchiveEncoder {
+            count,
+            index: Vec::new(),
+            sha3: Sha3_256::new(),
+        }
+    }
+
+    /// Encodes the next proof, returning the bytes to append to the
+    /// archive, which start with the archive header for the first
+    /// proof.
+    ///
+    /// Returns [`ProofError::ParameterMismatch`] if all `count` proofs
+    /// were already pushed, and [`ProofError::FormatError`] if the
+    /// proof has no header.
+    pub fn push(&mut self, proof: &RangeProof) -> Result<Vec<u8>, ProofError> {
+        let pushed = self.index.len() / INDEX_ENTRY_LEN;
+        if pushed == self.count {
+            return Err(ProofError::ParameterMismatch);
+        }
+        if proof.params.is_none() {
+            return Err(ProofError::FormatError);
+        }
+
+        let mut bytes = self.header(pushed);
+        let proof_bytes = proof.to_bytes();
+        let shape = RangeProof::decode_header(&proof_bytes)?;
+        bytes.extend_from_slice(&proof_bytes);
+        self.index.extend_from_slice(&index_entry(&shape));
+        self.sha3.update(&bytes);
+        Ok(bytes)
+    }
+
+    /// Finishes the archive, returning the bytes to append to it: the
+    /// shape index and the checksum, after the archive header if no
+    /// proofs were pushed.
+    ///
+    /// Returns [`ProofError::ParameterMismatch`] if fewer than `count`
+    /// proofs were pushed.
+    pub fn finish(mut self) -> Result<Vec<u8>, ProofError> {
+        let pushed = self.index.len() / INDEX_ENTRY_LEN;
+        if pushed != self.count {
+            return Err(ProofError::ParameterMismatch);
+        }
+
+        let mut bytes = self.header(pushed);
+        bytes.extend_from_slice(&self.index);
+        self.sha3.update(&bytes);
+        bytes.extend_from_slice(&self.sha3.finalize());
+        Ok(bytes)
+    }
+
+    /// Returns the archive header if it was not yet written, i.e. if no
+    /// proofs were `pushed`.
+    fn header(&self, pushed: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        if pushed == 0 {
+            bytes.extend_from_slice(MAGIC);
+            bytes.push(ProofArchive::VERSION);
+            bytes.extend_from_slice(&(self.count as u32).to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// Decodes an archive from chunks of bytes as they arrive, returning
+/// each proof as soon as it is complete.
+///
+/// The checksum is only checked once the whole archive has arrived, so
+/// the proofs returned by [`ArchiveDecoder::push`] are not known to
+/// belong to an intact archive until [`ArchiveDecoder::finish`]
+/// succeeds.
+#[derive(Clone, Debug, Default)]
+pub struct ArchiveDecoder {
+    buf: Vec<u8>,
+    sha3: Sha3_256,
+    count: Option<usize>,
+    shapes: Vec<ProofShape>,
+    complete: bool,
+}
+
+impl ArchiveDecoder {
+    /// Creates a decoder for one archive.
+    pub fn new() -> Self {
+        ArchiveDecoder::default()
+    }
+
+    /// Appends `chunk` to the buffered bytes, and returns the proofs it
+    /// completed.
+    ///
+    /// Returns [`ProofError::FormatError`] if the archive is invalid,
+    /// or if bytes are pushed after its end; the decoder is then reset
+    /// and its buffered bytes are dropped.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<RangeProof>, ProofError> {
+        self.buf.extend_from_slice(chunk);
+        let result = self.advance();
+        if result.is_err() {
+            *self = ArchiveDecoder::new();
+        }
+        result
+    }
+
+    /// Returns the number of proofs decoded so far.
+    pub fn decoded(&self) -> usize {
+        self.shapes.len()
+    }
+
+    /// Checks that the whole archive has arrived and matches its
+    /// checksum, returning the shapes of its proofs.
+    ///
+    /// Returns [`ProofError::FormatError`] if the archive is truncated.
+    pub fn finish(self) -> Result<Vec<ProofShape>, ProofError> {
+        if self.complete {
+            Ok(self.shapes)
+        } else {
+            Err(ProofError::FormatError)
+        }
+    }
+
+    fn advance(&mut self) -> Result<Vec<RangeProof>, ProofError> {
+        if self.complete {
+            return Err(ProofError::FormatError);
+        }
+        let count = match self.count {
+            Some(count) => count,
+            None => {
+                if self.buf.len() < ARCHIVE_HEADER_LEN {
+                    return Ok(Vec::new());
+                }
+                let count = read_archive_header(&self.buf)?;
+                self.consume(ARCHIVE_HEADER_LEN);
+                self.count = Some(count);
+                count
+            }
+        };
+
+        let mut proofs = Vec::new();
+        while self.shapes.len() < count && self.buf.len() >= SHAPE_PREFIX_LEN {
+            let shape = RangeProof::decode_header(&self.buf)?;
+            if self.buf.len() < shape.byte_len {
+                break;
+            }
+            proofs.push(RangeProof::from_bytes(&self.buf[..shape.byte_len])?);
+            self.consume(shape.byte_len);
+            self.shapes.push(shape);
+        }
+
+        let trailer_len = count * INDEX_ENTRY_LEN + CHECKSUM_LEN;
+        if self.shapes.len() == count && self.buf.len() >= trailer_len {
+            if self.buf.len() > trailer_len {
+                return Err(ProofError::FormatError);
+            }
+            let (index, checksum) = self.buf.split_at(count * INDEX_ENTRY_LEN);
+            let matches =
+                index
+                    .chunks(INDEX_ENTRY_LEN)
+                    .zip(self.shapes.iter())
+                    .all(|(entry, shape)| {
+                        LittleEndian::read_u32(&entry[..4]) as usize == shape.byte_len
+                            && index_entry_matches(entry, shape)
+                    });
+            self.sha3.update(index);
+            if !matches || self.sha3.clone().finalize()[..] != *checksum {
+                return Err(ProofError::FormatError);
+            }
+            self.buf.clear();
+            self.complete = true;
+        }
+        Ok(proofs)
+    }
+
+    /// Hashes and drops the first `len` buffered bytes.
+    fn consume(&mut self, len: usize) {
+        self.sha3.update(&self.buf[..len]);
+        self.buf.drain(..len);
+    }
+}
+
+/// Parses the archive header, returning the number of proofs.
+fn read_archive_header(slice: &[u8]) -> Result<usize, ProofError> {
+    if slice.len() < ARCHIVE_HEADER_LEN || &slice[..4] != MAGIC || slice[4] != ProofArchive::VERSION
+    {
+        return Err(ProofError::FormatError);
+    }
+    Ok(LittleEndian::read_u32(&slice[5..9]) as usize)
+}
+
+/// Returns the index entry for a proof of the given shape.
+fn index_entry(shape: &ProofShape) -> [u8; INDEX_ENTRY_LEN] {
+    let mut entry = [0u8; INDEX_ENTRY_LEN];
+    LittleEndian::write_u32(&mut entry[..4], shape.byte_len as u32);
+    entry[4] = shape.n as u8;
+    entry[5] = shape.m.trailing_zeros() as u8;
+    entry
+}
+
+/// Checks that the bitsize and aggregation size of an index entry
+/// match the shape of the proof.
+fn index_entry_matches(entry: &[u8], shape: &ProofShape) -> bool {
+    entry[4] as usize == shape.n && entry[5] as u32 == shape.m.trailing_zeros()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::Scalar;
+    use merlin::Transcript;
+
+    use crate::generators::{BulletproofGens, PedersenGens};
+
+    fn proofs() -> Vec<RangeProof> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        [(8, 1), (64, 2), (16, 1)]
+            .iter()
+            .map(|&(n, m)| {
+                let values: Vec<u64> = (1..=m as u64).collect();
+                let blindings: Vec<Scalar> = values.iter().map(|&v| Scalar::from(v)).collect();
+                RangeProof::prove_multiple(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"ArchiveTest"),
+                    &values,
+                    &blindings,
+                    n,
+                )
+                .unwrap()
+                .0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn archive_roundtrip() {
+        let proofs = proofs();
+        let archive = ProofArchive::new(&proofs).unwrap();
+        assert_eq!(archive.len(), 3);
+        assert_eq!(archive.shapes()[1].n, 64);
+        assert_eq!(archive.shapes()[1].m, 2);
+        assert_eq!(archive.proof(2), Ok(proofs[2].clone()));
+        assert_eq!(archive.proof(3), Err(ProofError::ParameterMismatch));
+
+        let bytes = archive.as_bytes();
+        assert_eq!(ProofArchive::from_bytes(bytes), Ok(archive.clone()));
+        assert!(ProofArchive::new(&[]).unwrap().is_empty());
+
+        // The checksum covers every byte
+        let mut corrupted = bytes.to_vec();
+        corrupted[100] ^= 1;
+        assert_eq!(
+            ProofArchive::from_bytes(&corrupted),
+            Err(ProofError::FormatError)
+        );
+        assert_eq!(
+            ProofArchive::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ProofError::FormatError)
+        );
+
+        // Streaming decoding, in chunks that split proofs
+        let mut decoder = ArchiveDecoder::new();
+        let mut decoded = Vec::new();
+        for chunk in bytes.chunks(333) {
+            decoded.extend(decoder.push(chunk).unwrap());
+        }
+        assert_eq!(decoded, proofs);
+        assert_eq!(decoder.finish(), Ok(archive.shapes().to_vec()));
+
+        let mut decoder = ArchiveDecoder::new();
+        decoder.push(&bytes[..bytes.len() - 1]).unwrap();
+        assert_eq!(decoder.finish(), Err(ProofError::FormatError));
+
+        let mut encoder = ArchiveEncoder::new(2);
+        encoder.push(&proofs[0]).unwrap();
+        encoder.push(&proofs[1]).unwrap();
+        assert_eq!(encoder.push(&proofs[2]), Err(ProofError::ParameterMismatch));
+        assert!(ArchiveEncoder::new(1).finish().is_err());
+    }
+}
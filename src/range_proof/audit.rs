@@ -0,0 +1,291 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! The `audit` module contains encrypted records of the blinding
+//! factors a party used in the aggregated multiparty computation
+//! protocol.
+//!
+//! A party created for an organization draws its blinding factors from
+//! the RNG passed to
+//! [`assign_position_with_rng`](super::party::PartyAwaitingPosition::assign_position_with_rng)
+//! and
+//! [`apply_challenge_with_rng`](super::party::PartyAwaitingBitChallenge::apply_challenge_with_rng),
+//! which can be backed by an HSM.  With
+//! [`apply_challenge_with_audit`](super::party::PartyAwaitingPolyChallenge::apply_challenge_with_audit),
+//! it also exports the blinding factors it used, sealed under an audit
+//! key held by the organization, so that the organization can later
+//! recompute and check its own commitments and proof shares.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use blstrs::Scalar;
+use byteorder::{ByteOrder, LittleEndian};
+use clear_on_drop::clear::Clear;
+use digest::Digest;
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha3::Sha3_256;
+use subtle::ConstantTimeEq;
+
+use crate::errors::MPCError;
+use crate::util::read32;
+
+/// Domain separation tag of the keystream encrypting a record.
+const ENCRYPTION_DST: &[u8] = b"BLS_BULLETPROOFS_AUDIT_ENCRYPTION";
+/// Domain separation tag of the authentication tag of a record.
+const MAC_DST: &[u8] = b"BLS_BULLETPROOFS_AUDIT_MAC";
+
+/// The blinding factors a party used for its share of an aggregated
+/// proof.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlindingRecord {
+    pub(super) j: usize,
+    pub(super) a_blinding: Scalar,
+    pub(super) s_blinding: Scalar,
+    pub(super) s_L: Vec<Scalar>,
+    pub(super) s_R: Vec<Scalar>,
+    pub(super) t_1_blinding: Scalar,
+    pub(super) t_2_blinding: Scalar,
+}
+
+impl BlindingRecord {
+    /// Returns the position of the party in the aggregation.
+    pub fn position(&self) -> usize {
+        self.j
+    }
+
+    /// Returns the blinding factor of the commitment \\(A\\) to the bits.
+    pub fn a_blinding(&self) -> &Scalar {
+        &self.a_blinding
+    }
+
+    /// Returns the blinding factor of the commitment \\(S\\) to the
+    /// blinding vectors.
+    pub fn s_blinding(&self) -> &Scalar {
+        &self.s_blinding
+    }
+
+    /// Returns the blinding vector \\(\mathbf{s}\_L\\).
+    pub fn s_L(&self) -> &[Scalar] {
+        &self.s_L
+    }
+
+    /// Returns the blinding vector \\(\mathbf{s}\_R\\).
+    pub fn s_R(&self) -> &[Scalar] {
+        &self.s_R
+    }
+
+    /// Returns the blinding factor of the commitment \\(T_1\\).
+    pub fn t_1_blinding(&self) -> &Scalar {
+        &self.t_1_blinding
+    }
+
+    /// Returns the blinding factor of the commitment \\(T_2\\).
+    pub fn t_2_blinding(&self) -> &Scalar {
+        &self.t_2_blinding
+    }
+
+    /// Encrypts the record under `audit_key`, with a nonce drawn from `rng`.
+    pub fn seal<T: RngCore + CryptoRng>(
+        &self,
+        audit_key: &[u8; 32],
+        rng: &mut T,
+    ) -> SealedBlindings {
+        let mut nonce = [0u8; 32];
+        rng.fill_bytes(&mut nonce);
+
+        let mut ciphertext = self.to_bytes();
+        apply_keystream(audit_key, &nonce, &mut ciphertext);
+        let tag = mac(audit_key, &nonce, &ciphertext);
+        SealedBlindings {
+            nonce,
+            ciphertext,
+            tag,
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(8 + 32 * (4 + 2 * self.s_L.len()));
+        let mut len = [0u8; 4];
+        LittleEndian::write_u32(&mut len, self.j as u32);
+        buf.extend_from_slice(&len);
+        LittleEndian::write_u32(&mut len, self.s_L.len() as u32);
+        buf.extend_from_slice(&len);
+        for s in [
+            &self.a_blinding,
+            &self.s_blinding,
+            &self.t_1_blinding,
+            &self.t_2_blinding,
+        ]
+        .iter()
+        .copied()
+        .chain(self.s_L.iter())
+        .chain(self.s_R.iter())
+        {
+            buf.extend_from_slice(&s.to_bytes_le());
+        }
+        buf
+    }
+
+    fn from_bytes(slice: &[u8]) -> Result<BlindingRecord, MPCError> {
+        if slice.len() < 8 {
+            return Err(MPCError::InvalidAuditRecord);
+        }
+        let j = LittleEndian::read_u32(&slice[0..4]) as usize;
+        let n = LittleEndian::read_u32(&slice[4..8]) as usize;
+        if n.checked_mul(64)
+            .and_then(|len| len.checked_add(8 + 4 * 32))
+            != Some(slice.len())
+        {
+            return Err(MPCError::InvalidAuditRecord);
+        }
+
+        let mut scalars = slice[8..].chunks(32).map(|bytes| {
+            Option::from(Scalar::from_bytes_le(&read32(bytes))).ok_or(MPCError::InvalidAuditRecord)
+        });
+        let mut scalar = || scalars.next().ok_or(MPCError::InvalidAuditRecord)?;
+        let (a_blinding, s_blinding) = (scalar()?, scalar()?);
+        let (t_1_blinding, t_2_blinding) = (scalar()?, scalar()?);
+        let s_L = (&mut scalars).take(n).collect::<Result<Vec<_>, _>>()?;
+        let s_R = scalars.collect::<Result<Vec<_>, _>>()?;
+
+        Ok(BlindingRecord {
+            j,
+            a_blinding,
+            s_blinding,
+            s_L,
+            s_R,
+            t_1_blinding,
+            t_2_blinding,
+        })
+    }
+}
+
+/// Overwrite secrets with null bytes when they go out of scope.
+impl Drop for BlindingRecord {
+    fn drop(&mut self) {
+        self.a_blinding.clear();
+        self.s_blinding.clear();
+        self.t_1_blinding.clear();
+        self.t_2_blinding.clear();
+        for e in self.s_L.iter_mut().chain(self.s_R.iter_mut()) {
+            e.clear();
+        }
+    }
+}
+
+/// A [`BlindingRecord`] encrypted and authenticated under an audit key.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct SealedBlindings {
+    nonce: [u8; 32],
+    ciphertext: Vec<u8>,
+    tag: [u8; 32],
+}
+
+impl SealedBlindings {
+    /// Decrypts the record with `audit_key`.
+    ///
+    /// Returns [`MPCError::InvalidAuditRecord`] if the key is wrong or
+    /// the record was altered.
+    pub fn open(&self, audit_key: &[u8; 32]) -> Result<BlindingRecord, MPCError> {
+        let tag = mac(audit_key, &self.nonce, &self.ciphertext);
+        if !bool::from(tag.ct_eq(&self.tag)) {
+            return Err(MPCError::InvalidAuditRecord);
+        }
+
+        let mut plaintext = self.ciphertext.clone();
+        apply_keystream(audit_key, &self.nonce, &mut plaintext);
+        let record = BlindingRecord::from_bytes(&plaintext);
+        for b in plaintext.iter_mut() {
+            *b = 0;
+        }
+        record
+    }
+}
+
+/// XORs `buf` with the keystream for `key` and `nonce`.
+fn apply_keystream(key: &[u8; 32], nonce: &[u8; 32], buf: &mut [u8]) {
+    let mut seed: [u8; 32] = Sha3_256::new()
+        .chain(ENCRYPTION_DST)
+        .chain(key)
+        .chain(nonce)
+        .finalize()
+        .into();
+    let mut keystream = ChaCha20Rng::from_seed(seed);
+    seed.clear();
+
+    let mut block = [0u8; 64];
+    for chunk in buf.chunks_mut(64) {
+        keystream.fill_bytes(&mut block);
+        for (b, k) in chunk.iter_mut().zip(block.iter()) {
+            *b ^= k;
+        }
+    }
+    block.clear();
+}
+
+/// Computes the authentication tag of `ciphertext` for `key` and `nonce`.
+fn mac(key: &[u8; 32], nonce: &[u8; 32], ciphertext: &[u8]) -> [u8; 32] {
+    Sha3_256::new()
+        .chain(MAC_DST)
+        .chain(key)
+        .chain(nonce)
+        .chain(ciphertext)
+        .finalize()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use group::ff::Field;
+    use rand::thread_rng;
+
+    use crate::generators::{BulletproofGens, PedersenGens};
+    use crate::range_proof::messages::{BitChallenge, PolyChallenge};
+    use crate::range_proof::party::Party;
+
+    #[test]
+    fn sealed_blindings_round_trip() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let mut rng = thread_rng();
+        let audit_key = [7u8; 32];
+
+        let party = Party::new(&bp_gens, &pc_gens, 42, Scalar::from(3u64), 8).unwrap();
+        let (party, _) = party.assign_position_with_rng(0, &mut rng).unwrap();
+        let bit_challenge = BitChallenge {
+            y: Scalar::random(&mut rng),
+            z: Scalar::random(&mut rng),
+        };
+        let (party, _) = party.apply_challenge_with_rng(&bit_challenge, &mut rng);
+        let poly_challenge = PolyChallenge {
+            x: Scalar::random(&mut rng),
+        };
+        let (share, sealed) = party
+            .apply_challenge_with_audit(&poly_challenge, &audit_key, &mut rng)
+            .unwrap();
+
+        let record = sealed.open(&audit_key).unwrap();
+        assert_eq!(record.position(), 0);
+        assert_eq!((record.s_L().len(), record.s_R().len()), (8, 8));
+        // The record reproduces the party's blinding of e
+        assert_eq!(
+            share.e_blinding,
+            record.a_blinding() + record.s_blinding() * poly_challenge.x
+        );
+
+        assert_eq!(sealed.open(&[8u8; 32]), Err(MPCError::InvalidAuditRecord));
+        let mut altered = sealed;
+        altered.ciphertext[10] ^= 1;
+        assert_eq!(altered.open(&audit_key), Err(MPCError::InvalidAuditRecord));
+    }
+}
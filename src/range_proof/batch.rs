@@ -0,0 +1,958 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Batched verification of independently created range proofs.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::iter;
+use core::ptr;
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use byteorder::{ByteOrder, LittleEndian};
+use group::ff::Field;
+use group::Group;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::{padded_params, RangeProof};
+use crate::telemetry;
+use crate::transcript::TranscriptProtocol;
+use crate::util::{self, read48};
+
+/// Collects the terms of one or more verification equations so that
+/// they can be checked with a single multiscalar multiplication.
+///
+/// Terms on the shared generators (\\(B\\), \\(\tilde{B}\\), \\(\mathbf{G}\\)
+/// and \\(\mathbf{H}\\)) are merged, so that every generator appears at most
+/// once in the final multiscalar multiplication no matter how many proofs
+/// were accumulated.
+pub(crate) struct VerificationAccumulator<'g> {
+    pub(crate) bp_gens: &'g BulletproofGens,
+    pub(crate) pc_gens: &'g PedersenGens,
+    /// Scalar for the Pedersen value base \\(B\\)
+    B_scalar: Scalar,
+    /// Scalar for the Pedersen blinding base \\(\tilde{B}\\)
+    B_blinding_scalar: Scalar,
+    /// Scalars for each party's \\(\mathbf{G}\\) generators
+    G_scalars: Vec<Vec<Scalar>>,
+    /// Scalars for each party's \\(\mathbf{H}\\) generators
+    H_scalars: Vec<Vec<Scalar>>,
+    /// Scalars for the proof-specific points
+    dynamic_scalars: Vec<Scalar>,
+    /// Proof-specific points: proof commitments, IPP points and value commitments
+    dynamic_points: Vec<G1Projective>,
+    /// The sum of the proof-specific terms evaluated by [`Self::flush`]
+    flushed: G1Projective,
+}
+
+impl<'g> VerificationAccumulator<'g> {
+    /// Creates an empty accumulator over the given generators.
+    pub(crate) fn new(bp_gens: &'g BulletproofGens, pc_gens: &'g PedersenGens) -> Self {
+        VerificationAccumulator {
+            bp_gens,
+            pc_gens,
+            B_scalar: Scalar::zero(),
+            B_blinding_scalar: Scalar::zero(),
+            G_scalars: Vec::new(),
+            H_scalars: Vec::new(),
+            dynamic_scalars: Vec::new(),
+            dynamic_points: Vec::new(),
+            flushed: G1Projective::identity(),
+        }
+    }
+
+    /// Returns `true` if the accumulator is over the given generators.
+    ///
+    /// The Bulletproofs generators are compared by address, since
+    /// comparing them by value would cost more than it saves.
+    fn uses(&self, bp_gens: &BulletproofGens, pc_gens: &PedersenGens) -> bool {
+        ptr::eq(self.bp_gens, bp_gens)
+            && self.pc_gens.B == pc_gens.B
+            && self.pc_gens.B_blinding == pc_gens.B_blinding
+    }
+
+    /// Adds `weight * scalar_i * point_i` for the proof-specific points.
+    pub(crate) fn add_proof_terms<S, P>(&mut self, weight: Scalar, scalars: S, points: P)
+    where
+        S: IntoIterator<Item = Scalar>,
+        P: IntoIterator<Item = G1Projective>,
+    {
+        for (s, P) in scalars.into_iter().zip(points) {
+            self.dynamic_scalars.push(weight * s);
+            self.dynamic_points.push(P);
+        }
+    }
+
+    /// Adds `weight * B_scalar` and `weight * B_blinding_scalar` to the
+    /// scalars of the Pedersen bases.
+    pub(crate) fn add_pedersen_terms(
+        &mut self,
+        weight: Scalar,
+        B_scalar: Scalar,
+        B_blinding_scalar: Scalar,
+    ) {
+        self.B_scalar += weight * B_scalar;
+        self.B_blinding_scalar += weight * B_blinding_scalar;
+    }
+
+    /// Adds the weighted scalars for the aggregated generators
+    /// `bp_gens.G(n, m)` and `bp_gens.H(n, m)`.
+    pub(crate) fn add_generator_terms<IG, IH>(
+        &mut self,
+        weight: Scalar,
+        n: usize,
+        m: usize,
+        g: IG,
+        h: IH,
+    ) where
+        IG: IntoIterator<Item = Scalar>,
+        IH: IntoIterator<Item = Scalar>,
+    {
+        Self::add_party_scalars(&mut self.G_scalars, weight, n, m, g);
+        Self::add_party_scalars(&mut self.H_scalars, weight, n, m, h);
+    }
+
+    fn add_party_scalars<I: IntoIterator<Item = Scalar>>(
+        party_scalars: &mut Vec<Vec<Scalar>>,
+        weight: Scalar,
+        n: usize,
+        m: usize,
+        scalars: I,
+    ) {
+        if party_scalars.len() < m {
+            party_scalars.resize(m, Vec::new());
+        }
+        for party in party_scalars.iter_mut().take(m) {
+            if party.len() < n {
+                party.resize(n, Scalar::zero());
+            }
+        }
+        for (k, s) in scalars.into_iter().enumerate().take(n * m) {
+            party_scalars[k / n][k % n] += weight * s;
+        }
+    }
+
+    /// Evaluates the proof-specific terms accumulated so far and drops
+    /// them, so that memory use stays bounded when many proofs are
+    /// accumulated.  The terms on the shared generators are kept.
+    pub(crate) fn flush(&mut self) {
+        telemetry::record_msm(self.dynamic_scalars.len());
+        self.flushed += util::multiscalar_mul(&self.dynamic_scalars, &self.dynamic_points);
+        self.dynamic_scalars.clear();
+        self.dynamic_points.clear();
+    }
+
+    /// Consumes the accumulator, returning the merged terms as a pair
+    /// of equally long scalar and point vectors.
+    ///
+    /// Terms evaluated by [`Self::flush`] are not included.
+    pub(crate) fn into_terms(self) -> (Vec<Scalar>, Vec<G1Projective>) {
+        let bp_gens = self.bp_gens;
+
+        let scalars = iter::once(self.B_scalar)
+            .chain(iter::once(self.B_blinding_scalar))
+            .chain(self.G_scalars.iter().flatten().copied())
+            .chain(self.H_scalars.iter().flatten().copied())
+            .chain(self.dynamic_scalars)
+            .collect();
+
+        let G_points = self
+            .G_scalars
+            .iter()
+            .enumerate()
+            .flat_map(|(j, G_j)| bp_gens.share(j).G(G_j.len()).copied());
+        let H_points = self
+            .H_scalars
+            .iter()
+            .enumerate()
+            .flat_map(|(j, H_j)| bp_gens.share(j).H(H_j.len()).copied());
+        let points = iter::once(self.pc_gens.B)
+            .chain(iter::once(self.pc_gens.B_blinding))
+            .chain(G_points)
+            .chain(H_points)
+            .chain(self.dynamic_points)
+            .collect();
+
+        (scalars, points)
+    }
+
+    /// Computes the sum of all accumulated terms.
+    pub(crate) fn evaluate(self) -> G1Projective {
+        Self::evaluate_many(iter::once(self))
+    }
+
+    /// Computes the sum of all terms accumulated by several
+    /// accumulators, over possibly different generators.
+    ///
+    /// The terms of all accumulators are merged into one multiscalar
+    /// multiplication, with the generators of each accumulator
+    /// included as additional terms.
+    fn evaluate_many<I: IntoIterator<Item = Self>>(accs: I) -> G1Projective {
+        let mut flushed = G1Projective::identity();
+        let mut scalars = Vec::new();
+        let mut points = Vec::new();
+        for acc in accs {
+            flushed += acc.flushed;
+            let (acc_scalars, acc_points) = acc.into_terms();
+            scalars.extend(acc_scalars);
+            points.extend(acc_points);
+        }
+
+        telemetry::record_msm(scalars.len());
+        util::multiscalar_mul(&scalars, &points) + flushed
+    }
+}
+
+/// Checks that a proof of bitsize `n` over `m` commitments can be
+/// verified, after padding a single value to a supported bitsize.
+fn check_bitsize(n: usize, m: usize) -> Result<(), ProofError> {
+    match padded_params(n, m) {
+        (8, _) | (16, _) | (32, _) | (64, _) => Ok(()),
+        _ => Err(ProofError::InvalidBitsize),
+    }
+}
+
+/// The number of proofs accumulated by [`ProofBatch::verify_stream_with_rng`]
+/// between flushes of the proof-specific terms.
+const STREAM_CHUNK_SIZE: usize = 64;
+
+/// A range proof together with the commitments and bitsize it was
+/// created for.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct BatchEntry {
+    proof: RangeProof,
+    value_commitments: Vec<G1Affine>,
+    n: usize,
+}
+
+/// A collection of independently created range proofs, together with
+/// the value commitments each of them proves.
+///
+/// Unlike an aggregated [`RangeProof`], the proofs in a `ProofBatch`
+/// do not need any interaction between the provers: every prover
+/// creates its own proof, and the batch only bundles them for
+/// transport and verification.
+///
+/// All proofs in the batch are checked with one combined multiscalar
+/// multiplication, with each proof's verification equation scaled by
+/// an independent random weight.  The weights can be drawn from any
+/// RNG with [`ProofBatch::verify_with_rng`], supplied directly with
+/// [`ProofBatch::verify_with_weights`], or derived from the batch
+/// contents with [`ProofBatch::verify_deterministic`].
+///
+/// # Encoding
+///
+/// The batch is encoded as a 4-byte little-endian number of entries,
+/// followed by each entry as:
+///
+/// * one byte for the bitsize \\(n\\),
+/// * a 4-byte little-endian number of commitments \\(m\\),
+/// * \\(m\\) compressed commitments,
+/// * a 4-byte little-endian length of the proof,
+/// * the proof as encoded by [`RangeProof::to_bytes`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ProofBatch {
+    entries: Vec<BatchEntry>,
+}
+
+impl ProofBatch {
+    /// Creates an empty batch.
+    pub fn new() -> Self {
+        ProofBatch {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds a `proof` for the given `value_commitments` with bitsize `n`.
+    ///
+    /// Returns [`ProofError::InvalidBitsize`] if `n` is not 8, 16, 32
+    /// or 64, or, for a single commitment, between 1 and 64.
+    pub fn push(
+        &mut self,
+        proof: RangeProof,
+        value_commitments: Vec<G1Affine>,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        check_bitsize(n, value_commitments.len())?;
+        self.entries.push(BatchEntry {
+            proof,
+            value_commitments,
+            n,
+        });
+        Ok(())
+    }
+
+    /// Returns the number of proofs in the batch.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the batch contains no proofs.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns an iterator over the proofs in the batch, with the
+    /// commitments and bitsize of each.
+    pub fn iter(&self) -> impl Iterator<Item = (&RangeProof, &[G1Affine], usize)> {
+        self.entries
+            .iter()
+            .map(|e| (&e.proof, &e.value_commitments[..], e.n))
+    }
+
+    /// Verifies all proofs in the batch with one multiscalar multiplication.
+    ///
+    /// Every proof is verified against its own copy of `transcript`,
+    /// which must have the same initial state as the provers' transcripts.
+    /// The combination weights are drawn from `rng`, so passing a
+    /// deterministically seeded RNG makes verification reproducible.
+    pub fn verify_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let weights: Vec<(Scalar, Scalar)> = self
+            .entries
+            .iter()
+            .map(|_| (Scalar::random(&mut *rng), Scalar::random(&mut *rng)))
+            .collect();
+        self.verify_with_weights(bp_gens, pc_gens, transcript, &weights)
+    }
+
+    /// Verifies all proofs in the batch with one multiscalar
+    /// multiplication, using caller-supplied combination weights.
+    ///
+    /// `weights` must hold one pair of scalars per proof, in batch
+    /// order.  The first scalar of each pair scales the proof's whole
+    /// verification equation, and the second combines its \\(t(x)\\)
+    /// check with its inner-product check.  For the batch check to be
+    /// sound, all weights must be nonzero and unpredictable to the
+    /// provers, e.g. derived from a shared random beacon after the
+    /// proofs are fixed.
+    ///
+    /// Returns [`ProofError::InvalidBatchWeights`] if the number of
+    /// pairs does not match the number of proofs, or if any weight is zero.
+    pub fn verify_with_weights(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        weights: &[(Scalar, Scalar)],
+    ) -> Result<(), ProofError> {
+        let gens = alloc::vec![(bp_gens, pc_gens); self.entries.len()];
+        self.verify_with_gens_and_weights(&gens, transcript, weights)
+    }
+
+    /// Verifies all proofs in the batch with one multiscalar
+    /// multiplication, where each proof may have been created with
+    /// different generators, e.g. per-asset Pedersen bases.
+    ///
+    /// `gens` must hold the generators of each proof, in batch order.
+    /// Proofs over the same generators share their generator terms, and
+    /// the generators of every distinct set are included in the
+    /// multiscalar multiplication as additional terms.  Returns
+    /// [`ProofError::InvalidGeneratorsLength`] if the number of
+    /// generator sets does not match the number of proofs.
+    ///
+    /// Otherwise, this is [`ProofBatch::verify_with_rng`].
+    pub fn verify_with_gens_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        gens: &[(&BulletproofGens, &PedersenGens)],
+        transcript: &Transcript,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let weights: Vec<(Scalar, Scalar)> = self
+            .entries
+            .iter()
+            .map(|_| (Scalar::random(&mut *rng), Scalar::random(&mut *rng)))
+            .collect();
+        self.verify_with_gens_and_weights(gens, transcript, &weights)
+    }
+
+    /// Verifies all proofs in the batch, each with its own generators,
+    /// with one multiscalar multiplication.
+    /// This is a convenience wrapper around [`ProofBatch::verify_with_gens_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify_with_gens(
+        &self,
+        gens: &[(&BulletproofGens, &PedersenGens)],
+        transcript: &Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_with_gens_with_rng(gens, transcript, &mut thread_rng())
+    }
+
+    /// Verifies all proofs in the batch, each with its own generators,
+    /// with one multiscalar multiplication, using caller-supplied
+    /// combination weights.
+    ///
+    /// See [`ProofBatch::verify_with_gens_with_rng`] for `gens` and
+    /// [`ProofBatch::verify_with_weights`] for `weights`.
+    pub fn verify_with_gens_and_weights(
+        &self,
+        gens: &[(&BulletproofGens, &PedersenGens)],
+        transcript: &Transcript,
+        weights: &[(Scalar, Scalar)],
+    ) -> Result<(), ProofError> {
+        let proofs: Vec<_> = self.iter().collect();
+        verify_triples(&proofs, gens, transcript, weights)
+    }
+
+    /// Derives the combination weights for [`ProofBatch::verify_with_weights`]
+    /// from a transcript over the batch contents.
+    ///
+    /// The weights are challenges of a copy of `transcript` after
+    /// appending every proof in the batch along with its bitsize and
+    /// commitments, so anyone holding the batch and the transcript can
+    /// recompute them.
+    pub fn derive_weights(&self, transcript: &Transcript) -> Vec<(Scalar, Scalar)> {
+        let mut transcript = transcript.clone();
+        transcript.rangeproof_batch_domain_sep(self.entries.len() as u64);
+
+        for entry in self.entries.iter() {
+            transcript.append_u64(b"n", entry.n as u64);
+            transcript.append_u64(b"m", entry.value_commitments.len() as u64);
+            for V in entry.value_commitments.iter() {
+                transcript.append_point(b"V", &V.into());
+            }
+            transcript.append_message(b"proof", &entry.proof.to_bytes());
+        }
+
+        self.entries
+            .iter()
+            .map(|_| {
+                (
+                    transcript.challenge_scalar(b"weight"),
+                    transcript.challenge_scalar(b"c"),
+                )
+            })
+            .collect()
+    }
+
+    /// Verifies all proofs in the batch with one multiscalar
+    /// multiplication, using weights derived from the batch contents by
+    /// [`ProofBatch::derive_weights`].
+    ///
+    /// Unlike [`ProofBatch::verify_with_rng`], the result depends only on
+    /// the batch and `transcript`, so it can be reproduced exactly.
+    pub fn verify_deterministic(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+    ) -> Result<(), ProofError> {
+        let weights = self.derive_weights(transcript);
+        self.verify_with_weights(bp_gens, pc_gens, transcript, &weights)
+    }
+
+    /// Verifies a stream of proofs, each with its commitments and
+    /// bitsize, without collecting them into a batch first.
+    ///
+    /// The proofs are checked as by [`ProofBatch::verify_with_rng`],
+    /// but the proof-specific terms are evaluated after every chunk of
+    /// proofs and then dropped, so memory use is bounded by the largest
+    /// proof rather than growing with the number of proofs.  Only the
+    /// scalars of the shared generators are combined across the whole
+    /// stream, at the cost of one smaller multiscalar multiplication
+    /// per chunk.
+    pub fn verify_stream_with_rng<I, T>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        proofs: I,
+        rng: &mut T,
+    ) -> Result<(), ProofError>
+    where
+        I: IntoIterator<Item = (RangeProof, Vec<G1Affine>, usize)>,
+        T: RngCore + CryptoRng,
+    {
+        let mut acc = VerificationAccumulator::new(bp_gens, pc_gens);
+        let mut size = 0;
+        let mut result = Ok(());
+        for (proof, value_commitments, n) in proofs {
+            result = proof.accumulate_verification(
+                &mut acc,
+                Scalar::random(&mut *rng),
+                Scalar::random(&mut *rng),
+                &mut transcript.clone(),
+                &value_commitments,
+                n,
+            );
+            size += 1;
+            if result.is_err() {
+                break;
+            }
+            if size % STREAM_CHUNK_SIZE == 0 {
+                acc.flush();
+            }
+        }
+
+        if result.is_ok() && !bool::from(acc.evaluate().is_identity()) {
+            result = Err(ProofError::VerificationError);
+        }
+        telemetry::record_batch(size);
+        telemetry::record_verification("stream", &result);
+        result
+    }
+
+    /// Verifies a stream of proofs, each with its commitments and bitsize.
+    /// This is a convenience wrapper around [`ProofBatch::verify_stream_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify_stream<I>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        proofs: I,
+    ) -> Result<(), ProofError>
+    where
+        I: IntoIterator<Item = (RangeProof, Vec<G1Affine>, usize)>,
+    {
+        ProofBatch::verify_stream_with_rng(bp_gens, pc_gens, transcript, proofs, &mut thread_rng())
+    }
+
+    /// Verifies all proofs in the batch with one multiscalar multiplication.
+    /// This is a convenience wrapper around [`ProofBatch::verify_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_with_rng(bp_gens, pc_gens, transcript, &mut thread_rng())
+    }
+
+    /// Serializes the batch into a byte array.
+    ///
+    /// See the type-level documentation for the layout.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut len = [0u8; 4];
+
+        LittleEndian::write_u32(&mut len, self.entries.len() as u32);
+        buf.extend_from_slice(&len);
+        for entry in self.entries.iter() {
+            // `push` and `from_bytes` only admit bitsizes of at most 64
+            buf.push(entry.n as u8);
+            LittleEndian::write_u32(&mut len, entry.value_commitments.len() as u32);
+            buf.extend_from_slice(&len);
+            for V in entry.value_commitments.iter() {
+                buf.extend_from_slice(&V.to_compressed());
+            }
+            let proof_bytes = entry.proof.to_bytes();
+            LittleEndian::write_u32(&mut len, proof_bytes.len() as u32);
+            buf.extend_from_slice(&len);
+            buf.extend_from_slice(&proof_bytes);
+        }
+        buf
+    }
+
+    /// Deserializes the batch from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a `ProofBatch`.
+    pub fn from_bytes(slice: &[u8]) -> Result<ProofBatch, ProofError> {
+        fn take<'a>(slice: &mut &'a [u8], len: usize) -> Result<&'a [u8], ProofError> {
+            if slice.len() < len {
+                return Err(ProofError::FormatError);
+            }
+            let (head, tail) = slice.split_at(len);
+            *slice = tail;
+            Ok(head)
+        }
+
+        let mut slice = slice;
+        let num_entries = LittleEndian::read_u32(take(&mut slice, 4)?) as usize;
+
+        // Each entry takes at least 9 bytes, which bounds the allocation below.
+        if num_entries > slice.len() / 9 {
+            return Err(ProofError::FormatError);
+        }
+
+        let mut entries = Vec::with_capacity(num_entries);
+        for _ in 0..num_entries {
+            let n = take(&mut slice, 1)?[0] as usize;
+            let m = LittleEndian::read_u32(take(&mut slice, 4)?) as usize;
+            if m > slice.len() / 48 {
+                return Err(ProofError::FormatError);
+            }
+            let value_commitments = (0..m)
+                .map(|_| {
+                    let bytes = read48(take(&mut slice, 48)?);
+                    Option::from(G1Affine::from_compressed(&bytes)).ok_or(ProofError::FormatError)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            check_bitsize(n, m).map_err(|_| ProofError::FormatError)?;
+            let proof_len = LittleEndian::read_u32(take(&mut slice, 4)?) as usize;
+            let proof = RangeProof::from_bytes(take(&mut slice, proof_len)?)?;
+            entries.push(BatchEntry {
+                proof,
+                value_commitments,
+                n,
+            });
+        }
+
+        if !slice.is_empty() {
+            return Err(ProofError::FormatError);
+        }
+
+        Ok(ProofBatch { entries })
+    }
+}
+
+impl RangeProof {
+    /// Verifies many independently created range proofs with one
+    /// multiscalar multiplication, each with its commitments and bitsize.
+    ///
+    /// This checks the proofs as [`ProofBatch::verify_with_rng`] does,
+    /// without moving them into a [`ProofBatch`] first.  Every proof is
+    /// verified against its own copy of `transcript`, which must have
+    /// the same initial state as the provers' transcripts.
+    ///
+    /// Returns [`ProofError::VerificationError`] if any of the proofs
+    /// does not verify, without identifying which one, and the error
+    /// of the first proof that cannot be checked at all, e.g.
+    /// [`ProofError::InvalidBitsize`] for an unsupported bitsize.
+    pub fn batch_verify_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        proofs: &[(&RangeProof, &[G1Affine], usize)],
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let weights: Vec<(Scalar, Scalar)> = proofs
+            .iter()
+            .map(|_| (Scalar::random(&mut *rng), Scalar::random(&mut *rng)))
+            .collect();
+        let gens = alloc::vec![(bp_gens, pc_gens); proofs.len()];
+        verify_triples(proofs, &gens, transcript, &weights)
+    }
+
+    /// Verifies many independently created range proofs with one
+    /// multiscalar multiplication.
+    /// This is a convenience wrapper around [`RangeProof::batch_verify_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn batch_verify(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        proofs: &[(&RangeProof, &[G1Affine], usize)],
+    ) -> Result<(), ProofError> {
+        RangeProof::batch_verify_with_rng(bp_gens, pc_gens, transcript, proofs, &mut thread_rng())
+    }
+}
+
+/// Verifies `proofs`, each with its commitments and bitsize, with one
+/// multiscalar multiplication, where each proof has its own generators
+/// in `gens` and its own pair of combination weights in `weights`, and
+/// records the outcome.
+fn verify_triples(
+    proofs: &[(&RangeProof, &[G1Affine], usize)],
+    gens: &[(&BulletproofGens, &PedersenGens)],
+    transcript: &Transcript,
+    weights: &[(Scalar, Scalar)],
+) -> Result<(), ProofError> {
+    telemetry::record_batch(proofs.len());
+    let result = check_triples(proofs, gens, transcript, weights);
+    telemetry::record_verification("batch", &result);
+    result
+}
+
+fn check_triples(
+    proofs: &[(&RangeProof, &[G1Affine], usize)],
+    gens: &[(&BulletproofGens, &PedersenGens)],
+    transcript: &Transcript,
+    weights: &[(Scalar, Scalar)],
+) -> Result<(), ProofError> {
+    if gens.len() != proofs.len() {
+        return Err(ProofError::InvalidGeneratorsLength);
+    }
+    if weights.len() != proofs.len() {
+        return Err(ProofError::InvalidBatchWeights);
+    }
+    if weights
+        .iter()
+        .any(|(weight, c)| bool::from(weight.is_zero() | c.is_zero()))
+    {
+        return Err(ProofError::InvalidBatchWeights);
+    }
+
+    let mut accs: Vec<VerificationAccumulator> = Vec::new();
+    for (((proof, value_commitments, n), (bp_gens, pc_gens)), (weight, c)) in
+        proofs.iter().zip(gens.iter()).zip(weights.iter())
+    {
+        let i = match accs.iter().position(|acc| acc.uses(bp_gens, pc_gens)) {
+            Some(i) => i,
+            None => {
+                accs.push(VerificationAccumulator::new(bp_gens, pc_gens));
+                accs.len() - 1
+            }
+        };
+        proof.accumulate_verification(
+            &mut accs[i],
+            *weight,
+            *c,
+            &mut transcript.clone(),
+            value_commitments,
+            *n,
+        )?;
+    }
+
+    if bool::from(VerificationAccumulator::evaluate_many(accs).is_identity()) {
+        Ok(())
+    } else {
+        Err(ProofError::VerificationError)
+    }
+}
+
+impl Serialize for ProofBatch {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for ProofBatch {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        util::deserialize_bytes(deserializer, "ProofBatch", ProofBatch::from_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_batch(bp_gens: &BulletproofGens, pc_gens: &PedersenGens) -> ProofBatch {
+        let mut rng = rand::thread_rng();
+        let mut batch = ProofBatch::new();
+
+        for (values, n) in [
+            (vec![7u64], 8),
+            (vec![1u64 << 20, 3], 32),
+            (vec![42u64], 64),
+        ] {
+            let blindings: Vec<Scalar> = values.iter().map(|_| Scalar::random(&mut rng)).collect();
+            let mut transcript = Transcript::new(b"ProofBatchTest");
+            let (proof, commitments) = RangeProof::prove_multiple(
+                bp_gens,
+                pc_gens,
+                &mut transcript,
+                &values,
+                &blindings,
+                n,
+            )
+            .unwrap();
+            batch.push(proof, commitments, n).unwrap();
+        }
+
+        batch
+    }
+
+    #[test]
+    fn range_proof_batch_verify() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let batch = make_batch(&bp_gens, &pc_gens);
+        let transcript = Transcript::new(b"ProofBatchTest");
+
+        let proofs: Vec<_> = batch.iter().collect();
+        assert!(RangeProof::batch_verify(&bp_gens, &pc_gens, &transcript, &proofs).is_ok());
+        assert!(RangeProof::batch_verify(&bp_gens, &pc_gens, &transcript, &[]).is_ok());
+
+        let mut wrong = proofs.clone();
+        wrong[0].1 = proofs[2].1;
+        assert_eq!(
+            RangeProof::batch_verify(&bp_gens, &pc_gens, &transcript, &wrong),
+            Err(ProofError::VerificationError)
+        );
+        let mut unsupported = proofs.clone();
+        unsupported[1].2 = 12;
+        assert_eq!(
+            RangeProof::batch_verify(&bp_gens, &pc_gens, &transcript, &unsupported),
+            Err(ProofError::InvalidBitsize)
+        );
+    }
+
+    #[test]
+    fn batch_verifies_and_roundtrips() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let batch = make_batch(&bp_gens, &pc_gens);
+
+        let transcript = Transcript::new(b"ProofBatchTest");
+        assert!(batch.verify(&bp_gens, &pc_gens, &transcript).is_ok());
+
+        let decoded = ProofBatch::from_bytes(&batch.to_bytes()).unwrap();
+        assert_eq!(decoded, batch);
+        let decoded: ProofBatch =
+            bincode::deserialize(&bincode::serialize(&batch).unwrap()).unwrap();
+        assert!(decoded.verify(&bp_gens, &pc_gens, &transcript).is_ok());
+
+        // Bitsizes that cannot be verified are rejected, rather than
+        // truncated when encoded
+        let (proof, commitments, _) = batch.iter().next().unwrap();
+        let mut other = batch.clone();
+        assert_eq!(
+            other.push(proof.clone(), commitments.to_vec(), 264),
+            Err(ProofError::InvalidBitsize)
+        );
+        assert_eq!(other, batch);
+        assert!(other.push(proof.clone(), commitments.to_vec(), 12).is_ok());
+        let (proof, commitments, _) = batch.iter().nth(1).unwrap();
+        assert_eq!(
+            other.push(proof.clone(), commitments.to_vec(), 12),
+            Err(ProofError::InvalidBitsize)
+        );
+        let mut bytes = batch.to_bytes();
+        bytes[4] = 65;
+        assert_eq!(ProofBatch::from_bytes(&bytes), Err(ProofError::FormatError));
+    }
+
+    #[test]
+    fn stream_verifies_without_collecting() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let batch = make_batch(&bp_gens, &pc_gens);
+        let transcript = Transcript::new(b"ProofBatchTest");
+
+        // Enough proofs to flush more than once
+        let stream = || {
+            batch
+                .entries
+                .iter()
+                .cycle()
+                .take(2 * STREAM_CHUNK_SIZE + 1)
+                .map(|e| (e.proof.clone(), e.value_commitments.clone(), e.n))
+        };
+        assert!(ProofBatch::verify_stream(&bp_gens, &pc_gens, &transcript, stream()).is_ok());
+
+        let wrong = pc_gens.commit(Scalar::from(8u64), Scalar::one()).into();
+        let tampered = stream()
+            .enumerate()
+            .map(|(i, (proof, mut commitments, n))| {
+                if i == STREAM_CHUNK_SIZE + 3 {
+                    commitments[0] = wrong;
+                }
+                (proof, commitments, n)
+            });
+        assert_eq!(
+            ProofBatch::verify_stream(&bp_gens, &pc_gens, &transcript, tampered),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn batch_rejects_mismatched_commitment() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let mut batch = make_batch(&bp_gens, &pc_gens);
+
+        let wrong = pc_gens.commit(Scalar::from(8u64), Scalar::one());
+        batch.entries[0].value_commitments[0] = wrong.into();
+
+        let transcript = Transcript::new(b"ProofBatchTest");
+        assert_eq!(
+            batch.verify(&bp_gens, &pc_gens, &transcript),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn batch_verifies_with_supplied_weights() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let batch = make_batch(&bp_gens, &pc_gens);
+        let transcript = Transcript::new(b"ProofBatchTest");
+
+        let weights: Vec<(Scalar, Scalar)> = (0..batch.len() as u64)
+            .map(|i| (Scalar::from(2 * i + 1), Scalar::from(2 * i + 2)))
+            .collect();
+        assert!(batch
+            .verify_with_weights(&bp_gens, &pc_gens, &transcript, &weights)
+            .is_ok());
+
+        assert_eq!(
+            batch.verify_with_weights(&bp_gens, &pc_gens, &transcript, &weights[1..]),
+            Err(ProofError::InvalidBatchWeights)
+        );
+        let mut zero = weights.clone();
+        zero[1].0 = Scalar::zero();
+        assert_eq!(
+            batch.verify_with_weights(&bp_gens, &pc_gens, &transcript, &zero),
+            Err(ProofError::InvalidBatchWeights)
+        );
+    }
+
+    #[test]
+    fn batch_derives_deterministic_weights() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let mut batch = make_batch(&bp_gens, &pc_gens);
+        let transcript = Transcript::new(b"ProofBatchTest");
+
+        let weights = batch.derive_weights(&transcript);
+        assert_eq!(weights.len(), batch.len());
+        assert_eq!(weights, batch.derive_weights(&transcript));
+        assert!(batch
+            .verify_deterministic(&bp_gens, &pc_gens, &transcript)
+            .is_ok());
+
+        batch.entries.swap(0, 1);
+        assert_ne!(weights, batch.derive_weights(&transcript));
+        assert!(batch
+            .verify_deterministic(&bp_gens, &pc_gens, &transcript)
+            .is_ok());
+    }
+
+    #[test]
+    fn batch_verifies_across_generators() {
+        let (asset_pc_gens, asset_bp_gens, _) =
+            crate::generators::derive_generators(b"ProofBatchTest", b"asset", 64, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+
+        let mut batch = make_batch(&bp_gens, &pc_gens);
+        let asset_batch = make_batch(&asset_bp_gens, &asset_pc_gens);
+        for (proof, commitments, n) in asset_batch.iter() {
+            batch.push(proof.clone(), commitments.to_vec(), n).unwrap();
+        }
+        let mut gens = vec![(&bp_gens, &pc_gens); 3];
+        gens.extend(vec![(&asset_bp_gens, &asset_pc_gens); 3]);
+
+        let transcript = Transcript::new(b"ProofBatchTest");
+        assert!(batch.verify_with_gens(&gens, &transcript).is_ok());
+
+        // Proofs do not verify under another asset's generators
+        gens.swap(0, 3);
+        assert_eq!(
+            batch.verify_with_gens(&gens, &transcript),
+            Err(ProofError::VerificationError)
+        );
+        assert_eq!(
+            batch.verify_with_gens(&gens[1..], &transcript),
+            Err(ProofError::InvalidGeneratorsLength)
+        );
+    }
+}
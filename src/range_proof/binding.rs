@@ -0,0 +1,314 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Binding of range proofs to a prover's BLS public key.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use blstrs::{G1Affine, G2Affine, G2Projective, Scalar};
+use group::prime::PrimeCurveAffine;
+use group::Curve;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::RangeProof;
+use crate::transcript::TranscriptProtocol;
+use crate::util::{self, read48, read96};
+
+/// Domain separation tag for hashing the transcript digest to \\(\mathbb G\_2\\).
+const BINDING_DST: &[u8] = b"BLS_BULLETPROOFS_PROVER_BINDING_BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+/// Binds a [`RangeProof`] to the BLS public key of its prover.
+///
+/// The public key is appended to the proving transcript before any
+/// proof data, so the proof only verifies against the same key.  On
+/// its own this only records a claimed prover; to attribute the proof
+/// to the holder of the secret key, the binding can also carry a BLS
+/// signature (in \\(\mathbb G\_2\\)) over a digest of the final
+/// proving transcript.
+///
+/// # Encoding
+///
+/// The binding is encoded as the compressed public key, followed by
+/// the compressed signature if there is one.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ProverBinding {
+    public_key: G1Affine,
+    signature: Option<G2Affine>,
+}
+
+impl ProverBinding {
+    /// Creates an unsigned binding to `public_key`.
+    pub fn new(public_key: G1Affine) -> Self {
+        ProverBinding {
+            public_key,
+            signature: None,
+        }
+    }
+
+    /// Returns the prover's public key.
+    pub fn public_key(&self) -> &G1Affine {
+        &self.public_key
+    }
+
+    /// Returns the prover's signature over the transcript digest, if any.
+    pub fn signature(&self) -> Option<&G2Affine> {
+        self.signature.as_ref()
+    }
+
+    /// Appends the public key to `transcript`.
+    fn append_to(&self, transcript: &mut Transcript) -> Result<(), ProofError> {
+        transcript.append_message(b"dom-sep", b"prover-binding v1");
+        transcript.validate_and_append_point(b"pk", &self.public_key.into())
+    }
+
+    /// Hashes the digest of the proving `transcript` to \\(\mathbb G\_2\\).
+    fn message_point(transcript: &mut Transcript) -> G2Projective {
+        let mut digest = [0u8; 32];
+        transcript.challenge_bytes(b"prover-binding-digest", &mut digest);
+        G2Projective::hash_to_curve(&digest, BINDING_DST, &[])
+    }
+
+    /// Serializes the binding into a byte array of 48 or 144 bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(48 + 96);
+        buf.extend_from_slice(&self.public_key.to_compressed());
+        if let Some(signature) = self.signature {
+            buf.extend_from_slice(&signature.to_compressed());
+        }
+        buf
+    }
+
+    /// Deserializes the binding from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a `ProverBinding`.
+    pub fn from_bytes(slice: &[u8]) -> Result<ProverBinding, ProofError> {
+        if slice.len() != 48 && slice.len() != 48 + 96 {
+            return Err(ProofError::FormatError);
+        }
+
+        let public_key = Option::from(G1Affine::from_compressed(&read48(slice)))
+            .ok_or(ProofError::FormatError)?;
+        let signature = if slice.len() == 48 {
+            None
+        } else {
+            Some(
+                Option::from(G2Affine::from_compressed(&read96(&slice[48..])))
+                    .ok_or(ProofError::FormatError)?,
+            )
+        };
+
+        Ok(ProverBinding {
+            public_key,
+            signature,
+        })
+    }
+}
+
+impl RangeProof {
+    /// Create a rangeproof for a set of values, bound to the BLS public
+    /// key of `secret_key`.
+    ///
+    /// If `sign` is `true`, the returned [`ProverBinding`] also carries a
+    /// signature by `secret_key` over the digest of the proving transcript.
+    /// Otherwise, this is [`RangeProof::prove_multiple_with_rng`] with
+    /// the public key prepended to the transcript.
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_multiple_bound_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        secret_key: &Scalar,
+        sign: bool,
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<G1Affine>, ProverBinding), ProofError> {
+        let mut binding = ProverBinding::new((G1Affine::generator() * secret_key).to_affine());
+        binding.append_to(transcript)?;
+
+        let (proof, value_commitments) = RangeProof::prove_multiple_with_rng(
+            bp_gens, pc_gens, transcript, values, blindings, n, rng,
+        )?;
+
+        if sign {
+            let message = ProverBinding::message_point(transcript);
+            binding.signature = Some((message * secret_key).to_affine());
+        }
+
+        Ok((proof, value_commitments, binding))
+    }
+
+    /// Create a rangeproof for a set of values, bound to the BLS public
+    /// key of `secret_key`.
+    /// This is a convenience wrapper around [`RangeProof::prove_multiple_bound_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_multiple_bound(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        secret_key: &Scalar,
+        sign: bool,
+    ) -> Result<(RangeProof, Vec<G1Affine>, ProverBinding), ProofError> {
+        RangeProof::prove_multiple_bound_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            secret_key,
+            sign,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies a rangeproof created by [`RangeProof::prove_multiple_bound_with_rng`].
+    ///
+    /// The proof must verify with the binding's public key prepended to
+    /// `transcript`, and if the binding carries a signature, it must be
+    /// a valid signature by that key over the transcript digest.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_multiple_bound_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        binding: &ProverBinding,
+        value_commitments: &[G1Affine],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        binding.append_to(transcript)?;
+        self.verify_multiple_with_rng(bp_gens, pc_gens, transcript, value_commitments, n, rng)?;
+
+        if let Some(signature) = binding.signature {
+            let message = ProverBinding::message_point(transcript).to_affine();
+            if !util::verify_bls_signature(&binding.public_key, &message, &signature) {
+                return Err(ProofError::VerificationError);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verifies a rangeproof created by [`RangeProof::prove_multiple_bound`].
+    /// This is a convenience wrapper around [`RangeProof::verify_multiple_bound_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify_multiple_bound(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        binding: &ProverBinding,
+        value_commitments: &[G1Affine],
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple_bound_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            binding,
+            value_commitments,
+            n,
+            &mut thread_rng(),
+        )
+    }
+}
+
+impl Serialize for ProverBinding {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for ProverBinding {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        util::deserialize_bytes(deserializer, "ProverBinding", ProverBinding::from_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use group::ff::Field;
+
+    #[test]
+    fn bound_proof_verifies_only_with_its_key() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
+        let secret_key = Scalar::random(&mut rng);
+        let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+
+        let mut transcript = Transcript::new(b"ProverBindingTest");
+        let (proof, commitments, binding) = RangeProof::prove_multiple_bound(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[5, 6],
+            &blindings,
+            32,
+            &secret_key,
+            true,
+        )
+        .unwrap();
+        assert!(binding.signature().is_some());
+
+        let verify = |binding: &ProverBinding| {
+            let mut transcript = Transcript::new(b"ProverBindingTest");
+            proof.verify_multiple_bound(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                binding,
+                &commitments,
+                32,
+            )
+        };
+
+        assert!(verify(&binding).is_ok());
+        let decoded = ProverBinding::from_bytes(&binding.to_bytes()).unwrap();
+        assert!(verify(&decoded).is_ok());
+
+        // An unsigned binding to the same key still verifies.
+        assert!(verify(&ProverBinding::new(*binding.public_key())).is_ok());
+
+        // A different key, or a signature from a different key, does not.
+        let other_key = Scalar::random(&mut rng);
+        let other = ProverBinding::new((G1Affine::generator() * other_key).to_affine());
+        assert_eq!(verify(&other), Err(ProofError::VerificationError));
+        let forged = ProverBinding {
+            public_key: binding.public_key,
+            signature: Some((G2Affine::generator() * other_key).to_affine()),
+        };
+        assert_eq!(verify(&forged), Err(ProofError::VerificationError));
+    }
+}
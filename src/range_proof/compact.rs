@@ -0,0 +1,402 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Compact binary encoding of the MPC messages.
+//!
+//! Points are encoded as compressed affine points of 48 bytes, and
+//! scalars as 32 little-endian bytes.  The vectors of a
+//! [`ProofShare`] are batched back to back without length prefixes,
+//! their length following from the size of the message.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use byteorder::{ByteOrder, LittleEndian};
+use group::Curve;
+
+use crate::errors::MPCError;
+use crate::util::{read32, read48};
+
+use super::messages::*;
+
+/// The size of the header of a packed round.
+const PACKET_HEADER_LEN: usize = 8;
+
+fn write_point(buf: &mut Vec<u8>, point: &G1Projective) {
+    buf.extend_from_slice(&point.to_affine().to_compressed());
+}
+
+fn read_point(slice: &[u8]) -> Result<G1Projective, MPCError> {
+    Option::from(G1Affine::from_compressed(&read48(slice)))
+        .map(|P: G1Affine| P.into())
+        .ok_or(MPCError::FormatError)
+}
+
+fn read_scalar(slice: &[u8]) -> Result<Scalar, MPCError> {
+    Option::from(Scalar::from_bytes_le(&read32(slice))).ok_or(MPCError::FormatError)
+}
+
+fn read_scalars(slice: &[u8]) -> Result<Vec<Scalar>, MPCError> {
+    slice.chunks(32).map(read_scalar).collect()
+}
+
+/// An MPC message with a compact binary encoding, which can be packed
+/// with the other messages of its round by [`pack_round`].
+pub trait RoundMessage: Sized {
+    /// Serializes the message into a byte vector.
+    fn to_bytes(&self) -> Vec<u8>;
+
+    /// Deserializes the message from a byte slice.
+    fn from_bytes(slice: &[u8]) -> Result<Self, MPCError>;
+}
+
+impl BitCommitment {
+    /// The size of the encoding of a [`BitCommitment`].
+    pub const SERIALIZED_SIZE: usize = 3 * 48;
+
+    /// Serializes the message into a byte vector of
+    /// [`BitCommitment::SERIALIZED_SIZE`] bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(BitCommitment::SERIALIZED_SIZE);
+        write_point(&mut buf, &self.V_j);
+        write_point(&mut buf, &self.A_j);
+        write_point(&mut buf, &self.S_j);
+        buf
+    }
+
+    /// Deserializes the message from a byte slice.
+    ///
+    /// Returns an error if the slice has the wrong size, or does not
+    /// encode valid points.
+    pub fn from_bytes(slice: &[u8]) -> Result<BitCommitment, MPCError> {
+        if slice.len() != BitCommitment::SERIALIZED_SIZE {
+            return Err(MPCError::FormatError);
+        }
+        Ok(BitCommitment {
+            V_j: read_point(&slice[0..])?,
+            A_j: read_point(&slice[48..])?,
+            S_j: read_point(&slice[2 * 48..])?,
+        })
+    }
+}
+
+impl GroupBitCommitment {
+    /// Serializes the message into a byte vector of \\(48 (k + 2)\\)
+    /// bytes, for a group of \\(k\\) parties.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity((self.V.len() + 2) * 48);
+        write_point(&mut buf, &self.A);
+        write_point(&mut buf, &self.S);
+        for V_j in self.V.iter() {
+            write_point(&mut buf, V_j);
+        }
+        buf
+    }
+
+    /// Deserializes the message from a byte slice.
+    ///
+    /// Returns an error if the slice has the wrong size, or does not
+    /// encode valid points.
+    pub fn from_bytes(slice: &[u8]) -> Result<GroupBitCommitment, MPCError> {
+        let k = (slice.len() / 48).saturating_sub(2);
+        if k == 0 || (k + 2) * 48 != slice.len() {
+            return Err(MPCError::FormatError);
+        }
+        Ok(GroupBitCommitment {
+            A: read_point(&slice[0..])?,
+            S: read_point(&slice[48..])?,
+            V: slice[2 * 48..]
+                .chunks(48)
+                .map(read_point)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl BitChallenge {
+    /// The size of the encoding of a [`BitChallenge`].
+    pub const SERIALIZED_SIZE: usize = 2 * 32;
+
+    /// Serializes the message into a byte vector of
+    /// [`BitChallenge::SERIALIZED_SIZE`] bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(BitChallenge::SERIALIZED_SIZE);
+        buf.extend_from_slice(&self.y.to_bytes_le());
+        buf.extend_from_slice(&self.z.to_bytes_le());
+        buf
+    }
+
+    /// Deserializes the message from a byte slice.
+    ///
+    /// Returns an error if the slice has the wrong size, or does not
+    /// encode canonical scalars.
+    pub fn from_bytes(slice: &[u8]) -> Result<BitChallenge, MPCError> {
+        if slice.len() != BitChallenge::SERIALIZED_SIZE {
+            return Err(MPCError::FormatError);
+        }
+        Ok(BitChallenge {
+            y: read_scalar(&slice[0..])?,
+            z: read_scalar(&slice[32..])?,
+        })
+    }
+}
+
+impl PolyCommitment {
+    /// The size of the encoding of a [`PolyCommitment`].
+    pub const SERIALIZED_SIZE: usize = 2 * 48;
+
+    /// Serializes the message into a byte vector of
+    /// [`PolyCommitment::SERIALIZED_SIZE`] bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(PolyCommitment::SERIALIZED_SIZE);
+        write_point(&mut buf, &self.T_1_j);
+        write_point(&mut buf, &self.T_2_j);
+        buf
+    }
+
+    /// Deserializes the message from a byte slice.
+    ///
+    /// Returns an error if the slice has the wrong size, or does not
+    /// encode valid points.
+    pub fn from_bytes(slice: &[u8]) -> Result<PolyCommitment, MPCError> {
+        if slice.len() != PolyCommitment::SERIALIZED_SIZE {
+            return Err(MPCError::FormatError);
+        }
+        Ok(PolyCommitment {
+            T_1_j: read_point(&slice[0..])?,
+            T_2_j: read_point(&slice[48..])?,
+        })
+    }
+}
+
+impl PolyChallenge {
+    /// The size of the encoding of a [`PolyChallenge`].
+    pub const SERIALIZED_SIZE: usize = 32;
+
+    /// Serializes the message into a byte vector of
+    /// [`PolyChallenge::SERIALIZED_SIZE`] bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.x.to_bytes_le().to_vec()
+    }
+
+    /// Deserializes the message from a byte slice.
+    ///
+    /// Returns an error if the slice has the wrong size, or does not
+    /// encode a canonical scalar.
+    pub fn from_bytes(slice: &[u8]) -> Result<PolyChallenge, MPCError> {
+        if slice.len() != PolyChallenge::SERIALIZED_SIZE {
+            return Err(MPCError::FormatError);
+        }
+        Ok(PolyChallenge {
+            x: read_scalar(slice)?,
+        })
+    }
+}
+
+impl ProofShare {
+    /// Returns the size of the encoding of the [`ProofShare`] of a
+    /// party proving an `n`-bit range, or of a group of parties with
+    /// `n` bits in total.
+    pub fn serialized_size(n: usize) -> usize {
+        (3 + 2 * n) * 32
+    }
+
+    /// Serializes the message into a byte vector of
+    /// [`ProofShare::serialized_size`] bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(ProofShare::serialized_size(self.l_vec.len()));
+        buf.extend_from_slice(&self.t_x.to_bytes_le());
+        buf.extend_from_slice(&self.t_x_blinding.to_bytes_le());
+        buf.extend_from_slice(&self.e_blinding.to_bytes_le());
+        for s in self.l_vec.iter().chain(self.r_vec.iter()) {
+            buf.extend_from_slice(&s.to_bytes_le());
+        }
+        buf
+    }
+
+    /// Deserializes the message from a byte slice.
+    ///
+    /// Returns an error if the slice has the wrong size, or does not
+    /// encode canonical scalars.
+    pub fn from_bytes(slice: &[u8]) -> Result<ProofShare, MPCError> {
+        let n = slice.len().saturating_sub(3 * 32) / 64;
+        if ProofShare::serialized_size(n) != slice.len() {
+            return Err(MPCError::FormatError);
+        }
+        let (scalars, l_and_r) = slice.split_at(3 * 32);
+        let (l, r) = l_and_r.split_at(n * 32);
+        Ok(ProofShare {
+            t_x: read_scalar(&scalars[0..])?,
+            t_x_blinding: read_scalar(&scalars[32..])?,
+            e_blinding: read_scalar(&scalars[2 * 32..])?,
+            l_vec: read_scalars(l)?,
+            r_vec: read_scalars(r)?,
+        })
+    }
+}
+
+macro_rules! impl_round_message {
+    ($($message:ident),*) => {
+        $(
+            impl RoundMessage for $message {
+                fn to_bytes(&self) -> Vec<u8> {
+                    $message::to_bytes(self)
+                }
+
+                fn from_bytes(slice: &[u8]) -> Result<Self, MPCError> {
+                    $message::from_bytes(slice)
+                }
+            }
+        )*
+    };
+}
+
+impl_round_message!(
+    BitCommitment,
+    GroupBitCommitment,
+    BitChallenge,
+    PolyCommitment,
+    PolyChallenge,
+    ProofShare
+);
+
+/// Packs the messages of one round, e.g. the [`BitCommitment`]s of the
+/// parties of a sub-dealer, into one buffer.
+///
+/// The messages must all have the same size, which is the case for the
+/// messages of parties proving ranges of the same bitsize.  The buffer
+/// has a header of 8 bytes, with the number of messages and their size,
+/// followed by the messages.
+///
+/// Returns an error if the messages have different sizes.
+pub fn pack_round<M: RoundMessage>(messages: &[M]) -> Result<Vec<u8>, MPCError> {
+    let encoded: Vec<Vec<u8>> = messages.iter().map(|m| m.to_bytes()).collect();
+    let len = encoded.first().map(|m| m.len()).unwrap_or(0);
+    if encoded.iter().any(|m| m.len() != len) {
+        return Err(MPCError::FormatError);
+    }
+
+    let mut buf = alloc::vec![0u8; PACKET_HEADER_LEN];
+    LittleEndian::write_u32(&mut buf[0..4], encoded.len() as u32);
+    LittleEndian::write_u32(&mut buf[4..8], len as u32);
+    buf.reserve(encoded.len() * len);
+    for m in encoded {
+        buf.extend_from_slice(&m);
+    }
+    Ok(buf)
+}
+
+/// Unpacks the messages of one round from a buffer created by
+/// [`pack_round`].
+///
+/// Returns an error if the buffer has the wrong size, or any message
+/// cannot be parsed.
+pub fn unpack_round<M: RoundMessage>(slice: &[u8]) -> Result<Vec<M>, MPCError> {
+    if slice.len() < PACKET_HEADER_LEN {
+        return Err(MPCError::FormatError);
+    }
+    let count = LittleEndian::read_u32(&slice[0..4]) as usize;
+    let len = LittleEndian::read_u32(&slice[4..8]) as usize;
+    let body = &slice[PACKET_HEADER_LEN..];
+    if count.checked_mul(len) != Some(body.len()) {
+        return Err(MPCError::FormatError);
+    }
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    if len == 0 {
+        return Err(MPCError::FormatError);
+    }
+
+    body.chunks(len).map(M::from_bytes).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::generators::{BulletproofGens, PedersenGens};
+    use crate::range_proof::dealer::Dealer;
+    use crate::range_proof::party::Party;
+    use merlin::Transcript;
+
+    #[test]
+    fn compact_messages_roundtrip() {
+        let (n, m) = (16, 2);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut transcript = Transcript::new(b"CompactTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = [5u64, 600]
+            .iter()
+            .enumerate()
+            .map(|(j, &v)| {
+                Party::new(&bp_gens, &pc_gens, v, Scalar::from(j as u64 + 1), n)
+                    .unwrap()
+                    .assign_position(j)
+                    .unwrap()
+            })
+            .unzip();
+
+        // Each round travels packed, and is decoded on arrival
+        let packed = pack_round(&bit_commitments).unwrap();
+        assert_eq!(
+            packed.len(),
+            PACKET_HEADER_LEN + m * BitCommitment::SERIALIZED_SIZE
+        );
+        let (dealer, bit_challenge) = dealer
+            .receive_bit_commitments(unpack_round(&packed).unwrap())
+            .unwrap();
+        let bit_challenge = BitChallenge::from_bytes(&bit_challenge.to_bytes()).unwrap();
+
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&bit_challenge))
+            .unzip();
+        let packed = pack_round(&poly_commitments).unwrap();
+        let (dealer, poly_challenge) = dealer
+            .receive_poly_commitments(unpack_round(&packed).unwrap())
+            .unwrap();
+        let poly_challenge = PolyChallenge::from_bytes(&poly_challenge.to_bytes()).unwrap();
+
+        let shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge).unwrap())
+            .collect();
+        assert_eq!(shares[0].to_bytes().len(), ProofShare::serialized_size(n));
+        let packed = pack_round(&shares).unwrap();
+        let proof = dealer
+            .receive_shares(&unpack_round::<ProofShare>(&packed).unwrap())
+            .unwrap();
+
+        let V: Vec<_> = bit_commitments.iter().map(|c| c.V_j.to_affine()).collect();
+        assert!(proof
+            .verify_multiple(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"CompactTest"),
+                &V,
+                n
+            )
+            .is_ok());
+
+        // Truncated packets are rejected
+        assert_eq!(
+            unpack_round::<ProofShare>(&packed[..packed.len() - 1]).err(),
+            Some(MPCError::FormatError)
+        );
+        let group: GroupBitCommitment = bit_commitments[1].into();
+        assert_eq!(
+            GroupBitCommitment::from_bytes(&group.to_bytes()).unwrap().V,
+            group.V
+        );
+    }
+}
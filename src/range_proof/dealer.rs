@@ -16,7 +16,7 @@ extern crate alloc;
 
 use alloc::vec::Vec;
 
-use blstrs::{G1Projective, Scalar};
+use blstrs::{G1Affine, G1Projective, Scalar};
 use group::ff::Field;
 use group::Curve;
 use merlin::Transcript;
@@ -27,14 +27,42 @@ use crate::range_proof::RangeProof;
 use crate::transcript::TranscriptProtocol;
 use crate::{inner_product_proof, ProofError};
 
+#[cfg(feature = "verifier")]
 use rand::{CryptoRng, RngCore};
 
 use crate::util;
 
-#[cfg(feature = "std")]
+#[cfg(all(feature = "thread_rng", feature = "verifier"))]
 use rand::thread_rng;
 
+use super::delegated::{DelegatingProver, DelegationRequest};
 use super::messages::*;
+use super::pending::PendingRangeProof;
+
+/// The aggregated proof shares, as the inputs of the inner-product
+/// argument.
+struct IppInputs {
+    t_x: Scalar,
+    t_x_blinding: Scalar,
+    e_blinding: Scalar,
+    Q: G1Projective,
+    y_inv: Scalar,
+    l_vec: Vec<Scalar>,
+    r_vec: Vec<Scalar>,
+}
+
+/// Returns the position of the first party of each group, starting
+/// from `offset`.
+fn group_offsets(groups: &[GroupBitCommitment], offset: usize) -> Vec<usize> {
+    groups
+        .iter()
+        .scan(offset, |offset, gc| {
+            let j = *offset;
+            *offset += gc.V.len();
+            Some(j)
+        })
+        .collect()
+}
 
 /// Used to construct a dealer for the aggregated rangeproof MPC protocol.
 pub struct Dealer {}
@@ -84,6 +112,7 @@ impl Dealer {
             initial_transcript,
             n,
             m,
+            ipp_cutoff: 1,
         })
     }
 }
@@ -98,9 +127,23 @@ pub struct DealerAwaitingBitCommitments<'a, 'b> {
     initial_transcript: Transcript,
     n: usize,
     m: usize,
+    ipp_cutoff: usize,
 }
 
 impl<'a, 'b> DealerAwaitingBitCommitments<'a, 'b> {
+    /// Sets the recursion cutoff `k` of the inner-product argument, as
+    /// in [`InnerProductProof::create_with_cutoff`](crate::InnerProductProof::create_with_cutoff).
+    ///
+    /// The default cutoff is 1.  The cutoff must be a power of 2 no
+    /// larger than \\(n \cdot m\\).
+    pub fn with_ipp_cutoff(mut self, k: usize) -> Result<Self, MPCError> {
+        if !k.is_power_of_two() || k > self.n * self.m {
+            return Err(MPCError::InvalidCutoff);
+        }
+        self.ipp_cutoff = k;
+        Ok(self)
+    }
+
     /// Receive each party's [`BitCommitment`]s and compute the [`BitChallenge`].
     pub fn receive_bit_commitments(
         self,
@@ -110,16 +153,48 @@ impl<'a, 'b> DealerAwaitingBitCommitments<'a, 'b> {
             return Err(MPCError::WrongNumBitCommitments);
         }
 
+        self.receive_groups(bit_commitments.into_iter().map(Into::into).collect())
+    }
+
+    /// Receive the [`GroupBitCommitment`]s of [`SubDealer`]s, each
+    /// aggregating the parties at consecutive positions, and compute
+    /// the [`BitChallenge`].
+    ///
+    /// The groups must be given in the order of their positions, and
+    /// cover all `m` parties.  The rest of the protocol then runs with
+    /// one aggregated message per group: in particular, the indexes of
+    /// [`MPCError::MalformedProofShares`] are the indexes of the groups.
+    pub fn receive_group_bit_commitments(
+        self,
+        group_bit_commitments: Vec<GroupBitCommitment>,
+    ) -> Result<(DealerAwaitingPolyCommitments<'a, 'b>, BitChallenge), MPCError> {
+        if group_bit_commitments.iter().any(|gc| gc.V.is_empty())
+            || self.m
+                != group_bit_commitments
+                    .iter()
+                    .map(|gc| gc.V.len())
+                    .sum::<usize>()
+        {
+            return Err(MPCError::WrongNumBitCommitments);
+        }
+
+        self.receive_groups(group_bit_commitments)
+    }
+
+    fn receive_groups(
+        self,
+        bit_commitments: Vec<GroupBitCommitment>,
+    ) -> Result<(DealerAwaitingPolyCommitments<'a, 'b>, BitChallenge), MPCError> {
         // Commit each V_j individually
-        for vc in bit_commitments.iter() {
-            self.transcript.append_point(b"V", &vc.V_j);
+        for V_j in bit_commitments.iter().flat_map(|gc| gc.V.iter()) {
+            self.transcript.append_point(b"V", V_j);
         }
 
         // Commit aggregated A_j, S_j
-        let A: G1Projective = bit_commitments.iter().map(|vc| vc.A_j).sum();
+        let A: G1Projective = bit_commitments.iter().map(|gc| gc.A).sum();
         self.transcript.append_point(b"A", &A);
 
-        let S: G1Projective = bit_commitments.iter().map(|vc| vc.S_j).sum();
+        let S: G1Projective = bit_commitments.iter().map(|gc| gc.S).sum();
         self.transcript.append_point(b"S", &S);
 
         let y = self.transcript.challenge_scalar(b"y");
@@ -130,6 +205,7 @@ impl<'a, 'b> DealerAwaitingBitCommitments<'a, 'b> {
             DealerAwaitingPolyCommitments {
                 n: self.n,
                 m: self.m,
+                ipp_cutoff: self.ipp_cutoff,
                 transcript: self.transcript,
                 initial_transcript: self.initial_transcript,
                 bp_gens: self.bp_gens,
@@ -149,12 +225,13 @@ impl<'a, 'b> DealerAwaitingBitCommitments<'a, 'b> {
 pub struct DealerAwaitingPolyCommitments<'a, 'b> {
     n: usize,
     m: usize,
+    ipp_cutoff: usize,
     transcript: &'a mut Transcript,
     initial_transcript: Transcript,
     bp_gens: &'b BulletproofGens,
     pc_gens: &'b PedersenGens,
     bit_challenge: BitChallenge,
-    bit_commitments: Vec<BitCommitment>,
+    bit_commitments: Vec<GroupBitCommitment>,
     /// Aggregated commitment to the parties' bits
     A: G1Projective,
     /// Aggregated commitment to the parties' bit blindings
@@ -168,7 +245,7 @@ impl<'a, 'b> DealerAwaitingPolyCommitments<'a, 'b> {
         self,
         poly_commitments: Vec<PolyCommitment>,
     ) -> Result<(DealerAwaitingProofShares<'a, 'b>, PolyChallenge), MPCError> {
-        if self.m != poly_commitments.len() {
+        if self.bit_commitments.len() != poly_commitments.len() {
             return Err(MPCError::WrongNumPolyCommitments);
         }
 
@@ -186,6 +263,7 @@ impl<'a, 'b> DealerAwaitingPolyCommitments<'a, 'b> {
             DealerAwaitingProofShares {
                 n: self.n,
                 m: self.m,
+                ipp_cutoff: self.ipp_cutoff,
                 transcript: self.transcript,
                 initial_transcript: self.initial_transcript,
                 bp_gens: self.bp_gens,
@@ -207,15 +285,17 @@ impl<'a, 'b> DealerAwaitingPolyCommitments<'a, 'b> {
 /// A dealer which has sent the [`PolyChallenge`] to the parties and
 /// is waiting to aggregate their [`ProofShare`]s into a
 /// [`RangeProof`].
+#[cfg_attr(not(feature = "verifier"), allow(dead_code))]
 pub struct DealerAwaitingProofShares<'a, 'b> {
     n: usize,
     m: usize,
+    ipp_cutoff: usize,
     transcript: &'a mut Transcript,
     initial_transcript: Transcript,
     bp_gens: &'b BulletproofGens,
     pc_gens: &'b PedersenGens,
     bit_challenge: BitChallenge,
-    bit_commitments: Vec<BitCommitment>,
+    bit_commitments: Vec<GroupBitCommitment>,
     poly_challenge: PolyChallenge,
     poly_commitments: Vec<PolyCommitment>,
     A: G1Projective,
@@ -225,21 +305,32 @@ pub struct DealerAwaitingProofShares<'a, 'b> {
 }
 
 impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
-    /// Assembles proof shares into an `RangeProof`.
-    ///
-    /// Used as a helper function by `receive_trusted_shares` (which
-    /// just hands back the result) and `receive_shares` (which
-    /// validates the proof shares.
-    fn assemble_shares(&mut self, proof_shares: &[ProofShare]) -> Result<RangeProof, ProofError> {
-        if self.m != proof_shares.len() {
+    /// Returns the value commitments of all parties.
+    fn value_commitments(&self) -> Vec<G1Affine> {
+        self.bit_commitments
+            .iter()
+            .flat_map(|gc| gc.V.iter().map(|V_j| V_j.to_affine()))
+            .collect()
+    }
+
+    /// Aggregates the proof shares into the inputs of the
+    /// inner-product argument, appending the aggregated scalars to
+    /// the transcript.
+    fn aggregate_shares(&mut self, proof_shares: &[ProofShare]) -> Result<IppInputs, ProofError> {
+        if self.bit_commitments.len() != proof_shares.len() {
             return Err(MPCError::WrongNumProofShares.into());
         }
 
         // Validate lengths for each share
         let mut bad_shares = Vec::<usize>::new(); // no allocations until we append
-        for (j, share) in proof_shares.iter().enumerate() {
+        for (j, (share, offset)) in proof_shares
+            .iter()
+            .zip(group_offsets(&self.bit_commitments, 0))
+            .enumerate()
+        {
+            let k = self.bit_commitments[j].V.len();
             share
-                .check_size(self.n, &self.bp_gens, j)
+                .check_size(self.n, k, &self.bp_gens, offset)
                 .unwrap_or_else(|_| {
                     bad_shares.push(j);
                 });
@@ -272,12 +363,8 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
         let w = self.transcript.challenge_scalar(b"w");
         let Q = self.pc_gens.B * w;
 
-        let G_factors: Vec<Scalar> = iter::repeat(Scalar::one()).take(self.n * self.m).collect();
-        let H_factors: Vec<Scalar> = util::exp_iter(
-            Option::from(self.bit_challenge.y.invert()).ok_or(ProofError::FormatError)?,
-        )
-        .take(self.n * self.m)
-        .collect();
+        let y_inv: Scalar =
+            Option::from(self.bit_challenge.y.invert()).ok_or(ProofError::FormatError)?;
 
         let l_vec: Vec<Scalar> = proof_shares
             .iter()
@@ -288,15 +375,43 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
             .flat_map(|ps| ps.r_vec.clone().into_iter())
             .collect();
 
-        let ipp_proof = inner_product_proof::InnerProductProof::create(
+        Ok(IppInputs {
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            Q,
+            y_inv,
+            l_vec,
+            r_vec,
+        })
+    }
+
+    /// Assembles proof shares into an `RangeProof`.
+    ///
+    /// Used as a helper function by `receive_trusted_shares` (which
+    /// just hands back the result) and `receive_shares` (which
+    /// validates the proof shares.
+    fn assemble_shares(
+        &mut self,
+        proof_shares: &[ProofShare],
+        progress: Option<&dyn Fn(f32)>,
+    ) -> Result<RangeProof, ProofError> {
+        let inputs = self.aggregate_shares(proof_shares)?;
+
+        let G_factors: Vec<Scalar> = iter::repeat(Scalar::one()).take(self.n * self.m).collect();
+        let H_factors: Vec<Scalar> = util::exp_iter(inputs.y_inv).take(self.n * self.m).collect();
+
+        let ipp_proof = inner_product_proof::InnerProductProof::create_with_progress(
             self.transcript,
-            &Q,
+            &inputs.Q,
             &G_factors,
             &H_factors,
             self.bp_gens.G(self.n, self.m).cloned().collect(),
             self.bp_gens.H(self.n, self.m).cloned().collect(),
-            l_vec,
-            r_vec,
+            inputs.l_vec,
+            inputs.r_vec,
+            self.ipp_cutoff,
+            progress,
         )?;
 
         Ok(RangeProof {
@@ -304,10 +419,76 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
             S: self.S.to_affine(),
             T_1: self.T_1.to_affine(),
             T_2: self.T_2.to_affine(),
-            t_x,
-            t_x_blinding,
-            e_blinding,
+            t_x: inputs.t_x,
+            t_x_blinding: inputs.t_x_blinding,
+            e_blinding: inputs.e_blinding,
             ipp_proof,
+            params: Some((self.n, self.m)),
+        })
+    }
+
+    /// Aggregates the proof shares, and hands the inner-product
+    /// argument off to a [`DelegatedInnerProduct`](super::DelegatedInnerProduct) run elsewhere.
+    ///
+    /// The transcript is moved into the returned
+    /// [`DelegatingProver`], which derives the challenges of the
+    /// inner-product argument and assembles the proof.
+    pub(super) fn delegate_shares(
+        mut self,
+        proof_shares: &[ProofShare],
+    ) -> Result<(DelegatingProver<'a>, DelegationRequest), ProofError> {
+        if self.ipp_cutoff != 1 {
+            return Err(ProofError::InvalidCutoff);
+        }
+        let inputs = self.aggregate_shares(proof_shares)?;
+        self.transcript
+            .innerproduct_domain_sep((self.n * self.m) as u64);
+
+        let prover = DelegatingProver {
+            transcript: self.transcript,
+            n: self.n,
+            m: self.m,
+            A: self.A.to_affine(),
+            S: self.S.to_affine(),
+            T_1: self.T_1.to_affine(),
+            T_2: self.T_2.to_affine(),
+            t_x: inputs.t_x,
+            t_x_blinding: inputs.t_x_blinding,
+            e_blinding: inputs.e_blinding,
+            L_vec: Vec::new(),
+            R_vec: Vec::new(),
+        };
+        let request = DelegationRequest {
+            n: self.n,
+            m: self.m,
+            Q: inputs.Q.to_affine(),
+            y_inv: inputs.y_inv,
+            l_vec: inputs.l_vec,
+            r_vec: inputs.r_vec,
+        };
+        Ok((prover, request))
+    }
+
+    /// Aggregates the proof shares into a [`PendingRangeProof`], whose
+    /// inner-product argument is computed later.
+    pub(super) fn defer_shares(
+        mut self,
+        proof_shares: &[ProofShare],
+    ) -> Result<PendingRangeProof, ProofError> {
+        let inputs = self.aggregate_shares(proof_shares)?;
+        Ok(PendingRangeProof {
+            n: self.n,
+            m: self.m,
+            V: self.value_commitments(),
+            A: self.A.to_affine(),
+            S: self.S.to_affine(),
+            T_1: self.T_1.to_affine(),
+            T_2: self.T_2.to_affine(),
+            t_x: inputs.t_x,
+            t_x_blinding: inputs.t_x_blinding,
+            e_blinding: inputs.e_blinding,
+            l_vec: inputs.l_vec,
+            r_vec: inputs.r_vec,
         })
     }
 
@@ -317,7 +498,7 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
     ///
     /// This is a convenience wrapper around receive_shares_with_rng
     ///
-    #[cfg(feature = "std")]
+    #[cfg(all(feature = "thread_rng", feature = "verifier"))]
     pub fn receive_shares(self, proof_shares: &[ProofShare]) -> Result<RangeProof, ProofError> {
         self.receive_shares_with_rng(proof_shares, &mut thread_rng())
     }
@@ -335,18 +516,16 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
     /// performing local aggregation,
     /// [`receive_trusted_shares`](DealerAwaitingProofShares::receive_trusted_shares)
     /// saves time by skipping verification of the aggregated proof.
+    /// Verifying the aggregated proof requires the `verifier` feature.
+    #[cfg(feature = "verifier")]
     pub fn receive_shares_with_rng<T: RngCore + CryptoRng>(
         mut self,
         proof_shares: &[ProofShare],
         rng: &mut T,
     ) -> Result<RangeProof, ProofError> {
-        let proof = self.assemble_shares(proof_shares)?;
+        let proof = self.assemble_shares(proof_shares, None)?;
 
-        let Vs: Vec<_> = self
-            .bit_commitments
-            .iter()
-            .map(|vc| vc.V_j.to_affine())
-            .collect();
+        let Vs = self.value_commitments();
 
         // See comment in `Dealer::new` for why we use `initial_transcript`
         let transcript = &mut self.initial_transcript;
@@ -358,11 +537,14 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
         } else {
             // Proof verification failed. Now audit the parties:
             let mut bad_shares = Vec::new();
-            for j in 0..self.m {
+            for (j, offset) in group_offsets(&self.bit_commitments, 0)
+                .into_iter()
+                .enumerate()
+            {
                 match proof_shares[j].audit_share(
                     &self.bp_gens,
                     &self.pc_gens,
-                    j,
+                    offset,
                     &self.bit_commitments[j],
                     &self.bit_challenge,
                     &self.poly_commitments[j],
@@ -394,6 +576,269 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
         mut self,
         proof_shares: &[ProofShare],
     ) -> Result<RangeProof, ProofError> {
-        self.assemble_shares(proof_shares)
+        self.assemble_shares(proof_shares, None)
+    }
+
+    /// Assemble the final aggregated [`RangeProof`] from the given
+    /// trusted `proof_shares`, as by
+    /// [`receive_trusted_shares`](DealerAwaitingProofShares::receive_trusted_shares),
+    /// reporting the progress of the inner-product argument.
+    pub(crate) fn receive_trusted_shares_with_progress(
+        mut self,
+        proof_shares: &[ProofShare],
+        progress: &dyn Fn(f32),
+    ) -> Result<RangeProof, ProofError> {
+        self.assemble_shares(proof_shares, Some(progress))
+    }
+}
+
+/// Used to construct a sub-dealer, which aggregates the messages of
+/// the parties at consecutive positions for a [`Dealer`].
+///
+/// Very large aggregations can be coordinated as a tree of dealers:
+/// each sub-dealer relays the challenges of its dealer to its parties,
+/// and forwards one aggregated message per round, which the dealer
+/// receives with
+/// [`receive_group_bit_commitments`](DealerAwaitingBitCommitments::receive_group_bit_commitments).
+/// A sub-dealer can itself aggregate the messages of other sub-dealers.
+/// The final proof is a standard [`RangeProof`].
+pub struct SubDealer {}
+
+impl SubDealer {
+    /// Creates a new sub-dealer aggregating the `k` parties at positions
+    /// `offset..offset + k`, proving `n`-bit ranges.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<'b>(
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        n: usize,
+        offset: usize,
+        k: usize,
+    ) -> Result<SubDealerAwaitingBitCommitments<'b>, MPCError> {
+        if !(n == 8 || n == 16 || n == 32 || n == 64) {
+            return Err(MPCError::InvalidBitsize);
+        }
+        if k == 0 {
+            return Err(MPCError::InvalidAggregation);
+        }
+        if bp_gens.gens_capacity < n {
+            return Err(MPCError::InvalidGeneratorsLength);
+        }
+        match offset.checked_add(k) {
+            Some(end) if end <= bp_gens.party_capacity => {}
+            _ => return Err(MPCError::InvalidGeneratorsLength),
+        }
+
+        Ok(SubDealerAwaitingBitCommitments {
+            bp_gens,
+            pc_gens,
+            n,
+            offset,
+            k,
+        })
+    }
+}
+
+/// A sub-dealer waiting for its parties to send their [`BitCommitment`]s.
+pub struct SubDealerAwaitingBitCommitments<'b> {
+    bp_gens: &'b BulletproofGens,
+    pc_gens: &'b PedersenGens,
+    n: usize,
+    offset: usize,
+    k: usize,
+}
+
+impl<'b> SubDealerAwaitingBitCommitments<'b> {
+    /// Receive each party's [`BitCommitment`]s, and aggregate them into
+    /// the [`GroupBitCommitment`] for the dealer.
+    pub fn receive_bit_commitments(
+        self,
+        bit_commitments: Vec<BitCommitment>,
+    ) -> Result<(SubDealerAwaitingPolyCommitments<'b>, GroupBitCommitment), MPCError> {
+        if self.k != bit_commitments.len() {
+            return Err(MPCError::WrongNumBitCommitments);
+        }
+
+        self.receive_groups(bit_commitments.into_iter().map(Into::into).collect())
+    }
+
+    /// Receive the [`GroupBitCommitment`]s of other sub-dealers, given in
+    /// the order of their positions, and aggregate them into the
+    /// [`GroupBitCommitment`] for the dealer.
+    pub fn receive_group_bit_commitments(
+        self,
+        group_bit_commitments: Vec<GroupBitCommitment>,
+    ) -> Result<(SubDealerAwaitingPolyCommitments<'b>, GroupBitCommitment), MPCError> {
+        if group_bit_commitments.iter().any(|gc| gc.V.is_empty())
+            || self.k
+                != group_bit_commitments
+                    .iter()
+                    .map(|gc| gc.V.len())
+                    .sum::<usize>()
+        {
+            return Err(MPCError::WrongNumBitCommitments);
+        }
+
+        self.receive_groups(group_bit_commitments)
+    }
+
+    fn receive_groups(
+        self,
+        bit_commitments: Vec<GroupBitCommitment>,
+    ) -> Result<(SubDealerAwaitingPolyCommitments<'b>, GroupBitCommitment), MPCError> {
+        let group_bit_commitment = GroupBitCommitment {
+            V: bit_commitments
+                .iter()
+                .flat_map(|gc| gc.V.iter().cloned())
+                .collect(),
+            A: bit_commitments.iter().map(|gc| gc.A).sum(),
+            S: bit_commitments.iter().map(|gc| gc.S).sum(),
+        };
+
+        Ok((
+            SubDealerAwaitingPolyCommitments {
+                bp_gens: self.bp_gens,
+                pc_gens: self.pc_gens,
+                n: self.n,
+                offset: self.offset,
+                bit_commitments,
+            },
+            group_bit_commitment,
+        ))
+    }
+}
+
+/// A sub-dealer which has forwarded its [`GroupBitCommitment`] to the
+/// dealer, and relayed the [`BitChallenge`] of the dealer to its
+/// parties, and is waiting for their [`PolyCommitment`]s.
+pub struct SubDealerAwaitingPolyCommitments<'b> {
+    bp_gens: &'b BulletproofGens,
+    pc_gens: &'b PedersenGens,
+    n: usize,
+    offset: usize,
+    bit_commitments: Vec<GroupBitCommitment>,
+}
+
+impl<'b> SubDealerAwaitingPolyCommitments<'b> {
+    /// Receive the [`PolyCommitment`]s of the parties, given the
+    /// `bit_challenge` they were sent, and aggregate them into the
+    /// [`PolyCommitment`] for the dealer.
+    pub fn receive_poly_commitments(
+        self,
+        bit_challenge: &BitChallenge,
+        poly_commitments: Vec<PolyCommitment>,
+    ) -> Result<(SubDealerAwaitingProofShares<'b>, PolyCommitment), MPCError> {
+        if self.bit_commitments.len() != poly_commitments.len() {
+            return Err(MPCError::WrongNumPolyCommitments);
+        }
+
+        let group_poly_commitment = PolyCommitment {
+            T_1_j: poly_commitments.iter().map(|pc| pc.T_1_j).sum(),
+            T_2_j: poly_commitments.iter().map(|pc| pc.T_2_j).sum(),
+        };
+
+        Ok((
+            SubDealerAwaitingProofShares {
+                bp_gens: self.bp_gens,
+                pc_gens: self.pc_gens,
+                n: self.n,
+                offset: self.offset,
+                bit_challenge: *bit_challenge,
+                bit_commitments: self.bit_commitments,
+                poly_commitments,
+            },
+            group_poly_commitment,
+        ))
+    }
+}
+
+/// A sub-dealer which has relayed the [`PolyChallenge`] of the dealer
+/// to its parties, and is waiting to aggregate their [`ProofShare`]s.
+pub struct SubDealerAwaitingProofShares<'b> {
+    bp_gens: &'b BulletproofGens,
+    pc_gens: &'b PedersenGens,
+    n: usize,
+    offset: usize,
+    bit_challenge: BitChallenge,
+    bit_commitments: Vec<GroupBitCommitment>,
+    poly_commitments: Vec<PolyCommitment>,
+}
+
+impl<'b> SubDealerAwaitingProofShares<'b> {
+    /// Aggregate the `proof_shares` of the parties, given the
+    /// `poly_challenge` they were sent, into the [`ProofShare`] for the
+    /// dealer, after auditing each of them.
+    ///
+    /// If any shares are malformed, the indexes of the parties, or of
+    /// the groups received by this sub-dealer, which sent them are
+    /// returned as part of the [`MPCError`].
+    pub fn receive_shares(
+        self,
+        poly_challenge: &PolyChallenge,
+        proof_shares: &[ProofShare],
+    ) -> Result<ProofShare, MPCError> {
+        if self.bit_commitments.len() != proof_shares.len() {
+            return Err(MPCError::WrongNumProofShares);
+        }
+
+        let mut bad_shares = Vec::new();
+        for (j, offset) in group_offsets(&self.bit_commitments, self.offset)
+            .into_iter()
+            .enumerate()
+        {
+            match proof_shares[j].audit_share(
+                self.bp_gens,
+                self.pc_gens,
+                offset,
+                &self.bit_commitments[j],
+                &self.bit_challenge,
+                &self.poly_commitments[j],
+                poly_challenge,
+            ) {
+                Ok(_) => {}
+                Err(_) => bad_shares.push(j),
+            }
+        }
+        if !bad_shares.is_empty() {
+            return Err(MPCError::MalformedProofShares { bad_shares });
+        }
+
+        Ok(ProofShare::aggregate(proof_shares))
+    }
+
+    /// Aggregate the `proof_shares` of the parties into the
+    /// [`ProofShare`] for the dealer, checking only their sizes.
+    ///
+    /// ## WARNING
+    ///
+    /// This function does **NOT** validate the proof shares.  The
+    /// dealer still detects a malformed aggregated share, but can only
+    /// attribute it to this sub-dealer as a whole.
+    pub fn receive_trusted_shares(
+        self,
+        proof_shares: &[ProofShare],
+    ) -> Result<ProofShare, MPCError> {
+        if self.bit_commitments.len() != proof_shares.len() {
+            return Err(MPCError::WrongNumProofShares);
+        }
+
+        let mut bad_shares = Vec::new();
+        for (j, offset) in group_offsets(&self.bit_commitments, self.offset)
+            .into_iter()
+            .enumerate()
+        {
+            let k = self.bit_commitments[j].V.len();
+            if proof_shares[j]
+                .check_size(self.n, k, self.bp_gens, offset)
+                .is_err()
+            {
+                bad_shares.push(j);
+            }
+        }
+        if !bad_shares.is_empty() {
+            return Err(MPCError::MalformedProofShares { bad_shares });
+        }
+
+        Ok(ProofShare::aggregate(proof_shares))
     }
 }
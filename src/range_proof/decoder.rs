@@ -0,0 +1,160 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+//! Incremental decoding of range proofs from chunks of bytes.
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::errors::ProofError;
+use crate::range_proof::{RangeProof, SHAPE_PREFIX_LEN};
+
+/// The state of a [`ProofDecoder`] after a chunk of bytes was pushed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DecodeState {
+    /// The proof is incomplete and needs this many more bytes.
+    ///
+    /// Until the fixed-size prefix of the proof has arrived, this is
+    /// the number of bytes missing from the prefix, which is a lower
+    /// bound; afterwards it is exact.
+    NeedBytes(usize),
+    /// The proof is complete.
+    Complete(Box<RangeProof>),
+}
+
+/// Assembles a [`RangeProof`] from chunks of bytes as they arrive, e.g.
+/// from the network, without buffering the whole message first.
+///
+/// The proof length is read from its header with
+/// [`RangeProof::decode_header`] as soon as enough bytes are available,
+/// so the decoder can report exactly how many bytes are still missing.
+/// Bytes pushed beyond the end of a proof are kept as the start of the
+/// next one.  Only the versioned encoding is supported, since legacy
+/// encodings do not record their shape.
+#[derive(Clone, Debug, Default)]
+pub struct ProofDecoder {
+    buf: Vec<u8>,
+    len: Option<usize>,
+}
+
+impl ProofDecoder {
+    /// Creates an empty decoder.
+    pub fn new() -> Self {
+        ProofDecoder::default()
+    }
+
+    /// Appends `chunk` to the buffered bytes, and decodes the proof if
+    /// it is complete.
+    ///
+    /// Returns an error if the header is invalid or the completed proof
+    /// cannot be parsed; the decoder is then reset and its buffered
+    /// bytes are dropped.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<DecodeState, ProofError> {
+        self.buf.extend_from_slice(chunk);
+        let result = self.advance();
+        if result.is_err() {
+            self.buf.clear();
+            self.len = None;
+        }
+        result
+    }
+
+    /// Returns the number of bytes buffered for the current proof.
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    fn advance(&mut self) -> Result<DecodeState, ProofError> {
+        let len = match self.len {
+            Some(len) => len,
+            None => {
+                if !self.buf.is_empty() && self.buf[0] & 0x80 != 0 {
+                    return Err(ProofError::FormatError);
+                }
+                if self.buf.len() < SHAPE_PREFIX_LEN {
+                    return Ok(DecodeState::NeedBytes(SHAPE_PREFIX_LEN - self.buf.len()));
+                }
+                let len = RangeProof::decode_header(&self.buf)?.byte_len;
+                self.len = Some(len);
+                len
+            }
+        };
+
+        if self.buf.len() < len {
+            return Ok(DecodeState::NeedBytes(len - self.buf.len()));
+        }
+        let rest = self.buf.split_off(len);
+        let proof = RangeProof::from_bytes(&self.buf);
+        self.buf = rest;
+        self.len = None;
+        Ok(DecodeState::Complete(Box::new(proof?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::Scalar;
+    use merlin::Transcript;
+
+    use crate::generators::{BulletproofGens, PedersenGens};
+
+    #[test]
+    fn decodes_proof_from_chunks() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut transcript = Transcript::new(b"DecoderTest");
+        let (proof, _) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[1, 2],
+            &[Scalar::from(3u64), Scalar::from(4u64)],
+            32,
+        )
+        .unwrap();
+        let bytes = proof.to_bytes();
+
+        let mut decoder = ProofDecoder::new();
+        assert_eq!(
+            decoder.push(&bytes[..10]),
+            Ok(DecodeState::NeedBytes(SHAPE_PREFIX_LEN - 10))
+        );
+        assert_eq!(
+            decoder.push(&bytes[10..SHAPE_PREFIX_LEN + 5]),
+            Ok(DecodeState::NeedBytes(bytes.len() - SHAPE_PREFIX_LEN - 5))
+        );
+        let mut state = Ok(DecodeState::NeedBytes(0));
+        for chunk in bytes[SHAPE_PREFIX_LEN + 5..].chunks(100) {
+            state = decoder.push(chunk);
+        }
+        assert_eq!(state, Ok(DecodeState::Complete(Box::new(proof.clone()))));
+        assert_eq!(decoder.buffered(), 0);
+
+        // Two proofs in one chunk, the second split across two
+        let mut twice = bytes.clone();
+        twice.extend_from_slice(&bytes);
+        assert_eq!(
+            decoder.push(&twice[..bytes.len() + 1]),
+            Ok(DecodeState::Complete(Box::new(proof.clone())))
+        );
+        assert_eq!(decoder.buffered(), 1);
+        assert_eq!(
+            decoder.push(&twice[bytes.len() + 1..]),
+            Ok(DecodeState::Complete(Box::new(proof.clone())))
+        );
+
+        // Legacy encodings do not record their length
+        assert_eq!(
+            decoder.push(&bytes[crate::range_proof::HEADER_LEN..]),
+            Err(ProofError::FormatError)
+        );
+        assert_eq!(decoder.buffered(), 0);
+    }
+}
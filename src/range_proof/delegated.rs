@@ -0,0 +1,520 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Delegated proving, where a weak client hands the large
+//! multiscalar multiplications of a range proof to an untrusted server.
+//!
+//! The client commits to the bits of its values and computes the
+//! vectors \\(\mathbf{l}(x), \mathbf{r}(x)\\) as in the aggregation
+//! protocol, which only takes point additions and scalar arithmetic.
+//! These vectors are blinded by the vectors \\(\mathbf{s}\_L,
+//! \mathbf{s}\_R\\) committed to in \\(S\\), so they reveal nothing
+//! about the values: they can be sent to the server in a
+//! [`DelegationRequest`], in the same way as a party of the
+//! aggregation protocol sends them to the dealer.
+//!
+//! The server then runs the inner-product argument with a
+//! [`DelegatedInnerProduct`], one round at a time.  The client keeps
+//! the transcript, so it derives each challenge from the points the
+//! server sends in an [`IppStep`], and finally assembles the
+//! [`RangeProof`] itself.  This takes \\(\lg(n \cdot m)\\) round trips.
+//!
+//! The only multiscalar multiplication left to the client is the
+//! commitment \\(S\\), which does not depend on the values, and is
+//! computed ahead of time in a [`ProvingPad`], e.g. while the device
+//! is idle and charging.
+//!
+//! A malicious server cannot learn anything about the values, but it
+//! can make the client assemble an invalid proof, so the proof should
+//! be verified by whoever accepts it, as usual.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::mem;
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use clear_on_drop::clear::Clear;
+use group::ff::Field;
+use group::Curve;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use super::dealer::Dealer;
+use super::party::{self, Party};
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::inner_product_proof::{inner_product, InnerProductProof};
+use crate::range_proof::RangeProof;
+use crate::transcript::TranscriptProtocol;
+use crate::util;
+
+/// The blinding vectors of the bits of the values of a range proof,
+/// with their commitments, computed ahead of time.
+///
+/// A pad must only be used for a single proof: it is consumed by
+/// [`DelegatingProver::new_with_rng`], and cannot be cloned.  It must
+/// be computed with the same generators as the proof.
+pub struct ProvingPad {
+    n: usize,
+    shares: Vec<PadShare>,
+}
+
+/// The blinding vectors of the bits of the value at one position.
+struct PadShare {
+    s_blinding: Scalar,
+    s_L: Vec<Scalar>,
+    s_R: Vec<Scalar>,
+    S: G1Projective,
+}
+
+impl ProvingPad {
+    /// Computes a pad for a proof of `m` values in the range \\([0, 2^n)\\).
+    pub fn new_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        n: usize,
+        m: usize,
+        rng: &mut T,
+    ) -> Result<ProvingPad, ProofError> {
+        if !(n == 8 || n == 16 || n == 32 || n == 64) {
+            return Err(ProofError::InvalidBitsize);
+        }
+        if !m.is_power_of_two() {
+            return Err(ProofError::InvalidAggregation);
+        }
+        if bp_gens.gens_capacity < n || bp_gens.party_capacity < m {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        let shares = (0..m)
+            .map(|j| {
+                let s_blinding = Scalar::random(&mut *rng);
+                let s_L: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut *rng)).collect();
+                let s_R: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut *rng)).collect();
+                let S =
+                    party::bit_blinding_commitment(bp_gens, pc_gens, j, n, &s_blinding, &s_L, &s_R);
+                PadShare {
+                    s_blinding,
+                    s_L,
+                    s_R,
+                    S,
+                }
+            })
+            .collect();
+        Ok(ProvingPad { n, shares })
+    }
+
+    /// Computes a pad for a proof of `m` values in the range \\([0, 2^n)\\).
+    /// This is a convenience wrapper around [`ProvingPad::new_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn new(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        n: usize,
+        m: usize,
+    ) -> Result<ProvingPad, ProofError> {
+        ProvingPad::new_with_rng(bp_gens, pc_gens, n, m, &mut thread_rng())
+    }
+
+    /// Returns the bitsize \\(n\\) of the range.
+    pub fn bitsize(&self) -> usize {
+        self.n
+    }
+
+    /// Returns the number of values \\(m\\) in the proof.
+    pub fn aggregation_size(&self) -> usize {
+        self.shares.len()
+    }
+}
+
+/// Overwrite secrets with null bytes when they go out of scope.
+impl Drop for ProvingPad {
+    fn drop(&mut self) {
+        for share in self.shares.iter_mut() {
+            share.s_blinding.clear();
+            for e in share.s_L.iter_mut().chain(share.s_R.iter_mut()) {
+                e.clear();
+            }
+        }
+    }
+}
+
+/// The inputs of the inner-product argument, sent by the client to
+/// the server.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DelegationRequest {
+    pub(super) n: usize,
+    pub(super) m: usize,
+    pub(super) Q: G1Affine,
+    pub(super) y_inv: Scalar,
+    pub(super) l_vec: Vec<Scalar>,
+    pub(super) r_vec: Vec<Scalar>,
+}
+
+/// A step of the inner-product argument, sent by the server to the
+/// client.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub enum IppStep {
+    /// The points \\(L, R\\) of the next round, for which the client
+    /// returns a challenge with [`DelegatingProver::apply_round`].
+    Round {
+        /// The point \\(L\\) of the round
+        L: G1Affine,
+        /// The point \\(R\\) of the round
+        R: G1Affine,
+    },
+    /// The final scalars \\(a, b\\), with which the client assembles
+    /// the proof with [`DelegatingProver::finish`].
+    Done {
+        /// The final scalar \\(a\\)
+        a: Scalar,
+        /// The final scalar \\(b\\)
+        b: Scalar,
+    },
+}
+
+/// The client of a delegated range proof, which keeps the transcript
+/// and assembles the proof.
+pub struct DelegatingProver<'a> {
+    pub(super) transcript: &'a mut Transcript,
+    pub(super) n: usize,
+    pub(super) m: usize,
+    pub(super) A: G1Affine,
+    pub(super) S: G1Affine,
+    pub(super) T_1: G1Affine,
+    pub(super) T_2: G1Affine,
+    pub(super) t_x: Scalar,
+    pub(super) t_x_blinding: Scalar,
+    pub(super) e_blinding: Scalar,
+    pub(super) L_vec: Vec<G1Projective>,
+    pub(super) R_vec: Vec<G1Projective>,
+}
+
+impl<'a> DelegatingProver<'a> {
+    /// Starts a delegated proof that the `values` are in the range
+    /// \\([0, 2^n)\\), using the blinding vectors of `pad`.
+    ///
+    /// Returns the client state, the [`DelegationRequest`] to send to
+    /// the server, and the commitments to the values.  Returns
+    /// [`ProofError::ParameterMismatch`] if the pad was computed for
+    /// another bitsize or number of values.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &'a mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        mut pad: ProvingPad,
+        rng: &mut T,
+    ) -> Result<(DelegatingProver<'a>, DelegationRequest, Vec<G1Affine>), ProofError> {
+        if values.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+        if pad.n != n || pad.shares.len() != values.len() {
+            return Err(ProofError::ParameterMismatch);
+        }
+
+        let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, values.len())?;
+
+        let parties: Vec<_> = values
+            .iter()
+            .zip(blindings.iter())
+            .map(|(&v, &v_blinding)| Party::new(bp_gens, pc_gens, v, v_blinding, n))
+            // Collect the iterator of Results into a Result<Vec>, then unwrap it
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .zip(mem::take(&mut pad.shares))
+            .enumerate()
+            .map(|(j, (p, share))| {
+                p.assign_position_with_bit_blindings(
+                    j,
+                    share.s_blinding,
+                    share.s_L,
+                    share.s_R,
+                    share.S,
+                    &mut *rng,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .unzip();
+
+        let value_commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j.to_affine()).collect();
+
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments)?;
+
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge_with_rng(&bit_challenge, &mut *rng))
+            .unzip();
+
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
+
+        let proof_shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge))
+            // Collect the iterator of Results into a Result<Vec>, then unwrap it
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (prover, request) = dealer.delegate_shares(&proof_shares)?;
+        Ok((prover, request, value_commitments))
+    }
+
+    /// Starts a delegated proof that the `values` are in the range
+    /// \\([0, 2^n)\\), using the blinding vectors of `pad`.
+    /// This is a convenience wrapper around [`DelegatingProver::new_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn new(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &'a mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        pad: ProvingPad,
+    ) -> Result<(DelegatingProver<'a>, DelegationRequest, Vec<G1Affine>), ProofError> {
+        DelegatingProver::new_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            pad,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Receives the points \\(L, R\\) of a round of the inner-product
+    /// argument, and returns the challenge \\(u\\) for the server.
+    ///
+    /// Returns [`ProofError::FormatError`] if the server sends more
+    /// rounds than the argument has.
+    pub fn apply_round(&mut self, L: &G1Affine, R: &G1Affine) -> Result<Scalar, ProofError> {
+        if (self.n * self.m) >> self.L_vec.len() <= 1 {
+            return Err(ProofError::FormatError);
+        }
+        let L = G1Projective::from(L);
+        let R = G1Projective::from(R);
+        self.transcript.append_point(b"L", &L);
+        self.transcript.append_point(b"R", &R);
+        self.L_vec.push(L);
+        self.R_vec.push(R);
+        Ok(self.transcript.challenge_scalar(b"u"))
+    }
+
+    /// Assembles the [`RangeProof`] from the final scalars \\(a, b\\)
+    /// of the inner-product argument.
+    ///
+    /// Returns [`ProofError::FormatError`] if the server has not sent
+    /// all rounds of the argument.
+    pub fn finish(self, a: Scalar, b: Scalar) -> Result<RangeProof, ProofError> {
+        if (self.n * self.m) >> self.L_vec.len() != 1 {
+            return Err(ProofError::FormatError);
+        }
+        Ok(RangeProof {
+            A: self.A,
+            S: self.S,
+            T_1: self.T_1,
+            T_2: self.T_2,
+            t_x: self.t_x,
+            t_x_blinding: self.t_x_blinding,
+            e_blinding: self.e_blinding,
+            ipp_proof: InnerProductProof {
+                L_vec: self.L_vec,
+                R_vec: self.R_vec,
+                a_vec: alloc::vec![a],
+                b_vec: alloc::vec![b],
+            },
+            params: Some((self.n, self.m)),
+        })
+    }
+}
+
+/// The server of a delegated range proof, which runs the
+/// inner-product argument for a [`DelegatingProver`].
+pub struct DelegatedInnerProduct {
+    Q: G1Projective,
+    G: Vec<G1Projective>,
+    H: Vec<G1Projective>,
+    a: Vec<Scalar>,
+    b: Vec<Scalar>,
+}
+
+impl DelegatedInnerProduct {
+    /// Starts the inner-product argument for `request`, and returns
+    /// the first step for the client.
+    pub fn new(
+        bp_gens: &BulletproofGens,
+        request: DelegationRequest,
+    ) -> Result<(DelegatedInnerProduct, IppStep), ProofError> {
+        let (n, m) = (request.n, request.m);
+        if !(n == 8 || n == 16 || n == 32 || n == 64) {
+            return Err(ProofError::InvalidBitsize);
+        }
+        if !m.is_power_of_two() {
+            return Err(ProofError::InvalidAggregation);
+        }
+        if bp_gens.gens_capacity < n || bp_gens.party_capacity < m {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        if request.l_vec.len() != n * m || request.r_vec.len() != n * m {
+            return Err(ProofError::FormatError);
+        }
+
+        // Unlike the local prover, apply the factors up front, which
+        // costs the server another n * m scalar multiplications but
+        // keeps the rounds uniform.
+        let H = bp_gens
+            .H(n, m)
+            .zip(util::exp_iter(request.y_inv))
+            .map(|(H_i, y_inv_i)| H_i * y_inv_i)
+            .collect();
+        let ipp = DelegatedInnerProduct {
+            Q: request.Q.into(),
+            G: bp_gens.G(n, m).cloned().collect(),
+            H,
+            a: request.l_vec,
+            b: request.r_vec,
+        };
+        let step = ipp.step();
+        Ok((ipp, step))
+    }
+
+    /// Folds the vectors with the challenge `u` from the client, and
+    /// returns the next step.
+    pub fn apply_challenge(&mut self, u: &Scalar) -> Result<IppStep, ProofError> {
+        let n = self.a.len() / 2;
+        if n == 0 {
+            return Err(ProofError::FormatError);
+        }
+        let u_inv: Scalar = Option::from(u.invert()).ok_or(ProofError::FormatError)?;
+
+        for i in 0..n {
+            self.a[i] = self.a[i] * u + self.a[n + i] * u_inv;
+            self.b[i] = self.b[i] * u_inv + self.b[n + i] * u;
+            self.G[i] = self.G[i] * u_inv + self.G[n + i] * u;
+            self.H[i] = self.H[i] * u + self.H[n + i] * u_inv;
+        }
+        self.a.truncate(n);
+        self.b.truncate(n);
+        self.G.truncate(n);
+        self.H.truncate(n);
+
+        Ok(self.step())
+    }
+
+    /// Computes the points of the current round, or the final scalars.
+    fn step(&self) -> IppStep {
+        let n = self.a.len() / 2;
+        if n == 0 {
+            return IppStep::Done {
+                a: self.a[0],
+                b: self.b[0],
+            };
+        }
+        let (a_L, a_R) = self.a.split_at(n);
+        let (b_L, b_R) = self.b.split_at(n);
+        let (G_L, G_R) = self.G.split_at(n);
+        let (H_L, H_R) = self.H.split_at(n);
+
+        let c_L = inner_product(a_L, b_R);
+        let c_R = inner_product(a_R, b_L);
+
+        let L = util::multiscalar_mul(
+            &[a_L, b_R, &[c_L]].concat(),
+            &[G_R, H_L, &[self.Q]].concat(),
+        );
+        let R = util::multiscalar_mul(
+            &[a_R, b_L, &[c_R]].concat(),
+            &[G_L, H_R, &[self.Q]].concat(),
+        );
+
+        IppStep::Round {
+            L: L.to_affine(),
+            R: R.to_affine(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delegated_proof_verifies() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let pad = ProvingPad::new(&bp_gens, &pc_gens, 32, 2).unwrap();
+        assert_eq!((pad.bitsize(), pad.aggregation_size()), (32, 2));
+
+        let mut transcript = Transcript::new(b"DelegatedTest");
+        let (mut client, request, commitments) = DelegatingProver::new(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[7, 1 << 20],
+            &[Scalar::from(3u64), Scalar::from(4u64)],
+            32,
+            pad,
+        )
+        .unwrap();
+
+        // The request goes over the wire to the server
+        let request: DelegationRequest =
+            bincode::deserialize(&bincode::serialize(&request).unwrap()).unwrap();
+        let (mut server, mut step) = DelegatedInnerProduct::new(&bp_gens, request).unwrap();
+        let mut rounds = 0;
+        let proof = loop {
+            match step {
+                IppStep::Round { L, R } => {
+                    let u = client.apply_round(&L, &R).unwrap();
+                    step = server.apply_challenge(&u).unwrap();
+                    rounds += 1;
+                }
+                IppStep::Done { a, b } => break client.finish(a, b).unwrap(),
+            }
+        };
+        assert_eq!(rounds, 6);
+        assert_eq!(
+            server.apply_challenge(&Scalar::one()).unwrap_err(),
+            ProofError::FormatError
+        );
+
+        let mut transcript = Transcript::new(b"DelegatedTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 32)
+            .is_ok());
+
+        // A pad is made for a fixed shape
+        let pad = ProvingPad::new(&bp_gens, &pc_gens, 32, 2).unwrap();
+        let mut transcript = Transcript::new(b"DelegatedTest");
+        assert!(matches!(
+            DelegatingProver::new(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &[7],
+                &[Scalar::from(3u64)],
+                32,
+                pad,
+            ),
+            Err(ProofError::ParameterMismatch)
+        ));
+    }
+}
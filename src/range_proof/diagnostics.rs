@@ -0,0 +1,187 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Diagnostic verification of range proofs, checking each component
+//! of the verification equation separately.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use group::ff::Field;
+use group::Group;
+use merlin::Transcript;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::{RangeProof, VerificationAccumulator};
+
+/// A component check of range proof verification which failed, as
+/// reported by [`RangeProof::verify_multiple_debug`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FailedCheck {
+    /// The proof could not be replayed into the transcript, so the
+    /// challenges and the commitment \\(P\\) to \\(\mathbf{l}(x)\\)
+    /// and \\(\mathbf{r}(x)\\) could not be reconstructed, e.g.
+    /// because a point of the proof is invalid.
+    CommitmentReconstruction(ProofError),
+    /// The check of \\(\hat{t} = t(x)\\) failed, i.e.
+    /// \\(t\_x B + \tilde{t}\_x \tilde{B} \neq z^2 \langle \mathbf{z}^m, \mathbf{V} \rangle + \delta(y,z) B + x T\_1 + x^2 T\_2\\).
+    PolynomialEvaluation,
+    /// The inner-product argument does not open the reconstructed
+    /// commitment \\(P\\), bound to \\(t\_x\\), to its final scalars.
+    InnerProduct,
+}
+
+impl RangeProof {
+    /// Verifies a rangeproof for a given value commitment \\(V\\),
+    /// checking each component of the verification equation separately.
+    ///
+    /// This is [`RangeProof::verify_multiple_debug`] for the `m=1` case.
+    pub fn verify_single_debug(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &G1Affine,
+        n: usize,
+    ) -> Result<(), Vec<FailedCheck>> {
+        self.verify_multiple_debug(bp_gens, pc_gens, transcript, &[*V], n)
+    }
+
+    /// Verifies an aggregated rangeproof for the given value
+    /// commitments, checking each component of the verification
+    /// equation separately, and returns every check which failed.
+    ///
+    /// [`RangeProof::verify_multiple`] combines the checks into one
+    /// multiscalar multiplication with a random challenge, which only
+    /// tells whether all of them hold.  This is meant for debugging
+    /// proofs from other implementations, and is several times slower.
+    /// A proof is valid iff this returns `Ok(())`.
+    pub fn verify_multiple_debug(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[G1Affine],
+        n: usize,
+    ) -> Result<(), Vec<FailedCheck>> {
+        // With c = 0, the accumulated equation is the inner-product
+        // check alone, and the difference to c = 1 is the t(x) check.
+        let evaluate = |c: Scalar, transcript: &mut Transcript| {
+            let mut acc = VerificationAccumulator::new(bp_gens, pc_gens);
+            self.accumulate_verification(
+                &mut acc,
+                Scalar::one(),
+                c,
+                transcript,
+                value_commitments,
+                n,
+            )
+            .map(|_| acc.evaluate())
+            .map_err(|e| alloc::vec![FailedCheck::CommitmentReconstruction(e)])
+        };
+        let inner_product = evaluate(Scalar::zero(), &mut transcript.clone())?;
+        let polynomial: G1Projective = evaluate(Scalar::one(), transcript)? - inner_product;
+
+        let mut failed = Vec::new();
+        if !bool::from(polynomial.is_identity()) {
+            failed.push(FailedCheck::PolynomialEvaluation);
+        }
+        if !bool::from(inner_product.is_identity()) {
+            failed.push(FailedCheck::InnerProduct);
+        }
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(failed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use group::Curve;
+
+    #[test]
+    fn debug_verification_reports_failed_check() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let (proof, V) = RangeProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"DebugTest"),
+            1000,
+            &Scalar::from(7u64),
+            16,
+        )
+        .unwrap();
+
+        let verify = |proof: &RangeProof, V: &G1Affine| {
+            proof.verify_single_debug(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"DebugTest"),
+                V,
+                16,
+            )
+        };
+        assert_eq!(verify(&proof, &V), Ok(()));
+
+        // Another value commitment changes all challenges
+        let other = pc_gens
+            .commit(Scalar::from(1001u64), Scalar::from(7u64))
+            .to_affine();
+        assert_eq!(
+            verify(&proof, &other),
+            Err(alloc::vec![
+                FailedCheck::PolynomialEvaluation,
+                FailedCheck::InnerProduct
+            ])
+        );
+
+        // The final scalars are not appended to the transcript
+        let mut tampered = proof.clone();
+        tampered.ipp_proof.a_vec[0] += Scalar::one();
+        assert_eq!(
+            verify(&tampered, &V),
+            Err(alloc::vec![FailedCheck::InnerProduct])
+        );
+
+        // A value out of range only fails the t(x) check
+        let (proof_out_of_range, V_out_of_range) = RangeProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"DebugTest"),
+            1 << 16,
+            &Scalar::from(7u64),
+            16,
+        )
+        .unwrap();
+        assert_eq!(
+            verify(&proof_out_of_range, &V_out_of_range),
+            Err(alloc::vec![FailedCheck::PolynomialEvaluation])
+        );
+
+        assert_eq!(
+            proof.verify_single_debug(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"DebugTest"),
+                &V,
+                32,
+            ),
+            Err(alloc::vec![FailedCheck::CommitmentReconstruction(
+                ProofError::ParameterMismatch
+            )])
+        );
+    }
+}
@@ -0,0 +1,254 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Verification of one large aggregated range proof, shared between
+//! several machines.
+//!
+//! The verification equation of a proof of \\(m\\) values holds iff a
+//! multiscalar multiplication of about \\(2nm\\) terms is the identity.
+//! A coordinator replays the proof once with
+//! [`RangeProof::verification_shares`], which splits the terms into
+//! independent [`VerificationShare`]s.  Each machine evaluates its
+//! share into a [`PartialSum`], and the coordinator checks that the
+//! partial sums add up to the identity with
+//! [`RangeProof::check_partial_sums`], at the cost of one point
+//! addition per share.
+//!
+//! The partial sums are trusted: a machine returning a wrong partial
+//! sum can make an invalid proof pass, so the machines must be trusted
+//! by the coordinator, or their shares re-evaluated when in doubt.
+
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use group::prime::PrimeCurveAffine;
+use group::{Curve, Group};
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::RangeProof;
+use crate::util;
+
+/// A slice of the terms of a range proof's verification equation, to
+/// be evaluated by one machine.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct VerificationShare {
+    index: usize,
+    count: usize,
+    scalars: Vec<Scalar>,
+    points: Vec<G1Affine>,
+}
+
+/// The sum of the terms of a [`VerificationShare`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PartialSum {
+    /// The index of the share
+    pub index: usize,
+    /// The sum of the terms of the share
+    pub sum: G1Affine,
+}
+
+impl VerificationShare {
+    /// Returns the index of the share among the shares of the proof.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the number of shares of the proof.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the number of terms of the share.
+    pub fn len(&self) -> usize {
+        self.scalars.len()
+    }
+
+    /// Returns true if the share has no terms.
+    pub fn is_empty(&self) -> bool {
+        self.scalars.is_empty()
+    }
+
+    /// Computes the sum of the terms of the share.
+    pub fn evaluate(&self) -> PartialSum {
+        let points: Vec<G1Projective> = self.points.iter().map(G1Projective::from).collect();
+        let sum = util::multiscalar_mul(&self.scalars, &points);
+        PartialSum {
+            index: self.index,
+            sum: sum.to_affine(),
+        }
+    }
+}
+
+impl RangeProof {
+    /// Replays the proof for the given value commitments, and splits
+    /// the terms of its verification equation into `count` shares of
+    /// about equal size, to be evaluated by different machines.
+    ///
+    /// As for [`RangeProof::verify_multiple`], `transcript` must have
+    /// the same initial state as the prover's.  Returns an error if the
+    /// proof cannot be replayed, e.g. because of a wrong bitsize.
+    ///
+    /// Panics if `count` is zero.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verification_shares_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[G1Affine],
+        n: usize,
+        count: usize,
+        rng: &mut T,
+    ) -> Result<Vec<VerificationShare>, ProofError> {
+        assert!(count > 0, "at least one share is required");
+
+        let (scalars, points) = self.verification_terms_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            n,
+            rng,
+        )?;
+        let mut points_affine = vec![G1Affine::identity(); points.len()];
+        G1Projective::batch_normalize(&points, &mut points_affine);
+
+        let chunk = scalars.len().div_ceil(count);
+        Ok((0..count)
+            .map(|index| {
+                let start = (index * chunk).min(scalars.len());
+                let end = (start + chunk).min(scalars.len());
+                VerificationShare {
+                    index,
+                    count,
+                    scalars: scalars[start..end].to_vec(),
+                    points: points_affine[start..end].to_vec(),
+                }
+            })
+            .collect())
+    }
+
+    /// Replays the proof for the given value commitments, and splits
+    /// the terms of its verification equation into `count` shares.
+    /// This is a convenience wrapper around [`RangeProof::verification_shares_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verification_shares(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[G1Affine],
+        n: usize,
+        count: usize,
+    ) -> Result<Vec<VerificationShare>, ProofError> {
+        self.verification_shares_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            n,
+            count,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Checks that the partial sums of all `count` shares of a proof,
+    /// in any order, add up to the identity.
+    ///
+    /// Returns [`ProofError::ParameterMismatch`] unless there is
+    /// exactly one partial sum for each share, and
+    /// [`ProofError::VerificationError`] if the proof does not verify.
+    pub fn check_partial_sums(count: usize, partials: &[PartialSum]) -> Result<(), ProofError> {
+        let mut seen = vec![false; count];
+        for partial in partials {
+            match seen.get_mut(partial.index) {
+                Some(seen) if !*seen => *seen = true,
+                _ => return Err(ProofError::ParameterMismatch),
+            }
+        }
+        if seen.iter().any(|seen| !seen) {
+            return Err(ProofError::ParameterMismatch);
+        }
+
+        let total: G1Projective = partials
+            .iter()
+            .map(|partial| G1Projective::from(partial.sum))
+            .sum();
+        if bool::from(total.is_identity()) {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_sums_verify() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 4);
+        let blindings: Vec<Scalar> = (1..=4u64).map(Scalar::from).collect();
+        let (proof, commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"DistributedTest"),
+            &[1, 2, 3, 4],
+            &blindings,
+            32,
+        )
+        .unwrap();
+
+        let partials = |commitments: &[G1Affine]| {
+            proof
+                .verification_shares(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"DistributedTest"),
+                    commitments,
+                    32,
+                    3,
+                )
+                .unwrap()
+                .iter()
+                .map(VerificationShare::evaluate)
+                .collect::<Vec<_>>()
+        };
+
+        let mut valid = partials(&commitments);
+        valid.reverse();
+        assert!(RangeProof::check_partial_sums(3, &valid).is_ok());
+        assert_eq!(
+            RangeProof::check_partial_sums(3, &valid[..2]),
+            Err(ProofError::ParameterMismatch)
+        );
+        assert_eq!(
+            RangeProof::check_partial_sums(3, &[valid[0], valid[0], valid[1]]),
+            Err(ProofError::ParameterMismatch)
+        );
+
+        let mut wrong = commitments.clone();
+        wrong.swap(0, 1);
+        assert_eq!(
+            RangeProof::check_partial_sums(3, &partials(&wrong)),
+            Err(ProofError::VerificationError)
+        );
+    }
+}
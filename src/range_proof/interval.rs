@@ -0,0 +1,236 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Range proofs for values in an arbitrary public interval.
+//!
+//! A value \\(v\\) committed as \\(V\\) lies in \\([a, b]\\) iff both
+//! \\(v - a\\) and \\(b - v\\) are non-negative.  The proof is an
+//! aggregated rangeproof of these two values for the commitments
+//! \\(V - a \cdot B\\) and \\(b \cdot B - V\\), which the verifier derives
+//! from \\(V\\) itself, so the caller only handles the commitment to
+//! \\(v\\).  The blindings of the two commitments cancel out, and the
+//! bounds are appended to the transcript before any proof data.
+//!
+//! The bitsize is the smallest supported one in which \\(b - a\\) fits,
+//! so both the prover and the verifier derive it from the bounds.
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use group::Curve;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::RangeProof;
+
+impl RangeProof {
+    /// Create a rangeproof that the value `v` committed with
+    /// `v_blinding` is in the interval \\([min, max]\\).
+    ///
+    /// `bp_gens` must have a party capacity of at least 2, and a
+    /// generators capacity of at least [`RangeProof::interval_bitsize`].
+    /// Returns the proof together with the commitment \\(V\\) to `v`,
+    /// or [`ProofError::ValueOutsideInterval`] if `v` is not in the
+    /// interval.
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_in_interval_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        min: u64,
+        max: u64,
+        rng: &mut T,
+    ) -> Result<(RangeProof, G1Affine), ProofError> {
+        if v < min || v > max {
+            return Err(ProofError::ValueOutsideInterval);
+        }
+        append_interval(transcript, min, max);
+        let (proof, _) = RangeProof::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &[v - min, max - v],
+            &[*v_blinding, -v_blinding],
+            RangeProof::interval_bitsize(min, max),
+            rng,
+        )?;
+        Ok((
+            proof,
+            pc_gens.commit(Scalar::from(v), *v_blinding).to_affine(),
+        ))
+    }
+
+    /// Create a rangeproof that the value `v` is in the interval
+    /// \\([min, max]\\).
+    /// This is a convenience wrapper around [`RangeProof::prove_in_interval_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn prove_in_interval(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        min: u64,
+        max: u64,
+    ) -> Result<(RangeProof, G1Affine), ProofError> {
+        RangeProof::prove_in_interval_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            v,
+            v_blinding,
+            min,
+            max,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies a rangeproof created by [`RangeProof::prove_in_interval_with_rng`]
+    /// for the value commitment \\(V\\) and the interval \\([min, max]\\).
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_in_interval_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &G1Affine,
+        min: u64,
+        max: u64,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        if min > max {
+            return Err(ProofError::ValueOutsideInterval);
+        }
+        append_interval(transcript, min, max);
+        let V = G1Projective::from(V);
+        let mut shifted = [G1Affine::default(); 2];
+        G1Projective::batch_normalize(
+            &[
+                V - pc_gens.B * Scalar::from(min),
+                pc_gens.B * Scalar::from(max) - V,
+            ],
+            &mut shifted,
+        );
+        self.verify_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &shifted,
+            RangeProof::interval_bitsize(min, max),
+            rng,
+        )
+    }
+
+    /// Verifies a rangeproof created by [`RangeProof::prove_in_interval`].
+    /// This is a convenience wrapper around [`RangeProof::verify_in_interval_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify_in_interval(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &G1Affine,
+        min: u64,
+        max: u64,
+    ) -> Result<(), ProofError> {
+        self.verify_in_interval_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            V,
+            min,
+            max,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Returns the bitsize of a proof for the interval \\([min, max]\\):
+    /// the smallest of 8, 16, 32 and 64 in which \\(max - min\\) fits.
+    pub fn interval_bitsize(min: u64, max: u64) -> usize {
+        let width = max.saturating_sub(min);
+        [8, 16, 32]
+            .iter()
+            .copied()
+            .find(|n| width >> n == 0)
+            .unwrap_or(64)
+    }
+}
+
+/// Appends the public interval to `transcript`.
+fn append_interval(transcript: &mut Transcript, min: u64, max: u64) {
+    transcript.append_message(b"dom-sep", b"interval-rangeproof v1");
+    transcript.append_u64(b"min", min);
+    transcript.append_u64(b"max", max);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interval_proof_bounds_value() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 2);
+        let blinding = Scalar::from(5u64);
+        assert_eq!(RangeProof::interval_bitsize(1000, 1255), 8);
+        assert_eq!(RangeProof::interval_bitsize(1000, 1256), 16);
+        assert_eq!(RangeProof::interval_bitsize(0, u64::MAX), 64);
+
+        let (proof, V) = RangeProof::prove_in_interval(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"IntervalTest"),
+            1500,
+            &blinding,
+            1000,
+            2000,
+        )
+        .unwrap();
+        assert_eq!(
+            V,
+            pc_gens.commit(Scalar::from(1500u64), blinding).to_affine()
+        );
+
+        let verify = |min, max| {
+            proof.verify_in_interval(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"IntervalTest"),
+                &V,
+                min,
+                max,
+            )
+        };
+        assert_eq!(verify(1000, 2000), Ok(()));
+        assert_eq!(verify(1000, 1999), Err(ProofError::VerificationError));
+        assert_eq!(verify(1600, 2000), Err(ProofError::VerificationError));
+        assert_eq!(verify(2000, 1000), Err(ProofError::ValueOutsideInterval));
+
+        for v in [999, 2001] {
+            assert_eq!(
+                RangeProof::prove_in_interval(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"IntervalTest"),
+                    v,
+                    &blinding,
+                    1000,
+                    2000,
+                ),
+                Err(ProofError::ValueOutsideInterval)
+            );
+        }
+    }
+}
@@ -9,6 +9,24 @@
 //!
 //! For more explanation of how the `dealer`, `party`, and `messages` modules orchestrate the protocol execution, see
 //! [the API for the aggregated multiparty computation protocol](../aggregation/index.html#api-for-the-aggregated-multiparty-computation-protocol).
+//!
+//! Besides serde, each message has a compact encoding with `to_bytes`
+//! and `from_bytes`, using compressed affine points and batching the
+//! scalars of the proof shares without any framing.  The messages
+//! of one round can be packed into one buffer with [`pack_round`].
+//! Per party proving an \(n\)-bit range, the compact messages are:
+//!
+//! | Round | Message              | Direction       | Bytes                 |
+//! |-------|----------------------|-----------------|-----------------------|
+//! | 1     | [`BitCommitment`]    | party to dealer | 144                   |
+//! | 1     | [`BitChallenge`]     | dealer to party | 64                    |
+//! | 2     | [`PolyCommitment`]   | party to dealer | 96                    |
+//! | 2     | [`PolyChallenge`]    | dealer to party | 32                    |
+//! | 3     | [`ProofShare`]       | party to dealer | \(96 + 64 n\)       |
+//!
+//! That is 4528 bytes in total for \(n = 64\), dominated by the
+//! proof share.  A [`GroupBitCommitment`] of \(k\) parties takes
+//! \(48 (k + 2)\) bytes.
 
 extern crate alloc;
 
@@ -18,6 +36,10 @@ use core::iter;
 use group::{ff::Field, Group};
 
 use crate::generators::{BulletproofGens, PedersenGens};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+pub use super::compact::{pack_round, unpack_round, RoundMessage};
 
 /// A commitment to the bits of a party's value.
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
@@ -27,6 +49,28 @@ pub struct BitCommitment {
     pub(super) S_j: G1Projective,
 }
 
+/// The [`BitCommitment`]s of a group of parties at consecutive
+/// positions, as aggregated by a [`SubDealer`](super::dealer::SubDealer).
+///
+/// The value commitments are kept individually, while the commitments
+/// to the bits and to the bit blindings are summed.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GroupBitCommitment {
+    pub(super) V: Vec<G1Projective>,
+    pub(super) A: G1Projective,
+    pub(super) S: G1Projective,
+}
+
+impl From<BitCommitment> for GroupBitCommitment {
+    fn from(bit_commitment: BitCommitment) -> Self {
+        GroupBitCommitment {
+            V: alloc::vec![bit_commitment.V_j],
+            A: bit_commitment.A_j,
+            S: bit_commitment.S_j,
+        }
+    }
+}
+
 /// Challenge values derived from all parties' [`BitCommitment`]s.
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
 pub struct BitChallenge {
@@ -35,6 +79,8 @@ pub struct BitChallenge {
 }
 
 /// A commitment to a party's polynomial coefficents.
+///
+/// The commitments of a group of parties are aggregated by summing them.
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
 pub struct PolyCommitment {
     pub(super) T_1_j: G1Projective,
@@ -49,6 +95,10 @@ pub struct PolyChallenge {
 
 /// A party's proof share, ready for aggregation into the final
 /// [`RangeProof`](::RangeProof).
+///
+/// The shares of a group of parties at consecutive positions are
+/// aggregated into one share by summing the scalars and concatenating
+/// the vectors.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ProofShare {
     pub(super) t_x: Scalar,
@@ -59,18 +109,36 @@ pub struct ProofShare {
 }
 
 impl ProofShare {
-    /// Checks consistency of all sizes in the proof share and returns the size of the l/r vector.
+    /// Aggregates the shares of a group of parties at consecutive
+    /// positions into one share.
+    pub(super) fn aggregate(shares: &[ProofShare]) -> ProofShare {
+        // todo: replace fold() with sum() when supported in blstrs
+        let sum = |f: fn(&ProofShare) -> Scalar| {
+            shares.iter().map(f).fold(Scalar::zero(), |sum, x| sum + x)
+        };
+        ProofShare {
+            t_x: sum(|ps| ps.t_x),
+            t_x_blinding: sum(|ps| ps.t_x_blinding),
+            e_blinding: sum(|ps| ps.e_blinding),
+            l_vec: shares.iter().flat_map(|ps| ps.l_vec.clone()).collect(),
+            r_vec: shares.iter().flat_map(|ps| ps.r_vec.clone()).collect(),
+        }
+    }
+
+    /// Checks consistency of all sizes in the proof share of the `k`
+    /// parties at positions `j..j + k`.
     pub(super) fn check_size(
         &self,
         expected_n: usize,
+        k: usize,
         bp_gens: &BulletproofGens,
         j: usize,
     ) -> Result<(), ()> {
-        if self.l_vec.len() != expected_n {
+        if Some(self.l_vec.len()) != expected_n.checked_mul(k) {
             return Err(());
         }
 
-        if self.r_vec.len() != expected_n {
+        if self.r_vec.len() != self.l_vec.len() {
             return Err(());
         }
 
@@ -78,21 +146,23 @@ impl ProofShare {
             return Err(());
         }
 
-        if j >= bp_gens.party_capacity {
-            return Err(());
+        match j.checked_add(k) {
+            Some(end) if k > 0 && end <= bp_gens.party_capacity => {}
+            _ => return Err(()),
         }
 
         Ok(())
     }
 
-    /// Audit an individual proof share to determine whether it is
+    /// Audit the proof share of an individual party, or of a group of
+    /// parties starting at position `j`, to determine whether it is
     /// malformed.
     pub(super) fn audit_share(
         &self,
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         j: usize,
-        bit_commitment: &BitCommitment,
+        bit_commitment: &GroupBitCommitment,
         bit_challenge: &BitChallenge,
         poly_commitment: &PolyCommitment,
         poly_challenge: &PolyChallenge,
@@ -100,9 +170,10 @@ impl ProofShare {
         use crate::inner_product_proof::inner_product;
         use crate::util;
 
-        let n = self.l_vec.len();
+        let k = bit_commitment.V.len();
+        let n = self.l_vec.len().checked_div(k).ok_or(())?;
 
-        self.check_size(n, bp_gens, j)?;
+        self.check_size(n, k, bp_gens, j)?;
 
         let (y, z) = (&bit_challenge.y, &bit_challenge.z);
         let x = &poly_challenge.x;
@@ -119,51 +190,67 @@ impl ProofShare {
             return Err(());
         }
 
+        // z^(j+p) * 2^i for the i-th bit of the p-th party of the group
+        let powers_of_2: Vec<Scalar> = util::exp_iter(Scalar::from(2u64)).take(n).collect();
+        let z_and_2 = util::exp_iter(*z)
+            .take(k)
+            .flat_map(|z_p| powers_of_2.iter().map(move |exp_2| z_j * z_p * exp_2));
+
         let g = self.l_vec.iter().map(|l_i| minus_z - l_i);
         let h = self
             .r_vec
             .iter()
-            .zip(util::exp_iter(Scalar::from(2u64)))
+            .zip(z_and_2)
             .zip(util::exp_iter(y_inv))
-            .map(|((r_i, exp_2), exp_y_inv)| {
-                z + exp_y_inv * y_jn_inv * (-r_i) + exp_y_inv * y_jn_inv * (zz * z_j * exp_2)
+            .map(|((r_i, z_and_2), exp_y_inv)| {
+                z + exp_y_inv * y_jn_inv * (-r_i) + exp_y_inv * y_jn_inv * (zz * z_and_2)
             });
 
-        let P_check: G1Projective = iter::once(Scalar::one())
+        let (scalars, points): (Vec<Scalar>, Vec<G1Projective>) = iter::once(Scalar::one())
             .chain(iter::once(*x))
             .chain(iter::once(-self.e_blinding))
             .chain(g)
             .chain(h)
             .zip(
-                iter::once(&bit_commitment.A_j)
-                    .chain(iter::once(&bit_commitment.S_j))
+                iter::once(&bit_commitment.A)
+                    .chain(iter::once(&bit_commitment.S))
                     .chain(iter::once(&pc_gens.B_blinding))
-                    .chain(bp_gens.share(j).G(n))
-                    .chain(bp_gens.share(j).H(n)),
+                    .chain((j..j + k).flat_map(|p| bp_gens.share(p).G(n)))
+                    .chain((j..j + k).flat_map(|p| bp_gens.share(p).H(n))),
             )
-            .map(|(s, P)| P * s)
-            .sum();
+            .map(|(s, P)| (s, *P))
+            .unzip();
+        let P_check = util::multiscalar_mul(&scalars, &points);
         if !bool::from(P_check.is_identity()) {
             return Err(());
         }
 
-        let sum_of_powers_y = util::sum_of_powers(&y, n);
+        // Summing over the group, with y^(j*n) * y^(p*n) = y^((j+p)*n)
+        let y_n = util::scalar_exp_vartime(y, n as u64);
+        let sum_of_powers_y = util::sum_of_powers(&y, n) * util::sum_of_powers(&y_n, k);
         let sum_of_powers_2 = util::sum_of_powers(&Scalar::from(2u64), n);
-        let delta = (z - zz) * sum_of_powers_y * y_jn - z * zz * sum_of_powers_2 * z_j;
-        let t_check: G1Projective = iter::once(zz * z_j)
+        let sum_of_powers_z = util::sum_of_powers(z, k);
+        let delta =
+            (z - zz) * sum_of_powers_y * y_jn - z * zz * sum_of_powers_2 * z_j * sum_of_powers_z;
+        let (scalars, points): (Vec<Scalar>, Vec<G1Projective>) = util::exp_iter(*z)
+            .take(k)
+            .map(|z_p| zz * z_j * z_p)
             .chain(iter::once(*x))
             .chain(iter::once(x * x))
             .chain(iter::once(delta - self.t_x))
             .chain(iter::once(-self.t_x_blinding))
             .zip(
-                iter::once(&bit_commitment.V_j)
+                bit_commitment
+                    .V
+                    .iter()
                     .chain(iter::once(&poly_commitment.T_1_j))
                     .chain(iter::once(&poly_commitment.T_2_j))
                     .chain(iter::once(&pc_gens.B))
                     .chain(iter::once(&pc_gens.B_blinding)),
             )
-            .map(|(s, P)| P * s)
-            .sum();
+            .map(|(s, P)| (s, *P))
+            .unzip();
+        let t_check = util::multiscalar_mul(&scalars, &points);
 
         if bool::from(t_check.is_identity()) {
             Ok(())
@@ -172,3 +259,21 @@ impl ProofShare {
         }
     }
 }
+
+/// Overwrites the share with null bytes.
+///
+/// A share reveals nothing beyond the proof once it has been
+/// aggregated, but a prover which assembles its own proof can wipe the
+/// shares of its parties afterwards.
+#[cfg(feature = "zeroize")]
+impl Zeroize for ProofShare {
+    fn zeroize(&mut self) {
+        use clear_on_drop::clear::Clear;
+
+        self.t_x.clear();
+        self.t_x_blinding.clear();
+        self.e_blinding.clear();
+        crate::util::clear_scalars(&mut self.l_vec);
+        crate::util::clear_scalars(&mut self.r_vec);
+    }
+}
@@ -8,36 +8,123 @@
 #![cfg_attr(feature = "docs", doc(include = "../../docs/range-proof-protocol.md"))]
 
 extern crate alloc;
-#[cfg(feature = "std")]
+#[cfg(feature = "thread_rng")]
 extern crate rand;
 
-#[cfg(feature = "std")]
+#[cfg(feature = "thread_rng")]
 use self::rand::thread_rng;
+#[cfg(feature = "prover")]
+use alloc::borrow::Borrow;
 use alloc::vec::Vec;
+#[cfg(feature = "verifier")]
 use group::ff::Field;
-use group::{Curve, Group};
+use group::prime::PrimeCurveAffine;
+#[cfg(any(feature = "prover", feature = "verifier"))]
+use group::Curve;
+use group::Group;
 
+#[cfg(feature = "verifier")]
 use core::iter;
+#[cfg(feature = "prover")]
+use core::sync::atomic::AtomicBool;
 
 use blstrs::{G1Affine, G1Projective, Scalar};
+#[cfg(any(feature = "prover", feature = "verifier"))]
 use merlin::Transcript;
 
+#[cfg(feature = "prover")]
+use crate::amount::Amount;
 use crate::errors::ProofError;
-use crate::generators::{BulletproofGens, PedersenGens};
+use crate::generators::BulletproofGens;
+#[cfg(any(feature = "prover", feature = "verifier"))]
+use crate::generators::PedersenGens;
+#[cfg(feature = "verifier")]
+use crate::inner_product_proof::inner_product;
 use crate::inner_product_proof::InnerProductProof;
+#[cfg(feature = "verifier")]
+use crate::telemetry;
+#[cfg(feature = "verifier")]
 use crate::transcript::TranscriptProtocol;
 use crate::util;
 
+#[cfg(feature = "prover")]
+use self::messages::ProofShare;
+
+#[cfg(feature = "prover")]
+use rand::SeedableRng;
+#[cfg(any(feature = "prover", feature = "verifier"))]
 use rand::{CryptoRng, RngCore};
-use serde::de::Visitor;
-use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "prover")]
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(all(feature = "zeroize", feature = "prover"))]
+use zeroize::Zeroize;
 
 // Modules for MPC protocol
 
+#[cfg(feature = "prover")]
+pub mod audit;
+#[cfg(feature = "prover")]
 pub mod dealer;
+#[cfg(feature = "prover")]
 pub mod messages;
+#[cfg(feature = "prover")]
 pub mod party;
 
+mod archive;
+#[cfg(feature = "verifier")]
+mod batch;
+#[cfg(all(feature = "prover", feature = "verifier"))]
+mod binding;
+#[cfg(feature = "prover")]
+mod compact;
+mod decoder;
+#[cfg(feature = "prover")]
+mod delegated;
+#[cfg(feature = "verifier")]
+mod diagnostics;
+#[cfg(feature = "verifier")]
+mod distributed;
+#[cfg(all(feature = "prover", feature = "verifier"))]
+mod interval;
+#[cfg(all(feature = "prover", feature = "verifier"))]
+mod nonce;
+#[cfg(all(feature = "prover", feature = "verifier"))]
+mod offset;
+#[cfg(feature = "prover")]
+mod pending;
+#[cfg(all(feature = "prover", feature = "verifier"))]
+mod receipt;
+#[cfg(feature = "verifier")]
+mod record;
+#[cfg(all(feature = "prover", feature = "verifier"))]
+mod sum;
+
+pub use self::archive::{ArchiveDecoder, ArchiveEncoder, ProofArchive};
+#[cfg(feature = "verifier")]
+pub use self::batch::ProofBatch;
+#[cfg(feature = "verifier")]
+pub(crate) use self::batch::VerificationAccumulator;
+#[cfg(all(feature = "prover", feature = "verifier"))]
+pub use self::binding::ProverBinding;
+pub use self::decoder::{DecodeState, ProofDecoder};
+#[cfg(feature = "prover")]
+pub use self::delegated::{
+    DelegatedInnerProduct, DelegatingProver, DelegationRequest, IppStep, ProvingPad,
+};
+#[cfg(feature = "verifier")]
+pub use self::diagnostics::FailedCheck;
+#[cfg(feature = "verifier")]
+pub use self::distributed::{PartialSum, VerificationShare};
+#[cfg(all(feature = "prover", feature = "verifier"))]
+pub use self::nonce::NoncedProof;
+#[cfg(feature = "prover")]
+pub use self::pending::PendingRangeProof;
+#[cfg(all(feature = "prover", feature = "verifier"))]
+pub use self::receipt::ProofReceipt;
+#[cfg(feature = "verifier")]
+pub use self::record::VerificationRecord;
+
 /// The `RangeProof` struct represents a proof that one or more values
 /// are in a range.
 ///
@@ -45,9 +132,11 @@ pub mod party;
 /// verifying aggregated range proofs.  The single-value case is
 /// implemented as a special case of aggregated range proofs.
 ///
-/// The bitsize of the range, as well as the list of commitments to
-/// the values, are not included in the proof, and must be known to
-/// the verifier.
+/// The list of commitments to the values is not included in the proof,
+/// and must be known to the verifier, as must the bitsize of the range.
+/// Serialized proofs start with a header recording the bitsize and the
+/// aggregation size, so that a proof verified with the wrong parameters
+/// fails with [`ProofError::ParameterMismatch`].
 ///
 /// This implementation requires that both the bitsize `n` and the
 /// aggregation size `m` be powers of two, so that `n = 8, 16, 32, 64`
@@ -55,6 +144,13 @@ pub mod party;
 /// not given as an explicit parameter, but is determined by the
 /// number of values or commitments passed to the prover or verifier.
 ///
+/// The one exception is a single value, whose bitsize may be anything
+/// from 1 to 64: it is padded to the next power of two \(N\) and
+/// proven as a pair of values of bitsize \(N\), as described for
+/// [`RangeProof::prove_single_with_rng`].  Every verifier accepts such
+/// a proof with its one commitment and the unpadded bitsize `n`, while
+/// the proof header records \(N\) and an aggregation size of 2.
+///
 /// # Note
 ///
 /// For proving, these functions run the multiparty aggregation
@@ -79,6 +175,90 @@ pub struct RangeProof {
     e_blinding: Scalar,
     /// Proof data for the inner-product argument.
     ipp_proof: InnerProductProof,
+    /// The bitsize \\(n\\) and aggregation size \\(m\\), unless the
+    /// proof was decoded from the legacy encoding without a header.
+    params: Option<(usize, usize)>,
+}
+
+/// The length of the serialized [`RangeProof`] header.
+const HEADER_LEN: usize = 4;
+
+/// The length of the prefix read by [`RangeProof::decode_header`]: the
+/// header, the points \\(A,S,T_1,T_2\\), the scalars
+/// \\(t_x, \tilde{t}_x, \tilde{e}\\), and the first byte of the
+/// inner-product argument.
+const SHAPE_PREFIX_LEN: usize = HEADER_LEN + 4 * 48 + 3 * 32 + 1;
+
+/// The shape of a serialized [`RangeProof`], as returned by
+/// [`RangeProof::decode_header`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ProofShape {
+    /// The version of the serialization format
+    pub version: u8,
+    /// The bitsize \\(n\\) of the range
+    pub n: usize,
+    /// The number of values \\(m\\) in the proof
+    pub m: usize,
+    /// The length in bytes of the serialized proof
+    pub byte_len: usize,
+}
+
+/// The challenge-derived scalars of a [`RangeProof`]'s verification
+/// equation, as returned by [`RangeProof::verification_scalars`].
+///
+/// See the [range proof notes](index.html#verifier-s-algorithm) for how
+/// these scalars are combined with the proof data and the generators.
+#[cfg(feature = "verifier")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerificationScalars {
+    /// Challenge \\(y\\) for the bit commitments
+    pub y: Scalar,
+    /// The inverse \\(y^{-1}\\) of the challenge \\(y\\)
+    pub y_inv: Scalar,
+    /// Challenge \\(z\\) for the bit commitments
+    pub z: Scalar,
+    /// Challenge \\(x\\) at which \\(t(x)\\) is evaluated
+    pub x: Scalar,
+    /// Challenge \\(w\\) binding the inner-product argument to \\(t(x)\\)
+    pub w: Scalar,
+    /// Squares of the inner-product challenges \\(u\_{i}^{2}\\)
+    pub u_sq: Vec<Scalar>,
+    /// Squares of the inverted inner-product challenges \\(u\_{i}^{-2}\\)
+    pub u_inv_sq: Vec<Scalar>,
+    /// The inner-product scalars \\(s\_{i}\\), one for every
+    /// \\(k\\) generators for an inner-product cutoff \\(k\\)
+    pub s: Vec<Scalar>,
+}
+
+/// A phase of proof creation, as reported to a progress observer by
+/// [`RangeProof::prove_multiple_with_progress_with_rng`].
+#[cfg(feature = "prover")]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ProvingPhase {
+    /// Committing to the bits of the values
+    Commitments,
+    /// Committing to the coefficients of the polynomial \(t(x)\)
+    PolyCommitments,
+    /// Evaluating the polynomials at the challenge \(x\)
+    Evaluation,
+    /// Creating the inner-product argument
+    InnerProduct,
+    /// The proof is complete
+    Done,
+}
+
+/// An estimate of the work done to verify a proof, computed from its
+/// public parameters before any proof data is decoded.
+///
+/// See [`RangeProof::verification_cost`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct VerificationCost {
+    /// The number of terms of the verification multiscalar
+    /// multiplication, which dominates the cost
+    pub msm_terms: usize,
+    /// The estimated number of scalar multiplications and additions
+    /// done to compute the scalars of the multiscalar multiplication
+    pub scalar_ops: usize,
 }
 
 impl RangeProof {
@@ -86,6 +266,22 @@ impl RangeProof {
     /// blinding scalar `v_blinding`.
     /// This is a convenience wrapper around [`RangeProof::prove_multiple`].
     ///
+    /// The blinding factor can be a `Scalar` or a
+    /// [`BlindingFactor`](crate::BlindingFactor), which keeps it out of
+    /// logs and clears it on drop.
+    ///
+    /// The value is not decomposed into a bit vector up front: each
+    /// party reads the bits of `v` with a shift and a mask as it
+    /// commits to them, so there is no decomposition or buffer for a
+    /// caller to supply.
+    ///
+    /// The bitsize `n` may be anything from 1 to 64.  A bitsize other
+    /// than 8, 16, 32 or 64 is padded to the next of these, \\(N\\):
+    /// the proof is then an aggregated proof of \\(v\\) and
+    /// \\(v + 2^{N} - 2^{n}\\) with bitsize \\(N\\), which together show
+    /// that \\(v < 2^{n}\\), and `n` is appended to the transcript.  Such
+    /// proofs need `bp_gens` with a party capacity of at least 2.
+    ///
     /// # Example
     /// ```
     /// extern crate rand;
@@ -139,23 +335,22 @@ impl RangeProof {
     /// );
     /// # }
     /// ```
+    #[cfg(feature = "prover")]
     pub fn prove_single_with_rng<T: RngCore + CryptoRng>(
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         transcript: &mut Transcript,
         v: u64,
-        v_blinding: &Scalar,
+        v_blinding: &impl Borrow<Scalar>,
         n: usize,
         rng: &mut T,
     ) -> Result<(RangeProof, G1Affine), ProofError> {
+        let v_blinding = v_blinding.borrow();
+        let (values, n) = pad_value(transcript, v, n);
+        let blindings = alloc::vec![*v_blinding; values.len()];
+
         let (p, Vs) = RangeProof::prove_multiple_with_rng(
-            bp_gens,
-            pc_gens,
-            transcript,
-            &[v],
-            &[*v_blinding],
-            n,
-            rng,
+            bp_gens, pc_gens, transcript, &values, &blindings, n, rng,
         )?;
         Ok((p, Vs[0]))
     }
@@ -164,13 +359,13 @@ impl RangeProof {
     /// blinding scalar `v_blinding`.
     /// This is a convenience wrapper around [`RangeProof::prove_single_with_rng`],
     /// passing in a threadsafe RNG.
-    #[cfg(feature = "std")]
+    #[cfg(all(feature = "thread_rng", feature = "prover"))]
     pub fn prove_single(
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         transcript: &mut Transcript,
         v: u64,
-        v_blinding: &Scalar,
+        v_blinding: &impl Borrow<Scalar>,
         n: usize,
     ) -> Result<(RangeProof, G1Affine), ProofError> {
         RangeProof::prove_single_with_rng(
@@ -186,6 +381,9 @@ impl RangeProof {
 
     /// Create a rangeproof for a set of values.
     ///
+    /// As for [`RangeProof::prove_single_with_rng`], the blinding
+    /// factors can be `Scalar`s or [`BlindingFactor`](crate::BlindingFactor)s.
+    ///
     /// # Example
     /// ```
     /// extern crate rand;
@@ -239,13 +437,181 @@ impl RangeProof {
     /// );
     /// # }
     /// ```
+    #[cfg(feature = "prover")]
     pub fn prove_multiple_with_rng(
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         transcript: &mut Transcript,
         values: &[u64],
-        blindings: &[Scalar],
+        blindings: &[impl Borrow<Scalar>],
+        n: usize,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
+        RangeProof::prove_multiple_with_ipp_cutoff_with_rng(
+            bp_gens, pc_gens, transcript, values, blindings, n, 1, rng,
+        )
+    }
+
+    /// Create a rangeproof for a set of values, whose inner-product
+    /// argument stops recursing at vectors of length `k` and sends them
+    /// in the clear.
+    ///
+    /// A larger cutoff trades a slightly larger proof for fewer
+    /// verifier challenges.  The cutoff is encoded in the proof, so
+    /// verification is unchanged.  It must be a power of 2 no larger
+    /// than \\(n \cdot m\\), otherwise [`ProofError::InvalidCutoff`]
+    /// is returned.
+    #[cfg(feature = "prover")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_multiple_with_ipp_cutoff_with_rng(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[impl Borrow<Scalar>],
+        n: usize,
+        k: usize,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
+        RangeProof::prove_multiple_inner(
+            bp_gens, pc_gens, transcript, values, blindings, n, k, None, None, rng,
+        )
+    }
+
+    /// Create a rangeproof for a set of values, which can be aborted by
+    /// setting the `cancel` flag from another thread.
+    ///
+    /// The flag is checked between the phases of the protocol, before
+    /// the bit commitments, the polynomial commitments, the proof shares
+    /// and the inner-product argument are computed.  Once it is set,
+    /// proving stops at the next check with [`ProofError::Cancelled`].
+    #[cfg(feature = "prover")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_multiple_cancellable_with_rng(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[impl Borrow<Scalar>],
+        n: usize,
+        cancel: &AtomicBool,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
+        RangeProof::prove_multiple_inner(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            1,
+            Some(cancel),
+            None,
+            rng,
+        )
+    }
+
+    /// Create a rangeproof for a set of values, which can be aborted by
+    /// setting the `cancel` flag.
+    /// This is a convenience wrapper around [`RangeProof::prove_multiple_cancellable_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(all(feature = "thread_rng", feature = "prover"))]
+    pub fn prove_multiple_cancellable(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[impl Borrow<Scalar>],
+        n: usize,
+        cancel: &AtomicBool,
+    ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
+        RangeProof::prove_multiple_cancellable_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            cancel,
+            thread_rng(),
+        )
+    }
+
+    /// Create a rangeproof for a set of values, reporting its progress
+    /// to `progress`, e.g. to show a progress bar while proving a large
+    /// aggregation.
+    ///
+    /// The observer is called with the current [`ProvingPhase`] and the
+    /// estimated fraction of the proof done, between 0 and 1, after the
+    /// bit commitments of each party, at the start of every other phase
+    /// and after each round of the inner-product argument.  The
+    /// fraction is estimated from the number of group operations: the
+    /// bit commitments take about a third of the work, and the
+    /// inner-product argument the rest.
+    #[cfg(feature = "prover")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_multiple_with_progress_with_rng(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[impl Borrow<Scalar>],
+        n: usize,
+        progress: &dyn Fn(ProvingPhase, f32),
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
+        RangeProof::prove_multiple_inner(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            1,
+            None,
+            Some(progress),
+            rng,
+        )
+    }
+
+    /// Create a rangeproof for a set of values, reporting its progress
+    /// to `progress`.
+    /// This is a convenience wrapper around [`RangeProof::prove_multiple_with_progress_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(all(feature = "thread_rng", feature = "prover"))]
+    pub fn prove_multiple_with_progress(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[impl Borrow<Scalar>],
         n: usize,
+        progress: &dyn Fn(ProvingPhase, f32),
+    ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
+        RangeProof::prove_multiple_with_progress_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            progress,
+            thread_rng(),
+        )
+    }
+
+    #[cfg(feature = "prover")]
+    #[allow(clippy::too_many_arguments)]
+    fn prove_multiple_inner(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[impl Borrow<Scalar>],
+        n: usize,
+        k: usize,
+        cancel: Option<&AtomicBool>,
+        progress: Option<&dyn Fn(ProvingPhase, f32)>,
         mut rng: impl RngCore + CryptoRng,
     ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
         use self::dealer::*;
@@ -255,42 +621,82 @@ impl RangeProof {
             return Err(ProofError::WrongNumBlindingFactors);
         }
 
-        let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, values.len())?;
+        let dealer =
+            Dealer::new(bp_gens, pc_gens, transcript, n, values.len())?.with_ipp_cutoff(k)?;
 
         let parties: Vec<_> = values
             .iter()
             .zip(blindings.iter())
-            .map(|(&v, &v_blinding)| Party::new(bp_gens, pc_gens, v, v_blinding, n))
+            .map(|(&v, v_blinding)| Party::new(bp_gens, pc_gens, v, *v_blinding.borrow(), n))
             // Collect the iterator of Results into a Result<Vec>, then unwrap it
             .collect::<Result<Vec<_>, _>>()?;
 
-        let (parties, bit_commitments): (Vec<_>, Vec<_>) = parties
-            .into_iter()
-            .enumerate()
-            .map(|(j, p)| {
-                p.assign_position_with_rng(j, &mut rng)
+        use crate::util::COMMITMENT_WORK;
+
+        let report = |phase, fraction| util::report_progress(progress, phase, fraction);
+        let m = parties.len();
+
+        // Every party draws from its own RNG, so that the proof is the
+        // same whether or not the parties run in parallel.
+        let mut secrets = PartySecrets::new(m, &mut rng);
+
+        util::check_cancelled(cancel)?;
+        report(ProvingPhase::Commitments, 0.0);
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = map_parties(
+            parties,
+            &mut secrets.rngs,
+            |j, p, rng| {
+                p.assign_position_with_rng(j, rng)
                     .expect("We already checked the parameters, so this should never happen")
-            })
-            .unzip();
+            },
+            |j| {
+                report(
+                    ProvingPhase::Commitments,
+                    COMMITMENT_WORK * (j + 1) as f32 / m as f32,
+                )
+            },
+        )
+        .into_iter()
+        .unzip();
 
         let value_commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j.to_affine()).collect();
 
         let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments)?;
 
-        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
-            .into_iter()
-            .map(|p| p.apply_challenge_with_rng(&bit_challenge, &mut rng))
-            .unzip();
+        util::check_cancelled(cancel)?;
+        report(ProvingPhase::PolyCommitments, COMMITMENT_WORK);
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = map_parties(
+            parties,
+            &mut secrets.rngs,
+            |_, p, rng| p.apply_challenge_with_rng(&bit_challenge, rng),
+            |_| {},
+        )
+        .into_iter()
+        .unzip();
 
         let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
 
-        let proof_shares: Vec<_> = parties
-            .into_iter()
-            .map(|p| p.apply_challenge(&poly_challenge))
-            // Collect the iterator of Results into a Result<Vec>, then unwrap it
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let proof = dealer.receive_trusted_shares(&proof_shares)?;
+        util::check_cancelled(cancel)?;
+        report(ProvingPhase::Evaluation, COMMITMENT_WORK);
+        secrets.shares = map_parties(
+            parties,
+            &mut secrets.rngs,
+            |_, p, _| p.apply_challenge(&poly_challenge),
+            |_| {},
+        )
+        .into_iter()
+        // Collect the iterator of Results into a Result<Vec>, then unwrap it
+        .collect::<Result<Vec<_>, _>>()?;
+
+        util::check_cancelled(cancel)?;
+        report(ProvingPhase::InnerProduct, COMMITMENT_WORK);
+        let proof = dealer.receive_trusted_shares_with_progress(&secrets.shares, &|fraction| {
+            report(
+                ProvingPhase::InnerProduct,
+                COMMITMENT_WORK + (1.0 - COMMITMENT_WORK) * fraction,
+            )
+        })?;
+        report(ProvingPhase::Done, 1.0);
 
         Ok((proof, value_commitments))
     }
@@ -298,13 +704,13 @@ impl RangeProof {
     /// Create a rangeproof for a set of values.
     /// This is a convenience wrapper around [`RangeProof::prove_multiple_with_rng`],
     /// passing in a threadsafe RNG.
-    #[cfg(feature = "std")]
+    #[cfg(all(feature = "thread_rng", feature = "prover"))]
     pub fn prove_multiple(
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         transcript: &mut Transcript,
         values: &[u64],
-        blindings: &[Scalar],
+        blindings: &[impl Borrow<Scalar>],
         n: usize,
     ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
         RangeProof::prove_multiple_with_rng(
@@ -318,18 +724,236 @@ impl RangeProof {
         )
     }
 
+    /// Create independent (non-aggregated) rangeproofs for many pairs
+    /// of values and blinding factors.
+    ///
+    /// Each proof is created as by [`RangeProof::prove_single_with_rng`]
+    /// on a copy of `transcript`, so every proof verifies with a
+    /// transcript in the same initial state.  The parameters are
+    /// checked once for the whole batch, and with the `rayon` feature
+    /// the proofs are created in parallel, each with its own RNG seeded
+    /// from `rng`.
+    ///
+    /// Returns the proofs with their value commitments, in the order of
+    /// `values_and_blindings`.
+    #[cfg(feature = "prover")]
+    pub fn prove_many_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        values_and_blindings: &[(u64, Scalar)],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<Vec<(RangeProof, G1Affine)>, ProofError> {
+        let (padded_n, m) = padded_params(n, 1);
+        if !(padded_n == 8 || padded_n == 16 || padded_n == 32 || padded_n == 64) {
+            return Err(ProofError::InvalidBitsize);
+        }
+        if bp_gens.gens_capacity < padded_n || bp_gens.party_capacity < m {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        let rngs: Vec<ChaCha20Rng> = values_and_blindings
+            .iter()
+            .map(|_| {
+                let mut seed = [0u8; 32];
+                rng.fill_bytes(&mut seed);
+                ChaCha20Rng::from_seed(seed)
+            })
+            .collect();
+        let prove = |(&(v, v_blinding), mut rng): (&(u64, Scalar), ChaCha20Rng)| {
+            let mut transcript = transcript.clone();
+            RangeProof::prove_single_with_rng(
+                bp_gens,
+                pc_gens,
+                &mut transcript,
+                v,
+                &v_blinding,
+                n,
+                &mut rng,
+            )
+        };
+
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+
+            values_and_blindings
+                .par_iter()
+                .zip(rngs.into_par_iter())
+                .map(prove)
+                .collect()
+        }
+
+        #[cfg(not(feature = "rayon"))]
+        values_and_blindings.iter().zip(rngs).map(prove).collect()
+    }
+
+    /// Create independent rangeproofs for many pairs of values and
+    /// blinding factors, on the threads of `pool` rather than rayon's
+    /// global thread pool.
+    ///
+    /// This keeps bulk proving from contending with other work, such as
+    /// latency-critical verification, that runs on the global pool or
+    /// on threads of its own.  Otherwise this is the same as
+    /// [`RangeProof::prove_many_with_rng`].
+    #[cfg(all(feature = "rayon", feature = "prover"))]
+    pub fn prove_many_in_pool_with_rng<T: RngCore + CryptoRng>(
+        pool: &rayon::ThreadPool,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        values_and_blindings: &[(u64, Scalar)],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<Vec<(RangeProof, G1Affine)>, ProofError> {
+        // `rng` need not be `Send`, so seed one that is
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        let mut rng = ChaCha20Rng::from_seed(seed);
+
+        pool.install(|| {
+            RangeProof::prove_many_with_rng(
+                bp_gens,
+                pc_gens,
+                transcript,
+                values_and_blindings,
+                n,
+                &mut rng,
+            )
+        })
+    }
+
+    /// Create independent rangeproofs for many pairs of values and
+    /// blinding factors.
+    /// This is a convenience wrapper around [`RangeProof::prove_many_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(all(feature = "thread_rng", feature = "prover"))]
+    pub fn prove_many(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        values_and_blindings: &[(u64, Scalar)],
+        n: usize,
+    ) -> Result<Vec<(RangeProof, G1Affine)>, ProofError> {
+        RangeProof::prove_many_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values_and_blindings,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Create a rangeproof for a set of values, whose inner-product
+    /// argument stops recursing at vectors of length `k`.
+    /// This is a convenience wrapper around [`RangeProof::prove_multiple_with_ipp_cutoff_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(all(feature = "thread_rng", feature = "prover"))]
+    pub fn prove_multiple_with_ipp_cutoff(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[impl Borrow<Scalar>],
+        n: usize,
+        k: usize,
+    ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
+        RangeProof::prove_multiple_with_ipp_cutoff_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            k,
+            thread_rng(),
+        )
+    }
+
+    /// Create a rangeproof for a set of decimal amounts, expressed in
+    /// base units of \\(10^{-\mathrm{scale}}\\).
+    ///
+    /// Every amount is converted to `scale` and checked to fit in `n`
+    /// bits before proving, so a mis-scaled amount is reported as a
+    /// [`ProofError::InvalidAmount`] rather than producing a proof that
+    /// fails to verify.  A single amount may have any bitsize `n` from
+    /// 1 to 64, which is padded as for [`RangeProof::prove_single_with_rng`].
+    #[cfg(feature = "prover")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_amounts_with_rng(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        amounts: &[Amount],
+        blindings: &[impl Borrow<Scalar>],
+        scale: u8,
+        n: usize,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
+        let (padded_n, _) = padded_params(n, amounts.len());
+        if !(padded_n == 8 || padded_n == 16 || padded_n == 32 || padded_n == 64) {
+            return Err(ProofError::InvalidBitsize);
+        }
+        let values = amounts
+            .iter()
+            .map(|a| a.to_units(scale, n))
+            .collect::<Result<Vec<u64>, _>>()?;
+
+        if let ([v], [v_blinding]) = (&values[..], blindings) {
+            let (proof, V) = RangeProof::prove_single_with_rng(
+                bp_gens, pc_gens, transcript, *v, v_blinding, n, &mut rng,
+            )?;
+            return Ok((proof, alloc::vec![V]));
+        }
+        RangeProof::prove_multiple_with_rng(
+            bp_gens, pc_gens, transcript, &values, blindings, n, rng,
+        )
+    }
+
+    /// Create a rangeproof for a set of decimal amounts.
+    /// This is a convenience wrapper around [`RangeProof::prove_amounts_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(all(feature = "thread_rng", feature = "prover"))]
+    pub fn prove_amounts(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        amounts: &[Amount],
+        blindings: &[impl Borrow<Scalar>],
+        scale: u8,
+        n: usize,
+    ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
+        RangeProof::prove_amounts_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            amounts,
+            blindings,
+            scale,
+            n,
+            thread_rng(),
+        )
+    }
+
     /// Verifies a rangeproof for a given value commitment \\(V\\).
     ///
     /// This is a convenience wrapper around `verify_multiple` for the `m=1` case.
-    pub fn verify_single_with_rng<T: RngCore + CryptoRng>(
+    #[cfg(feature = "verifier")]
+    pub fn verify_single_with_rng<C, T>(
         &self,
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         transcript: &mut Transcript,
-        V: &G1Affine,
+        V: &C,
         n: usize,
         rng: &mut T,
-    ) -> Result<(), ProofError> {
+    ) -> Result<(), ProofError>
+    where
+        C: Copy + Into<G1Affine>,
+        T: RngCore + CryptoRng,
+    {
         self.verify_multiple_with_rng(bp_gens, pc_gens, transcript, &[*V], n, rng)
     }
 
@@ -337,45 +961,86 @@ impl RangeProof {
     ///
     /// This is a convenience wrapper around [`RangeProof::verify_single_with_rng`],
     /// passing in a threadsafe RNG.
-    #[cfg(feature = "std")]
-    pub fn verify_single(
+    #[cfg(all(feature = "thread_rng", feature = "verifier"))]
+    pub fn verify_single<C: Copy + Into<G1Affine>>(
         &self,
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         transcript: &mut Transcript,
-        V: &G1Affine,
+        V: &C,
         n: usize,
     ) -> Result<(), ProofError> {
         self.verify_single_with_rng(bp_gens, pc_gens, transcript, V, n, &mut thread_rng())
     }
 
     /// Verifies an aggregated rangeproof for the given value commitments.
-    pub fn verify_multiple_with_rng<T: RngCore + CryptoRng>(
+    ///
+    /// A single commitment may have any bitsize `n` from 1 to 64, which
+    /// is padded as described for [`RangeProof::prove_single_with_rng`].
+    #[cfg(feature = "verifier")]
+    pub fn verify_multiple_with_rng<C, T>(
         &self,
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         transcript: &mut Transcript,
-        value_commitments: &[G1Affine],
+        value_commitments: &[C],
         n: usize,
         rng: &mut T,
-    ) -> Result<(), ProofError> {
-        let value_commitments: Vec<G1Projective> = value_commitments
-            .iter()
-            .map(|c| G1Projective::from(c))
-            .collect();
+    ) -> Result<(), ProofError>
+    where
+        C: Copy + Into<G1Affine>,
+        T: RngCore + CryptoRng,
+    {
+        let value_commitments: Vec<G1Affine> =
+            value_commitments.iter().map(|&V| V.into()).collect();
+        let mut acc = VerificationAccumulator::new(bp_gens, pc_gens);
+        let result = self
+            .accumulate_verification(
+                &mut acc,
+                Scalar::one(),
+                Scalar::random(rng),
+                transcript,
+                &value_commitments,
+                n,
+            )
+            .and_then(|_| {
+                if bool::from(acc.evaluate().is_identity()) {
+                    Ok(())
+                } else {
+                    Err(ProofError::VerificationError)
+                }
+            });
+        telemetry::record_verification("range", &result);
+        result
+    }
 
+    /// Replays the proof transcript for the given value commitments and
+    /// returns the challenge-derived scalars of the verification equation.
+    ///
+    /// This performs the same transcript operations as
+    /// [`RangeProof::verify_multiple`], so `transcript` must have the
+    /// same initial state as the prover's transcript, and is left in the
+    /// same state as after verification.  The returned scalars are enough
+    /// to build a custom verifier (e.g. one that combines several
+    /// statements into one multiscalar multiplication) without
+    /// re-deriving the transcript logic.
+    ///
+    /// The bitsize `n` must be 8, 16, 32 or 64: the statement of a
+    /// padded single-value proof is the pair of commitments it proves.
+    #[cfg(feature = "verifier")]
+    pub fn verification_scalars(
+        &self,
+        transcript: &mut Transcript,
+        value_commitments: &[G1Affine],
+        n: usize,
+    ) -> Result<VerificationScalars, ProofError> {
         let m = value_commitments.len();
 
-        // First, replay the "interactive" protocol using the proof
-        // data to recompute all challenges.
         if !(n == 8 || n == 16 || n == 32 || n == 64) {
             return Err(ProofError::InvalidBitsize);
         }
-        if bp_gens.gens_capacity < n {
-            return Err(ProofError::InvalidGeneratorsLength);
-        }
-        if bp_gens.party_capacity < m {
-            return Err(ProofError::InvalidGeneratorsLength);
+        if self.params.is_some() && self.params != Some((n, m)) {
+            return Err(ProofError::ParameterMismatch);
         }
 
         transcript.rangeproof_domain_sep(n as u64, m as u64);
@@ -383,7 +1048,7 @@ impl RangeProof {
         for V in value_commitments.iter() {
             // Allow the commitments to be zero (0 value, 0 blinding)
             // See https://github.com/dalek-cryptography/bulletproofs/pull/248#discussion_r255167177
-            transcript.append_point(b"V", V);
+            transcript.append_point(b"V", &V.into());
         }
 
         transcript.validate_and_append_point(b"A", &self.A.into())?;
@@ -391,8 +1056,6 @@ impl RangeProof {
 
         let y = transcript.challenge_scalar(b"y");
         let z = transcript.challenge_scalar(b"z");
-        let zz = z * z;
-        let minus_z = -z;
 
         transcript.validate_and_append_point(b"T_1", &self.T_1.into())?;
         transcript.validate_and_append_point(b"T_2", &self.T_2.into())?;
@@ -405,14 +1068,132 @@ impl RangeProof {
 
         let w = transcript.challenge_scalar(b"w");
 
-        // Challenge value for batching statements to be verified
-        let c = Scalar::random(rng);
+        // y is inverted in the same batch as the inner-product challenges
+        let mut y_inv = [y];
+        let (u_sq, u_inv_sq, s) =
+            self.ipp_proof
+                .verification_scalars_inverting(n * m, transcript, &mut y_inv)?;
+
+        Ok(VerificationScalars {
+            y,
+            y_inv: y_inv[0],
+            z,
+            x,
+            w,
+            u_sq,
+            u_inv_sq,
+            s,
+        })
+    }
+
+    /// Returns the terms of the verification equation for an aggregated
+    /// rangeproof as a pair of equally long scalar and point vectors.
+    ///
+    /// The proof is valid iff \\(\sum\_i s\_i \cdot P\_i\\) over the
+    /// returned terms is the identity.  This allows callers to merge the
+    /// range proof check into a larger multiscalar multiplication that
+    /// also contains the terms of their own protocol, after multiplying
+    /// all returned scalars by a random weight.
+    ///
+    /// Each of the Pedersen and Bulletproofs generators appears at most
+    /// once in the returned points.
+    #[cfg(feature = "verifier")]
+    pub fn verification_terms_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[G1Affine],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(Vec<Scalar>, Vec<G1Projective>), ProofError> {
+        let mut acc = VerificationAccumulator::new(bp_gens, pc_gens);
+        self.accumulate_verification(
+            &mut acc,
+            Scalar::one(),
+            Scalar::random(rng),
+            transcript,
+            value_commitments,
+            n,
+        )?;
+        Ok(acc.into_terms())
+    }
+
+    /// Returns the terms of the verification equation for an aggregated
+    /// rangeproof as a pair of equally long scalar and point vectors.
+    /// This is a convenience wrapper around [`RangeProof::verification_terms_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(all(feature = "thread_rng", feature = "verifier"))]
+    pub fn verification_terms(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[G1Affine],
+        n: usize,
+    ) -> Result<(Vec<Scalar>, Vec<G1Projective>), ProofError> {
+        self.verification_terms_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Replays the proof transcript and adds the terms of this proof's
+    /// verification equation, scaled by `weight`, to `acc`.
+    ///
+    /// The equation holds iff the sum of all accumulated terms is the
+    /// identity, so several proofs can be checked at once by
+    /// accumulating each of them with an independent random `weight`.
+    ///
+    /// The challenge `c` combines the check of \\(t(x)\\) with the
+    /// inner-product check, and must also be random and nonzero.
+    #[cfg(feature = "verifier")]
+    pub(crate) fn accumulate_verification(
+        &self,
+        acc: &mut VerificationAccumulator<'_>,
+        weight: Scalar,
+        c: Scalar,
+        transcript: &mut Transcript,
+        value_commitments: &[G1Affine],
+        n: usize,
+    ) -> Result<(), ProofError> {
+        let (value_commitments, n) = pad_commitments(acc.pc_gens, transcript, value_commitments, n);
+        let value_commitments = &value_commitments[..];
+        let m = value_commitments.len();
+
+        // First, replay the "interactive" protocol using the proof
+        // data to recompute all challenges.
+        if !(n == 8 || n == 16 || n == 32 || n == 64) {
+            return Err(ProofError::InvalidBitsize);
+        }
+        if acc.bp_gens.gens_capacity < n {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        if acc.bp_gens.party_capacity < m {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        let VerificationScalars {
+            y,
+            y_inv,
+            z,
+            x,
+            w,
+            u_sq: x_sq,
+            u_inv_sq: x_inv_sq,
+            s,
+        } = self.verification_scalars(transcript, value_commitments, n)?;
+        let zz = z * z;
+        let minus_z = -z;
 
-        let (x_sq, x_inv_sq, s) = self.ipp_proof.verification_scalars(n * m, transcript)?;
         let s_inv = s.iter().rev();
 
-        let a = self.ipp_proof.a;
-        let b = self.ipp_proof.b;
+        let a = &self.ipp_proof.a_vec;
+        let b = &self.ipp_proof.b_vec;
 
         // Construct concat_z_and_2, an iterator of the values of
         // z^0 * \vec(2)^n || z^1 * \vec(2)^n || ... || z^(m-1) * \vec(2)^n
@@ -422,61 +1203,58 @@ impl RangeProof {
             .flat_map(|exp_z| powers_of_2.iter().map(move |exp_2| exp_2 * exp_z))
             .collect();
 
-        let g = s.iter().map(|s_i| minus_z - a * s_i);
-        let y_inv = Option::from(y.invert()).ok_or(ProofError::FormatError)?;
+        // With an inner-product cutoff k, the i-th generators are scaled
+        // by s[i / k] and the (i mod k)-th final scalars.
+        let g = s
+            .iter()
+            .flat_map(|s_t| a.iter().map(move |a_j| minus_z - a_j * s_t));
         let h = s_inv
+            .flat_map(|s_t_inv| b.iter().map(move |b_j| b_j * s_t_inv))
             .zip(util::exp_iter(y_inv))
             .zip(concat_z_and_2.iter())
-            .map(|((s_i_inv, exp_y_inv), z_and_2)| z + exp_y_inv * (zz * z_and_2 - b * s_i_inv));
+            .map(|((b_s_i_inv, exp_y_inv), z_and_2)| z + exp_y_inv * (zz * z_and_2 - b_s_i_inv));
 
         let value_commitment_scalars = util::exp_iter(z).take(m).map(|z_exp| c * zz * z_exp);
-        let basepoint_scalar = w * (self.t_x - a * b) + c * (delta(n, m, &y, &z) - self.t_x);
-
-        // TODO: replace this dot product with blst_p1s_mult_pippenger once it's supported in blstrs
-
-        let scalars = iter::once(Scalar::one())
-            .chain(iter::once(x))
-            .chain(iter::once(c * x))
-            .chain(iter::once(c * x * x))
-            .chain(x_sq.iter().cloned())
-            .chain(x_inv_sq.iter().cloned())
-            .chain(iter::once(-self.e_blinding - c * self.t_x_blinding))
-            .chain(iter::once(basepoint_scalar))
-            .chain(g)
-            .chain(h)
-            .chain(value_commitment_scalars);
-
-        let points = iter::once(G1Projective::from(&self.A))
-            .chain(iter::once(G1Projective::from(&self.S)))
-            .chain(iter::once(G1Projective::from(&self.T_1)))
-            .chain(iter::once(G1Projective::from(&self.T_2)))
-            .chain(self.ipp_proof.L_vec.iter().copied())
-            .chain(self.ipp_proof.R_vec.iter().copied())
-            .chain(iter::once(pc_gens.B_blinding))
-            .chain(iter::once(pc_gens.B))
-            .chain(bp_gens.G(n, m).copied())
-            .chain(bp_gens.H(n, m).copied())
-            .chain(value_commitments.iter().copied());
-
-        let mega_check: G1Projective = scalars.zip(points).map(|(s, P)| P * s).sum();
-
-        if bool::from(mega_check.is_identity()) {
-            Ok(())
-        } else {
-            Err(ProofError::VerificationError)
-        }
+        let basepoint_scalar =
+            w * (self.t_x - inner_product(a, b)) + c * (delta(n, m, &y, &z) - self.t_x);
+
+        acc.add_proof_terms(
+            weight,
+            iter::once(Scalar::one())
+                .chain(iter::once(x))
+                .chain(iter::once(c * x))
+                .chain(iter::once(c * x * x))
+                .chain(x_sq.iter().cloned())
+                .chain(x_inv_sq.iter().cloned())
+                .chain(value_commitment_scalars),
+            iter::once(G1Projective::from(&self.A))
+                .chain(iter::once(G1Projective::from(&self.S)))
+                .chain(iter::once(G1Projective::from(&self.T_1)))
+                .chain(iter::once(G1Projective::from(&self.T_2)))
+                .chain(self.ipp_proof.L_vec.iter().copied())
+                .chain(self.ipp_proof.R_vec.iter().copied())
+                .chain(value_commitments.iter().map(G1Projective::from)),
+        );
+        acc.add_pedersen_terms(
+            weight,
+            basepoint_scalar,
+            -self.e_blinding - c * self.t_x_blinding,
+        );
+        acc.add_generator_terms(weight, n, m, g, h);
+
+        Ok(())
     }
 
     /// Verifies an aggregated rangeproof for the given value commitments.
     /// This is a convenience wrapper around [`RangeProof::verify_multiple_with_rng`],
     /// passing in a threadsafe RNG.
-    #[cfg(feature = "std")]
-    pub fn verify_multiple(
+    #[cfg(all(feature = "thread_rng", feature = "verifier"))]
+    pub fn verify_multiple<C: Copy + Into<G1Affine>>(
         &self,
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         transcript: &mut Transcript,
-        value_commitments: &[G1Affine],
+        value_commitments: &[C],
         n: usize,
     ) -> Result<(), ProofError> {
         self.verify_multiple_with_rng(
@@ -489,20 +1267,158 @@ impl RangeProof {
         )
     }
 
-    /// Serializes the proof into a byte array of \\(2 \lg n + 9\\)
-    /// 32-byte elements, where \\(n\\) is the number of secret bits.
+    /// Performs the structural checks of verification for a proof of
+    /// \\(m\\) values of bitsize \\(n\\), without the multiscalar
+    /// multiplication.
+    ///
+    /// Point decompression, subgroup and canonical-scalar checks are
+    /// performed by [`RangeProof::from_bytes`]; this checks that the
+    /// parameters are valid and match the proof header and generators,
+    /// that the inner-product argument has the right length, and that
+    /// no commitment is the identity.  A proof that passes these checks
+    /// may still fail verification, but one that fails them is rejected
+    /// by [`RangeProof::verify_multiple`] with the same error.
+    pub fn validate(
+        &self,
+        bp_gens: &BulletproofGens,
+        n: usize,
+        m: usize,
+    ) -> Result<(), ProofError> {
+        let (n, m) = padded_params(n, m);
+        if !(n == 8 || n == 16 || n == 32 || n == 64) {
+            return Err(ProofError::InvalidBitsize);
+        }
+        if !m.is_power_of_two() {
+            return Err(ProofError::InvalidAggregation);
+        }
+        if self.params.is_some() && self.params != Some((n, m)) {
+            return Err(ProofError::ParameterMismatch);
+        }
+        if bp_gens.gens_capacity < n || bp_gens.party_capacity < m {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        let ipp = &self.ipp_proof;
+        if ipp.L_vec.len() >= 32 || ipp.cutoff() << ipp.L_vec.len() != n * m {
+            return Err(ProofError::VerificationError);
+        }
+
+        let points = [self.A, self.S, self.T_1, self.T_2];
+        let mut ipp_points = ipp.L_vec.iter().chain(ipp.R_vec.iter());
+        if points.iter().any(|P| bool::from(P.is_identity()))
+            || ipp_points.any(|P| bool::from(P.is_identity()))
+        {
+            return Err(ProofError::VerificationError);
+        }
+
+        Ok(())
+    }
+
+    /// The version of the serialization format written by
+    /// [`RangeProof::to_bytes`], recorded in the proof header.
+    pub const VERSION: u8 = 1;
+
+    /// The version number denoting the legacy serialization format,
+    /// which has no header.
+    pub const LEGACY_VERSION: u8 = 0;
+
+    /// Returns the bitsize \\(n\\) the proof was created for, unless it
+    /// was decoded from the legacy encoding without a header.
+    pub fn bitsize(&self) -> Option<usize> {
+        self.params.map(|(n, _)| n)
+    }
+
+    /// Returns the number of values \\(m\\) the proof was created for,
+    /// unless it was decoded from the legacy encoding without a header.
+    pub fn aggregation_size(&self) -> Option<usize> {
+        self.params.map(|(_, m)| m)
+    }
+
+    /// Returns an estimate of the peak number of bytes of scratch
+    /// memory allocated by the prover to create a proof of \\(m\\)
+    /// values of bitsize \\(n\\), not counting the generators.
+    ///
+    /// The peak is reached when the inner-product argument starts,
+    /// holding the parties' proof shares, their concatenation, the
+    /// generator factors and a copy of the \\(2nm\\) generators it
+    /// folds in place.
+    pub fn prover_scratch_size(n: usize, m: usize) -> usize {
+        use core::mem::size_of;
+
+        let nm = n * m;
+        let lg_nm = nm.next_power_of_two().trailing_zeros() as usize;
+        nm * (6 * size_of::<Scalar>() + 2 * size_of::<G1Projective>())
+            + 2 * lg_nm * size_of::<G1Projective>()
+    }
+
+    /// Returns the cost of verifying a proof of \(m\) values of
+    /// bitsize \(n\), e.g. to price or schedule verification before
+    /// decoding the proof.
+    ///
+    /// The number of terms is attained by proofs created with the
+    /// default inner-product cutoff, and is an upper bound otherwise.
+    /// The scalar operations are an estimate, counting about ten per
+    /// generator, for the generator scalars and the inner-product
+    /// scalars \(s\), and a few per inner-product round and value.
+    /// Batch verification shares the \(2nm + 2\) generator terms
+    /// between proofs of the same generators.
+    pub fn verification_cost(n: usize, m: usize) -> Result<VerificationCost, ProofError> {
+        let (n, m) = padded_params(n, m);
+        if !(n == 8 || n == 16 || n == 32 || n == 64) {
+            return Err(ProofError::InvalidBitsize);
+        }
+        if !m.is_power_of_two() {
+            return Err(ProofError::InvalidAggregation);
+        }
+        let nm = n.checked_mul(m).ok_or(ProofError::InvalidAggregation)?;
+        let lg_nm = nm.trailing_zeros() as usize;
+
+        // A, S, T_1, T_2, the L and R points, the value commitments, the
+        // Pedersen generators and the Bulletproofs generators.
+        let msm_terms = 4 + 2 * lg_nm + m + 2 + 2 * nm;
+        // Per generator: s_i, z^j 2^i, and the G and H scalars with
+        // their weights.  Per round: the squared challenges, their batch
+        // inversion and weights.  Per value: the powers of z.
+        let scalar_ops = 10 * nm + 8 * lg_nm + 4 * m + 24;
+
+        Ok(VerificationCost {
+            msm_terms,
+            scalar_ops,
+        })
+    }
+
+    /// Serializes the proof into a byte array.
+    ///
+    /// For an aggregated proof of \\(m\\) values of \\(n\\) bits each,
+    /// the encoding takes \\(4 + 48 \cdot (4 + 2 \lg nm) + 32 \cdot 5\\)
+    /// bytes: a 4-byte header, compressed G1 points of 48 bytes and
+    /// little-endian scalars of 32 bytes.
     ///
     /// # Layout
     ///
     /// The layout of the range proof encoding is:
     ///
-    /// * four compressed Ristretto points \\(A,S,T_1,T_2\\),
+    /// * a header of four bytes: the version \\(1\\), the bitsize
+    ///   \\(n\\), \\(\lg m\\) for the aggregation size \\(m\\), and
+    ///   flags, which must be zero,
+    /// * four compressed G1 points \\(A,S,T_1,T_2\\),
     /// * three scalars \\(t_x, \tilde{t}_x, \tilde{e}\\),
-    /// * \\(n\\) pairs of compressed Ristretto points \\(L_0,R_0\dots,L_{n-1},R_{n-1}\\),
+    /// * \\(\lg nm\\) pairs of compressed G1 points
+    ///   \\(L_0,R_0\dots,L_{k-1},R_{k-1}\\), for \\(k = \lg nm\\),
     /// * two scalars \\(a, b\\).
+    ///
+    /// Proofs created with an inner-product cutoff encode the inner-product
+    /// argument as described in [`InnerProductProof::to_bytes`].
+    ///
+    /// Proofs decoded from the legacy encoding are encoded without a
+    /// header.
     pub fn to_bytes(&self) -> Vec<u8> {
-        // 7 elements: points A, S, T1, T2, scalars tx, tx_bl, e_bl.
-        let mut buf = Vec::with_capacity(7 * 32 + self.ipp_proof.serialized_size());
+        // Points A, S, T1, T2, scalars tx, tx_bl, e_bl.
+        let mut buf =
+            Vec::with_capacity(HEADER_LEN + 4 * 48 + 3 * 32 + self.ipp_proof.serialized_size());
+        if let Some((n, m)) = self.params {
+            buf.extend_from_slice(&[RangeProof::VERSION, n as u8, m.trailing_zeros() as u8, 0]);
+        }
         buf.extend_from_slice(&self.A.to_compressed());
         buf.extend_from_slice(&self.S.to_compressed());
         buf.extend_from_slice(&self.T_1.to_compressed());
@@ -514,14 +1430,140 @@ impl RangeProof {
         buf
     }
 
-    /// Deserializes the proof from a byte slice.
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Proofs in the legacy encoding without a header are recognized
+    /// by their first byte, which has the compression flag of \\(A\\)
+    /// set, whereas header versions do not.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a
+    /// `RangeProof`, or if the header is of an unknown version, has an
+    /// invalid bitsize or unknown flags, or does not match the size of
+    /// the proof.
+    pub fn from_bytes(slice: &[u8]) -> Result<RangeProof, ProofError> {
+        match slice.first() {
+            Some(version) if version & 0x80 == 0 => {}
+            _ => return RangeProof::from_bytes_legacy(slice),
+        }
+        let (n, m) = RangeProof::read_header(slice)?;
+
+        let mut proof = RangeProof::from_bytes_legacy(&slice[HEADER_LEN..])?;
+        let ipp_len = proof.ipp_proof.cutoff() << proof.ipp_proof.L_vec.len();
+        if n.checked_mul(m) != Some(ipp_len) {
+            return Err(ProofError::FormatError);
+        }
+        proof.params = Some((n, m));
+
+        Ok(proof)
+    }
+
+    /// Reads the shape of a serialized proof from its fixed-size
+    /// prefix, without decoding any points or scalars.
+    ///
+    /// This allows proofs to be routed or rejected by their size before
+    /// any curve operations.  The shape is not checked against the rest
+    /// of the proof, which [`RangeProof::from_bytes`] still has to
+    /// validate.
+    ///
+    /// Returns an error if `slice` is shorter than the prefix, if the
+    /// header is invalid, or if the proof is in the legacy encoding,
+    /// which does not record its shape.
+    pub fn decode_header(slice: &[u8]) -> Result<ProofShape, ProofError> {
+        if slice.len() < SHAPE_PREFIX_LEN || slice[0] & 0x80 != 0 {
+            return Err(ProofError::FormatError);
+        }
+        let (n, m) = RangeProof::read_header(slice)?;
+        let lg_nm = (n * m).trailing_zeros() as usize;
+
+        // The first byte of the inner-product argument is either its
+        // cutoff, or the first byte of a compressed point, which has
+        // the compression flag set.
+        let lg_k = match slice[SHAPE_PREFIX_LEN - 1] {
+            0 => return Err(ProofError::FormatError),
+            lg_k if lg_k & 0x80 == 0 => lg_k as usize,
+            _ => 0,
+        };
+        if lg_k > lg_nm {
+            return Err(ProofError::FormatError);
+        }
+        let ipp_len = if lg_k > 0 { 1 } else { 0 } + 2 * (lg_nm - lg_k) * 48 + ((2 * 32) << lg_k);
+
+        Ok(ProofShape {
+            version: slice[0],
+            n,
+            m,
+            byte_len: SHAPE_PREFIX_LEN - 1 + ipp_len,
+        })
+    }
+
+    /// Parses the header of a serialized proof, returning the bitsize
+    /// \\(n\\) and the aggregation size \\(m\\).
+    fn read_header(slice: &[u8]) -> Result<(usize, usize), ProofError> {
+        if slice.len() < HEADER_LEN {
+            return Err(ProofError::FormatError);
+        }
+
+        let (version, n, lg_m, flags) = (slice[0], slice[1] as usize, slice[2], slice[3]);
+        if version != RangeProof::VERSION || flags != 0 {
+            return Err(ProofError::FormatError);
+        }
+        if !(n == 8 || n == 16 || n == 32 || n == 64) || lg_m >= 26 {
+            return Err(ProofError::FormatError);
+        }
+
+        Ok((n, 1 << lg_m))
+    }
+
+    /// Re-encodes a serialized proof from the format `from_version` to
+    /// the format `to_version`, without re-proving.
     ///
-    /// Returns an error if the byte slice cannot be parsed into a `RangeProof`.
-    pub fn from_bytes(slice: &[u8]) -> Result<RangeProof, ProofError> {
-        if slice.len() < 4 * 48 {
+    /// The legacy format does not record the bitsize \\(n\\) or the
+    /// aggregation size \\(m\\), so `bitsize` must be the bitsize the
+    /// proof was created with; \\(m\\) is recovered from the size of
+    /// the proof.  When migrating from a format with a header, the
+    /// header must agree with `bitsize`.
+    ///
+    /// Returns an error if either version is unknown, if `bytes` is not
+    /// a valid proof in the format `from_version`, or if `bitsize` is
+    /// invalid or does not match the proof.
+    pub fn migrate(
+        bytes: &[u8],
+        from_version: u8,
+        to_version: u8,
+        bitsize: usize,
+    ) -> Result<Vec<u8>, ProofError> {
+        let known =
+            |version| version == RangeProof::LEGACY_VERSION || version == RangeProof::VERSION;
+        if !known(from_version) || !known(to_version) {
+            return Err(ProofError::FormatError);
+        }
+        if !(bitsize == 8 || bitsize == 16 || bitsize == 32 || bitsize == 64) {
+            return Err(ProofError::InvalidBitsize);
+        }
+
+        let mut proof = RangeProof::from_bytes(bytes)?;
+        let has_header = proof.params.is_some();
+        if has_header != (from_version == RangeProof::VERSION) {
             return Err(ProofError::FormatError);
         }
-        if (slice.len() - 4 * 48) % 32 != 0 {
+
+        let nm = proof.ipp_proof.cutoff() << proof.ipp_proof.L_vec.len();
+        if nm < bitsize || (has_header && proof.bitsize() != Some(bitsize)) {
+            return Err(ProofError::ParameterMismatch);
+        }
+
+        proof.params = if to_version == RangeProof::VERSION {
+            Some((bitsize, nm / bitsize))
+        } else {
+            None
+        };
+
+        Ok(proof.to_bytes())
+    }
+
+    /// Deserializes a proof without a header.
+    fn from_bytes_legacy(slice: &[u8]) -> Result<RangeProof, ProofError> {
+        if slice.len() < 4 * 48 {
             return Err(ProofError::FormatError);
         }
         if (slice.len() - 4 * 48) < 3 * 32 {
@@ -560,6 +1602,7 @@ impl RangeProof {
             t_x_blinding,
             e_blinding,
             ipp_proof,
+            params: None,
         })
     }
 }
@@ -578,31 +1621,189 @@ impl<'de> Deserialize<'de> for RangeProof {
     where
         D: Deserializer<'de>,
     {
-        struct RangeProofVisitor;
+        util::deserialize_bytes(deserializer, "RangeProof", RangeProof::from_bytes)
+    }
+}
+
+/// Applies `f` to every party, with its position and its RNG, split
+/// across threads when the `rayon` feature is enabled.
+///
+/// `done` is called with the position of every party once it is done,
+/// in order of the positions.
+#[cfg(feature = "prover")]
+fn map_parties<P, R, F>(
+    parties: Vec<P>,
+    rngs: &mut [ChaCha20Rng],
+    f: F,
+    mut done: impl FnMut(usize),
+) -> Vec<R>
+where
+    P: Send,
+    R: Send,
+    F: Fn(usize, P, &mut ChaCha20Rng) -> R + Sync,
+{
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
 
-        impl<'de> Visitor<'de> for RangeProofVisitor {
-            type Value = RangeProof;
+        let results: Vec<R> = parties
+            .into_par_iter()
+            .zip(rngs.par_iter_mut())
+            .enumerate()
+            .map(|(j, (p, rng))| f(j, p, rng))
+            .collect();
+        (0..results.len()).for_each(&mut done);
+        results
+    }
 
-            fn expecting(&self, formatter: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
-                formatter.write_str("a valid RangeProof")
-            }
+    #[cfg(not(feature = "rayon"))]
+    parties
+        .into_iter()
+        .zip(rngs.iter_mut())
+        .enumerate()
+        .map(|(j, (p, rng))| {
+            let result = f(j, p, rng);
+            done(j);
+            result
+        })
+        .collect()
+}
 
-            fn visit_bytes<E>(self, v: &[u8]) -> Result<RangeProof, E>
-            where
-                E: serde::de::Error,
-            {
-                // Using Error::custom requires T: Display, which our error
-                // type only implements when it implements std::error::Error.
-                #[cfg(feature = "std")]
-                return RangeProof::from_bytes(v).map_err(serde::de::Error::custom);
-                // In no-std contexts, drop the error message.
-                #[cfg(not(feature = "std"))]
-                return RangeProof::from_bytes(v)
-                    .map_err(|_| serde::de::Error::custom("deserialization error"));
-            }
+/// The secrets of the parties of a proof being created locally: the
+/// RNGs from which they draw their blinding factors, and their proof
+/// shares.
+///
+/// With the `zeroize` feature, these are wiped when the guard is
+/// dropped, so on every exit from the prover, including errors and
+/// cancellation.
+#[cfg(feature = "prover")]
+struct PartySecrets {
+    rngs: Vec<ChaCha20Rng>,
+    shares: Vec<ProofShare>,
+}
+
+#[cfg(feature = "prover")]
+impl PartySecrets {
+    /// Seeds an RNG for each of `m` parties from `rng`.
+    fn new<T: RngCore + CryptoRng>(m: usize, rng: &mut T) -> Self {
+        let rngs = (0..m)
+            .map(|_| {
+                let mut seed = Seed::from([0u8; 32]);
+                rng.fill_bytes(&mut seed[..]);
+                let seed: &[u8; 32] = &seed;
+                ChaCha20Rng::from_seed(*seed)
+            })
+            .collect();
+        PartySecrets {
+            rngs,
+            shares: Vec::new(),
+        }
+    }
+}
+
+/// The parties' RNGs could replay every blinding factor, so they are
+/// wiped together with the shares.
+#[cfg(all(feature = "prover", feature = "zeroize"))]
+impl Drop for PartySecrets {
+    fn drop(&mut self) {
+        for share in self.shares.iter_mut() {
+            share.zeroize();
+        }
+        for rng in self.rngs.iter_mut() {
+            // Reseeding overwrites the key and the buffered output
+            *rng = ChaCha20Rng::from_seed(Default::default());
+        }
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+/// The seed of a party's RNG, wiped on drop with the `zeroize` feature.
+#[cfg(all(feature = "prover", feature = "zeroize"))]
+type Seed = zeroize::Zeroizing<[u8; 32]>;
+
+/// The seed of a party's RNG.
+#[cfg(all(feature = "prover", not(feature = "zeroize")))]
+type Seed = [u8; 32];
+
+/// Returns the supported bitsize to which a proof of bitsize `n` is
+/// padded, if `n` is between 1 and 64 but not 8, 16, 32 or 64.
+fn padded_bitsize(n: usize) -> Option<usize> {
+    match n {
+        1..=7 => Some(8),
+        9..=15 => Some(16),
+        17..=31 => Some(32),
+        33..=63 => Some(64),
+        _ => None,
+    }
+}
+
+/// Returns the bitsize and aggregation size of the proof created for
+/// `m` values of bitsize `n`, which differ from `n` and `m` only for a
+/// single value of a padded bitsize.
+pub(crate) fn padded_params(n: usize, m: usize) -> (usize, usize) {
+    match padded_bitsize(n) {
+        Some(padded) if m == 1 => (padded, 2),
+        _ => (n, m),
+    }
+}
+
+/// Returns \\(2^{N} - 2^{n}\\) for a bitsize `n` padded to `padded`.
+#[cfg(any(feature = "prover", feature = "verifier"))]
+fn padding_offset(n: usize, padded: usize) -> u64 {
+    ((1u128 << padded) - (1u128 << n)) as u64
+}
+
+/// Appends the bitsize of a padded proof to `transcript`.
+#[cfg(any(feature = "prover", feature = "verifier"))]
+fn append_bitsize(transcript: &mut Transcript, n: usize) {
+    transcript.append_message(b"dom-sep", b"padded-rangeproof v1");
+    transcript.append_u64(b"n", n as u64);
+}
+
+/// Maps a value `v` of bitsize `n` to the values and bitsize of the
+/// proof created for it: \\(v\\) and \\(v + 2^{N} - 2^{n}\\) of the
+/// padded bitsize \\(N\\), after appending `n` to `transcript`, if
+/// `n` is padded, and `v` and `n` unchanged otherwise.
+///
+/// Values of \\(2^{n}\\) or more wrap around, and give a proof which
+/// does not verify, as for the supported bitsizes.
+#[cfg(feature = "prover")]
+fn pad_value(transcript: &mut Transcript, v: u64, n: usize) -> (Vec<u64>, usize) {
+    match padded_bitsize(n) {
+        Some(padded) => {
+            append_bitsize(transcript, n);
+            (
+                alloc::vec![v, v.wrapping_add(padding_offset(n, padded))],
+                padded,
+            )
         }
+        None => (alloc::vec![v], n),
+    }
+}
 
-        deserializer.deserialize_bytes(RangeProofVisitor)
+/// Maps the commitments `value_commitments` of bitsize `n` to the
+/// commitments and bitsize of the proof created for them, as
+/// [`pad_value`] maps a value: a single commitment \\(V\\) of a padded
+/// bitsize becomes \\(V\\) and \\(V + (2^{N} - 2^{n}) B\\).
+#[cfg(feature = "verifier")]
+pub(crate) fn pad_commitments(
+    pc_gens: &PedersenGens,
+    transcript: &mut Transcript,
+    value_commitments: &[G1Affine],
+    n: usize,
+) -> (Vec<G1Affine>, usize) {
+    match (padded_bitsize(n), value_commitments) {
+        (Some(padded), [V]) => {
+            append_bitsize(transcript, n);
+            let V = G1Projective::from(V);
+            let mut Vs = alloc::vec![G1Affine::default(); 2];
+            G1Projective::batch_normalize(
+                &[V, V + pc_gens.B * Scalar::from(padding_offset(n, padded))],
+                &mut Vs,
+            );
+            (Vs, padded)
+        }
+        _ => (value_commitments.to_vec(), n),
     }
 }
 
@@ -610,6 +1811,7 @@ impl<'de> Deserialize<'de> for RangeProof {
 /// \\[
 /// \delta(y,z) = (z - z^{2}) \langle \mathbf{1}, {\mathbf{y}}^{n \cdot m} \rangle - \sum_{j=0}^{m-1} z^{j+3} \cdot \langle \mathbf{1}, {\mathbf{2}}^{n \cdot m} \rangle
 /// \\]
+#[cfg(feature = "verifier")]
 fn delta(n: usize, m: usize, y: &Scalar, z: &Scalar) -> Scalar {
     let sum_y = util::sum_of_powers(y, n * m);
     let sum_2 = util::sum_of_powers(&Scalar::from(2u64), n);
@@ -622,6 +1824,7 @@ fn delta(n: usize, m: usize, y: &Scalar, z: &Scalar) -> Scalar {
 mod tests {
     use super::*;
 
+    use crate::errors::AmountError;
     use crate::generators::PedersenGens;
 
     #[test]
@@ -709,6 +1912,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn serde_roundtrips_through_self_describing_formats() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let (proof, _) = RangeProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"SerdeTest"),
+            7,
+            &Scalar::from(3u64),
+            16,
+        )
+        .unwrap();
+
+        let cbor = serde_cbor::to_vec(&proof).unwrap();
+        assert_eq!(serde_cbor::from_slice::<RangeProof>(&cbor).unwrap(), proof);
+        let json = serde_json::to_vec(&proof).unwrap();
+        assert_eq!(serde_json::from_slice::<RangeProof>(&json).unwrap(), proof);
+
+        let mut truncated = proof.to_bytes();
+        truncated.pop();
+        let json = serde_json::to_vec(&truncated).unwrap();
+        assert!(serde_json::from_slice::<RangeProof>(&json).is_err());
+    }
+
     #[test]
     fn create_and_verify_n_32_m_1() {
         singleparty_create_and_verify_helper(32, 1);
@@ -749,6 +1977,692 @@ mod tests {
         singleparty_create_and_verify_helper(64, 8);
     }
 
+    #[test]
+    fn padded_bitsize_bounds_value() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+        let blinding = Scalar::from(7u64);
+
+        let prove = |v: u64, n| {
+            RangeProof::prove_single(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"PaddedTest"),
+                v,
+                &blinding,
+                n,
+            )
+            .unwrap()
+        };
+        let verify = |proof: &RangeProof, V: &G1Affine, n| {
+            proof.verify_single(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"PaddedTest"),
+                V,
+                n,
+            )
+        };
+
+        let (proof, V) = prove((1 << 52) - 1, 52);
+        assert_eq!(
+            V,
+            pc_gens
+                .commit(Scalar::from((1u64 << 52) - 1), blinding)
+                .to_affine()
+        );
+        assert_eq!(verify(&proof, &V, 52), Ok(()));
+        // The true bitsize is bound to the proof
+        assert!(verify(&proof, &V, 53).is_err());
+        assert!(verify(&proof, &V, 64).is_err());
+
+        let (proof, V) = prove(1 << 52, 52);
+        assert_eq!(verify(&proof, &V, 52), Err(ProofError::VerificationError));
+
+        let (proof, V) = prove(5, 3);
+        assert_eq!(verify(&proof, &V, 3), Ok(()));
+        assert_eq!(
+            RangeProof::prove_single(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"PaddedTest"),
+                0,
+                &blinding,
+                65,
+            ),
+            Err(ProofError::InvalidBitsize)
+        );
+
+        // Batch proving and verification pad in the same way
+        let transcript = Transcript::new(b"PaddedTest");
+        let proofs = RangeProof::prove_many(
+            &bp_gens,
+            &pc_gens,
+            &transcript,
+            &[(3, blinding), (9, blinding)],
+            4,
+        )
+        .unwrap();
+        let statements: Vec<(&RangeProof, &[G1Affine], usize)> = proofs
+            .iter()
+            .map(|(proof, V)| (proof, core::slice::from_ref(V), 4))
+            .collect();
+        assert_eq!(
+            RangeProof::batch_verify(&bp_gens, &pc_gens, &transcript, &statements),
+            Ok(())
+        );
+
+        let proofs = RangeProof::prove_many(
+            &bp_gens,
+            &pc_gens,
+            &transcript,
+            &[(3, blinding), (16, blinding)],
+            4,
+        )
+        .unwrap();
+        let statements: Vec<(&RangeProof, &[G1Affine], usize)> = proofs
+            .iter()
+            .map(|(proof, V)| (proof, core::slice::from_ref(V), 4))
+            .collect();
+        assert_eq!(
+            RangeProof::batch_verify(&bp_gens, &pc_gens, &transcript, &statements),
+            Err(ProofError::VerificationError)
+        );
+        assert_eq!(
+            RangeProof::prove_many(
+                &BulletproofGens::new(64, 1),
+                &pc_gens,
+                &transcript,
+                &[(3, blinding)],
+                4,
+            )
+            .unwrap_err(),
+            ProofError::InvalidGeneratorsLength
+        );
+    }
+
+    #[test]
+    fn verification_terms_sum_to_identity() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let blindings = [Scalar::from(5u64), Scalar::from(6u64)];
+
+        let mut transcript = Transcript::new(b"VerificationTermsTest");
+        let (proof, commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[10, 20],
+            &blindings,
+            32,
+        )
+        .unwrap();
+
+        let terms = |commitments: &[G1Affine]| {
+            let mut transcript = Transcript::new(b"VerificationTermsTest");
+            let (scalars, points) = proof
+                .verification_terms(&bp_gens, &pc_gens, &mut transcript, commitments, 32)
+                .unwrap();
+            assert_eq!(scalars.len(), points.len());
+            scalars
+                .iter()
+                .zip(points.iter())
+                .map(|(s, P)| P * s)
+                .sum::<G1Projective>()
+        };
+
+        assert!(bool::from(terms(&commitments).is_identity()));
+        let wrong = [commitments[1], commitments[0]];
+        assert!(!bool::from(terms(&wrong).is_identity()));
+    }
+
+    #[test]
+    fn ipp_cutoff_roundtrip() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let blindings = [Scalar::from(5u64), Scalar::from(6u64)];
+
+        let mut transcript = Transcript::new(b"CutoffTest");
+        let (full, _) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[1, 2],
+            &blindings,
+            32,
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"CutoffTest");
+        let (proof, commitments) = RangeProof::prove_multiple_with_ipp_cutoff(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[1, 2],
+            &blindings,
+            32,
+            8,
+        )
+        .unwrap();
+        // 3 fewer rounds, so 6 fewer points and 14 more scalars
+        assert_eq!(
+            proof.to_bytes().len(),
+            full.to_bytes().len() + 1 - 6 * 48 + 14 * 32
+        );
+
+        let proof = RangeProof::from_bytes(&proof.to_bytes()).unwrap();
+        let mut transcript = Transcript::new(b"CutoffTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 32)
+            .is_ok());
+        let mut transcript = Transcript::new(b"CutoffTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments[..1], 32)
+            .is_err());
+
+        let mut transcript = Transcript::new(b"CutoffTest");
+        assert_eq!(
+            RangeProof::prove_multiple_with_ipp_cutoff(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &[1, 2],
+                &blindings,
+                32,
+                3,
+            )
+            .unwrap_err(),
+            ProofError::InvalidCutoff
+        );
+    }
+
+    #[test]
+    fn proof_header() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let blindings = [Scalar::from(5u64), Scalar::from(6u64)];
+
+        let mut transcript = Transcript::new(b"HeaderTest");
+        let (proof, commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[1, 2],
+            &blindings,
+            16,
+        )
+        .unwrap();
+        let bytes = proof.to_bytes();
+        assert_eq!(&bytes[..HEADER_LEN], &[RangeProof::VERSION, 16, 1, 0]);
+
+        let proof = RangeProof::from_bytes(&bytes).unwrap();
+        assert_eq!(proof.bitsize(), Some(16));
+        assert_eq!(proof.aggregation_size(), Some(2));
+        assert_eq!(proof.to_bytes(), bytes);
+
+        let mut transcript = Transcript::new(b"HeaderTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 16)
+            .is_ok());
+        let mut transcript = Transcript::new(b"HeaderTest");
+        assert_eq!(
+            proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 32),
+            Err(ProofError::ParameterMismatch)
+        );
+        let mut transcript = Transcript::new(b"HeaderTest");
+        assert_eq!(
+            proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments[..1], 16),
+            Err(ProofError::ParameterMismatch)
+        );
+
+        // Headers inconsistent with the proof size are rejected.
+        for header in &[
+            [2, 16, 1, 0],
+            [1, 12, 1, 0],
+            [1, 16, 1, 1],
+            [1, 32, 1, 0],
+            [1, 16, 0, 0],
+        ] {
+            let mut bad = bytes.clone();
+            bad[..HEADER_LEN].copy_from_slice(header);
+            assert_eq!(
+                RangeProof::from_bytes(&bad).unwrap_err(),
+                ProofError::FormatError
+            );
+        }
+
+        // Legacy proofs without a header still decode and verify.
+        let legacy = RangeProof::from_bytes(&bytes[HEADER_LEN..]).unwrap();
+        assert_eq!(legacy.bitsize(), None);
+        assert_eq!(legacy.aggregation_size(), None);
+        assert_eq!(legacy.to_bytes(), &bytes[HEADER_LEN..]);
+        let mut transcript = Transcript::new(b"HeaderTest");
+        assert!(legacy
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 16)
+            .is_ok());
+    }
+
+    #[test]
+    fn migrate_proof_format() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 4);
+        let blindings = [Scalar::from(7u64); 4];
+
+        let mut transcript = Transcript::new(b"MigrateTest");
+        let (proof, commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[1, 2, 3, 4],
+            &blindings,
+            16,
+        )
+        .unwrap();
+        let current = proof.to_bytes();
+        let legacy = &current[HEADER_LEN..];
+
+        let upgraded =
+            RangeProof::migrate(legacy, RangeProof::LEGACY_VERSION, RangeProof::VERSION, 16)
+                .unwrap();
+        assert_eq!(upgraded, current);
+        let mut transcript = Transcript::new(b"MigrateTest");
+        assert!(RangeProof::from_bytes(&upgraded)
+            .unwrap()
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 16)
+            .is_ok());
+
+        let downgraded = RangeProof::migrate(
+            &current,
+            RangeProof::VERSION,
+            RangeProof::LEGACY_VERSION,
+            16,
+        )
+        .unwrap();
+        assert_eq!(downgraded, legacy);
+        assert_eq!(
+            RangeProof::migrate(&current, RangeProof::VERSION, RangeProof::VERSION, 16).unwrap(),
+            current
+        );
+
+        // The source format must match the encoding.
+        assert_eq!(
+            RangeProof::migrate(legacy, RangeProof::VERSION, RangeProof::VERSION, 16),
+            Err(ProofError::FormatError)
+        );
+        assert_eq!(
+            RangeProof::migrate(&current, 2, RangeProof::VERSION, 16),
+            Err(ProofError::FormatError)
+        );
+        // The bitsize must be valid and agree with the proof.
+        assert_eq!(
+            RangeProof::migrate(
+                &current,
+                RangeProof::VERSION,
+                RangeProof::LEGACY_VERSION,
+                32
+            ),
+            Err(ProofError::ParameterMismatch)
+        );
+        assert_eq!(
+            RangeProof::migrate(legacy, RangeProof::LEGACY_VERSION, RangeProof::VERSION, 12),
+            Err(ProofError::InvalidBitsize)
+        );
+
+        // Without a header a different split of n * m is also consistent,
+        // but then fails verification with the original parameters.
+        let wrong =
+            RangeProof::migrate(legacy, RangeProof::LEGACY_VERSION, RangeProof::VERSION, 32)
+                .unwrap();
+        let mut transcript = Transcript::new(b"MigrateTest");
+        assert_eq!(
+            RangeProof::from_bytes(&wrong).unwrap().verify_multiple(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &commitments,
+                16
+            ),
+            Err(ProofError::ParameterMismatch)
+        );
+    }
+
+    #[test]
+    fn validate_checks_structure() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let blindings = [Scalar::from(3u64), Scalar::from(4u64)];
+
+        let mut transcript = Transcript::new(b"ValidateTest");
+        let (proof, _) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[5, 6],
+            &blindings,
+            16,
+        )
+        .unwrap();
+        assert_eq!(proof.validate(&bp_gens, 16, 2), Ok(()));
+        assert_eq!(
+            proof.validate(&bp_gens, 12, 2),
+            Err(ProofError::InvalidBitsize)
+        );
+        assert_eq!(
+            proof.validate(&bp_gens, 16, 3),
+            Err(ProofError::InvalidAggregation)
+        );
+        assert_eq!(
+            proof.validate(&bp_gens, 32, 1),
+            Err(ProofError::ParameterMismatch)
+        );
+        assert_eq!(
+            proof.validate(&BulletproofGens::new(8, 2), 16, 2),
+            Err(ProofError::InvalidGeneratorsLength)
+        );
+
+        // Without a header the shape is checked against the proof size.
+        let mut legacy = proof.clone();
+        legacy.params = None;
+        assert_eq!(legacy.validate(&bp_gens, 32, 1), Ok(()));
+        assert_eq!(
+            legacy.validate(&bp_gens, 16, 1),
+            Err(ProofError::VerificationError)
+        );
+
+        let mut bad = proof;
+        bad.T_1 = G1Affine::identity();
+        assert_eq!(
+            bad.validate(&bp_gens, 16, 2),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn decode_proof_shape() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 4);
+        let blindings = [Scalar::from(1u64); 4];
+
+        for &(n, m, k) in &[(8, 1, 1), (32, 4, 1), (16, 2, 4), (8, 4, 32)] {
+            let mut transcript = Transcript::new(b"ShapeTest");
+            let (proof, _) = RangeProof::prove_multiple_with_ipp_cutoff(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &[1, 2, 3, 4][..m],
+                &blindings[..m],
+                n,
+                k,
+            )
+            .unwrap();
+            let bytes = proof.to_bytes();
+            let shape = RangeProof::decode_header(&bytes[..SHAPE_PREFIX_LEN]).unwrap();
+            assert_eq!(
+                shape,
+                ProofShape {
+                    version: RangeProof::VERSION,
+                    n,
+                    m,
+                    byte_len: bytes.len(),
+                }
+            );
+        }
+
+        let mut transcript = Transcript::new(b"ShapeTest");
+        let (proof, _) =
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 1, &blindings[0], 8)
+                .unwrap();
+        let bytes = proof.to_bytes();
+        assert!(RangeProof::decode_header(&bytes[..SHAPE_PREFIX_LEN - 1]).is_err());
+        // Legacy encodings have no header
+        assert!(RangeProof::decode_header(&bytes[HEADER_LEN..]).is_err());
+    }
+
+    #[test]
+    fn prove_many_independent_proofs() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let values_and_blindings: Vec<(u64, Scalar)> =
+            (0..5u64).map(|v| (v * 1000, Scalar::from(v + 1))).collect();
+
+        let transcript = Transcript::new(b"ProveManyTest");
+        let proofs =
+            RangeProof::prove_many(&bp_gens, &pc_gens, &transcript, &values_and_blindings, 32)
+                .unwrap();
+        assert_eq!(proofs.len(), values_and_blindings.len());
+
+        for ((proof, commitment), (v, v_blinding)) in proofs.iter().zip(&values_and_blindings) {
+            assert_eq!(
+                *commitment,
+                pc_gens.commit(Scalar::from(*v), *v_blinding).to_affine()
+            );
+            let mut transcript = Transcript::new(b"ProveManyTest");
+            assert!(proof
+                .verify_single(&bp_gens, &pc_gens, &mut transcript, commitment, 32)
+                .is_ok());
+        }
+
+        assert_eq!(
+            RangeProof::prove_many(&bp_gens, &pc_gens, &transcript, &values_and_blindings, 64)
+                .unwrap_err(),
+            ProofError::InvalidGeneratorsLength
+        );
+        assert_eq!(
+            RangeProof::prove_many(&bp_gens, &pc_gens, &transcript, &values_and_blindings, 65)
+                .unwrap_err(),
+            ProofError::InvalidBitsize
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn prove_many_in_pool() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        let transcript = Transcript::new(b"PoolTest");
+        let values_and_blindings: Vec<(u64, Scalar)> =
+            (0..4u64).map(|v| (v, Scalar::from(v + 1))).collect();
+
+        let proofs = RangeProof::prove_many_in_pool_with_rng(
+            &pool,
+            &bp_gens,
+            &pc_gens,
+            &transcript,
+            &values_and_blindings,
+            16,
+            &mut rand::thread_rng(),
+        )
+        .unwrap();
+        for (proof, commitment) in proofs.iter() {
+            assert!(proof
+                .verify_single(&bp_gens, &pc_gens, &mut transcript.clone(), commitment, 16)
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn prove_multiple_cancellable() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let blindings = [Scalar::from(7u64), Scalar::from(8u64)];
+        let cancel = AtomicBool::new(false);
+
+        let mut transcript = Transcript::new(b"CancelTest");
+        let (proof, commitments) = RangeProof::prove_multiple_cancellable(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[3, 4],
+            &blindings,
+            32,
+            &cancel,
+        )
+        .unwrap();
+        let mut transcript = Transcript::new(b"CancelTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 32)
+            .is_ok());
+
+        cancel.store(true, core::sync::atomic::Ordering::Relaxed);
+        let mut transcript = Transcript::new(b"CancelTest");
+        assert_eq!(
+            RangeProof::prove_multiple_cancellable(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &[3, 4],
+                &blindings,
+                32,
+                &cancel,
+            )
+            .unwrap_err(),
+            ProofError::Cancelled
+        );
+    }
+
+    #[test]
+    fn verification_scalars_match_verifier_transcript() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 2);
+        let blindings = [Scalar::from(7u64), Scalar::from(8u64)];
+
+        let mut transcript = Transcript::new(b"VerificationScalarsTest");
+        let (proof, commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[3, 4],
+            &blindings,
+            16,
+        )
+        .unwrap();
+
+        let mut scalars_transcript = Transcript::new(b"VerificationScalarsTest");
+        let scalars = proof
+            .verification_scalars(&mut scalars_transcript, &commitments, 16)
+            .unwrap();
+        assert_eq!(scalars.u_sq.len(), 5);
+        assert_eq!(scalars.u_inv_sq.len(), 5);
+        assert_eq!(scalars.s.len(), 32);
+        assert_eq!(scalars.y * scalars.y_inv, Scalar::one());
+        for (u_sq, u_inv_sq) in scalars.u_sq.iter().zip(scalars.u_inv_sq.iter()) {
+            assert_eq!(u_sq * u_inv_sq, Scalar::one());
+        }
+
+        // The transcript must be left in the same state as after verification.
+        let mut verify_transcript = Transcript::new(b"VerificationScalarsTest");
+        proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut verify_transcript, &commitments, 16)
+            .unwrap();
+        assert_eq!(
+            scalars_transcript.challenge_scalar(b"next"),
+            verify_transcript.challenge_scalar(b"next")
+        );
+
+        let mut other_transcript = Transcript::new(b"VerificationScalarsTest");
+        let other = proof
+            .verification_scalars(&mut other_transcript, &[commitments[1], commitments[0]], 16)
+            .unwrap();
+        assert_ne!(scalars.y, other.y);
+    }
+
+    #[test]
+    fn prove_amounts_checks_scale_and_bitsize() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let blindings = [Scalar::from(1u64), Scalar::from(2u64)];
+        let amounts = [
+            Amount::parse("1.5", 1).unwrap(),
+            Amount::parse("0.25", 2).unwrap(),
+        ];
+
+        let mut transcript = Transcript::new(b"AmountTest");
+        let (proof, commitments) = RangeProof::prove_amounts(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &amounts,
+            &blindings,
+            6,
+            32,
+        )
+        .unwrap();
+        assert_eq!(
+            commitments[0],
+            pc_gens
+                .commit(Scalar::from(1_500_000u64), blindings[0])
+                .to_affine()
+        );
+        let mut transcript = Transcript::new(b"AmountTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 32)
+            .is_ok());
+
+        let mut transcript = Transcript::new(b"AmountTest");
+        assert_eq!(
+            RangeProof::prove_amounts(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &amounts,
+                &blindings,
+                1,
+                32,
+            ),
+            Err(ProofError::InvalidAmount(AmountError::TooPrecise {
+                scale: 1
+            }))
+        );
+        assert_eq!(
+            RangeProof::prove_amounts(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &amounts,
+                &blindings,
+                10,
+                32,
+            ),
+            Err(ProofError::InvalidAmount(AmountError::ExceedsBitsize {
+                units: 15_000_000_000,
+                bitsize: 32
+            }))
+        );
+
+        // A single amount may have a bitsize other than 8, 16, 32 or 64
+        let mut transcript = Transcript::new(b"AmountTest");
+        let (proof, commitments) = RangeProof::prove_amounts(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &amounts[..1],
+            &blindings[..1],
+            6,
+            24,
+        )
+        .unwrap();
+        let mut transcript = Transcript::new(b"AmountTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 24)
+            .is_ok());
+        assert_eq!(
+            RangeProof::prove_amounts(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &amounts,
+                &blindings,
+                6,
+                24,
+            ),
+            Err(ProofError::InvalidBitsize)
+        );
+    }
+
     #[test]
     fn detect_dishonest_party_during_aggregation() {
         use self::dealer::*;
@@ -864,4 +2778,224 @@ mod tests {
 
         assert!(maybe_share0.unwrap_err() == MPCError::MaliciousDealer);
     }
+
+    #[test]
+    fn hierarchical_aggregation() {
+        use self::dealer::*;
+        use self::party::*;
+
+        use crate::errors::MPCError;
+
+        // Four parties in two groups of two, the last one dishonest
+        // unless `honest` is set
+        let (n, m) = (32, 4);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        let run = |honest: bool, trusted: bool| {
+            let values = [7u64, 1 << 20, 99, if honest { 12 } else { 1 << 40 }];
+            let parties: Vec<_> = values
+                .iter()
+                .enumerate()
+                .map(|(j, &v)| {
+                    Party::new(&bp_gens, &pc_gens, v, Scalar::from(j as u64 + 3), n)
+                        .unwrap()
+                        .assign_position(j)
+                        .unwrap()
+                })
+                .collect();
+            let (parties, bit_commitments): (Vec<_>, Vec<_>) = parties.into_iter().unzip();
+
+            let mut transcript = Transcript::new(b"HierarchicalTest");
+            let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+            let mut groups = Vec::new();
+            let mut group_bit_commitments = Vec::new();
+            for (offset, chunk) in [0, 2].iter().zip(bit_commitments.chunks(2)) {
+                let sub_dealer = SubDealer::new(&bp_gens, &pc_gens, n, *offset, 2).unwrap();
+                let (sub_dealer, group_bit_commitment) =
+                    sub_dealer.receive_bit_commitments(chunk.to_vec()).unwrap();
+                groups.push(sub_dealer);
+                group_bit_commitments.push(group_bit_commitment);
+            }
+            let (dealer, bit_challenge) = dealer
+                .receive_group_bit_commitments(group_bit_commitments)
+                .unwrap();
+
+            let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+                .into_iter()
+                .map(|p| p.apply_challenge(&bit_challenge))
+                .unzip();
+            let (groups, group_poly_commitments): (Vec<_>, Vec<_>) = groups
+                .into_iter()
+                .zip(poly_commitments.chunks(2))
+                .map(|(g, chunk)| {
+                    g.receive_poly_commitments(&bit_challenge, chunk.to_vec())
+                        .unwrap()
+                })
+                .unzip();
+            let (dealer, poly_challenge) = dealer
+                .receive_poly_commitments(group_poly_commitments)
+                .unwrap();
+
+            let shares: Vec<_> = parties
+                .into_iter()
+                .map(|p| p.apply_challenge(&poly_challenge).unwrap())
+                .collect();
+            let group_shares = groups
+                .into_iter()
+                .zip(shares.chunks(2))
+                .map(|(g, chunk)| {
+                    if trusted {
+                        g.receive_trusted_shares(chunk)
+                    } else {
+                        g.receive_shares(&poly_challenge, chunk)
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let V: Vec<_> = bit_commitments.iter().map(|c| c.V_j.to_affine()).collect();
+            Ok((dealer.receive_shares(&group_shares), V))
+        };
+
+        // The aggregated proof is a standard proof
+        let (proof, V) = run(true, false).unwrap();
+        assert!(proof
+            .unwrap()
+            .verify_multiple(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"HierarchicalTest"),
+                &V,
+                n
+            )
+            .is_ok());
+
+        // The sub-dealer detects its dishonest party
+        assert_eq!(
+            run(false, false).err(),
+            Some(MPCError::MalformedProofShares {
+                bad_shares: vec![1]
+            })
+        );
+
+        // Otherwise, the dealer detects the dishonest group
+        assert_eq!(
+            run(false, true).unwrap().0.err(),
+            Some(ProofError::ProvingError(MPCError::MalformedProofShares {
+                bad_shares: vec![1]
+            }))
+        );
+    }
+
+    #[test]
+    fn verification_cost_counts_terms() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 4);
+
+        for m in [1, 2, 4] {
+            let values: Vec<u64> = (0..m as u64).collect();
+            let blindings: Vec<Scalar> = (0..m as u64).map(Scalar::from).collect();
+            let (proof, commitments) = RangeProof::prove_multiple(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"VerificationCostTest"),
+                &values,
+                &blindings,
+                16,
+            )
+            .unwrap();
+            let (scalars, _) = proof
+                .verification_terms(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"VerificationCostTest"),
+                    &commitments,
+                    16,
+                )
+                .unwrap();
+
+            let cost = RangeProof::verification_cost(16, m).unwrap();
+            assert_eq!(cost.msm_terms, scalars.len());
+            assert!(cost.scalar_ops > cost.msm_terms);
+        }
+        assert_eq!(
+            RangeProof::verification_cost(16, 3),
+            Err(ProofError::InvalidAggregation)
+        );
+        assert_eq!(
+            RangeProof::verification_cost(65, 1),
+            Err(ProofError::InvalidBitsize)
+        );
+    }
+
+    #[test]
+    fn prove_multiple_reports_progress() {
+        use core::cell::RefCell;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 4);
+        let blindings: Vec<Scalar> = (1..=4u64).map(Scalar::from).collect();
+
+        let reports = RefCell::new(Vec::new());
+        let (proof, commitments) = RangeProof::prove_multiple_with_progress(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"ProgressTest"),
+            &[1, 2, 3, 4],
+            &blindings,
+            16,
+            &|phase, fraction| reports.borrow_mut().push((phase, fraction)),
+        )
+        .unwrap();
+        assert!(proof
+            .verify_multiple(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"ProgressTest"),
+                &commitments,
+                16
+            )
+            .is_ok());
+
+        let reports = reports.into_inner();
+        // The start and 4 parties, 2 phases, and the start and 6 rounds
+        // of the inner-product argument
+        assert_eq!(reports.len(), 1 + 4 + 2 + 1 + 6 + 1);
+        assert_eq!(reports[0], (ProvingPhase::Commitments, 0.0));
+        assert_eq!(*reports.last().unwrap(), (ProvingPhase::Done, 1.0));
+        assert!(reports.windows(2).all(|w| w[0].1 <= w[1].1));
+    }
+
+    #[test]
+    fn seeded_proof_is_stable() {
+        // The same proof must come out whether or not the parties and
+        // the inner-product argument run in parallel.
+        use digest::Digest;
+        use sha3::Sha3_256;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 4);
+        let blindings: Vec<Scalar> = (1..=4u64).map(Scalar::from).collect();
+        let (proof, _) = RangeProof::prove_multiple_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"StableTest"),
+            &[1, 2, 3, 4],
+            &blindings,
+            16,
+            ChaCha20Rng::from_seed([7u8; 32]),
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(Sha3_256::digest(&proof.to_bytes())),
+            "cc2a68a85878265e2ee5309081ddce09e314c4b0341e26dcf5b1bb14cb5cc2a2"
+        );
+    }
+
+    #[test]
+    fn prover_scratch_size_grows_with_parameters() {
+        let small = RangeProof::prover_scratch_size(32, 1);
+        assert!(small >= 32 * 2 * core::mem::size_of::<G1Projective>());
+        assert!(RangeProof::prover_scratch_size(64, 1) > small);
+        assert!(RangeProof::prover_scratch_size(32, 4) > 3 * small);
+    }
 }
@@ -0,0 +1,245 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+//! Binding of range proofs to a caller-supplied nonce.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use blstrs::{G1Affine, Scalar};
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::RangeProof;
+use crate::util;
+
+/// A [`RangeProof`] bound to a nonce chosen by the caller, e.g. the
+/// epoch of a settlement round.
+///
+/// The nonce is appended to the proving transcript before any proof
+/// data, so the proof only verifies for the same nonce, and cannot be
+/// replayed into a round with another one.  The nonce is also recorded
+/// in the clear, so that a verifier expecting another nonce rejects the
+/// proof with [`ProofError::NonceMismatch`] rather than a generic
+/// verification failure.
+///
+/// # Encoding
+///
+/// The proof is encoded as the nonce as a little-endian `u64`, followed
+/// by the encoding of the range proof.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NoncedProof {
+    nonce: u64,
+    proof: RangeProof,
+}
+
+impl NoncedProof {
+    /// Create an aggregated rangeproof for a set of values, bound to `nonce`.
+    ///
+    /// Otherwise, this is [`RangeProof::prove_multiple_with_rng`] with
+    /// the nonce prepended to the transcript.
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        nonce: u64,
+        rng: &mut T,
+    ) -> Result<(NoncedProof, Vec<G1Affine>), ProofError> {
+        append_nonce(transcript, nonce);
+        let (proof, value_commitments) = RangeProof::prove_multiple_with_rng(
+            bp_gens, pc_gens, transcript, values, blindings, n, rng,
+        )?;
+        Ok((NoncedProof { nonce, proof }, value_commitments))
+    }
+
+    /// Create an aggregated rangeproof for a set of values, bound to `nonce`.
+    /// This is a convenience wrapper around [`NoncedProof::prove_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn prove(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        nonce: u64,
+    ) -> Result<(NoncedProof, Vec<G1Affine>), ProofError> {
+        NoncedProof::prove_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            nonce,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies the proof for the given value commitments and the
+    /// `nonce` expected by the verifier.
+    ///
+    /// Returns [`ProofError::NonceMismatch`] if the proof records
+    /// another nonce.  A proof whose recorded nonce was altered after
+    /// proving fails with [`ProofError::VerificationError`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[G1Affine],
+        n: usize,
+        nonce: u64,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        if self.nonce != nonce {
+            return Err(ProofError::NonceMismatch);
+        }
+        append_nonce(transcript, nonce);
+        self.proof
+            .verify_multiple_with_rng(bp_gens, pc_gens, transcript, value_commitments, n, rng)
+    }
+
+    /// Verifies the proof for the given value commitments and `nonce`.
+    /// This is a convenience wrapper around [`NoncedProof::verify_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[G1Affine],
+        n: usize,
+        nonce: u64,
+    ) -> Result<(), ProofError> {
+        self.verify_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            n,
+            nonce,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Returns the nonce the proof is bound to.
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    /// Returns the range proof.
+    pub fn proof(&self) -> &RangeProof {
+        &self.proof
+    }
+
+    /// Serializes the proof into a byte vector.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let proof = self.proof.to_bytes();
+        let mut buf = Vec::with_capacity(8 + proof.len());
+        buf.extend_from_slice(&self.nonce.to_le_bytes());
+        buf.extend_from_slice(&proof);
+        buf
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a `NoncedProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<NoncedProof, ProofError> {
+        if slice.len() < 8 {
+            return Err(ProofError::FormatError);
+        }
+        let mut nonce = [0u8; 8];
+        nonce.copy_from_slice(&slice[..8]);
+        Ok(NoncedProof {
+            nonce: u64::from_le_bytes(nonce),
+            proof: RangeProof::from_bytes(&slice[8..])?,
+        })
+    }
+}
+
+/// Appends `nonce` to `transcript`.
+fn append_nonce(transcript: &mut Transcript, nonce: u64) {
+    transcript.append_message(b"dom-sep", b"nonce-binding v1");
+    transcript.append_u64(b"nonce", nonce);
+}
+
+impl Serialize for NoncedProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for NoncedProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        util::deserialize_bytes(deserializer, "NoncedProof", NoncedProof::from_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nonce_binding_prevents_replay() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+
+        let mut transcript = Transcript::new(b"NonceTest");
+        let (proof, commitments) = NoncedProof::prove(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[1000],
+            &[Scalar::from(7u64)],
+            16,
+            42,
+        )
+        .unwrap();
+        assert_eq!(proof.nonce(), 42);
+
+        let verify = |proof: &NoncedProof, nonce: u64| {
+            let mut transcript = Transcript::new(b"NonceTest");
+            proof.verify(&bp_gens, &pc_gens, &mut transcript, &commitments, 16, nonce)
+        };
+        assert!(verify(&proof, 42).is_ok());
+        assert_eq!(verify(&proof, 43), Err(ProofError::NonceMismatch));
+
+        let decoded: NoncedProof =
+            bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+        assert_eq!(decoded, proof);
+
+        // Rewriting the recorded nonce does not rebind the proof
+        let mut bytes = proof.to_bytes();
+        bytes[0] = 43;
+        let replayed = NoncedProof::from_bytes(&bytes).unwrap();
+        assert_eq!(verify(&replayed, 43), Err(ProofError::VerificationError));
+        assert_eq!(
+            NoncedProof::from_bytes(&bytes[..7]),
+            Err(ProofError::FormatError)
+        );
+    }
+}
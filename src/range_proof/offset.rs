@@ -0,0 +1,178 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Range proofs for values above a public offset.
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use group::Curve;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::RangeProof;
+
+impl RangeProof {
+    /// Create a rangeproof that the value `v` committed with
+    /// `v_blinding` is in the range \\([o, o + 2^n)\\) for the public
+    /// offset \\(o\\), e.g. a protocol-defined floor for balances.
+    ///
+    /// The proof is a rangeproof of \\(v - o\\) for the commitment
+    /// \\(V - o \cdot B\\), with the offset appended to the transcript
+    /// before any proof data.  Returns the proof together with the
+    /// commitment \\(V\\) to `v` itself, or
+    /// [`ProofError::ValueBelowOffset`] if `v` is less than `offset`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_with_offset_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        offset: u64,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, G1Affine), ProofError> {
+        let shifted = v.checked_sub(offset).ok_or(ProofError::ValueBelowOffset)?;
+        append_offset(transcript, offset);
+        let (proof, V) = RangeProof::prove_single_with_rng(
+            bp_gens, pc_gens, transcript, shifted, v_blinding, n, rng,
+        )?;
+        Ok((proof, (V + pc_gens.B * Scalar::from(offset)).to_affine()))
+    }
+
+    /// Create a rangeproof that the value `v` is at least `offset`.
+    /// This is a convenience wrapper around [`RangeProof::prove_with_offset_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn prove_with_offset(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        offset: u64,
+        n: usize,
+    ) -> Result<(RangeProof, G1Affine), ProofError> {
+        RangeProof::prove_with_offset_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            v,
+            v_blinding,
+            offset,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies a rangeproof created by [`RangeProof::prove_with_offset_with_rng`]
+    /// for the value commitment \\(V\\) and the public `offset`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_with_offset_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &G1Affine,
+        offset: u64,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        append_offset(transcript, offset);
+        let shifted = (G1Projective::from(V) - pc_gens.B * Scalar::from(offset)).to_affine();
+        self.verify_single_with_rng(bp_gens, pc_gens, transcript, &shifted, n, rng)
+    }
+
+    /// Verifies a rangeproof created by [`RangeProof::prove_with_offset`].
+    /// This is a convenience wrapper around [`RangeProof::verify_with_offset_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify_with_offset(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &G1Affine,
+        offset: u64,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_with_offset_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            V,
+            offset,
+            n,
+            &mut thread_rng(),
+        )
+    }
+}
+
+/// Appends the public offset to `transcript`.
+fn append_offset(transcript: &mut Transcript, offset: u64) {
+    transcript.append_message(b"dom-sep", b"offset-rangeproof v1");
+    transcript.append_u64(b"offset", offset);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offset_proof_shifts_commitment() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let blinding = Scalar::from(3u64);
+
+        let (proof, V) = RangeProof::prove_with_offset(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"OffsetTest"),
+            1300,
+            &blinding,
+            1100,
+            8,
+        )
+        .unwrap();
+        // The commitment is to the value itself
+        assert_eq!(
+            V,
+            pc_gens.commit(Scalar::from(1300u64), blinding).to_affine()
+        );
+
+        let verify = |offset| {
+            proof.verify_with_offset(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"OffsetTest"),
+                &V,
+                offset,
+                8,
+            )
+        };
+        assert_eq!(verify(1100), Ok(()));
+        assert_eq!(verify(1000), Err(ProofError::VerificationError));
+
+        assert_eq!(
+            RangeProof::prove_with_offset(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"OffsetTest"),
+                1000,
+                &blinding,
+                1100,
+                8,
+            ),
+            Err(ProofError::ValueBelowOffset)
+        );
+    }
+}
@@ -27,11 +27,15 @@ use rand::{CryptoRng, RngCore};
 
 use crate::errors::MPCError;
 use crate::generators::{BulletproofGens, PedersenGens};
+use crate::poly;
 use crate::util;
 
-#[cfg(feature = "std")]
+#[cfg(feature = "thread_rng")]
 use rand::thread_rng;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
+use super::audit::{BlindingRecord, SealedBlindings};
 use super::messages::*;
 
 /// Used to construct a party for the aggregated rangeproof MPC protocol.
@@ -79,7 +83,7 @@ pub struct PartyAwaitingPosition<'a> {
 impl<'a> PartyAwaitingPosition<'a> {
     /// Assigns a position in the aggregated proof to this party,
     /// allowing the party to commit to the bits of their value.
-    #[cfg(feature = "std")]
+    #[cfg(feature = "thread_rng")]
     pub fn assign_position(
         self,
         j: usize,
@@ -89,6 +93,9 @@ impl<'a> PartyAwaitingPosition<'a> {
 
     /// Assigns a position in the aggregated proof to this party,
     /// allowing the party to commit to the bits of their value.
+    ///
+    /// The blinding factors of the bit commitments are drawn from
+    /// `rng`, which may be any `CryptoRng`, e.g. one backed by an HSM.
     pub fn assign_position_with_rng(
         self,
         j: usize,
@@ -98,9 +105,60 @@ impl<'a> PartyAwaitingPosition<'a> {
             return Err(MPCError::InvalidGeneratorsLength);
         }
 
-        let bp_share = self.bp_gens.share(j);
+        let a_blinding = Scalar::random(&mut rng);
+        let s_blinding = Scalar::random(&mut rng);
+        let s_L: Vec<Scalar> = (0..self.n).map(|_| Scalar::random(&mut rng)).collect();
+        let s_R: Vec<Scalar> = (0..self.n).map(|_| Scalar::random(&mut rng)).collect();
 
+        let S = bit_blinding_commitment(
+            self.bp_gens,
+            self.pc_gens,
+            j,
+            self.n,
+            &s_blinding,
+            &s_L,
+            &s_R,
+        );
+
+        Ok(self.commit_bits(j, a_blinding, s_blinding, s_L, s_R, S))
+    }
+
+    /// Assigns a position in the aggregated proof to this party, using
+    /// the given blinding vectors \\(s_L, s_R\\) and their commitment
+    /// \\(S\\), which were computed ahead of time.
+    ///
+    /// The blinding vectors must be fresh, and must have length \\(n\\).
+    pub(super) fn assign_position_with_bit_blindings(
+        self,
+        j: usize,
+        s_blinding: Scalar,
+        s_L: Vec<Scalar>,
+        s_R: Vec<Scalar>,
+        S: G1Projective,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> Result<(PartyAwaitingBitChallenge<'a>, BitCommitment), MPCError> {
+        if self.bp_gens.party_capacity <= j {
+            return Err(MPCError::InvalidGeneratorsLength);
+        }
+        if s_L.len() != self.n || s_R.len() != self.n {
+            return Err(MPCError::InvalidBitsize);
+        }
         let a_blinding = Scalar::random(&mut rng);
+        Ok(self.commit_bits(j, a_blinding, s_blinding, s_L, s_R, S))
+    }
+
+    /// Commits to the bits of the value, and moves to the next state.
+    fn commit_bits(
+        self,
+        j: usize,
+        a_blinding: Scalar,
+        s_blinding: Scalar,
+        s_L: Vec<Scalar>,
+        s_R: Vec<Scalar>,
+        S: G1Projective,
+    ) -> (PartyAwaitingBitChallenge<'a>, BitCommitment) {
+        let bp_share = self.bp_gens.share(j);
+
         // Compute A = <a_L, G> + <a_R, H> + a_blinding * B_blinding
         let mut A = self.pc_gens.B_blinding * a_blinding;
 
@@ -116,30 +174,6 @@ impl<'a> PartyAwaitingPosition<'a> {
             i += 1;
         }
 
-        let s_blinding = Scalar::random(&mut rng);
-        let s_L: Vec<Scalar> = (0..self.n).map(|_| Scalar::random(&mut rng)).collect();
-        let s_R: Vec<Scalar> = (0..self.n).map(|_| Scalar::random(&mut rng)).collect();
-
-        // TODO: replace this dot product with blst_p1s_mult_pippenger once it's supported in blstrs
-
-        // Compute S = <s_L, G> + <s_R, H> + s_blinding * B_blinding
-        let S: G1Projective = iter::once(&s_blinding)
-            .chain(s_L.iter())
-            .chain(s_R.iter())
-            .zip(
-                iter::once(&self.pc_gens.B_blinding)
-                    .chain(bp_share.G(self.n))
-                    .chain(bp_share.H(self.n)),
-            )
-            .map(|(s, P)| P * s)
-            .sum();
-        // let S = RistrettoPoint::multiscalar_mul(
-        //     iter::once(&s_blinding).chain(s_L.iter()).chain(s_R.iter()),
-        //     iter::once(&self.pc_gens.B_blinding)
-        //         .chain(bp_share.G(self.n))
-        //         .chain(bp_share.H(self.n)),
-        // );
-
         // Return next state and all commitments
         let bit_commitment = BitCommitment {
             V_j: self.V,
@@ -157,18 +191,62 @@ impl<'a> PartyAwaitingPosition<'a> {
             s_L,
             s_R,
         };
-        Ok((next_state, bit_commitment))
+        (next_state, bit_commitment)
     }
 }
 
+/// Computes the commitment \\(S\\) to the blinding vectors of the bits
+/// of the value at position `j`.
+pub(super) fn bit_blinding_commitment(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    j: usize,
+    n: usize,
+    s_blinding: &Scalar,
+    s_L: &[Scalar],
+    s_R: &[Scalar],
+) -> G1Projective {
+    let bp_share = bp_gens.share(j);
+
+    // This is not a util::multiscalar_mul, whose Pippenger multiplication
+    // runs in variable time, since s_L and s_R are secret.
+
+    // Compute S = <s_L, G> + <s_R, H> + s_blinding * B_blinding
+    iter::once(s_blinding)
+        .chain(s_L.iter())
+        .chain(s_R.iter())
+        .zip(
+            iter::once(&pc_gens.B_blinding)
+                .chain(bp_share.G(n))
+                .chain(bp_share.H(n)),
+        )
+        .map(|(s, P)| P * s)
+        .sum()
+}
+
 /// Overwrite secrets with null bytes when they go out of scope.
 impl<'a> Drop for PartyAwaitingPosition<'a> {
     fn drop(&mut self) {
+        self.clear_secrets();
+    }
+}
+
+impl<'a> PartyAwaitingPosition<'a> {
+    /// Overwrites the secrets with null bytes.
+    fn clear_secrets(&mut self) {
         self.v.clear();
         self.v_blinding.clear();
     }
 }
 
+/// Overwrites the secrets with null bytes, as dropping the party does.
+#[cfg(feature = "zeroize")]
+impl<'a> Zeroize for PartyAwaitingPosition<'a> {
+    fn zeroize(&mut self) {
+        self.clear_secrets();
+    }
+}
+
 /// A party which has committed to the bits of its value
 /// and is waiting for the aggregated value challenge from the dealer.
 pub struct PartyAwaitingBitChallenge<'a> {
@@ -186,7 +264,7 @@ pub struct PartyAwaitingBitChallenge<'a> {
 impl<'a> PartyAwaitingBitChallenge<'a> {
     /// Receive a [`BitChallenge`] from the dealer and use it to
     /// compute commitments to the party's polynomial coefficients.
-    #[cfg(feature = "std")]
+    #[cfg(feature = "thread_rng")]
     pub fn apply_challenge(
         self,
         vc: &BitChallenge,
@@ -196,6 +274,9 @@ impl<'a> PartyAwaitingBitChallenge<'a> {
 
     /// Receive a [`BitChallenge`] from the dealer and use it to
     /// compute commitments to the party's polynomial coefficients.
+    ///
+    /// The blinding factors of the polynomial commitments are drawn
+    /// from `rng`.
     pub fn apply_challenge_with_rng(
         self,
         vc: &BitChallenge,
@@ -206,8 +287,8 @@ impl<'a> PartyAwaitingBitChallenge<'a> {
         let offset_z = util::scalar_exp_vartime(&vc.z, self.j as u64);
 
         // Calculate t by calculating vectors l0, l1, r0, r1 and multiplying
-        let mut l_poly = util::VecPoly1::zero(n);
-        let mut r_poly = util::VecPoly1::zero(n);
+        let mut l_poly = poly::VecPoly1::zero(n);
+        let mut r_poly = poly::VecPoly1::zero(n);
 
         let offset_zz = vc.z * vc.z * offset_z;
         let mut exp_y = offset_y; // start at y^j
@@ -230,8 +311,7 @@ impl<'a> PartyAwaitingBitChallenge<'a> {
         // Generate x by committing to T_1, T_2 (line 49-54)
         let t_1_blinding = Scalar::random(&mut rng);
         let t_2_blinding = Scalar::random(&mut rng);
-        let T_1 = self.pc_gens.commit(t_poly.1, t_1_blinding);
-        let T_2 = self.pc_gens.commit(t_poly.2, t_2_blinding);
+        let (T_1, T_2) = t_poly.commit(self.pc_gens, t_1_blinding, t_2_blinding);
 
         let poly_commitment = PolyCommitment {
             T_1_j: T_1,
@@ -239,6 +319,8 @@ impl<'a> PartyAwaitingBitChallenge<'a> {
         };
 
         let papc = PartyAwaitingPolyChallenge {
+            j: self.j,
+            s_R: self.s_R.clone(),
             v_blinding: self.v_blinding,
             a_blinding: self.a_blinding,
             s_blinding: self.s_blinding,
@@ -257,6 +339,13 @@ impl<'a> PartyAwaitingBitChallenge<'a> {
 /// Overwrite secrets with null bytes when they go out of scope.
 impl<'a> Drop for PartyAwaitingBitChallenge<'a> {
     fn drop(&mut self) {
+        self.clear_secrets();
+    }
+}
+
+impl<'a> PartyAwaitingBitChallenge<'a> {
+    /// Overwrites the secrets with null bytes.
+    fn clear_secrets(&mut self) {
         self.v.clear();
         self.v_blinding.clear();
         self.a_blinding.clear();
@@ -276,13 +365,23 @@ impl<'a> Drop for PartyAwaitingBitChallenge<'a> {
     }
 }
 
+/// Overwrites the secrets with null bytes, as dropping the party does.
+#[cfg(feature = "zeroize")]
+impl<'a> Zeroize for PartyAwaitingBitChallenge<'a> {
+    fn zeroize(&mut self) {
+        self.clear_secrets();
+    }
+}
+
 /// A party which has committed to their polynomial coefficents
 /// and is waiting for the polynomial challenge from the dealer.
 pub struct PartyAwaitingPolyChallenge {
+    j: usize,
+    s_R: Vec<Scalar>,
     offset_zz: Scalar,
-    l_poly: util::VecPoly1,
-    r_poly: util::VecPoly1,
-    t_poly: util::Poly2,
+    l_poly: poly::VecPoly1,
+    r_poly: poly::VecPoly1,
+    t_poly: poly::Poly2,
     v_blinding: Scalar,
     a_blinding: Scalar,
     s_blinding: Scalar,
@@ -300,7 +399,7 @@ impl PartyAwaitingPolyChallenge {
             return Err(MPCError::MaliciousDealer);
         }
 
-        let t_blinding_poly = util::Poly2(
+        let t_blinding_poly = poly::Poly2(
             self.offset_zz * self.v_blinding,
             self.t_1_blinding,
             self.t_2_blinding,
@@ -320,18 +419,67 @@ impl PartyAwaitingPolyChallenge {
             r_vec,
         })
     }
+
+    /// Receive a [`PolyChallenge`] from the dealer and compute the
+    /// party's proof share, together with a record of the blinding
+    /// factors the party used, sealed under `audit_key`.
+    ///
+    /// The nonce of the sealed record is drawn from `rng`.
+    pub fn apply_challenge_with_audit<T: RngCore + CryptoRng>(
+        self,
+        pc: &PolyChallenge,
+        audit_key: &[u8; 32],
+        rng: &mut T,
+    ) -> Result<(ProofShare, SealedBlindings), MPCError> {
+        let record = BlindingRecord {
+            j: self.j,
+            a_blinding: self.a_blinding,
+            s_blinding: self.s_blinding,
+            s_L: self.l_poly.1.clone(),
+            s_R: self.s_R.clone(),
+            t_1_blinding: self.t_1_blinding,
+            t_2_blinding: self.t_2_blinding,
+        };
+        let share = self.apply_challenge(pc)?;
+        Ok((share, record.seal(audit_key, rng)))
+    }
 }
 
 /// Overwrite secrets with null bytes when they go out of scope.
 impl Drop for PartyAwaitingPolyChallenge {
     fn drop(&mut self) {
+        self.clear_secrets();
+    }
+}
+
+impl PartyAwaitingPolyChallenge {
+    /// Overwrites the secrets with null bytes.
+    fn clear_secrets(&mut self) {
         self.v_blinding.clear();
         self.a_blinding.clear();
         self.s_blinding.clear();
         self.t_1_blinding.clear();
         self.t_2_blinding.clear();
+        for e in self.s_R.iter_mut() {
+            e.clear();
+        }
 
         // Note: polynomials r_poly, l_poly and t_poly
         // are cleared within their own Drop impls.
     }
 }
+
+/// Overwrites the secrets with null bytes, as dropping the party does.
+#[cfg(feature = "zeroize")]
+impl Zeroize for PartyAwaitingPolyChallenge {
+    fn zeroize(&mut self) {
+        self.clear_secrets();
+        util::clear_scalars(&mut self.l_poly.0);
+        util::clear_scalars(&mut self.l_poly.1);
+        util::clear_scalars(&mut self.r_poly.0);
+        util::clear_scalars(&mut self.r_poly.1);
+        self.t_poly.0.clear();
+        self.t_poly.1.clear();
+        self.t_poly.2.clear();
+    }
+}
@@ -0,0 +1,401 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Range proofs split at the inner-product argument.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::iter;
+
+use blstrs::{G1Affine, Scalar};
+use byteorder::{ByteOrder, LittleEndian};
+use group::ff::Field;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use super::dealer::Dealer;
+use super::party::Party;
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::inner_product_proof::{inner_product, InnerProductProof};
+use crate::range_proof::RangeProof;
+use crate::transcript::TranscriptProtocol;
+use crate::util;
+
+/// A [`RangeProof`] whose commitment phase is complete, but whose
+/// inner-product argument has not been computed yet.
+///
+/// The commitment phase touches the values and blinding factors, and
+/// runs where they are kept.  It leaves the vectors
+/// \\(\mathbf{l}(x), \mathbf{r}(x)\\), which are blinded by the
+/// vectors committed to in \\(S\\) and reveal nothing about the
+/// values, in the same way as the proof shares of the aggregation
+/// protocol.  The pending proof can then be serialized and handed to
+/// another thread or machine, which compresses the vectors into the
+/// inner-product argument with [`PendingRangeProof::compress`].
+///
+/// The challenges of the proof are replayed from a transcript with the
+/// same initial state as the prover's, as on verification.
+///
+/// # Encoding
+///
+/// The pending proof is encoded as the bitsize \\(n\\) as a byte, the
+/// number of values \\(m\\) as a 4-byte little-endian integer, the
+/// \\(m\\) compressed value commitments, the compressed points
+/// \\(A, S, T_1, T_2\\), the scalars \\(t_x, \tilde{t}_x, \tilde{e}\\),
+/// and the \\(n \cdot m\\) scalars of each of \\(\mathbf{l}(x)\\) and
+/// \\(\mathbf{r}(x)\\).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingRangeProof {
+    pub(super) n: usize,
+    pub(super) m: usize,
+    pub(super) V: Vec<G1Affine>,
+    pub(super) A: G1Affine,
+    pub(super) S: G1Affine,
+    pub(super) T_1: G1Affine,
+    pub(super) T_2: G1Affine,
+    pub(super) t_x: Scalar,
+    pub(super) t_x_blinding: Scalar,
+    pub(super) e_blinding: Scalar,
+    pub(super) l_vec: Vec<Scalar>,
+    pub(super) r_vec: Vec<Scalar>,
+}
+
+impl PendingRangeProof {
+    /// Runs the commitment phase of a rangeproof for a set of values.
+    ///
+    /// Returns the pending proof, and the commitments to the values.
+    /// The inner-product argument is then computed with
+    /// [`PendingRangeProof::compress`], on a transcript with the same
+    /// initial state as `transcript`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn commit_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(PendingRangeProof, Vec<G1Affine>), ProofError> {
+        if values.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+
+        let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, values.len())?;
+
+        let parties: Vec<_> = values
+            .iter()
+            .zip(blindings.iter())
+            .map(|(&v, &v_blinding)| Party::new(bp_gens, pc_gens, v, v_blinding, n))
+            // Collect the iterator of Results into a Result<Vec>, then unwrap it
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .enumerate()
+            .map(|(j, p)| {
+                p.assign_position_with_rng(j, &mut *rng)
+                    .expect("We already checked the parameters, so this should never happen")
+            })
+            .unzip();
+
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments)?;
+
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge_with_rng(&bit_challenge, &mut *rng))
+            .unzip();
+
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
+
+        let proof_shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge))
+            // Collect the iterator of Results into a Result<Vec>, then unwrap it
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let pending = dealer.defer_shares(&proof_shares)?;
+        let value_commitments = pending.V.clone();
+        Ok((pending, value_commitments))
+    }
+
+    /// Runs the commitment phase of a rangeproof for a set of values.
+    /// This is a convenience wrapper around [`PendingRangeProof::commit_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn commit(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+    ) -> Result<(PendingRangeProof, Vec<G1Affine>), ProofError> {
+        PendingRangeProof::commit_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Computes the inner-product argument, and returns the complete
+    /// [`RangeProof`].
+    ///
+    /// The `transcript` must have the same initial state as the one
+    /// passed to [`PendingRangeProof::commit_with_rng`].  Returns
+    /// [`ProofError::FormatError`] if the vectors do not match the
+    /// committed evaluation \\(t_x\\).
+    pub fn compress(
+        self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+    ) -> Result<RangeProof, ProofError> {
+        let (n, m) = (self.n, self.m);
+        if bp_gens.gens_capacity < n || bp_gens.party_capacity < m {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        if inner_product(&self.l_vec, &self.r_vec) != self.t_x {
+            return Err(ProofError::FormatError);
+        }
+
+        // Replay the challenges of the commitment phase
+        transcript.rangeproof_domain_sep(n as u64, m as u64);
+        for V in self.V.iter() {
+            transcript.append_point(b"V", &V.into());
+        }
+        transcript.append_point(b"A", &self.A.into());
+        transcript.append_point(b"S", &self.S.into());
+        let y = transcript.challenge_scalar(b"y");
+        let _z = transcript.challenge_scalar(b"z");
+        transcript.append_point(b"T_1", &self.T_1.into());
+        transcript.append_point(b"T_2", &self.T_2.into());
+        let _x = transcript.challenge_scalar(b"x");
+        transcript.append_scalar(b"t_x", &self.t_x);
+        transcript.append_scalar(b"t_x_blinding", &self.t_x_blinding);
+        transcript.append_scalar(b"e_blinding", &self.e_blinding);
+        let w = transcript.challenge_scalar(b"w");
+        let Q = pc_gens.B * w;
+
+        let G_factors = alloc::vec![Scalar::one(); n * m];
+        let H_factors: Vec<Scalar> =
+            util::exp_iter(Option::from(y.invert()).ok_or(ProofError::FormatError)?)
+                .take(n * m)
+                .collect();
+
+        let ipp_proof = InnerProductProof::create(
+            transcript,
+            &Q,
+            &G_factors,
+            &H_factors,
+            bp_gens.G(n, m).cloned().collect(),
+            bp_gens.H(n, m).cloned().collect(),
+            self.l_vec,
+            self.r_vec,
+        )?;
+
+        Ok(RangeProof {
+            A: self.A,
+            S: self.S,
+            T_1: self.T_1,
+            T_2: self.T_2,
+            t_x: self.t_x,
+            t_x_blinding: self.t_x_blinding,
+            e_blinding: self.e_blinding,
+            ipp_proof,
+            params: Some((n, m)),
+        })
+    }
+
+    /// Returns the commitments to the values.
+    pub fn value_commitments(&self) -> &[G1Affine] {
+        &self.V
+    }
+
+    /// Serializes the pending proof into a byte vector.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let nm = self.n * self.m;
+        let mut buf = Vec::with_capacity(5 + 48 * (self.m + 4) + 32 * (3 + 2 * nm));
+        let mut m = [0u8; 4];
+        LittleEndian::write_u32(&mut m, self.m as u32);
+
+        buf.push(self.n as u8);
+        buf.extend_from_slice(&m);
+        for P in self
+            .V
+            .iter()
+            .chain(iter::once(&self.A))
+            .chain(iter::once(&self.S))
+            .chain(iter::once(&self.T_1))
+            .chain(iter::once(&self.T_2))
+        {
+            buf.extend_from_slice(&P.to_compressed());
+        }
+        for s in iter::once(&self.t_x)
+            .chain(iter::once(&self.t_x_blinding))
+            .chain(iter::once(&self.e_blinding))
+            .chain(self.l_vec.iter())
+            .chain(self.r_vec.iter())
+        {
+            buf.extend_from_slice(&s.to_bytes_le());
+        }
+        buf
+    }
+
+    /// Deserializes the pending proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a
+    /// `PendingRangeProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<PendingRangeProof, ProofError> {
+        if slice.len() < 5 {
+            return Err(ProofError::FormatError);
+        }
+        let n = slice[0] as usize;
+        if !(n == 8 || n == 16 || n == 32 || n == 64) {
+            return Err(ProofError::InvalidBitsize);
+        }
+        let m = LittleEndian::read_u32(&slice[1..5]) as usize;
+        if !m.is_power_of_two() {
+            return Err(ProofError::InvalidAggregation);
+        }
+        let nm = n.checked_mul(m).ok_or(ProofError::FormatError)?;
+        let len = (m + 4)
+            .checked_mul(48)
+            .zip(nm.checked_mul(64))
+            .and_then(|(points, vecs)| points.checked_add(vecs))
+            .and_then(|len| len.checked_add(5 + 3 * 32));
+        if len != Some(slice.len()) {
+            return Err(ProofError::FormatError);
+        }
+
+        use crate::util::{read32, read48};
+
+        let mut points = slice[5..5 + 48 * (m + 4)].chunks(48).map(|bytes| {
+            Option::from(G1Affine::from_compressed(&read48(bytes))).ok_or(ProofError::FormatError)
+        });
+        let V = (&mut points).take(m).collect::<Result<Vec<_>, _>>()?;
+        let mut point = || points.next().ok_or(ProofError::FormatError)?;
+        let (A, S, T_1, T_2) = (point()?, point()?, point()?, point()?);
+
+        let mut scalars = slice[5 + 48 * (m + 4)..].chunks(32).map(|bytes| {
+            Option::from(Scalar::from_bytes_le(&read32(bytes))).ok_or(ProofError::FormatError)
+        });
+        let mut scalar = || scalars.next().ok_or(ProofError::FormatError)?;
+        let (t_x, t_x_blinding, e_blinding) = (scalar()?, scalar()?, scalar()?);
+        let l_vec = (&mut scalars).take(nm).collect::<Result<Vec<_>, _>>()?;
+        let r_vec = scalars.collect::<Result<Vec<_>, _>>()?;
+
+        Ok(PendingRangeProof {
+            n,
+            m,
+            V,
+            A,
+            S,
+            T_1,
+            T_2,
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            l_vec,
+            r_vec,
+        })
+    }
+}
+
+impl Serialize for PendingRangeProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for PendingRangeProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        util::deserialize_bytes(
+            deserializer,
+            "PendingRangeProof",
+            PendingRangeProof::from_bytes,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_proof_compresses_elsewhere() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 2);
+
+        let mut transcript = Transcript::new(b"PendingTest");
+        let (pending, commitments) = PendingRangeProof::commit(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[3, 60000],
+            &[Scalar::from(5u64), Scalar::from(6u64)],
+            16,
+        )
+        .unwrap();
+        assert_eq!(pending.value_commitments(), &commitments[..]);
+
+        // Hand the pending proof over, and compress it on a fresh transcript
+        let bytes = pending.to_bytes();
+        let decoded: PendingRangeProof =
+            bincode::deserialize(&bincode::serialize(&pending).unwrap()).unwrap();
+        assert_eq!(decoded, pending);
+        let proof = PendingRangeProof::from_bytes(&bytes)
+            .unwrap()
+            .compress(&bp_gens, &pc_gens, &mut Transcript::new(b"PendingTest"))
+            .unwrap();
+
+        let mut transcript = Transcript::new(b"PendingTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 16)
+            .is_ok());
+
+        // Compressing on another transcript yields an invalid proof
+        let proof = pending
+            .clone()
+            .compress(&bp_gens, &pc_gens, &mut Transcript::new(b"Other"))
+            .unwrap();
+        let mut transcript = Transcript::new(b"PendingTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, 16)
+            .is_err());
+
+        assert_eq!(
+            PendingRangeProof::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ProofError::FormatError)
+        );
+        let mut tampered = pending;
+        tampered.l_vec[0] += Scalar::one();
+        assert_eq!(
+            tampered
+                .compress(&bp_gens, &pc_gens, &mut Transcript::new(b"PendingTest"))
+                .unwrap_err(),
+            ProofError::FormatError
+        );
+    }
+}
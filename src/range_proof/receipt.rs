@@ -0,0 +1,354 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+//! Compact receipts from which range proofs can be re-created.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use blstrs::{G1Affine, Scalar};
+use byteorder::{ByteOrder, LittleEndian};
+use clear_on_drop::clear::Clear;
+use digest::Digest;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha3::Sha3_256;
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::commitments::BlindingFactor;
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::RangeProof;
+use crate::util::{self, read32};
+
+/// Domain separation tag for the digest of a proof.
+const DIGEST_DST: &[u8] = b"BLS_BULLETPROOFS_RECEIPT_PROOF_DIGEST";
+
+/// A compact record from which an aggregated [`RangeProof`] can be
+/// re-created bit-for-bit, e.g. for dispute resolution, instead of
+/// archiving the proof itself.
+///
+/// The receipt holds the seed of the proving RNG, the proven values,
+/// the derivation path of their blinding factors and a digest of the
+/// proof.  The blinding factors are derived from the path with
+/// [`ProofReceipt::derive_blinding`] under a blinding key held by the
+/// prover, which is not part of the receipt.  Given the key and a
+/// transcript with the same initial state as when proving,
+/// [`ProofReceipt::regenerate`] re-creates the proof and checks it
+/// against the digest.
+///
+/// The receipt reveals the values and the proving randomness, so it
+/// must be stored as privately as the blinding key.
+///
+/// # Encoding
+///
+/// The receipt is encoded as the 32-byte seed, the 32-byte proof
+/// digest, one byte for the bitsize \\(n\\), a 4-byte little-endian
+/// length of the path followed by the path, and a 4-byte little-endian
+/// number of values followed by each value as a little-endian `u64`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProofReceipt {
+    seed: [u8; 32],
+    digest: [u8; 32],
+    n: usize,
+    path: Vec<u8>,
+    values: Vec<u64>,
+}
+
+impl ProofReceipt {
+    /// Create an aggregated rangeproof for a set of values, with
+    /// blinding factors derived from `blinding_key` and `path`, and a
+    /// receipt from which it can be re-created.
+    ///
+    /// The seed of the proving RNG is drawn from `rng`.  Returns the
+    /// proof, the value commitments and the receipt.
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        blinding_key: &[u8; 32],
+        path: &[u8],
+        values: &[u64],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<G1Affine>, ProofReceipt), ProofError> {
+        let mut receipt = ProofReceipt {
+            seed: [0u8; 32],
+            digest: [0u8; 32],
+            n,
+            path: path.to_vec(),
+            values: values.to_vec(),
+        };
+        rng.fill_bytes(&mut receipt.seed);
+
+        let (proof, value_commitments) =
+            receipt.prove_seeded(bp_gens, pc_gens, transcript, blinding_key)?;
+        receipt.digest = proof_digest(&proof);
+        Ok((proof, value_commitments, receipt))
+    }
+
+    /// Create an aggregated rangeproof for a set of values, and a
+    /// receipt from which it can be re-created.
+    /// This is a convenience wrapper around [`ProofReceipt::prove_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn prove(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        blinding_key: &[u8; 32],
+        path: &[u8],
+        values: &[u64],
+        n: usize,
+    ) -> Result<(RangeProof, Vec<G1Affine>, ProofReceipt), ProofError> {
+        ProofReceipt::prove_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            blinding_key,
+            path,
+            values,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Re-creates the proof and the value commitments from the receipt.
+    ///
+    /// `transcript` must have the same initial state as when proving.
+    /// Returns [`ProofError::ReceiptMismatch`] if the re-created proof
+    /// does not match the digest recorded in the receipt, e.g. because
+    /// of another blinding key, transcript or generators.
+    pub fn regenerate(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        blinding_key: &[u8; 32],
+    ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
+        let (proof, value_commitments) =
+            self.prove_seeded(bp_gens, pc_gens, transcript, blinding_key)?;
+        if proof_digest(&proof) != self.digest {
+            return Err(ProofError::ReceiptMismatch);
+        }
+        Ok((proof, value_commitments))
+    }
+
+    /// Returns `true` if `proof` is the proof recorded in the receipt.
+    pub fn matches(&self, proof: &RangeProof) -> bool {
+        proof_digest(proof) == self.digest
+    }
+
+    /// Derives the blinding factor of the `j`-th value proven under
+    /// `path` from `blinding_key`.
+    pub fn derive_blinding(blinding_key: &[u8; 32], path: &[u8], j: usize) -> Scalar {
+        *BlindingFactor::derive(blinding_key, path, j).as_scalar()
+    }
+
+    /// Returns the proven values.
+    pub fn values(&self) -> &[u64] {
+        &self.values
+    }
+
+    /// Returns the derivation path of the blinding factors.
+    pub fn path(&self) -> &[u8] {
+        &self.path
+    }
+
+    /// Returns the bitsize of the proof.
+    pub fn bitsize(&self) -> usize {
+        self.n
+    }
+
+    /// Returns the digest of the recorded proof.
+    pub fn digest(&self) -> [u8; 32] {
+        self.digest
+    }
+
+    /// Proves the recorded values with an RNG seeded from the receipt.
+    fn prove_seeded(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        blinding_key: &[u8; 32],
+    ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
+        let mut blindings: Vec<Scalar> = (0..self.values.len())
+            .map(|j| ProofReceipt::derive_blinding(blinding_key, &self.path, j))
+            .collect();
+        let result = RangeProof::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &self.values,
+            &blindings,
+            self.n,
+            ChaCha20Rng::from_seed(self.seed),
+        );
+        for blinding in blindings.iter_mut() {
+            blinding.clear();
+        }
+        result
+    }
+
+    /// Serializes the receipt into a byte vector.
+    ///
+    /// See the type-level documentation for the layout.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(73 + self.path.len() + 4 + 8 * self.values.len());
+        buf.extend_from_slice(&self.seed);
+        buf.extend_from_slice(&self.digest);
+        buf.push(self.n as u8);
+        let mut len = [0u8; 4];
+        LittleEndian::write_u32(&mut len, self.path.len() as u32);
+        buf.extend_from_slice(&len);
+        buf.extend_from_slice(&self.path);
+        LittleEndian::write_u32(&mut len, self.values.len() as u32);
+        buf.extend_from_slice(&len);
+        for v in self.values.iter() {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf
+    }
+
+    /// Deserializes the receipt from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a `ProofReceipt`.
+    pub fn from_bytes(slice: &[u8]) -> Result<ProofReceipt, ProofError> {
+        if slice.len() < 69 {
+            return Err(ProofError::FormatError);
+        }
+        let seed = read32(&slice[0..32]);
+        let digest = read32(&slice[32..64]);
+        let n = slice[64] as usize;
+        let path_len = LittleEndian::read_u32(&slice[65..69]) as usize;
+
+        let rest = &slice[69..];
+        if rest.len() < path_len.checked_add(4).ok_or(ProofError::FormatError)? {
+            return Err(ProofError::FormatError);
+        }
+        let path = rest[..path_len].to_vec();
+        let m = LittleEndian::read_u32(&rest[path_len..path_len + 4]) as usize;
+
+        let rest = &rest[path_len + 4..];
+        if m.checked_mul(8) != Some(rest.len()) {
+            return Err(ProofError::FormatError);
+        }
+        let values = rest.chunks(8).map(LittleEndian::read_u64).collect();
+
+        Ok(ProofReceipt {
+            seed,
+            digest,
+            n,
+            path,
+            values,
+        })
+    }
+}
+
+/// Overwrite secrets with null bytes when they go out of scope.
+impl Drop for ProofReceipt {
+    fn drop(&mut self) {
+        self.seed.clear();
+        for v in self.values.iter_mut() {
+            v.clear();
+        }
+    }
+}
+
+/// Returns the digest of the encoding of `proof`.
+fn proof_digest(proof: &RangeProof) -> [u8; 32] {
+    Sha3_256::new()
+        .chain(DIGEST_DST)
+        .chain(proof.to_bytes())
+        .finalize()
+        .into()
+}
+
+impl Serialize for ProofReceipt {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for ProofReceipt {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        util::deserialize_bytes(deserializer, "ProofReceipt", ProofReceipt::from_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn receipt_regenerates_proof() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 2);
+        let blinding_key = [9u8; 32];
+
+        let (proof, commitments, receipt) = ProofReceipt::prove(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"ReceiptTest"),
+            &blinding_key,
+            b"m/0/7",
+            &[300, 4000],
+            16,
+        )
+        .unwrap();
+        assert!(receipt.matches(&proof));
+        assert_eq!(
+            commitments[1],
+            pc_gens
+                .commit(
+                    Scalar::from(4000u64),
+                    ProofReceipt::derive_blinding(&blinding_key, b"m/0/7", 1)
+                )
+                .into()
+        );
+
+        let decoded: ProofReceipt =
+            bincode::deserialize(&bincode::serialize(&receipt).unwrap()).unwrap();
+        assert_eq!(decoded, receipt);
+        let (regenerated, regenerated_commitments) = decoded
+            .regenerate(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"ReceiptTest"),
+                &blinding_key,
+            )
+            .unwrap();
+        assert_eq!(regenerated.to_bytes(), proof.to_bytes());
+        assert_eq!(regenerated_commitments, commitments);
+
+        assert_eq!(
+            receipt.regenerate(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"ReceiptTest"),
+                &[8u8; 32],
+            ),
+            Err(ProofError::ReceiptMismatch)
+        );
+        assert_eq!(
+            ProofReceipt::from_bytes(&receipt.to_bytes()[..80]),
+            Err(ProofError::FormatError)
+        );
+    }
+}
@@ -0,0 +1,222 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Records of verified statements, which a third party can check
+//! against the stored proofs.
+//!
+//! A [`VerificationRecord`] is a digest of everything a verification
+//! depended on: a context label chosen by the verifier, a fingerprint
+//! of the state of its transcript, the bitsize, commitments and proof
+//! of every entry, and the batch weights.  The weights are derived from
+//! the batch contents with [`ProofBatch::derive_weights`], so the whole
+//! verification is deterministic, and
+//! [`VerificationRecord::replay_check`] can repeat it exactly.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use blstrs::G1Affine;
+use digest::Digest;
+use merlin::Transcript;
+use sha3::Sha3_256;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::{ProofBatch, RangeProof};
+use crate::transcript::TranscriptProtocol;
+
+/// A compact, deterministic record of a verified set of range proofs.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct VerificationRecord {
+    /// The context label given by the verifier, e.g. describing the
+    /// block or the transaction the proofs belong to
+    pub context: Vec<u8>,
+    /// The number of proofs verified
+    pub proofs: usize,
+    /// The digest of the verified statement
+    pub digest: [u8; 32],
+}
+
+impl VerificationRecord {
+    /// Re-validates the record against the stored `batch`: checks that
+    /// the record was made for exactly these proofs, commitments and
+    /// bitsizes, under a transcript in the same state as `transcript`,
+    /// and verifies the proofs again.
+    ///
+    /// A record made by [`RangeProof::verify_recorded`] is replayed
+    /// with a batch holding just that proof.
+    ///
+    /// Returns [`ProofError::RecordMismatch`] if the record does not
+    /// match, and [`ProofError::VerificationError`] if the proofs do
+    /// not verify.
+    pub fn replay_check(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        batch: &ProofBatch,
+    ) -> Result<(), ProofError> {
+        let weights = batch.derive_weights(transcript);
+        if self.proofs != batch.len()
+            || self.digest != record_digest(&self.context, transcript, batch, &weights)
+        {
+            return Err(ProofError::RecordMismatch);
+        }
+        batch.verify_with_weights(bp_gens, pc_gens, transcript, &weights)
+    }
+}
+
+impl ProofBatch {
+    /// Verifies all proofs in the batch as
+    /// [`ProofBatch::verify_deterministic`] does, and returns a record
+    /// of the verified statement under the label `context`.
+    ///
+    /// The record is only returned if the proofs verify.
+    pub fn verify_recorded(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        context: &[u8],
+    ) -> Result<VerificationRecord, ProofError> {
+        let weights = self.derive_weights(transcript);
+        self.verify_with_weights(bp_gens, pc_gens, transcript, &weights)?;
+        Ok(VerificationRecord {
+            context: context.to_vec(),
+            proofs: self.len(),
+            digest: record_digest(context, transcript, self, &weights),
+        })
+    }
+}
+
+impl RangeProof {
+    /// Verifies the proof for the given value commitments, and returns
+    /// a record of the verified statement under the label `context`.
+    ///
+    /// The proof is verified as a batch of one by
+    /// [`ProofBatch::verify_recorded`], with a weight derived from the
+    /// proof rather than drawn from an RNG.  Unlike
+    /// [`RangeProof::verify_multiple`], `transcript` is not modified.
+    pub fn verify_recorded(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        context: &[u8],
+        value_commitments: &[G1Affine],
+        n: usize,
+    ) -> Result<VerificationRecord, ProofError> {
+        let mut batch = ProofBatch::new();
+        batch.push(self.clone(), value_commitments.to_vec(), n)?;
+        batch.verify_recorded(bp_gens, pc_gens, transcript, context)
+    }
+}
+
+/// Computes the digest of a record of `batch` verified with `weights`.
+fn record_digest(
+    context: &[u8],
+    transcript: &Transcript,
+    batch: &ProofBatch,
+    weights: &[(blstrs::Scalar, blstrs::Scalar)],
+) -> [u8; 32] {
+    // The state of the verifier's transcript cannot be read back, so it
+    // is fingerprinted with a challenge of a copy.
+    let mut state = [0u8; 32];
+    transcript
+        .clone()
+        .challenge_bytes(b"record-transcript", &mut state);
+
+    let mut record = Transcript::new(b"VerificationRecord");
+    record.append_message(b"dom-sep", b"verification-record v1");
+    record.append_message(b"context", context);
+    record.append_message(b"transcript", &state);
+    record.append_u64(b"k", batch.len() as u64);
+    for ((proof, value_commitments, n), (weight, c)) in batch.iter().zip(weights.iter()) {
+        record.append_u64(b"n", n as u64);
+        record.append_u64(b"m", value_commitments.len() as u64);
+        for V in value_commitments {
+            record.append_message(b"V", &V.to_compressed());
+        }
+        record.append_message(b"proof", &Sha3_256::digest(&proof.to_bytes()));
+        record.append_scalar(b"weight", weight);
+        record.append_scalar(b"c", c);
+    }
+
+    let mut digest = [0u8; 32];
+    record.challenge_bytes(b"record-digest", &mut digest);
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+    use blstrs::Scalar;
+
+    #[test]
+    fn record_replays_against_stored_proofs() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let mut batch = ProofBatch::new();
+        for v in 1..=3u64 {
+            let (proof, V) = RangeProof::prove_single(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"RecordTest"),
+                v,
+                &Scalar::from(v),
+                16,
+            )
+            .unwrap();
+            batch.push(proof, vec![V], 16).unwrap();
+        }
+
+        let transcript = Transcript::new(b"RecordTest");
+        let record = batch
+            .verify_recorded(&bp_gens, &pc_gens, &transcript, b"block 7")
+            .unwrap();
+        assert_eq!(record.proofs, 3);
+        assert_eq!(
+            record,
+            batch
+                .verify_recorded(&bp_gens, &pc_gens, &transcript, b"block 7")
+                .unwrap()
+        );
+        assert!(record
+            .replay_check(&bp_gens, &pc_gens, &transcript, &batch)
+            .is_ok());
+
+        // Another context, transcript or set of proofs does not match
+        let mut other = record.clone();
+        other.context = b"block 8".to_vec();
+        assert_eq!(
+            other.replay_check(&bp_gens, &pc_gens, &transcript, &batch),
+            Err(ProofError::RecordMismatch)
+        );
+        assert_eq!(
+            record.replay_check(&bp_gens, &pc_gens, &Transcript::new(b"OtherTest"), &batch),
+            Err(ProofError::RecordMismatch)
+        );
+        let (proof, V, n) = batch.iter().next().unwrap();
+        let mut single = ProofBatch::new();
+        single.push(proof.clone(), V.to_vec(), n).unwrap();
+        assert_eq!(
+            record.replay_check(&bp_gens, &pc_gens, &transcript, &single),
+            Err(ProofError::RecordMismatch)
+        );
+
+        let record = proof
+            .verify_recorded(&bp_gens, &pc_gens, &transcript, b"output 0", V, n)
+            .unwrap();
+        assert!(record
+            .replay_check(&bp_gens, &pc_gens, &transcript, &single)
+            .is_ok());
+    }
+}
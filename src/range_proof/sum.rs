@@ -0,0 +1,199 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Range proofs over the sum of a set of commitments.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use group::ff::Field;
+use group::{Curve, Group};
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::errors::{AmountError, ProofError};
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::RangeProof;
+use crate::transcript::TranscriptProtocol;
+
+impl RangeProof {
+    /// Commits to every `(value, blinding)` opening, and proves that
+    /// the sum of the values is an `n`-bit value with a single range
+    /// proof on the sum of the commitments.
+    ///
+    /// The individual commitments are appended to `transcript` before
+    /// the proof, so the proof only verifies against the same set of
+    /// commitments, in the same order.  Returns the proof together with
+    /// the individual commitments.
+    ///
+    /// Returns [`ProofError::FormatError`] if `openings` is empty, and
+    /// an [`AmountError`] if the sum does not fit in `n` bits.
+    pub fn prove_sum_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        openings: &[(u64, Scalar)],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
+        if openings.is_empty() {
+            return Err(ProofError::FormatError);
+        }
+
+        let mut sum = 0u64;
+        let mut sum_blinding = Scalar::zero();
+        for (value, blinding) in openings.iter() {
+            sum = sum.checked_add(*value).ok_or(AmountError::SumOverflow)?;
+            sum_blinding += blinding;
+        }
+        if n < 64 && sum >> n != 0 {
+            return Err(AmountError::ExceedsBitsize {
+                units: sum,
+                bitsize: n,
+            }
+            .into());
+        }
+
+        let commitments: Vec<G1Affine> = openings
+            .iter()
+            .map(|(value, blinding)| pc_gens.commit(Scalar::from(*value), *blinding).to_affine())
+            .collect();
+        append_commitments(transcript, &commitments);
+
+        let (proof, _) = RangeProof::prove_single_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            sum,
+            &sum_blinding,
+            n,
+            rng,
+        )?;
+        Ok((proof, commitments))
+    }
+
+    /// Proves that the sum of the committed values is an `n`-bit value.
+    /// This is a convenience wrapper around [`RangeProof::prove_sum_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn prove_sum(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        openings: &[(u64, Scalar)],
+        n: usize,
+    ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
+        RangeProof::prove_sum_with_rng(bp_gens, pc_gens, transcript, openings, n, &mut thread_rng())
+    }
+
+    /// Verifies a proof created by [`RangeProof::prove_sum_with_rng`],
+    /// recomputing the sum from the individual `commitments`.
+    pub fn verify_sum_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        commitments: &[G1Affine],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        if commitments.is_empty() {
+            return Err(ProofError::FormatError);
+        }
+        append_commitments(transcript, commitments);
+
+        let sum = commitments
+            .iter()
+            .fold(G1Projective::identity(), |acc, V| acc + V);
+        self.verify_single_with_rng(bp_gens, pc_gens, transcript, &sum.to_affine(), n, rng)
+    }
+
+    /// Verifies a proof created by [`RangeProof::prove_sum`].
+    /// This is a convenience wrapper around [`RangeProof::verify_sum_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify_sum(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        commitments: &[G1Affine],
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_sum_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            commitments,
+            n,
+            &mut thread_rng(),
+        )
+    }
+}
+
+fn append_commitments(transcript: &mut Transcript, commitments: &[G1Affine]) {
+    transcript.append_message(b"dom-sep", b"rangeproof-sum v1");
+    transcript.append_u64(b"k", commitments.len() as u64);
+    for V in commitments.iter() {
+        transcript.append_point(b"V", &G1Projective::from(V));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proves_sum_of_commitments() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut rng = rand::thread_rng();
+
+        let openings: Vec<(u64, Scalar)> = [1u64 << 30, 1 << 30, 12345]
+            .iter()
+            .map(|v| (*v, Scalar::random(&mut rng)))
+            .collect();
+        let mut transcript = Transcript::new(b"SumTest");
+        let (proof, commitments) =
+            RangeProof::prove_sum(&bp_gens, &pc_gens, &mut transcript, &openings, 32).unwrap();
+        assert_eq!(commitments.len(), 3);
+
+        let verify = |commitments: &[G1Affine]| {
+            let mut transcript = Transcript::new(b"SumTest");
+            proof.verify_sum(&bp_gens, &pc_gens, &mut transcript, commitments, 32)
+        };
+        assert!(verify(&commitments).is_ok());
+
+        // The proof is bound to the individual commitments
+        let mut reordered = commitments.clone();
+        reordered.swap(0, 2);
+        assert!(verify(&reordered).is_err());
+        assert!(verify(&commitments[..2]).is_err());
+        assert_eq!(verify(&[]), Err(ProofError::FormatError));
+
+        let mut transcript = Transcript::new(b"SumTest");
+        let openings = [(1u64 << 31, Scalar::one()), (1 << 31, Scalar::one())];
+        assert_eq!(
+            RangeProof::prove_sum(&bp_gens, &pc_gens, &mut transcript, &openings, 32).unwrap_err(),
+            ProofError::InvalidAmount(AmountError::ExceedsBitsize {
+                units: 1 << 32,
+                bitsize: 32
+            })
+        );
+        let openings = [(u64::MAX, Scalar::one()), (1, Scalar::one())];
+        assert_eq!(
+            RangeProof::prove_sum(&bp_gens, &pc_gens, &mut transcript, &openings, 64).unwrap_err(),
+            ProofError::InvalidAmount(AmountError::SumOverflow)
+        );
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Adapters for RNGs implementing other versions of `rand_core`.
+//!
+//! The `_with_rng` functions of this crate take RNGs implementing the
+//! `rand_core` 0.6 traits, as re-exported by `rand` 0.8.  With the
+//! `rand_core_09` feature, [`RngBridge`] adapts an RNG implementing the
+//! `rand_core` 0.9 traits, as used by `rand` 0.9, so that applications
+//! on the newer version can pass their RNGs without their own shims.
+
+use rand::{CryptoRng, RngCore};
+
+/// Wraps an RNG implementing the `rand_core` 0.9 traits, so that it can
+/// be passed to the `_with_rng` functions of this crate.
+///
+/// The bridge also works with a mutable reference to the RNG:
+///
+/// ```ignore
+/// // `rng` implements the rand_core 0.9 traits
+/// RangeProof::prove_single_with_rng(
+///     &bp_gens, &pc_gens, &mut transcript, v, &v_blinding, 32,
+///     &mut RngBridge::new(&mut rng),
+/// )?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct RngBridge<R>(R);
+
+impl<R> RngBridge<R> {
+    /// Wraps `rng`.
+    pub fn new(rng: R) -> Self {
+        RngBridge(rng)
+    }
+
+    /// Returns the wrapped RNG.
+    pub fn into_inner(self) -> R {
+        self.0
+    }
+}
+
+impl<R: rand_core_09::RngCore> RngCore for RngBridge<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl<R: rand_core_09::CryptoRng> CryptoRng for RngBridge<R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::Scalar;
+    use merlin::Transcript;
+
+    use crate::generators::{BulletproofGens, PedersenGens};
+    use crate::range_proof::RangeProof;
+
+    /// A `rand_core` 0.9 RNG from a `rand_core` 0.6 one.
+    struct NewerRng(rand_chacha::ChaCha20Rng);
+
+    impl rand_core_09::RngCore for NewerRng {
+        fn next_u32(&mut self) -> u32 {
+            self.0.next_u32()
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0.next_u64()
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            self.0.fill_bytes(dest)
+        }
+    }
+
+    impl rand_core_09::CryptoRng for NewerRng {}
+
+    #[test]
+    fn bridged_rng_proves() {
+        use rand::SeedableRng;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let blinding = Scalar::from(5u64);
+
+        let mut newer = NewerRng(rand_chacha::ChaCha20Rng::from_seed([3u8; 32]));
+        let (bridged, V) = RangeProof::prove_single_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"BridgeTest"),
+            77,
+            &blinding,
+            16,
+            &mut RngBridge::new(&mut newer),
+        )
+        .unwrap();
+
+        // The bridge passes the RNG output through unchanged
+        let (direct, _) = RangeProof::prove_single_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"BridgeTest"),
+            77,
+            &blinding,
+            16,
+            &mut rand_chacha::ChaCha20Rng::from_seed([3u8; 32]),
+        )
+        .unwrap();
+        assert_eq!(bridged, direct);
+        assert!(bridged
+            .verify_single(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"BridgeTest"),
+                &V,
+                16
+            )
+            .is_ok());
+    }
+}
@@ -0,0 +1,296 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Sessions of proofs of different types over one transcript.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use blstrs::G1Affine;
+#[cfg(feature = "prover")]
+use blstrs::Scalar;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::compose::{CryptoRngCore, TranscriptProver, TranscriptVerifier};
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::RangeProof;
+
+/// A sequence of proofs, possibly of different types, produced or
+/// verified over one continuous transcript.
+///
+/// Each proof in the session is preceded in the transcript by its
+/// index and its type, so the proofs only verify in the same order, in
+/// a session started with the same label.  The prover and the verifier
+/// each run a session, calling the `prove_*` and `verify_*` methods
+/// respectively for the same sequence of statements.
+///
+/// [`ProofSession::finish`] returns a digest of the whole session,
+/// which is equal for the prover and the verifier of a valid session,
+/// and can be used to bind the session to further protocol messages.
+pub struct ProofSession {
+    transcript: Transcript,
+    steps: u64,
+}
+
+impl ProofSession {
+    /// Starts a session with the given transcript `label`.
+    pub fn new(label: &'static [u8]) -> ProofSession {
+        let mut transcript = Transcript::new(label);
+        transcript.append_message(b"dom-sep", b"proof-session v1");
+        ProofSession {
+            transcript,
+            steps: 0,
+        }
+    }
+
+    /// Returns the number of proofs produced or verified in the session.
+    pub fn len(&self) -> usize {
+        self.steps as usize
+    }
+
+    /// Returns true if no proof was produced or verified in the session.
+    pub fn is_empty(&self) -> bool {
+        self.steps == 0
+    }
+
+    /// Appends the index and the type of the next proof to the
+    /// transcript, and returns the transcript for the proof.
+    pub(crate) fn step(&mut self, kind: &'static [u8]) -> &mut Transcript {
+        self.transcript.append_u64(b"session-step", self.steps);
+        self.transcript.append_message(b"session-kind", kind);
+        self.steps += 1;
+        &mut self.transcript
+    }
+
+    /// Creates an aggregated rangeproof for a set of values as the next
+    /// proof of the session.
+    ///
+    /// Otherwise, this is [`RangeProof::prove_multiple_with_rng`].
+    #[cfg(feature = "prover")]
+    pub fn prove_range_with_rng<T: RngCore + CryptoRng>(
+        &mut self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
+        let transcript = self.step(b"range");
+        RangeProof::prove_multiple_with_rng(bp_gens, pc_gens, transcript, values, blindings, n, rng)
+    }
+
+    /// Creates an aggregated rangeproof for a set of values as the next
+    /// proof of the session.
+    /// This is a convenience wrapper around [`ProofSession::prove_range_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(all(feature = "thread_rng", feature = "prover"))]
+    pub fn prove_range(
+        &mut self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+    ) -> Result<(RangeProof, Vec<G1Affine>), ProofError> {
+        self.prove_range_with_rng(bp_gens, pc_gens, values, blindings, n, &mut thread_rng())
+    }
+
+    /// Verifies an aggregated rangeproof as the next proof of the session.
+    ///
+    /// Otherwise, this is [`RangeProof::verify_multiple_with_rng`].
+    pub fn verify_range_with_rng<T: RngCore + CryptoRng>(
+        &mut self,
+        proof: &RangeProof,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        value_commitments: &[G1Affine],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let transcript = self.step(b"range");
+        proof.verify_multiple_with_rng(bp_gens, pc_gens, transcript, value_commitments, n, rng)
+    }
+
+    /// Verifies an aggregated rangeproof as the next proof of the session.
+    /// This is a convenience wrapper around [`ProofSession::verify_range_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify_range(
+        &mut self,
+        proof: &RangeProof,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        value_commitments: &[G1Affine],
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_range_with_rng(
+            proof,
+            bp_gens,
+            pc_gens,
+            value_commitments,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Proves the statement of `prover` as the next proof of the
+    /// session, and returns the serialized proof.
+    pub fn prove_with_rng(
+        &mut self,
+        prover: &dyn TranscriptProver,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Result<Vec<u8>, ProofError> {
+        let transcript = self.step(prover.label());
+        prover.prove(transcript, rng)
+    }
+
+    /// Proves the statement of `prover` as the next proof of the
+    /// session, and returns the serialized proof.
+    /// This is a convenience wrapper around [`ProofSession::prove_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn prove(&mut self, prover: &dyn TranscriptProver) -> Result<Vec<u8>, ProofError> {
+        self.prove_with_rng(prover, &mut thread_rng())
+    }
+
+    /// Verifies the proof of `verifier` as the next proof of the session.
+    pub fn verify_with_rng(
+        &mut self,
+        verifier: &dyn TranscriptVerifier,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Result<(), ProofError> {
+        let transcript = self.step(verifier.label());
+        verifier.verify(transcript, rng)
+    }
+
+    /// Verifies the proof of `verifier` as the next proof of the session.
+    /// This is a convenience wrapper around [`ProofSession::verify_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify(&mut self, verifier: &dyn TranscriptVerifier) -> Result<(), ProofError> {
+        self.verify_with_rng(verifier, &mut thread_rng())
+    }
+
+    /// Ends the session, and returns its digest.
+    pub fn finish(mut self) -> [u8; 32] {
+        self.transcript.append_u64(b"session-steps", self.steps);
+        let mut digest = [0u8; 32];
+        self.transcript
+            .challenge_bytes(b"session-digest", &mut digest);
+        digest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_binds_proof_order() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blindings = [Scalar::from(11u64)];
+
+        let mut prover = ProofSession::new(b"SessionTest");
+        let (first, first_commitments) = prover
+            .prove_range(&bp_gens, &pc_gens, &[7], &blindings, 8)
+            .unwrap();
+        let (second, second_commitments) = prover
+            .prove_range(&bp_gens, &pc_gens, &[1 << 30], &blindings, 32)
+            .unwrap();
+        assert_eq!(prover.len(), 2);
+        let digest = prover.finish();
+
+        let mut verifier = ProofSession::new(b"SessionTest");
+        assert!(verifier.is_empty());
+        verifier
+            .verify_range(&first, &bp_gens, &pc_gens, &first_commitments, 8)
+            .unwrap();
+        verifier
+            .verify_range(&second, &bp_gens, &pc_gens, &second_commitments, 32)
+            .unwrap();
+        assert_eq!(verifier.finish(), digest);
+
+        // The second proof does not verify out of order
+        let mut verifier = ProofSession::new(b"SessionTest");
+        assert_eq!(
+            verifier.verify_range(&second, &bp_gens, &pc_gens, &second_commitments, 32),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn session_composes_trait_objects() {
+        use crate::commitments::Commitment;
+        use crate::compose::*;
+        use crate::opening::OpeningProof;
+        use group::Curve;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 1);
+        let blinding = Scalar::from(21u64);
+        let commitment = Commitment::commit_to_bytes(&pc_gens, b"memo", blinding);
+        let values = [300u64];
+        let blindings = [blinding];
+
+        let provers: Vec<Box<dyn TranscriptProver>> = vec![
+            Box::new(RangeProver {
+                bp_gens: &bp_gens,
+                pc_gens: &pc_gens,
+                values: &values,
+                blindings: &blindings,
+                n: 16,
+            }),
+            Box::new(OpeningProver {
+                pc_gens: &pc_gens,
+                msg: b"memo",
+                blinding,
+            }),
+        ];
+        let mut prover = ProofSession::new(b"ComposeTest");
+        let proofs: Vec<Vec<u8>> = provers
+            .iter()
+            .map(|p| prover.prove(p.as_ref()).unwrap())
+            .collect();
+
+        let range = RangeProof::from_bytes(&proofs[0]).unwrap();
+        let opening = OpeningProof::from_bytes(&proofs[1]).unwrap();
+        let V = [pc_gens.commit(Scalar::from(300u64), blinding).to_affine()];
+        let verifiers: Vec<Box<dyn TranscriptVerifier>> = vec![
+            Box::new(RangeVerifier {
+                bp_gens: &bp_gens,
+                pc_gens: &pc_gens,
+                proof: &range,
+                commitments: &V,
+                n: 16,
+            }),
+            Box::new(OpeningVerifier {
+                pc_gens: &pc_gens,
+                proof: &opening,
+                commitment: &commitment,
+                msg: b"memo",
+            }),
+        ];
+        let mut verifier = ProofSession::new(b"ComposeTest");
+        for v in verifiers.iter() {
+            verifier.verify(v.as_ref()).unwrap();
+        }
+        assert_eq!(verifier.finish(), prover.finish());
+
+        // The proofs do not verify in another order
+        let mut verifier = ProofSession::new(b"ComposeTest");
+        assert!(verifier.verify(verifiers[1].as_ref()).is_err());
+    }
+}
@@ -0,0 +1,890 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Provisions-style proofs of liabilities.
+//!
+//! An exchange commits to the balance of every customer and publishes
+//! the commitments, each with a range proof, under a Merkle tree keyed
+//! by customer id.  The commitments sum homomorphically to a commitment
+//! to the total liabilities, which carries its own range proof, so
+//! that the total can be compared against the exchange's reserves
+//! without revealing any balance.
+//!
+//! Each customer receives the blinding factor of their commitment, and
+//! checks that their commitment opens to their balance and is included
+//! under the published root with an [`InclusionProof`].
+//!
+//! A proof of [`Reserves`] completes the picture: it shows ownership
+//! of a set of addresses with public balances, and that their total
+//! covers the committed liabilities.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use blstrs::{G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+use byteorder::{ByteOrder, LittleEndian};
+#[cfg(feature = "prover")]
+use group::ff::Field;
+#[cfg(feature = "prover")]
+use group::prime::PrimeCurveAffine;
+use group::{Curve, Group};
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+
+use crate::errors::{AmountError, ProofError};
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::merkle;
+use crate::range_proof::{ProofBatch, RangeProof};
+use crate::util::{self, read32, read48, read96};
+
+/// Domain separation tag for hashing the reserves session message to \\(\mathbb G\_2\\).
+const RESERVES_DST: &[u8] = b"BLS_BULLETPROOFS_SOLVENCY_RESERVES_BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+/// The length of an encoded address of [`Reserves`]: its public key,
+/// balance and signature.
+const ADDRESS_LEN: usize = 48 + 8 + 96;
+
+/// Domain tag of the Merkle tree of customer ids and commitments.
+const MERKLE_TAG: &[u8] = b"Solvency";
+
+/// The bitsize of the range proofs on the total liabilities and on the
+/// surplus of reserves.
+pub const TOTAL_BITSIZE: usize = 64;
+
+/// A published proof of liabilities.
+///
+/// # Encoding
+///
+/// The liabilities are encoded as a 4-byte little-endian number of
+/// customers, followed by each customer id as a 4-byte little-endian
+/// length and the id, the 4-byte little-endian length of the proof of
+/// the total and the proof as encoded by [`RangeProof::to_bytes`], and
+/// the customers' proofs as encoded by [`ProofBatch::to_bytes`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Liabilities {
+    /// The customer ids, in the order of the proofs
+    ids: Vec<Vec<u8>>,
+    /// The range proof and commitment for every customer
+    proofs: ProofBatch,
+    /// The range proof on the sum of the commitments
+    total_proof: RangeProof,
+}
+
+/// A proof that a customer's commitment is included under the root of
+/// a [`Liabilities`] Merkle tree.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    /// The index of the customer's leaf
+    pub index: usize,
+    /// The number of leaves in the tree
+    pub num_leaves: usize,
+    /// The sibling hashes on the path from the leaf to the root
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl Liabilities {
+    /// Commits to every customer's `(id, balance, blinding)` and proves
+    /// that each balance is an `n`-bit value and that their total is a
+    /// [`TOTAL_BITSIZE`]-bit value.
+    ///
+    /// All proofs are created on copies of `transcript`, the total
+    /// after appending a domain separator.  Returns the liabilities
+    /// together with the blinding factor of the total, which the
+    /// exchange keeps to relate the total to its reserves.
+    #[cfg(feature = "prover")]
+    pub fn prove_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        customers: &[(Vec<u8>, u64, Scalar)],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(Liabilities, Scalar), ProofError> {
+        if has_duplicates(customers.iter().map(|(id, _, _)| id)) {
+            return Err(ProofError::FormatError);
+        }
+
+        let mut total = 0u64;
+        let mut total_blinding = Scalar::zero();
+        let mut proofs = ProofBatch::new();
+        for (_, balance, blinding) in customers.iter() {
+            if n < 64 && *balance >> n != 0 {
+                return Err(AmountError::ExceedsBitsize {
+                    units: *balance,
+                    bitsize: n,
+                }
+                .into());
+            }
+            total = total
+                .checked_add(*balance)
+                .ok_or(AmountError::SumOverflow)?;
+            total_blinding += blinding;
+
+            let (proof, commitment) = RangeProof::prove_single_with_rng(
+                bp_gens,
+                pc_gens,
+                &mut transcript.clone(),
+                *balance,
+                blinding,
+                n,
+                rng,
+            )?;
+            proofs.push(proof, alloc::vec![commitment], n)?;
+        }
+
+        let (total_proof, _) = RangeProof::prove_single_with_rng(
+            bp_gens,
+            pc_gens,
+            &mut total_transcript(transcript),
+            total,
+            &total_blinding,
+            TOTAL_BITSIZE,
+            rng,
+        )?;
+
+        let liabilities = Liabilities {
+            ids: customers.iter().map(|(id, _, _)| id.clone()).collect(),
+            proofs,
+            total_proof,
+        };
+        Ok((liabilities, total_blinding))
+    }
+
+    /// Commits to every customer's balance and proves the liabilities.
+    /// This is a convenience wrapper around [`Liabilities::prove_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(all(feature = "thread_rng", feature = "prover"))]
+    pub fn prove(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        customers: &[(Vec<u8>, u64, Scalar)],
+        n: usize,
+    ) -> Result<(Liabilities, Scalar), ProofError> {
+        Liabilities::prove_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            customers,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies the range proofs of all customers, which must be
+    /// `n`-bit proofs of one commitment each, with one multiscalar
+    /// multiplication, then the range proof of the total.
+    pub fn verify_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        if self.ids.len() != self.proofs.len() || has_duplicates(self.ids.iter()) {
+            return Err(ProofError::FormatError);
+        }
+        if self
+            .proofs
+            .iter()
+            .any(|(_, commitments, bitsize)| commitments.len() != 1 || bitsize != n)
+        {
+            return Err(ProofError::ParameterMismatch);
+        }
+
+        self.proofs
+            .verify_with_rng(bp_gens, pc_gens, transcript, rng)?;
+        self.total_proof.verify_single_with_rng(
+            bp_gens,
+            pc_gens,
+            &mut total_transcript(transcript),
+            &self.total_commitment(),
+            TOTAL_BITSIZE,
+            rng,
+        )
+    }
+
+    /// Verifies the proofs of all customers and of the total.
+    /// This is a convenience wrapper around [`Liabilities::verify_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_with_rng(bp_gens, pc_gens, transcript, n, &mut thread_rng())
+    }
+
+    /// Returns an iterator over the customer ids and their commitments.
+    pub fn customers(&self) -> impl Iterator<Item = (&[u8], &G1Affine)> {
+        self.ids
+            .iter()
+            .zip(self.proofs.iter())
+            .map(|(id, (_, commitments, _))| (&id[..], &commitments[0]))
+    }
+
+    /// Returns the commitment to the total liabilities, the sum of the
+    /// customers' commitments.
+    pub fn total_commitment(&self) -> G1Affine {
+        self.customers()
+            .map(|(_, V)| G1Projective::from(V))
+            .fold(G1Projective::identity(), |acc, V| acc + V)
+            .to_affine()
+    }
+
+    /// Returns the root of the Merkle tree of customer ids and commitments.
+    pub fn root(&self) -> [u8; 32] {
+        merkle::root(MERKLE_TAG, self.leaves())
+    }
+
+    /// Returns the inclusion proof for the customer with the given `id`,
+    /// or `None` if there is no such customer.
+    pub fn inclusion_proof(&self, id: &[u8]) -> Option<InclusionProof> {
+        let index = self.ids.iter().position(|other| &other[..] == id)?;
+        Some(InclusionProof {
+            index,
+            num_leaves: self.ids.len(),
+            siblings: merkle::path(MERKLE_TAG, self.leaves(), index),
+        })
+    }
+
+    /// Returns the leaves of the Merkle tree of customer ids and commitments.
+    fn leaves(&self) -> Vec<[u8; 32]> {
+        self.customers().map(|(id, V)| leaf_hash(id, V)).collect()
+    }
+
+    /// Serializes the liabilities into a byte vector.
+    ///
+    /// See the type-level documentation for the layout.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut len = [0u8; 4];
+
+        LittleEndian::write_u32(&mut len, self.ids.len() as u32);
+        buf.extend_from_slice(&len);
+        for id in self.ids.iter() {
+            LittleEndian::write_u32(&mut len, id.len() as u32);
+            buf.extend_from_slice(&len);
+            buf.extend_from_slice(id);
+        }
+        let total_proof = self.total_proof.to_bytes();
+        LittleEndian::write_u32(&mut len, total_proof.len() as u32);
+        buf.extend_from_slice(&len);
+        buf.extend_from_slice(&total_proof);
+        buf.extend_from_slice(&self.proofs.to_bytes());
+        buf
+    }
+
+    /// Deserializes the liabilities from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into
+    /// `Liabilities`, or if the number of ids does not match the
+    /// number of proofs.
+    pub fn from_bytes(slice: &[u8]) -> Result<Liabilities, ProofError> {
+        let mut slice = slice;
+        let num_ids = LittleEndian::read_u32(take(&mut slice, 4)?) as usize;
+
+        // Each id takes at least 4 bytes, which bounds the allocation below.
+        if num_ids > slice.len() / 4 {
+            return Err(ProofError::FormatError);
+        }
+        let ids = (0..num_ids)
+            .map(|_| {
+                let len = LittleEndian::read_u32(take(&mut slice, 4)?) as usize;
+                Ok(take(&mut slice, len)?.to_vec())
+            })
+            .collect::<Result<Vec<_>, ProofError>>()?;
+        let total_len = LittleEndian::read_u32(take(&mut slice, 4)?) as usize;
+        let total_proof = RangeProof::from_bytes(take(&mut slice, total_len)?)?;
+        let proofs = ProofBatch::from_bytes(slice)?;
+
+        if ids.len() != proofs.len() {
+            return Err(ProofError::FormatError);
+        }
+        Ok(Liabilities {
+            ids,
+            proofs,
+            total_proof,
+        })
+    }
+}
+
+impl InclusionProof {
+    /// Verifies that the customer `id` with `commitment` is included
+    /// under `root`.
+    pub fn verify(
+        &self,
+        root: &[u8; 32],
+        id: &[u8],
+        commitment: &G1Affine,
+    ) -> Result<(), ProofError> {
+        merkle::verify_path(
+            MERKLE_TAG,
+            leaf_hash(id, commitment),
+            self.index,
+            self.num_leaves,
+            &self.siblings,
+            root,
+        )
+    }
+}
+
+/// A published proof of reserves against a [`Liabilities`].
+///
+/// The exchange lists the BLS public keys and public balances of its
+/// addresses, signs a message binding them to the liabilities with
+/// every key, and publishes a blinding factor \\(\tilde{r}\\) for the
+/// reserves commitment \\(R \cdot B + \tilde{r} \cdot \tilde{B}\\) of the
+/// total reserves \\(R\\).  The difference of the reserves and
+/// liabilities commitments carries a range proof, showing that the
+/// reserves cover the liabilities without revealing their total.
+///
+/// # Encoding
+///
+/// The reserves are encoded as a 4-byte little-endian number of
+/// addresses, followed by each address as its compressed public key, its
+/// balance as a little-endian `u64` and its compressed signature, in 152
+/// bytes, then the blinding factor of the reserves commitment, and the
+/// proof of the surplus as encoded by [`RangeProof::to_bytes`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Reserves {
+    /// The public key and balance of every address
+    addresses: Vec<(G1Affine, u64)>,
+    /// The signature by every address key over the session message
+    signatures: Vec<G2Affine>,
+    /// The blinding factor of the reserves commitment
+    blinding: Scalar,
+    /// The range proof on the surplus of reserves over liabilities
+    surplus_proof: RangeProof,
+}
+
+impl Reserves {
+    /// Proves that the addresses in `accounts`, given as the BLS secret
+    /// key and public balance of each, cover `liabilities`.
+    ///
+    /// `total` and `total_blinding` must open the liabilities'
+    /// [`total_commitment`](Liabilities::total_commitment), e.g. the
+    /// blinding factor returned by [`Liabilities::prove_with_rng`] and
+    /// the sum of the customers' balances.
+    ///
+    /// Returns [`ProofError::VerificationError`] if they do not, and
+    /// [`ProofError::UnbalancedValues`] if the reserves fall short of
+    /// the liabilities.
+    #[cfg(feature = "prover")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        liabilities: &Liabilities,
+        total: u64,
+        total_blinding: &Scalar,
+        accounts: &[(Scalar, u64)],
+        rng: &mut T,
+    ) -> Result<Reserves, ProofError> {
+        let liabilities_commitment = liabilities.total_commitment();
+        if pc_gens
+            .commit(Scalar::from(total), *total_blinding)
+            .to_affine()
+            != liabilities_commitment
+        {
+            return Err(ProofError::VerificationError);
+        }
+
+        let addresses: Vec<(G1Affine, u64)> = accounts
+            .iter()
+            .map(|(secret_key, balance)| {
+                ((G1Affine::generator() * secret_key).to_affine(), *balance)
+            })
+            .collect();
+        let reserves = sum_balances(&addresses)?;
+        let surplus = reserves
+            .checked_sub(total)
+            .ok_or(ProofError::UnbalancedValues)?;
+
+        let mut transcript = reserves_transcript(transcript, liabilities, &addresses);
+        let message = message_point(&mut transcript);
+        let signatures = accounts
+            .iter()
+            .map(|(secret_key, _)| (message * secret_key).to_affine())
+            .collect();
+
+        let blinding = Scalar::random(&mut *rng);
+        let (surplus_proof, _) = RangeProof::prove_single_with_rng(
+            bp_gens,
+            pc_gens,
+            &mut transcript,
+            surplus,
+            &(blinding - total_blinding),
+            TOTAL_BITSIZE,
+            rng,
+        )?;
+
+        Ok(Reserves {
+            addresses,
+            signatures,
+            blinding,
+            surplus_proof,
+        })
+    }
+
+    /// Proves that the addresses in `accounts` cover `liabilities`.
+    /// This is a convenience wrapper around [`Reserves::prove_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(all(feature = "thread_rng", feature = "prover"))]
+    pub fn prove(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        liabilities: &Liabilities,
+        total: u64,
+        total_blinding: &Scalar,
+        accounts: &[(Scalar, u64)],
+    ) -> Result<Reserves, ProofError> {
+        Reserves::prove_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            liabilities,
+            total,
+            total_blinding,
+            accounts,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies `liabilities` as with [`Liabilities::verify_with_rng`],
+    /// the ownership of every address, and that the reserves cover the
+    /// liabilities.
+    pub fn verify_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        liabilities: &Liabilities,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        // Each address may only be counted once
+        let mut keys: Vec<[u8; 48]> = self
+            .addresses
+            .iter()
+            .map(|(key, _)| key.to_compressed())
+            .collect();
+        keys.sort_unstable();
+        if self.signatures.len() != self.addresses.len()
+            || keys.windows(2).any(|pair| pair[0] == pair[1])
+        {
+            return Err(ProofError::FormatError);
+        }
+
+        liabilities.verify_with_rng(bp_gens, pc_gens, transcript, n, rng)?;
+
+        let mut transcript = reserves_transcript(transcript, liabilities, &self.addresses);
+        let message = message_point(&mut transcript).to_affine();
+        for ((key, _), signature) in self.addresses.iter().zip(self.signatures.iter()) {
+            if !util::verify_bls_signature(key, &message, signature) {
+                return Err(ProofError::VerificationError);
+            }
+        }
+
+        let surplus =
+            self.commitment(pc_gens)? - G1Projective::from(liabilities.total_commitment());
+        self.surplus_proof.verify_single_with_rng(
+            bp_gens,
+            pc_gens,
+            &mut transcript,
+            &surplus.to_affine(),
+            TOTAL_BITSIZE,
+            rng,
+        )
+    }
+
+    /// Verifies `liabilities`, the ownership of every address, and that
+    /// the reserves cover the liabilities.
+    /// This is a convenience wrapper around [`Reserves::verify_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    pub fn verify(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        liabilities: &Liabilities,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            liabilities,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Returns the public key and balance of every address.
+    pub fn addresses(&self) -> &[(G1Affine, u64)] {
+        &self.addresses
+    }
+
+    /// Returns the total balance of the addresses.
+    pub fn total(&self) -> Result<u64, ProofError> {
+        sum_balances(&self.addresses)
+    }
+
+    /// Returns the commitment to the total reserves, with the published
+    /// blinding factor.
+    pub fn commitment(&self, pc_gens: &PedersenGens) -> Result<G1Projective, ProofError> {
+        Ok(pc_gens.commit(Scalar::from(self.total()?), self.blinding))
+    }
+
+    /// Serializes the reserves into a byte vector.
+    ///
+    /// See the type-level documentation for the layout.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let surplus_proof = self.surplus_proof.to_bytes();
+        let mut buf =
+            Vec::with_capacity(36 + ADDRESS_LEN * self.addresses.len() + surplus_proof.len());
+        let mut len = [0u8; 4];
+
+        LittleEndian::write_u32(&mut len, self.addresses.len() as u32);
+        buf.extend_from_slice(&len);
+        for ((key, balance), signature) in self.addresses.iter().zip(self.signatures.iter()) {
+            buf.extend_from_slice(&key.to_compressed());
+            buf.extend_from_slice(&balance.to_le_bytes());
+            buf.extend_from_slice(&signature.to_compressed());
+        }
+        buf.extend_from_slice(&self.blinding.to_bytes_le());
+        buf.extend_from_slice(&surplus_proof);
+        buf
+    }
+
+    /// Deserializes the reserves from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into
+    /// `Reserves`.
+    pub fn from_bytes(slice: &[u8]) -> Result<Reserves, ProofError> {
+        let mut slice = slice;
+        let num_addresses = LittleEndian::read_u32(take(&mut slice, 4)?) as usize;
+        if num_addresses > slice.len() / ADDRESS_LEN {
+            return Err(ProofError::FormatError);
+        }
+
+        let mut addresses = Vec::with_capacity(num_addresses);
+        let mut signatures = Vec::with_capacity(num_addresses);
+        for _ in 0..num_addresses {
+            let bytes = take(&mut slice, ADDRESS_LEN)?;
+            let key = Option::from(G1Affine::from_compressed(&read48(bytes)))
+                .ok_or(ProofError::FormatError)?;
+            let balance = LittleEndian::read_u64(&bytes[48..56]);
+            let signature = Option::from(G2Affine::from_compressed(&read96(&bytes[56..])))
+                .ok_or(ProofError::FormatError)?;
+            addresses.push((key, balance));
+            signatures.push(signature);
+        }
+        let blinding = Option::from(Scalar::from_bytes_le(&read32(take(&mut slice, 32)?)))
+            .ok_or(ProofError::FormatError)?;
+        let surplus_proof = RangeProof::from_bytes(slice)?;
+
+        Ok(Reserves {
+            addresses,
+            signatures,
+            blinding,
+            surplus_proof,
+        })
+    }
+}
+
+fn sum_balances(addresses: &[(G1Affine, u64)]) -> Result<u64, ProofError> {
+    addresses.iter().try_fold(0u64, |total, (_, balance)| {
+        total
+            .checked_add(*balance)
+            .ok_or_else(|| AmountError::SumOverflow.into())
+    })
+}
+
+/// Returns a copy of `transcript` with the liabilities and the
+/// addresses of a proof of reserves appended.
+fn reserves_transcript(
+    transcript: &Transcript,
+    liabilities: &Liabilities,
+    addresses: &[(G1Affine, u64)],
+) -> Transcript {
+    let mut transcript = transcript.clone();
+    transcript.append_message(b"dom-sep", b"solvency-reserves v1");
+    transcript.append_message(b"root", &liabilities.root());
+    transcript.append_message(b"L", &liabilities.total_commitment().to_compressed());
+    transcript.append_u64(b"addresses", addresses.len() as u64);
+    for (key, balance) in addresses.iter() {
+        transcript.append_message(b"P", &key.to_compressed());
+        transcript.append_u64(b"balance", *balance);
+    }
+    transcript
+}
+
+/// Hashes the session message signed by the address keys to \\(\mathbb G\_2\\).
+fn message_point(transcript: &mut Transcript) -> G2Projective {
+    let mut digest = [0u8; 32];
+    transcript.challenge_bytes(b"reserves-digest", &mut digest);
+    G2Projective::hash_to_curve(&digest, RESERVES_DST, &[])
+}
+
+/// Returns a copy of `transcript` for the proof of the total.
+fn total_transcript(transcript: &Transcript) -> Transcript {
+    let mut transcript = transcript.clone();
+    transcript.append_message(b"dom-sep", b"solvency-total v1");
+    transcript
+}
+
+fn leaf_hash(id: &[u8], commitment: &G1Affine) -> [u8; 32] {
+    merkle::leaf_hash(
+        MERKLE_TAG,
+        &[
+            &(id.len() as u64).to_le_bytes(),
+            id,
+            &commitment.to_compressed(),
+        ],
+    )
+}
+
+/// Splits the first `len` bytes off `slice`.
+fn take<'a>(slice: &mut &'a [u8], len: usize) -> Result<&'a [u8], ProofError> {
+    if slice.len() < len {
+        return Err(ProofError::FormatError);
+    }
+    let (head, tail) = slice.split_at(len);
+    *slice = tail;
+    Ok(head)
+}
+
+fn has_duplicates<'a, I: Iterator<Item = &'a Vec<u8>>>(ids: I) -> bool {
+    let mut ids: Vec<&Vec<u8>> = ids.collect();
+    ids.sort();
+    ids.windows(2).any(|pair| pair[0] == pair[1])
+}
+
+impl Serialize for Liabilities {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for Liabilities {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        util::deserialize_bytes(deserializer, "Liabilities", Liabilities::from_bytes)
+    }
+}
+
+impl Serialize for Reserves {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for Reserves {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        util::deserialize_bytes(deserializer, "Reserves", Reserves::from_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn liabilities_and_inclusion() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let mut rng = rand::thread_rng();
+        let transcript = Transcript::new(b"SolvencyTest");
+
+        let customers: Vec<(Vec<u8>, u64, Scalar)> = (0..5u64)
+            .map(|i| (vec![b'c', i as u8], 100 * i, Scalar::random(&mut rng)))
+            .collect();
+        let (liabilities, total_blinding) =
+            Liabilities::prove(&bp_gens, &pc_gens, &transcript, &customers, 32).unwrap();
+        assert!(liabilities
+            .verify(&bp_gens, &pc_gens, &transcript, 32)
+            .is_ok());
+        assert_eq!(
+            liabilities.verify(&bp_gens, &pc_gens, &transcript, 64),
+            Err(ProofError::ParameterMismatch)
+        );
+
+        let decoded = Liabilities::from_bytes(&liabilities.to_bytes()).unwrap();
+        assert_eq!(decoded, liabilities);
+        let decoded: Liabilities =
+            bincode::deserialize(&bincode::serialize(&liabilities).unwrap()).unwrap();
+        assert_eq!(decoded, liabilities);
+        let bytes = liabilities.to_bytes();
+        assert_eq!(
+            Liabilities::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ProofError::FormatError)
+        );
+        assert_eq!(
+            liabilities.total_commitment(),
+            pc_gens
+                .commit(Scalar::from(1000u64), total_blinding)
+                .to_affine()
+        );
+
+        let root = liabilities.root();
+        for (id, balance, blinding) in customers.iter() {
+            let commitment = pc_gens
+                .commit(Scalar::from(*balance), *blinding)
+                .to_affine();
+            let proof = liabilities.inclusion_proof(id).unwrap();
+            assert!(proof.verify(&root, id, &commitment).is_ok());
+            assert!(proof.verify(&root, b"other", &commitment).is_err());
+            let other = pc_gens
+                .commit(Scalar::from(*balance + 1), *blinding)
+                .to_affine();
+            assert!(proof.verify(&root, id, &other).is_err());
+        }
+        assert!(liabilities.inclusion_proof(b"other").is_none());
+
+        let mut customers = customers;
+        customers[1].0 = customers[0].0.clone();
+        assert_eq!(
+            Liabilities::prove(&bp_gens, &pc_gens, &transcript, &customers, 32).unwrap_err(),
+            ProofError::FormatError
+        );
+        customers[1].0 = b"c1".to_vec();
+        customers[1].1 = 1 << 32;
+        assert_eq!(
+            Liabilities::prove(&bp_gens, &pc_gens, &transcript, &customers, 32).unwrap_err(),
+            ProofError::InvalidAmount(AmountError::ExceedsBitsize {
+                units: 1 << 32,
+                bitsize: 32
+            })
+        );
+
+        // Balances of a bitsize other than 8, 16, 32 or 64 are padded
+        let bp_gens = BulletproofGens::new(64, 2);
+        let (liabilities, _) =
+            Liabilities::prove(&bp_gens, &pc_gens, &transcript, &customers, 52).unwrap();
+        assert!(liabilities
+            .verify(&bp_gens, &pc_gens, &transcript, 52)
+            .is_ok());
+    }
+
+    #[test]
+    fn reserves_cover_liabilities() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let mut rng = rand::thread_rng();
+        let transcript = Transcript::new(b"SolvencyTest");
+
+        let customers: Vec<(Vec<u8>, u64, Scalar)> = (0..3u64)
+            .map(|i| (vec![b'c', i as u8], 100 + i, Scalar::random(&mut rng)))
+            .collect();
+        let (liabilities, total_blinding) =
+            Liabilities::prove(&bp_gens, &pc_gens, &transcript, &customers, 32).unwrap();
+
+        let accounts: Vec<(Scalar, u64)> = [200u64, 150]
+            .iter()
+            .map(|balance| (Scalar::random(&mut rng), *balance))
+            .collect();
+        let reserves = Reserves::prove(
+            &bp_gens,
+            &pc_gens,
+            &transcript,
+            &liabilities,
+            303,
+            &total_blinding,
+            &accounts,
+        )
+        .unwrap();
+        assert_eq!(reserves.total(), Ok(350));
+        assert!(reserves
+            .verify(&bp_gens, &pc_gens, &transcript, &liabilities, 32)
+            .is_ok());
+
+        let decoded = Reserves::from_bytes(&reserves.to_bytes()).unwrap();
+        assert_eq!(decoded, reserves);
+        let decoded: Reserves =
+            bincode::deserialize(&bincode::serialize(&reserves).unwrap()).unwrap();
+        assert_eq!(decoded, reserves);
+        let bytes = reserves.to_bytes();
+        assert_eq!(
+            Reserves::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ProofError::FormatError)
+        );
+
+        // The signatures are bound to the liabilities and the session
+        let reblinded: Vec<(Vec<u8>, u64, Scalar)> = customers
+            .iter()
+            .map(|(id, balance, _)| (id.clone(), *balance, Scalar::random(&mut rng)))
+            .collect();
+        let (other, _) =
+            Liabilities::prove(&bp_gens, &pc_gens, &transcript, &reblinded, 32).unwrap();
+        assert!(reserves
+            .verify(&bp_gens, &pc_gens, &transcript, &other, 32)
+            .is_err());
+        let other_transcript = Transcript::new(b"OtherSession");
+        assert!(reserves
+            .verify(&bp_gens, &pc_gens, &other_transcript, &liabilities, 32)
+            .is_err());
+
+        // An inflated balance breaks the signatures
+        let mut inflated = reserves.clone();
+        inflated.addresses[0].1 += 1;
+        assert_eq!(
+            inflated.verify(&bp_gens, &pc_gens, &transcript, &liabilities, 32),
+            Err(ProofError::VerificationError)
+        );
+        let mut duplicated = reserves.clone();
+        duplicated.addresses[1] = duplicated.addresses[0];
+        assert_eq!(
+            duplicated.verify(&bp_gens, &pc_gens, &transcript, &liabilities, 32),
+            Err(ProofError::FormatError)
+        );
+
+        let prove = |total: u64, accounts: &[(Scalar, u64)]| {
+            Reserves::prove(
+                &bp_gens,
+                &pc_gens,
+                &transcript,
+                &liabilities,
+                total,
+                &total_blinding,
+                accounts,
+            )
+        };
+        assert_eq!(
+            prove(302, &accounts).unwrap_err(),
+            ProofError::VerificationError
+        );
+        assert_eq!(
+            prove(303, &accounts[..1]).unwrap_err(),
+            ProofError::UnbalancedValues
+        );
+    }
+}
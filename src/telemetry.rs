@@ -0,0 +1,190 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Verification metrics, emitted through the `metrics` crate facade.
+//!
+//! With the `metrics` feature, the verifiers record:
+//!
+//! | Metric | Type | Labels |
+//! |--------|------|--------|
+//! | `bls_bulletproofs_proofs_verified_total` | counter | `kind`, `result` |
+//! | `bls_bulletproofs_verification_failures_total` | counter | `kind`, `reason` |
+//! | `bls_bulletproofs_batch_size` | histogram | |
+//! | `bls_bulletproofs_msm_terms` | histogram | |
+//!
+//! They are recorded by whichever recorder the application installs,
+//! e.g. a Prometheus exporter.  Without the feature, the functions of
+//! this module compile to nothing.
+
+use crate::errors::ProofError;
+
+#[cfg(feature = "metrics")]
+const PROOFS_VERIFIED: &str = "bls_bulletproofs_proofs_verified_total";
+#[cfg(feature = "metrics")]
+const VERIFICATION_FAILURES: &str = "bls_bulletproofs_verification_failures_total";
+#[cfg(feature = "metrics")]
+const BATCH_SIZE: &str = "bls_bulletproofs_batch_size";
+#[cfg(feature = "metrics")]
+const MSM_TERMS: &str = "bls_bulletproofs_msm_terms";
+
+/// Records the result of verifying a proof, or a batch of proofs, of
+/// the given kind.
+#[inline]
+pub(crate) fn record_verification(kind: &'static str, result: &Result<(), ProofError>) {
+    #[cfg(feature = "metrics")]
+    match result {
+        Ok(()) => {
+            metrics::counter!(PROOFS_VERIFIED, "kind" => kind, "result" => "ok").increment(1);
+        }
+        Err(e) => {
+            metrics::counter!(PROOFS_VERIFIED, "kind" => kind, "result" => "error").increment(1);
+            metrics::counter!(VERIFICATION_FAILURES, "kind" => kind, "reason" => reason(e))
+                .increment(1);
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (kind, result);
+}
+
+/// Records the number of proofs verified together by a batch verifier.
+#[inline]
+pub(crate) fn record_batch(size: usize) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!(BATCH_SIZE).record(size as f64);
+    #[cfg(not(feature = "metrics"))]
+    let _ = size;
+}
+
+/// Records the number of terms of a verification multiscalar
+/// multiplication.
+#[inline]
+pub(crate) fn record_msm(terms: usize) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!(MSM_TERMS).record(terms as f64);
+    #[cfg(not(feature = "metrics"))]
+    let _ = terms;
+}
+
+/// Returns the label of the failure reason `e`.
+#[cfg(feature = "metrics")]
+fn reason(e: &ProofError) -> &'static str {
+    match e {
+        ProofError::VerificationError => "verification_error",
+        ProofError::FormatError => "format_error",
+        ProofError::WrongNumBlindingFactors => "wrong_num_blinding_factors",
+        ProofError::InvalidBitsize => "invalid_bitsize",
+        ProofError::InvalidAggregation => "invalid_aggregation",
+        ProofError::InvalidGeneratorsLength => "invalid_generators_length",
+        ProofError::InvalidGenerators => "invalid_generators",
+        ProofError::InvalidCutoff => "invalid_cutoff",
+        ProofError::ParameterMismatch => "parameter_mismatch",
+        ProofError::InvalidBatchWeights => "invalid_batch_weights",
+        ProofError::UnbalancedValues => "unbalanced_values",
+        ProofError::NonceMismatch => "nonce_mismatch",
+        ProofError::ChainMismatch => "chain_mismatch",
+        ProofError::ValueBelowOffset => "value_below_offset",
+        ProofError::ValueOutsideInterval => "value_outside_interval",
+        ProofError::ReceiptMismatch => "receipt_mismatch",
+        ProofError::RecordMismatch => "record_mismatch",
+        ProofError::Cancelled => "cancelled",
+        ProofError::InvalidAmount(_) => "invalid_amount",
+        ProofError::ProvingError(_) => "proving_error",
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+
+    use std::sync::Mutex;
+
+    use blstrs::Scalar;
+    use merlin::Transcript;
+    use metrics::{
+        Counter, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+    };
+
+    use crate::generators::{BulletproofGens, PedersenGens};
+    use crate::range_proof::RangeProof;
+
+    /// A recorder which collects the keys of the registered metrics.
+    #[derive(Default)]
+    struct KeyRecorder(Mutex<Vec<Key>>);
+
+    impl Recorder for KeyRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            self.0.lock().unwrap().push(key.clone());
+            Counter::noop()
+        }
+
+        fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+            self.0.lock().unwrap().push(key.clone());
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+            self.0.lock().unwrap().push(key.clone());
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn verification_is_recorded() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let (proof, V) = RangeProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"MetricsTest"),
+            7,
+            &Scalar::from(3u64),
+            8,
+        )
+        .unwrap();
+
+        let recorder = KeyRecorder::default();
+        metrics::with_local_recorder(&recorder, || {
+            proof
+                .verify_single(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"MetricsTest"),
+                    &V,
+                    8,
+                )
+                .unwrap();
+            assert!(proof
+                .verify_single(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"OtherTest"),
+                    &V,
+                    8,
+                )
+                .is_err());
+        });
+
+        let keys = recorder.0.into_inner().unwrap();
+        let has = |name: &str, label: (&str, &str)| {
+            keys.iter().any(|key| {
+                key.name() == name
+                    && key
+                        .labels()
+                        .any(|l| l.key() == label.0 && l.value() == label.1)
+            })
+        };
+        assert!(has(PROOFS_VERIFIED, ("result", "ok")));
+        assert!(has(PROOFS_VERIFIED, ("result", "error")));
+        assert!(has(VERIFICATION_FAILURES, ("reason", "verification_error")));
+        assert!(keys.iter().any(|key| key.name() == MSM_TERMS));
+    }
+}
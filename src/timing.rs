@@ -0,0 +1,200 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+//! A harness for detecting timing leaks, in the style of
+//! [dudect](https://eprint.iacr.org/2016/1123).
+//!
+//! The operation under test is run on inputs from two classes of
+//! secrets, e.g. a fixed value and uniformly random values, in a random
+//! interleaving.  Welch's \\(t\\)-test then checks whether the timings
+//! of the two classes differ.  A large \\(|t|\\) is evidence of a
+//! timing leak; a small one is not proof of its absence, but running
+//! the harness against a specific build catches regressions introduced
+//! by code changes or compiler flags.
+//!
+//! The harness is only available with the `timing-tests` feature.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use std::time::Instant;
+
+use blstrs::Scalar;
+use group::ff::Field;
+use merlin::Transcript;
+use rand::{CryptoRng, Rng, RngCore};
+
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::RangeProof;
+
+/// The \\(|t|\\) above which a [`TimingReport`] indicates a leak, as
+/// used by dudect.
+pub const LEAKAGE_THRESHOLD: f64 = 4.5;
+
+/// The fraction of the slowest measurements discarded before testing,
+/// since those are dominated by interrupts and scheduling.
+const CROP_FRACTION: f64 = 0.1;
+
+/// The result of a timing measurement by [`measure`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TimingReport {
+    /// The number of measurements of each class used in the test
+    pub samples: [usize; 2],
+    /// The mean duration in nanoseconds of each class
+    pub means: [f64; 2],
+    /// Welch's \\(t\\)-statistic for the difference of the means
+    pub t: f64,
+}
+
+impl TimingReport {
+    /// Returns `true` if \\(|t|\\) exceeds [`LEAKAGE_THRESHOLD`].
+    pub fn leaks(&self) -> bool {
+        self.t.abs() > LEAKAGE_THRESHOLD
+    }
+}
+
+/// Measures `op` on `samples` inputs, each generated by `input` for a
+/// class chosen uniformly at random, and tests whether the timings of
+/// the two classes differ.
+///
+/// Only `op` is timed, so `input` should do all of the preparation that
+/// is not part of the operation under test.
+pub fn measure<I, R, G, F>(samples: usize, rng: &mut R, mut input: G, mut op: F) -> TimingReport
+where
+    R: RngCore,
+    G: FnMut(usize, &mut R) -> I,
+    F: FnMut(I),
+{
+    let mut timings: Vec<(usize, f64)> = Vec::with_capacity(samples);
+    for _ in 0..samples {
+        let class = rng.gen_range(0..2);
+        let input = input(class, rng);
+        let start = Instant::now();
+        op(input);
+        timings.push((class, start.elapsed().as_nanos() as f64));
+    }
+
+    // Discard the slowest measurements
+    let mut durations: Vec<f64> = timings.iter().map(|&(_, d)| d).collect();
+    durations.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let keep = samples - (samples as f64 * CROP_FRACTION) as usize;
+    let cutoff = durations
+        .get(keep.saturating_sub(1))
+        .copied()
+        .unwrap_or(0.0);
+
+    let class = |c: usize| -> Vec<f64> {
+        timings
+            .iter()
+            .filter(|&&(class, d)| class == c && d <= cutoff)
+            .map(|&(_, d)| d)
+            .collect()
+    };
+    let (fixed, random) = (class(0), class(1));
+
+    TimingReport {
+        samples: [fixed.len(), random.len()],
+        means: [mean(&fixed), mean(&random)],
+        t: welch_t(&fixed, &random),
+    }
+}
+
+/// Measures the range prover for `n`-bit values, comparing proofs of
+/// the value zero (class 0) with proofs of uniformly random values in
+/// \\([0, 2^n)\\) (class 1).
+pub fn measure_range_prover<R: RngCore + CryptoRng>(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    n: usize,
+    samples: usize,
+    rng: &mut R,
+) -> TimingReport {
+    let mut prover_rng = rand::thread_rng();
+    measure(
+        samples,
+        rng,
+        |class, rng| {
+            let v = match class {
+                0 => 0,
+                _ if n == 64 => rng.next_u64(),
+                _ => rng.next_u64() & ((1 << n) - 1),
+            };
+            (v, Scalar::random(&mut *rng))
+        },
+        |(v, v_blinding)| {
+            let mut transcript = Transcript::new(b"TimingTest");
+            let _ = RangeProof::prove_single_with_rng(
+                bp_gens,
+                pc_gens,
+                &mut transcript,
+                v,
+                &v_blinding,
+                n,
+                &mut prover_rng,
+            );
+        },
+    )
+}
+
+fn mean(xs: &[f64]) -> f64 {
+    xs.iter().sum::<f64>() / xs.len() as f64
+}
+
+fn variance(xs: &[f64]) -> f64 {
+    let m = mean(xs);
+    xs.iter().map(|x| (x - m) * (x - m)).sum::<f64>() / (xs.len() as f64 - 1.0)
+}
+
+/// Computes Welch's \\(t\\)-statistic for the difference of the means
+/// of `a` and `b`, or zero if either has fewer than two elements.
+fn welch_t(a: &[f64], b: &[f64]) -> f64 {
+    if a.len() < 2 || b.len() < 2 {
+        return 0.0;
+    }
+    let se = (variance(a) / a.len() as f64 + variance(b) / b.len() as f64).sqrt();
+    if se == 0.0 {
+        return 0.0;
+    }
+    (mean(a) - mean(b)) / se
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn welch_t_statistic() {
+        let a = [1.0, 2.0, 3.0, 4.0];
+        let b = [3.0, 4.0, 5.0, 6.0];
+        // means 2.5 and 4.5, variances 5/3
+        let expected = -2.0 / (2.0 * 5.0 / 12.0f64).sqrt();
+        assert!((welch_t(&a, &b) - expected).abs() < 1e-12);
+        assert_eq!(welch_t(&a, &a), 0.0);
+        assert_eq!(welch_t(&a, &[1.0]), 0.0);
+    }
+
+    #[test]
+    fn detects_obvious_leak() {
+        let mut rng = rand::thread_rng();
+        let report = measure(
+            200,
+            &mut rng,
+            |class, _| class,
+            |class| std::thread::sleep(std::time::Duration::from_micros(100 * class as u64)),
+        );
+        assert!(report.leaks());
+        assert!(report.means[1] > report.means[0]);
+    }
+
+    #[test]
+    fn range_prover_report() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let report = measure_range_prover(&bp_gens, &pc_gens, 8, 20, &mut rand::thread_rng());
+        assert!(report.samples[0] + report.samples[1] >= 18);
+        assert!(report.t.is_finite());
+    }
+}
@@ -23,6 +23,10 @@ pub trait TranscriptProtocol {
     /// Append a domain separator for a length-`n` inner product proof.
     fn innerproduct_domain_sep(&mut self, n: u64);
 
+    /// Append a domain separator for a batch of `k` range proofs.
+    #[cfg(feature = "verifier")]
+    fn rangeproof_batch_domain_sep(&mut self, k: u64);
+
     /// Append a domain separator for a constraint system.
     fn r1cs_domain_sep(&mut self);
 
@@ -62,6 +66,12 @@ impl TranscriptProtocol for Transcript {
         self.append_u64(b"n", n);
     }
 
+    #[cfg(feature = "verifier")]
+    fn rangeproof_batch_domain_sep(&mut self, k: u64) {
+        self.append_message(b"dom-sep", b"rangeproof-batch v1");
+        self.append_u64(b"k", k);
+    }
+
     fn r1cs_domain_sep(&mut self) {
         self.append_message(b"dom-sep", b"r1cs v1");
     }
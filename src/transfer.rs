@@ -0,0 +1,587 @@
+// Copyright (c) 2022, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the MIT license.
+// Please see the LICENSE file for more details.
+
+#![allow(non_snake_case)]
+
+//! Confidential transfers between account balances.
+//!
+//! In the account model, every account holds a Pedersen commitment
+//! \\(C\\) to its balance.  To transfer an amount \\(a\\), the sender
+//! publishes a debit commitment \\(D\\) to \\(a\\), which is subtracted
+//! from their balance, and a credit commitment \\(E\\) to \\(a\\), under
+//! a different blinding factor, which is added to the receiver's
+//! balance.
+//!
+//! As in Zether, the amounts are also encrypted to the account owners,
+//! so that each can follow their balance without being told the
+//! blinding factors.  Every commitment is the first half of a twisted
+//! ElGamal [`Ciphertext`], whose second half is a decryption handle
+//! \\(r \cdot P\\) for the owner's [`EncryptionKey`] \\(P\\), where \\(r\\)
+//! is the blinding factor of the commitment.  Ciphertexts add up like
+//! their commitments, so the handles of a balance are updated along
+//! with its commitment.
+//!
+//! A [`TransferProof`] shows in one bundle that
+//!
+//! * the amount is in range, so the debit cannot wrap around,
+//! * the sender's remaining balance \\(C - D\\) is in range,
+//! * the debit and the credit commit to the same amount, and
+//! * their handles decrypt them for the sender and the receiver.
+//!
+//! The first two are shown with one aggregated range proof over
+//! \\(D\\) and \\(C - D\\), and the last two with a sigma protocol over
+//! the amount and the two blinding factors.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Add, Sub};
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use clear_on_drop::clear::Clear;
+use group::ff::Field;
+use group::Curve;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "thread_rng")]
+use rand::thread_rng;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+#[cfg(feature = "prover")]
+use crate::errors::AmountError;
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::RangeProof;
+use crate::transcript::TranscriptProtocol;
+use crate::util::{self, read32, read48};
+
+/// The length of the encoding of the sigma protocol of a [`TransferProof`].
+const SIGMA_LEN: usize = 4 * 48 + 3 * 32;
+
+/// The secret key \\(s\\) of an account owner, which decrypts the
+/// amounts encrypted to their [`EncryptionKey`].
+///
+/// The key is cleared when dropped, and is not shown by its `Debug`
+/// impl.
+#[derive(Clone, Eq, PartialEq)]
+pub struct DecryptionKey(Scalar);
+
+/// The public key \\(P = s^{-1} \cdot \tilde{B}\\) to which amounts are
+/// encrypted for the owner of the [`DecryptionKey`] \\(s\\).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct EncryptionKey(G1Affine);
+
+/// A twisted ElGamal encryption of an amount \\(a\\) to an
+/// [`EncryptionKey`] \\(P\\).
+///
+/// The ciphertext is the Pedersen commitment
+/// \\(a \cdot B + r \cdot \tilde{B}\\), which range proofs are made
+/// over, and the decryption handle \\(r \cdot P\\).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Ciphertext {
+    /// The Pedersen commitment to the amount
+    pub commitment: G1Affine,
+    /// The decryption handle of the commitment's blinding factor
+    pub handle: G1Affine,
+}
+
+impl DecryptionKey {
+    /// Creates a uniformly random nonzero key.
+    pub fn random<T: RngCore + CryptoRng>(rng: &mut T) -> Self {
+        loop {
+            let s = Scalar::random(&mut *rng);
+            if !bool::from(s.is_zero()) {
+                return DecryptionKey(s);
+            }
+        }
+    }
+
+    /// Returns the encryption key for this key.
+    pub fn encryption_key(&self, pc_gens: &PedersenGens) -> EncryptionKey {
+        let s_inv: Scalar = Option::from(self.0.invert()).expect("The key is nonzero");
+        EncryptionKey((pc_gens.B_blinding * s_inv).to_affine())
+    }
+
+    /// Decrypts `ciphertext` to the point \\(a \cdot B\\).
+    ///
+    /// The amount \\(a\\) itself is a discrete logarithm, which an owner
+    /// recovers by comparison with the amounts they expect, or with a
+    /// table of small amounts.
+    pub fn decrypt(&self, ciphertext: &Ciphertext) -> G1Affine {
+        (G1Projective::from(ciphertext.commitment) - ciphertext.handle * self.0).to_affine()
+    }
+}
+
+impl fmt::Debug for DecryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DecryptionKey(..)")
+    }
+}
+
+impl Drop for DecryptionKey {
+    fn drop(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Overwrites the key with zero, as dropping it does.
+#[cfg(feature = "zeroize")]
+impl Zeroize for DecryptionKey {
+    fn zeroize(&mut self) {
+        self.0.clear();
+    }
+}
+
+impl EncryptionKey {
+    /// Encrypts `amount` with the blinding factor `blinding`.
+    pub fn encrypt(&self, pc_gens: &PedersenGens, amount: u64, blinding: &Scalar) -> Ciphertext {
+        Ciphertext {
+            commitment: pc_gens.commit(Scalar::from(amount), *blinding).to_affine(),
+            handle: (self.0 * blinding).to_affine(),
+        }
+    }
+
+    /// Returns the public key point \\(P\\).
+    pub fn as_point(&self) -> &G1Affine {
+        &self.0
+    }
+}
+
+impl Add for Ciphertext {
+    type Output = Ciphertext;
+
+    fn add(self, other: Ciphertext) -> Ciphertext {
+        Ciphertext {
+            commitment: (G1Projective::from(self.commitment) + other.commitment).to_affine(),
+            handle: (G1Projective::from(self.handle) + other.handle).to_affine(),
+        }
+    }
+}
+
+impl Sub for Ciphertext {
+    type Output = Ciphertext;
+
+    fn sub(self, other: Ciphertext) -> Ciphertext {
+        Ciphertext {
+            commitment: (G1Projective::from(self.commitment) - other.commitment).to_affine(),
+            handle: (G1Projective::from(self.handle) - other.handle).to_affine(),
+        }
+    }
+}
+
+impl From<G1Affine> for EncryptionKey {
+    fn from(point: G1Affine) -> Self {
+        EncryptionKey(point)
+    }
+}
+
+/// A proof that a transfer debits and credits the same in-range amount,
+/// encrypted to the sender and the receiver, and leaves the sender with
+/// an in-range balance.
+///
+/// # Encoding
+///
+/// The proof is encoded as the four compressed nonce commitments and
+/// the three response scalars of the sigma protocol, followed by the
+/// encoding of the range proof.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransferProof {
+    /// The aggregated range proof over the debit and the remaining balance
+    range_proof: RangeProof,
+    /// The nonce commitments for the debit, the credit and their handles
+    Y: [G1Affine; 4],
+    /// The responses for the amount and the debit and credit blindings
+    z: [Scalar; 3],
+}
+
+impl TransferProof {
+    /// Creates a proof for the transfer of `amount` out of the sender's
+    /// `balance`, committed with `balance_blinding`.
+    ///
+    /// The debit is encrypted to `sender` with `debit_blinding`, and the
+    /// credit to `receiver` with `credit_blinding`, and both amount and
+    /// remaining balance are proven to be `n`-bit values.  Returns the
+    /// proof together with the debit and credit ciphertexts.  The
+    /// sender's new balance is committed with
+    /// `balance_blinding - debit_blinding`.
+    ///
+    /// Returns [`ProofError::UnbalancedValues`] if `amount` exceeds
+    /// `balance`, and an [`AmountError`] if either does not fit in `n`
+    /// bits.
+    #[cfg(feature = "prover")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        balance: u64,
+        balance_blinding: &Scalar,
+        amount: u64,
+        debit_blinding: &Scalar,
+        credit_blinding: &Scalar,
+        sender: &EncryptionKey,
+        receiver: &EncryptionKey,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(TransferProof, Ciphertext, Ciphertext), ProofError> {
+        let remaining = balance
+            .checked_sub(amount)
+            .ok_or(ProofError::UnbalancedValues)?;
+        if n < 64 && balance >> n != 0 {
+            return Err(AmountError::ExceedsBitsize {
+                units: balance,
+                bitsize: n,
+            }
+            .into());
+        }
+
+        let C = pc_gens.commit(Scalar::from(balance), *balance_blinding);
+        let debit = sender.encrypt(pc_gens, amount, debit_blinding);
+        let credit = receiver.encrypt(pc_gens, amount, credit_blinding);
+        TransferProof::statement(transcript, &C, &debit, &credit, sender, receiver);
+
+        let (range_proof, _) = RangeProof::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &[amount, remaining],
+            &[*debit_blinding, balance_blinding - debit_blinding],
+            n,
+            &mut *rng,
+        )?;
+
+        // D = a * B + r_D * B_blinding, E = a * B + r_E * B_blinding,
+        // and the handles are r_D * P_sender and r_E * P_receiver
+        let [y_a, y_D, y_E] = [(); 3].map(|_| Scalar::random(&mut *rng));
+        let Y = [
+            pc_gens.commit(y_a, y_D),
+            pc_gens.commit(y_a, y_E),
+            sender.0 * y_D,
+            receiver.0 * y_E,
+        ];
+        let c = TransferProof::challenge(transcript, &Y)?;
+        let z = [
+            y_a + c * Scalar::from(amount),
+            y_D + c * debit_blinding,
+            y_E + c * credit_blinding,
+        ];
+
+        let mut Y_affine = [G1Affine::default(); 4];
+        G1Projective::batch_normalize(&Y, &mut Y_affine);
+        let proof = TransferProof {
+            range_proof,
+            Y: Y_affine,
+            z,
+        };
+        Ok((proof, debit, credit))
+    }
+
+    /// Creates a proof for the transfer of `amount` out of the sender's `balance`.
+    /// This is a convenience wrapper around [`TransferProof::create_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(all(feature = "thread_rng", feature = "prover"))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn create(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        balance: u64,
+        balance_blinding: &Scalar,
+        amount: u64,
+        debit_blinding: &Scalar,
+        credit_blinding: &Scalar,
+        sender: &EncryptionKey,
+        receiver: &EncryptionKey,
+        n: usize,
+    ) -> Result<(TransferProof, Ciphertext, Ciphertext), ProofError> {
+        TransferProof::create_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            balance,
+            balance_blinding,
+            amount,
+            debit_blinding,
+            credit_blinding,
+            sender,
+            receiver,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies the transfer of the amount encrypted in `debit` to
+    /// `sender` and in `credit` to `receiver` out of the sender's
+    /// `balance` commitment.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        balance: &G1Affine,
+        debit: &Ciphertext,
+        credit: &Ciphertext,
+        sender: &EncryptionKey,
+        receiver: &EncryptionKey,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let C = G1Projective::from(balance);
+        let D = G1Projective::from(debit.commitment);
+        TransferProof::statement(transcript, &C, debit, credit, sender, receiver);
+
+        self.range_proof.verify_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &[debit.commitment, (C - D).to_affine()],
+            n,
+            rng,
+        )?;
+
+        let Y = self.Y.map(G1Projective::from);
+        let c = TransferProof::challenge(transcript, &Y)?;
+        let [z_a, z_D, z_E] = self.z;
+        let checks = [
+            (pc_gens.commit(z_a, z_D), Y[0], debit.commitment),
+            (pc_gens.commit(z_a, z_E), Y[1], credit.commitment),
+            (sender.0 * z_D, Y[2], debit.handle),
+            (receiver.0 * z_E, Y[3], credit.handle),
+        ];
+        if checks.iter().all(|(lhs, Y, X)| *lhs == Y + X * c) {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Verifies the transfer of the amount encrypted in `debit` and
+    /// `credit` out of the sender's `balance` commitment.
+    /// This is a convenience wrapper around [`TransferProof::verify_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "thread_rng")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        balance: &G1Affine,
+        debit: &Ciphertext,
+        credit: &Ciphertext,
+        sender: &EncryptionKey,
+        receiver: &EncryptionKey,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            balance,
+            debit,
+            credit,
+            sender,
+            receiver,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Appends the balance commitment, the debit and credit ciphertexts
+    /// and the keys they are encrypted to to `transcript`.
+    fn statement(
+        transcript: &mut Transcript,
+        C: &G1Projective,
+        debit: &Ciphertext,
+        credit: &Ciphertext,
+        sender: &EncryptionKey,
+        receiver: &EncryptionKey,
+    ) {
+        transcript.append_message(b"dom-sep", b"transfer v1");
+        transcript.append_point(b"C", C);
+        transcript.append_point(b"D", &debit.commitment.into());
+        transcript.append_point(b"E", &credit.commitment.into());
+        transcript.append_point(b"P_D", &sender.0.into());
+        transcript.append_point(b"P_E", &receiver.0.into());
+        transcript.append_point(b"H_D", &debit.handle.into());
+        transcript.append_point(b"H_E", &credit.handle.into());
+    }
+
+    /// Appends the nonce commitments of the sigma protocol to
+    /// `transcript`, and returns the challenge.
+    fn challenge(transcript: &mut Transcript, Y: &[G1Projective; 4]) -> Result<Scalar, ProofError> {
+        for Y_i in Y.iter() {
+            transcript.validate_and_append_point(b"Y", Y_i)?;
+        }
+        Ok(transcript.challenge_scalar(b"c"))
+    }
+
+    /// Serializes the proof into a byte vector.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let range_proof = self.range_proof.to_bytes();
+        let mut buf = Vec::with_capacity(SIGMA_LEN + range_proof.len());
+        for Y_i in self.Y.iter() {
+            buf.extend_from_slice(&Y_i.to_compressed());
+        }
+        for z_i in self.z.iter() {
+            buf.extend_from_slice(&z_i.to_bytes_le());
+        }
+        buf.extend_from_slice(&range_proof);
+        buf
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a `TransferProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<TransferProof, ProofError> {
+        if slice.len() < SIGMA_LEN {
+            return Err(ProofError::FormatError);
+        }
+        let mut Y = [G1Affine::default(); 4];
+        for (Y_i, bytes) in Y.iter_mut().zip(slice[..4 * 48].chunks(48)) {
+            *Y_i = Option::from(G1Affine::from_compressed(&read48(bytes)))
+                .ok_or(ProofError::FormatError)?;
+        }
+        let mut z = [Scalar::zero(); 3];
+        for (z_i, bytes) in z.iter_mut().zip(slice[4 * 48..SIGMA_LEN].chunks(32)) {
+            *z_i = Option::from(Scalar::from_bytes_le(&read32(bytes)))
+                .ok_or(ProofError::FormatError)?;
+        }
+        let range_proof = RangeProof::from_bytes(&slice[SIGMA_LEN..])?;
+        Ok(TransferProof { range_proof, Y, z })
+    }
+}
+
+impl Serialize for TransferProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for TransferProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        util::deserialize_bytes(deserializer, "TransferProof", TransferProof::from_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfer_debits_and_credits_same_amount() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let mut rng = rand::thread_rng();
+        let [balance_blinding, debit_blinding, credit_blinding] =
+            [(); 3].map(|_| Scalar::random(&mut rng));
+        let sender_key = DecryptionKey::random(&mut rng);
+        let receiver_key = DecryptionKey::random(&mut rng);
+        let sender = sender_key.encryption_key(&pc_gens);
+        let receiver = receiver_key.encryption_key(&pc_gens);
+
+        let mut transcript = Transcript::new(b"TransferTest");
+        let (proof, debit, credit) = TransferProof::create(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            1000,
+            &balance_blinding,
+            250,
+            &debit_blinding,
+            &credit_blinding,
+            &sender,
+            &receiver,
+            32,
+        )
+        .unwrap();
+        let balance = sender.encrypt(&pc_gens, 1000, &balance_blinding);
+
+        let verify = |proof: &TransferProof, balance: &G1Affine, credit: &Ciphertext| {
+            let mut transcript = Transcript::new(b"TransferTest");
+            proof.verify(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                balance,
+                &debit,
+                credit,
+                &sender,
+                &receiver,
+                32,
+            )
+        };
+        assert!(verify(&proof, &balance.commitment, &credit).is_ok());
+
+        // Both owners decrypt the amount, and the sender their new balance
+        let amount = (pc_gens.B * Scalar::from(250u64)).to_affine();
+        assert_eq!(sender_key.decrypt(&debit), amount);
+        assert_eq!(receiver_key.decrypt(&credit), amount);
+        assert_ne!(sender_key.decrypt(&credit), amount);
+        assert_eq!(
+            sender_key.decrypt(&(balance - debit)),
+            (pc_gens.B * Scalar::from(750u64)).to_affine()
+        );
+
+        let decoded = TransferProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(verify(&decoded, &balance.commitment, &credit).is_ok());
+        let decoded: TransferProof =
+            bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+        assert_eq!(decoded, proof);
+        assert_eq!(
+            TransferProof::from_bytes(&proof.to_bytes()[..SIGMA_LEN - 1]),
+            Err(ProofError::FormatError)
+        );
+
+        // Crediting more than is debited
+        let inflated = receiver.encrypt(&pc_gens, 251, &credit_blinding);
+        assert_eq!(
+            verify(&proof, &balance.commitment, &inflated),
+            Err(ProofError::VerificationError)
+        );
+        // Encrypting the credit to someone other than the receiver
+        let misdirected = sender.encrypt(&pc_gens, 250, &credit_blinding);
+        assert_eq!(
+            verify(&proof, &balance.commitment, &misdirected),
+            Err(ProofError::VerificationError)
+        );
+        // Debiting an account with another balance
+        let other = pc_gens
+            .commit(Scalar::from(100u64), balance_blinding)
+            .to_affine();
+        assert!(verify(&proof, &other, &credit).is_err());
+
+        let mut transcript = Transcript::new(b"TransferTest");
+        assert_eq!(
+            TransferProof::create(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                100,
+                &balance_blinding,
+                250,
+                &debit_blinding,
+                &credit_blinding,
+                &sender,
+                &receiver,
+                32,
+            )
+            .unwrap_err(),
+            ProofError::UnbalancedValues
+        );
+    }
+}
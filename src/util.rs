@@ -9,41 +9,20 @@
 
 extern crate alloc;
 
-use alloc::vec;
-use alloc::vec::Vec;
-use blstrs::Scalar;
-use clear_on_drop::clear::Clear;
+#[cfg(feature = "verifier")]
+use blstrs::{pairing, G1Affine, G2Affine};
+use blstrs::{G1Projective, Scalar};
+#[cfg(feature = "prover")]
+use core::sync::atomic::{AtomicBool, Ordering};
 use group::ff::Field;
+#[cfg(feature = "verifier")]
+use group::prime::PrimeCurveAffine;
+use serde::de::{SeqAccess, Visitor};
+use serde::Deserializer;
 
-use crate::inner_product_proof::inner_product;
-
-/// Represents a degree-1 vector polynomial \\(\mathbf{a} + \mathbf{b} \cdot x\\).
-pub struct VecPoly1(pub Vec<Scalar>, pub Vec<Scalar>);
-
-/// Represents a degree-3 vector polynomial
-/// \\(\mathbf{a} + \mathbf{b} \cdot x + \mathbf{c} \cdot x^2 + \mathbf{d} \cdot x^3 \\).
-#[cfg(feature = "yoloproofs")]
-pub struct VecPoly3(
-    pub Vec<Scalar>,
-    pub Vec<Scalar>,
-    pub Vec<Scalar>,
-    pub Vec<Scalar>,
-);
-
-/// Represents a degree-2 scalar polynomial \\(a + b \cdot x + c \cdot x^2\\)
-pub struct Poly2(pub Scalar, pub Scalar, pub Scalar);
-
-/// Represents a degree-6 scalar polynomial, without the zeroth degree
-/// \\(a \cdot x + b \cdot x^2 + c \cdot x^3 + d \cdot x^4 + e \cdot x^5 + f \cdot x^6\\)
-#[cfg(feature = "yoloproofs")]
-pub struct Poly6 {
-    pub t1: Scalar,
-    pub t2: Scalar,
-    pub t3: Scalar,
-    pub t4: Scalar,
-    pub t5: Scalar,
-    pub t6: Scalar,
-}
+use crate::errors::ProofError;
+#[cfg(feature = "prover")]
+use crate::range_proof::ProvingPhase;
 
 /// Provides an iterator over the powers of a `Scalar`.
 ///
@@ -73,159 +52,10 @@ pub fn exp_iter(x: Scalar) -> ScalarExp {
     ScalarExp { x, next_exp_x }
 }
 
-pub fn add_vec(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
-    if a.len() != b.len() {
-        // throw some error
-        //println!("lengths of vectors don't match for vector addition");
-    }
-    let mut out = vec![Scalar::zero(); b.len()];
-    for i in 0..a.len() {
-        out[i] = a[i] + b[i];
-    }
-    out
-}
-
-impl VecPoly1 {
-    pub fn zero(n: usize) -> Self {
-        VecPoly1(vec![Scalar::zero(); n], vec![Scalar::zero(); n])
-    }
-
-    pub fn inner_product(&self, rhs: &VecPoly1) -> Poly2 {
-        // Uses Karatsuba's method
-        let l = self;
-        let r = rhs;
-
-        let t0 = inner_product(&l.0, &r.0);
-        let t2 = inner_product(&l.1, &r.1);
-
-        let l0_plus_l1 = add_vec(&l.0, &l.1);
-        let r0_plus_r1 = add_vec(&r.0, &r.1);
-
-        let t1 = inner_product(&l0_plus_l1, &r0_plus_r1) - t0 - t2;
-
-        Poly2(t0, t1, t2)
-    }
-
-    pub fn eval(&self, x: Scalar) -> Vec<Scalar> {
-        let n = self.0.len();
-        let mut out = vec![Scalar::zero(); n];
-        for i in 0..n {
-            out[i] = self.0[i] + self.1[i] * x;
-        }
-        out
-    }
-}
-
-#[cfg(feature = "yoloproofs")]
-impl VecPoly3 {
-    pub fn zero(n: usize) -> Self {
-        VecPoly3(
-            vec![Scalar::zero(); n],
-            vec![Scalar::zero(); n],
-            vec![Scalar::zero(); n],
-            vec![Scalar::zero(); n],
-        )
-    }
-
-    /// Compute an inner product of `lhs`, `rhs` which have the property that:
-    /// - `lhs.0` is zero;
-    /// - `rhs.2` is zero;
-    /// This is the case in the constraint system proof.
-    pub fn special_inner_product(lhs: &Self, rhs: &Self) -> Poly6 {
-        // TODO: make checks that l_poly.0 and r_poly.2 are zero.
-
-        let t1 = inner_product(&lhs.1, &rhs.0);
-        let t2 = inner_product(&lhs.1, &rhs.1) + inner_product(&lhs.2, &rhs.0);
-        let t3 = inner_product(&lhs.2, &rhs.1) + inner_product(&lhs.3, &rhs.0);
-        let t4 = inner_product(&lhs.1, &rhs.3) + inner_product(&lhs.3, &rhs.1);
-        let t5 = inner_product(&lhs.2, &rhs.3);
-        let t6 = inner_product(&lhs.3, &rhs.3);
-
-        Poly6 {
-            t1,
-            t2,
-            t3,
-            t4,
-            t5,
-            t6,
-        }
-    }
-
-    pub fn eval(&self, x: Scalar) -> Vec<Scalar> {
-        let n = self.0.len();
-        let mut out = vec![Scalar::zero(); n];
-        for i in 0..n {
-            out[i] = self.0[i] + x * (self.1[i] + x * (self.2[i] + x * self.3[i]));
-        }
-        out
-    }
-}
-
-impl Poly2 {
-    pub fn eval(&self, x: Scalar) -> Scalar {
-        self.0 + x * (self.1 + x * self.2)
-    }
-}
-
-#[cfg(feature = "yoloproofs")]
-impl Poly6 {
-    pub fn eval(&self, x: Scalar) -> Scalar {
-        x * (self.t1 + x * (self.t2 + x * (self.t3 + x * (self.t4 + x * (self.t5 + x * self.t6)))))
-    }
-}
-
-impl Drop for VecPoly1 {
-    fn drop(&mut self) {
-        for e in self.0.iter_mut() {
-            e.clear();
-        }
-        for e in self.1.iter_mut() {
-            e.clear();
-        }
-    }
-}
-
-impl Drop for Poly2 {
-    fn drop(&mut self) {
-        self.0.clear();
-        self.1.clear();
-        self.2.clear();
-    }
-}
-
-#[cfg(feature = "yoloproofs")]
-impl Drop for VecPoly3 {
-    fn drop(&mut self) {
-        for e in self.0.iter_mut() {
-            e.clear();
-        }
-        for e in self.1.iter_mut() {
-            e.clear();
-        }
-        for e in self.2.iter_mut() {
-            e.clear();
-        }
-        for e in self.3.iter_mut() {
-            e.clear();
-        }
-    }
-}
-
-#[cfg(feature = "yoloproofs")]
-impl Drop for Poly6 {
-    fn drop(&mut self) {
-        self.t1.clear();
-        self.t2.clear();
-        self.t3.clear();
-        self.t4.clear();
-        self.t5.clear();
-        self.t6.clear();
-    }
-}
-
 /// Raises `x` to the power `n` using binary exponentiation,
 /// with (1 to 2)*lg(n) scalar multiplications.
 /// TODO: a consttime version of this would be awfully similar to a Montgomery ladder.
+#[cfg(feature = "prover")]
 pub fn scalar_exp_vartime(x: &Scalar, mut n: u64) -> Scalar {
     let mut result = Scalar::one();
     let mut aux = *x; // x, x^2, x^4, x^8, ...
@@ -275,6 +105,155 @@ pub fn read32(data: &[u8]) -> [u8; 32] {
     buf32
 }
 
+/// Given `data` with `len >= 96`, return the first 96 bytes.
+#[cfg(feature = "verifier")]
+pub fn read96(data: &[u8]) -> [u8; 96] {
+    let mut buf96 = [0u8; 96];
+    buf96[..].copy_from_slice(&data[..96]);
+    buf96
+}
+
+/// Checks the BLS signature \(\sigma\) by `public_key` \(P\) on the
+/// hashed `message` \(M\), i.e. \(e(P, M) = e(G, \sigma)\).
+#[cfg(feature = "verifier")]
+pub fn verify_bls_signature(
+    public_key: &G1Affine,
+    message: &G2Affine,
+    signature: &G2Affine,
+) -> bool {
+    !bool::from(public_key.is_identity())
+        && !bool::from(signature.is_identity())
+        && pairing(public_key, message) == pairing(&G1Affine::generator(), signature)
+}
+
+/// Returns [`ProofError::Cancelled`] if the `cancel` flag is given and set.
+#[cfg(feature = "prover")]
+pub fn check_cancelled(cancel: Option<&AtomicBool>) -> Result<(), ProofError> {
+    match cancel {
+        Some(flag) if flag.load(Ordering::Relaxed) => Err(ProofError::Cancelled),
+        _ => Ok(()),
+    }
+}
+
+/// The estimated share of the work of proving spent on the vector
+/// commitments, as reported to progress observers; the inner-product
+/// argument takes most of the rest.
+#[cfg(feature = "prover")]
+pub const COMMITMENT_WORK: f32 = 1.0 / 3.0;
+
+/// Reports `phase` and the `fraction` of the proof done to the
+/// `progress` observer, if given.
+#[cfg(feature = "prover")]
+pub fn report_progress(
+    progress: Option<&dyn Fn(ProvingPhase, f32)>,
+    phase: ProvingPhase,
+    fraction: f32,
+) {
+    if let Some(progress) = progress {
+        progress(phase, fraction);
+    }
+}
+
+/// Overwrites every scalar of `scalars` with zero.
+///
+/// The scalars are cleared one by one, since clearing a `Vec` only
+/// clears its header.
+#[cfg(feature = "zeroize")]
+pub fn clear_scalars(scalars: &mut [Scalar]) {
+    use clear_on_drop::clear::Clear;
+
+    for s in scalars.iter_mut() {
+        s.clear();
+    }
+}
+
+/// A vector of secret scalars, which is wiped when it is dropped if
+/// the `zeroize` feature is enabled, on every exit from its scope.
+pub(crate) struct SecretScalars(pub(crate) alloc::vec::Vec<Scalar>);
+
+impl core::ops::Deref for SecretScalars {
+    type Target = alloc::vec::Vec<Scalar>;
+
+    fn deref(&self) -> &alloc::vec::Vec<Scalar> {
+        &self.0
+    }
+}
+
+impl core::ops::DerefMut for SecretScalars {
+    fn deref_mut(&mut self) -> &mut alloc::vec::Vec<Scalar> {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for SecretScalars {
+    fn drop(&mut self) {
+        clear_scalars(&mut self.0);
+    }
+}
+
+/// Replaces every scalar of `inputs` by its inverse, with a single
+/// field inversion (Montgomery's trick).
+///
+/// Returns [`ProofError::FormatError`], leaving `inputs` unchanged, if
+/// any of the scalars is zero.
+pub fn batch_invert(inputs: &mut [Scalar]) -> Result<(), ProofError> {
+    // products[i] is the product of the scalars before the i-th
+    let mut products = alloc::vec::Vec::with_capacity(inputs.len());
+    let mut acc = Scalar::one();
+    for input in inputs.iter() {
+        products.push(acc);
+        acc *= input;
+    }
+
+    let mut acc: Scalar = Option::from(acc.invert()).ok_or(ProofError::FormatError)?;
+    for (input, product) in inputs.iter_mut().zip(products).rev() {
+        let inverse = acc * product;
+        acc *= *input;
+        *input = inverse;
+    }
+    Ok(())
+}
+
+/// Computes the multiscalar multiplication \\(\sum\_i s\_i \cdot P\_i\\)
+/// of `scalars` and `points`, which must have the same length.
+///
+/// With the `fast-msm` feature this is a Pippenger multiplication by
+/// blst, which is much faster for many terms but runs in variable
+/// time, so it must only be used with public scalars.
+pub fn multiscalar_mul(scalars: &[Scalar], points: &[G1Projective]) -> G1Projective {
+    assert_eq!(scalars.len(), points.len());
+
+    #[cfg(feature = "fast-msm")]
+    {
+        use alloc::vec::Vec;
+        use blst::{blst_p1, blst_scalar, p1_affines};
+        use group::Group;
+
+        // blst normalizes the points with a batch inversion, which an
+        // identity point would poison, so such terms are left out.
+        let (scalars, points): (Vec<&Scalar>, Vec<&G1Projective>) = scalars
+            .iter()
+            .zip(points.iter())
+            .filter(|(_, P)| !bool::from(P.is_identity()))
+            .unzip();
+        if scalars.is_empty() {
+            return G1Projective::identity();
+        }
+        let points: Vec<blst_p1> = points.iter().map(|P| *(*P).as_ref()).collect();
+        let scalars: Vec<u8> = scalars
+            .iter()
+            .flat_map(|s| Into::<blst_scalar>::into(**s).b)
+            .collect();
+        let mut sum = G1Projective::identity();
+        *sum.as_mut() = p1_affines::from(&points).mult(&scalars, 255);
+        sum
+    }
+
+    #[cfg(not(feature = "fast-msm"))]
+    scalars.iter().zip(points.iter()).map(|(s, P)| P * s).sum()
+}
+
 /// Given `data` with `len >= 48`, return the first 48 bytes.
 pub fn read48(data: &[u8]) -> [u8; 48] {
     let mut buf48 = [0u8; 48];
@@ -282,10 +261,105 @@ pub fn read48(data: &[u8]) -> [u8; 48] {
     buf48
 }
 
+/// A serde visitor for a type serialized as its byte encoding, which
+/// is decoded with `from_bytes`.
+struct BytesVisitor<T> {
+    name: &'static str,
+    from_bytes: fn(&[u8]) -> Result<T, ProofError>,
+}
+
+impl<'de, T> Visitor<'de> for BytesVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        write!(formatter, "a valid {}", self.name)
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<T, E>
+    where
+        E: serde::de::Error,
+    {
+        // Using Error::custom requires T: Display, which our error
+        // type only implements when it implements std::error::Error.
+        #[cfg(feature = "std")]
+        return (self.from_bytes)(v).map_err(serde::de::Error::custom);
+        // In no-std contexts, drop the error message.
+        #[cfg(not(feature = "std"))]
+        return (self.from_bytes)(v).map_err(|_| serde::de::Error::custom("deserialization error"));
+    }
+
+    // Formats without a byte string type, such as JSON, encode
+    // the bytes as a sequence.
+    fn visit_seq<A>(self, mut seq: A) -> Result<T, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut bytes = alloc::vec::Vec::new();
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        self.visit_bytes(&bytes)
+    }
+}
+
+/// Deserializes a value of the type `name` from its byte encoding,
+/// given either as bytes or as a sequence of bytes.
+pub(crate) fn deserialize_bytes<'de, D, T>(
+    deserializer: D,
+    name: &'static str,
+    from_bytes: fn(&[u8]) -> Result<T, ProofError>,
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_bytes(BytesVisitor { name, from_bytes })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use clear_on_drop::clear::Clear;
+
+    use crate::inner_product_proof::inner_product;
+    use crate::poly::Poly2;
+
+    #[test]
+    fn batch_invert_matches_inverses() {
+        let scalars: Vec<Scalar> = (1..=5u64).map(|i| Scalar::from(i * 7919)).collect();
+        let mut inverses = scalars.clone();
+        batch_invert(&mut inverses).unwrap();
+        for (s, inverse) in scalars.iter().zip(inverses.iter()) {
+            assert_eq!(s * inverse, Scalar::one());
+        }
+        batch_invert(&mut []).unwrap();
+
+        let mut with_zero = vec![Scalar::from(3u64), Scalar::zero(), Scalar::from(5u64)];
+        assert_eq!(batch_invert(&mut with_zero), Err(ProofError::FormatError));
+        assert_eq!(with_zero[0], Scalar::from(3u64));
+    }
+
+    #[test]
+    fn multiscalar_mul_matches_scalar_muls() {
+        use group::Group;
+
+        let points: Vec<G1Projective> = (1..=40u64)
+            .map(|i| G1Projective::generator() * Scalar::from(i * i))
+            .chain(core::iter::once(G1Projective::identity()))
+            .collect();
+        let scalars: Vec<Scalar> = (0..points.len() as u64)
+            .map(|i| -Scalar::from(i + 3))
+            .collect();
+        let expected: G1Projective = scalars.iter().zip(points.iter()).map(|(s, P)| P * s).sum();
+        assert_eq!(multiscalar_mul(&scalars, &points), expected);
+        assert_eq!(multiscalar_mul(&scalars[..3], &points[..3]), {
+            points[0] * scalars[0] + points[1] * scalars[1] + points[2] * scalars[2]
+        });
+        assert_eq!(multiscalar_mul(&[], &[]), G1Projective::identity());
+    }
+
     #[test]
     fn exp_2_is_powers_of_2() {
         let exp_2: Vec<_> = exp_iter(Scalar::from(2u64)).take(4).collect();
@@ -385,6 +459,14 @@ mod tests {
         assert_eq!(v[1], Scalar::zero());
     }
 
+    #[test]
+    #[cfg(feature = "zeroize")]
+    fn clear_scalars_zeroes_every_scalar() {
+        let mut v: Vec<Scalar> = (1..=3u64).map(Scalar::from).collect();
+        clear_scalars(&mut v);
+        assert!(v.iter().all(|s| *s == Scalar::zero()));
+    }
+
     #[test]
     fn tuple_of_scalars_clear_on_drop() {
         let mut v = Poly2(